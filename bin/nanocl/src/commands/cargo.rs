@@ -4,12 +4,14 @@ use bollard_next::exec::{CreateExecOptions, StartExecOptions};
 use futures::{channel::mpsc, stream::FuturesUnordered, SinkExt, StreamExt};
 use ntex::rt;
 
-use nanocl_error::io::IoResult;
+use nanocl_error::io::{IoError, IoResult};
 use nanocld_client::{
   stubs::{
     cargo::{CargoDeleteQuery, CargoInspect, CargoSummary},
     generic::{GenericFilter, GenericListQueryNsp},
-    process::{OutputKind, ProcessLogQuery, ProcessStatsQuery},
+    process::{
+      OutputKind, ProcessLogQuery, ProcessStatsQuery, ProcessStatsSummaryQuery,
+    },
     system::{EventActorKind, NativeEventAction},
   },
   NanocldClient,
@@ -21,7 +23,7 @@ use crate::{
     CargoArg, CargoCommand, CargoCreateOpts, CargoExecOpts, CargoHistoryOpts,
     CargoLogsOpts, CargoPatchOpts, CargoRestartOpts, CargoRevertOpts, CargoRow,
     CargoRunOpts, CargoStatsOpts, GenericRemoveForceOpts, GenericRemoveOpts,
-    ProcessStatsRow,
+    ProcessStatsRow, ProcessStatsSummaryRow,
   },
   utils,
 };
@@ -231,6 +233,9 @@ async fn exec_cargo_stats(
   args: &CargoArg,
   opts: &CargoStatsOpts,
 ) -> IoResult<()> {
+  if let Some(since) = opts.since {
+    return exec_cargo_stats_summary(cli_conf, args, &opts.names, since).await;
+  }
   let client = cli_conf.client.clone();
   let query = ProcessStatsQuery {
     namespace: args.namespace.clone(),
@@ -286,6 +291,34 @@ async fn exec_cargo_stats(
   Ok(())
 }
 
+/// Summarize the resource usage sampled by the daemon since a duration
+/// to help picking the memory limit of the cargoes
+async fn exec_cargo_stats_summary(
+  cli_conf: &CliConfig,
+  args: &CargoArg,
+  names: &[String],
+  since: std::time::Duration,
+) -> IoResult<()> {
+  let since = chrono::Utc::now()
+    - chrono::Duration::from_std(since).map_err(|err| {
+      IoError::invalid_input("Since", err.to_string().as_str())
+    })?;
+  let query = ProcessStatsSummaryQuery {
+    namespace: args.namespace.clone(),
+    since: Some(since.timestamp()),
+  };
+  let mut rows = Vec::new();
+  for name in names {
+    let summaries = cli_conf
+      .client
+      .stats_summary_processes("cargo", name, Some(&query))
+      .await?;
+    rows.extend(summaries.into_iter().map(ProcessStatsSummaryRow::from));
+  }
+  utils::print::print_table(rows);
+  Ok(())
+}
+
 /// Execute the `nanocl cargo revert` command to revert a cargo to a previous state
 async fn exec_cargo_revert(
   cli_conf: &CliConfig,
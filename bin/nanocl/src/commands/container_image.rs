@@ -0,0 +1,116 @@
+use nanocl_error::io::IoResult;
+use nanocld_client::{
+  stubs::container_image::{ContainerImageDeleteQuery, ContainerImageSummary},
+  NanocldClient,
+};
+
+use crate::{
+  config::CliConfig,
+  models::{
+    ContainerImageArg, ContainerImageCommand, ContainerImagePullOpts,
+    ContainerImageRow, GenericInspectOpts, GenericRemoveForceOpts,
+    GenericRemoveOpts,
+  },
+  utils,
+};
+
+use super::{GenericCommand, GenericCommandLs, GenericCommandRm};
+
+impl GenericCommand for ContainerImageArg {
+  fn object_name() -> &'static str {
+    "images"
+  }
+}
+
+impl GenericCommandLs for ContainerImageArg {
+  type Item = ContainerImageRow;
+  type Args = ContainerImageArg;
+  type ApiItem = ContainerImageSummary;
+
+  fn get_key(item: &Self::Item) -> String {
+    item.name.clone()
+  }
+}
+
+impl GenericCommandRm<GenericRemoveForceOpts, ContainerImageDeleteQuery>
+  for ContainerImageArg
+{
+  fn get_query(
+    opts: &GenericRemoveOpts<GenericRemoveForceOpts>,
+    _namespace: Option<String>,
+  ) -> Option<ContainerImageDeleteQuery>
+  where
+    ContainerImageDeleteQuery: serde::Serialize,
+  {
+    Some(ContainerImageDeleteQuery {
+      force: Some(opts.others.force),
+    })
+  }
+}
+
+/// Function that execute when running `nanocl image pull`
+async fn exec_image_pull(
+  client: &NanocldClient,
+  opts: &ContainerImagePullOpts,
+) -> IoResult<()> {
+  for name in &opts.names {
+    let token = format!("image/{name}");
+    let pg_style = utils::progress::create_spinner_style(&token, "green");
+    let pg = utils::progress::create_progress("(pulling)", &pg_style);
+    if let Err(err) = client.pull_image(name, opts.secret.as_deref()).await {
+      pg.finish();
+      eprintln!("{name}: {err}");
+      continue;
+    }
+    pg.finish_with_message("(pulled)");
+  }
+  Ok(())
+}
+
+/// Function that execute when running `nanocl image inspect`
+async fn exec_image_inspect(
+  cli_conf: &CliConfig,
+  opts: &GenericInspectOpts,
+) -> IoResult<()> {
+  let image = cli_conf.client.inspect_image(&opts.key).await?;
+  let display = opts
+    .display
+    .clone()
+    .unwrap_or(cli_conf.user_config.display_format.clone());
+  utils::print::display_format(&display, image)?;
+  Ok(())
+}
+
+/// Function that execute when running `nanocl image prune`
+async fn exec_image_prune(client: &NanocldClient) -> IoResult<()> {
+  let result = client.prune_image().await?;
+  for id in &result.images_deleted {
+    println!("{id}");
+  }
+  println!(
+    "Total reclaimed space: {} MB",
+    result.space_reclaimed / 1024 / 1024
+  );
+  Ok(())
+}
+
+/// Function that execute when running `nanocl image`
+pub async fn exec_image(
+  cli_conf: &CliConfig,
+  args: &ContainerImageArg,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  match &args.command {
+    ContainerImageCommand::List(opts) => {
+      ContainerImageArg::exec_ls(client, args, opts).await
+    }
+    ContainerImageCommand::Pull(opts) => exec_image_pull(client, opts).await,
+    ContainerImageCommand::Inspect(opts) => {
+      exec_image_inspect(cli_conf, opts).await
+    }
+    ContainerImageCommand::Remove(opts) => {
+      ContainerImageArg::exec_rm(client, opts, None).await
+    }
+    ContainerImageCommand::Prune => exec_image_prune(client).await,
+  }
+}
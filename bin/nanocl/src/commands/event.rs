@@ -1,11 +1,11 @@
 use futures::StreamExt;
 
 use nanocl_error::io::IoResult;
-use nanocld_client::stubs::system::Event;
+use nanocld_client::stubs::system::{self, Event};
 
 use crate::{
   config::CliConfig,
-  models::{EventArg, EventCommand, EventRow},
+  models::{EventArg, EventCommand, EventRow, EventWatchOpts},
   utils,
 };
 
@@ -31,11 +31,18 @@ impl GenericCommandInspect for EventArg {
   type ApiItem = Event;
 }
 
-/// Function that execute when running `nanocl events`
-/// Will print the events emitted by the daemon
-pub async fn watch_event(cli_conf: &CliConfig) -> IoResult<()> {
+/// Function that execute when running `nanocl event watch`
+/// Will print the events emitted by the daemon matching the filters
+pub async fn watch_event(
+  cli_conf: &CliConfig,
+  opts: &EventWatchOpts,
+) -> IoResult<()> {
   let client = &cli_conf.client;
-  let mut stream = client.watch_events(None).await?;
+  let watch_opts = system::EventWatchOpts {
+    since: opts.since,
+    filter: utils::event::parse_filters(&opts.filters)?,
+  };
+  let mut stream = client.watch_events_since(None, watch_opts).await?;
   while let Some(event) = stream.next().await {
     let event = event?;
     utils::print::display_format(&cli_conf.user_config.display_format, event)?;
@@ -52,6 +59,6 @@ pub async fn exec_event(cli_conf: &CliConfig, args: &EventArg) -> IoResult<()> {
     EventCommand::Inspect(opts) => {
       EventArg::exec_inspect(cli_conf, opts, None).await
     }
-    EventCommand::Watch => watch_event(cli_conf).await,
+    EventCommand::Watch(opts) => watch_event(cli_conf, opts).await,
   }
 }
@@ -1,5 +1,6 @@
 mod backup;
 mod cargo;
+mod container_image;
 mod context;
 mod event;
 mod generic;
@@ -10,6 +11,7 @@ mod metric;
 mod namespace;
 mod node;
 mod process;
+mod proxy;
 mod resource;
 mod secret;
 mod state;
@@ -22,6 +24,7 @@ pub use generic::*;
 
 pub use backup::exec_backup;
 pub use cargo::exec_cargo;
+pub use container_image::exec_image;
 pub use context::exec_context;
 pub use event::exec_event;
 pub use info::exec_info;
@@ -31,6 +34,7 @@ pub use metric::exec_metric;
 pub use namespace::exec_namespace;
 pub use node::exec_node;
 pub use process::exec_process;
+pub use proxy::exec_proxy;
 pub use resource::exec_resource;
 pub use secret::exec_secret;
 pub use state::exec_state;
@@ -1,11 +1,14 @@
 use nanocl_error::io::IoResult;
-use nanocld_client::{stubs::namespace::NamespaceInspect, NanocldClient};
+use nanocld_client::{
+  stubs::namespace::{NamespaceDeleteQuery, NamespaceInspect},
+  NanocldClient,
+};
 
 use crate::{
   config::CliConfig,
   models::{
-    GenericDefaultOpts, NamespaceArg, NamespaceCommand, NamespaceCreateOpts,
-    NamespaceRow,
+    GenericRemoveOpts, NamespaceArg, NamespaceCloneOpts, NamespaceCommand,
+    NamespaceCreateOpts, NamespaceRemoveOpts, NamespaceRow,
   },
 };
 use nanocld_client::stubs::namespace::NamespaceSummary;
@@ -30,7 +33,21 @@ impl GenericCommandLs for NamespaceArg {
   }
 }
 
-impl GenericCommandRm<GenericDefaultOpts, String> for NamespaceArg {}
+impl GenericCommandRm<NamespaceRemoveOpts, NamespaceDeleteQuery>
+  for NamespaceArg
+{
+  fn get_query(
+    opts: &GenericRemoveOpts<NamespaceRemoveOpts>,
+    _namespace: Option<String>,
+  ) -> Option<NamespaceDeleteQuery>
+  where
+    NamespaceDeleteQuery: serde::Serialize,
+  {
+    Some(NamespaceDeleteQuery {
+      cascade: Some(opts.others.cascade),
+    })
+  }
+}
 
 impl GenericCommandInspect for NamespaceArg {
   type ApiItem = NamespaceInspect;
@@ -41,11 +58,26 @@ async fn exec_namespace_create(
   client: &NanocldClient,
   opts: &NamespaceCreateOpts,
 ) -> IoResult<()> {
-  let item = client.create_namespace(&opts.name).await?;
+  let item = client.create_namespace_from(&opts.clone().into()).await?;
   println!("{}", item.name);
   Ok(())
 }
 
+/// Function that execute when running `nanocl namespace clone`
+async fn exec_namespace_clone(
+  client: &NanocldClient,
+  opts: &NamespaceCloneOpts,
+) -> IoResult<()> {
+  let item = client
+    .clone_namespace(&opts.name, &opts.clone().into())
+    .await?;
+  for rule in &item.skipped_rules {
+    eprintln!("Skipped rule {}: {}", rule.resource, rule.reason);
+  }
+  println!("{}", item.namespace.name);
+  Ok(())
+}
+
 /// Function that execute when running `nanocl namespace`
 pub async fn exec_namespace(
   cli_conf: &CliConfig,
@@ -63,5 +95,6 @@ pub async fn exec_namespace(
     NamespaceCommand::Remove(opts) => {
       NamespaceArg::exec_rm(client, opts, None).await
     }
+    NamespaceCommand::Clone(opts) => exec_namespace_clone(client, opts).await,
   }
 }
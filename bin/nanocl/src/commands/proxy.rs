@@ -0,0 +1,38 @@
+use nanocl_error::io::{IoError, IoResult};
+use nanocld_client::stubs::metric::HttpAnalyticsQuery;
+
+use crate::{
+  config::CliConfig,
+  models::{ProxyArg, ProxyCommand, ProxyStatsOpts, ProxyStatsRow},
+  utils,
+};
+
+/// Execute the `nanocl proxy stats` command to summarize the http requests
+/// rolled up by the daemon since a duration
+async fn exec_proxy_stats(
+  cli_conf: &CliConfig,
+  opts: &ProxyStatsOpts,
+) -> IoResult<()> {
+  let since = chrono::Utc::now()
+    - chrono::Duration::from_std(opts.since).map_err(|err| {
+      IoError::invalid_input("Since", err.to_string().as_str())
+    })?;
+  let query = HttpAnalyticsQuery {
+    since: Some(since.timestamp()),
+    group_by: Some(opts.group_by.into()),
+    domain: opts.domain.clone(),
+    node_name: opts.node.clone(),
+    ..Default::default()
+  };
+  let analytics = cli_conf.client.http_analytics_metric(&query).await?;
+  let rows = analytics.into_iter().map(ProxyStatsRow::from);
+  utils::print::print_table(rows);
+  Ok(())
+}
+
+/// Function that execute when running `nanocl proxy`
+pub async fn exec_proxy(cli_conf: &CliConfig, args: &ProxyArg) -> IoResult<()> {
+  match &args.command {
+    ProxyCommand::Stats(opts) => exec_proxy_stats(cli_conf, opts).await,
+  }
+}
@@ -642,6 +642,22 @@ async fn state_apply(
       let pg = utils::progress::create_progress("(submitting)", &pg_style);
       let metadata = insert_nanocl_group(&job.metadata, &nanocl_group);
       job.metadata = Some(metadata);
+      if let Some(build) = &job.build {
+        let name = job.name.to_lowercase();
+        let image = utils::build::build_image(
+          client,
+          &name,
+          build,
+          &state_file.root,
+          &pg,
+        )
+        .await?;
+        for container in job.containers.iter_mut() {
+          if container.image.is_none() {
+            container.image = Some(image.clone());
+          }
+        }
+      }
       if client.inspect_job(&job.name).await.is_ok() {
         pg.set_message("(clearing)");
         let waiter = utils::process::wait_process_state(
@@ -678,6 +694,21 @@ async fn state_apply(
       let pg = utils::progress::create_progress("(submitting)", &pg_style);
       let metadata = insert_nanocl_group(&cargo.metadata, &nanocl_group);
       cargo.metadata = Some(metadata);
+      if let Some(build) = &cargo.build {
+        let name = match &cargo.container.image {
+          Some(image) => utils::build::image_repository(image),
+          None => cargo.name.to_lowercase(),
+        };
+        let image = utils::build::build_image(
+          client,
+          &name,
+          build,
+          &state_file.root,
+          &pg,
+        )
+        .await?;
+        cargo.container.image = Some(image);
+      }
       match client.inspect_cargo(&cargo.name, Some(&namespace)).await {
         Err(_) => {
           client.create_cargo(&cargo, Some(&namespace)).await?;
@@ -89,6 +89,7 @@ async fn execute_arg(cli_args: &Cli) -> IoResult<()> {
     Command::State(args) => commands::exec_state(&cli_conf, args).await,
     Command::Version => commands::exec_version(&cli_conf).await,
     Command::Vm(args) => commands::exec_vm(&cli_conf, args).await,
+    Command::Image(args) => commands::exec_image(&cli_conf, args).await,
     Command::Ps(args) => commands::exec_process(&cli_conf, args).await,
     Command::Install(args) => commands::exec_install(args).await,
     Command::Uninstall(args) => commands::exec_uninstall(args).await,
@@ -96,6 +97,7 @@ async fn execute_arg(cli_args: &Cli) -> IoResult<()> {
     Command::Context(args) => commands::exec_context(&cli_conf, args).await,
     Command::Info => commands::exec_info(&cli_conf).await,
     Command::Metric(args) => commands::exec_metric(&cli_conf, args).await,
+    Command::Proxy(args) => commands::exec_proxy(&cli_conf, args).await,
     Command::Backup(opts) => commands::exec_backup(&cli_conf, opts).await,
   }
 }
@@ -150,6 +152,18 @@ mod tests {
     assert_cli_ok!("namespace", "rm", "-y", NAMESPACE_NAME);
   }
 
+  /// Test Image commands
+  #[ntex::test]
+  async fn image() {
+    const IMAGE_NAME: &str = "ghcr.io/next-hat/nanocl-get-started:latest";
+    // Try to pull an image
+    assert_cli_ok!("image", "pull", IMAGE_NAME);
+    // Try to list images
+    assert_cli_ok!("image", "ls");
+    // Try to inspect an image
+    assert_cli_ok!("image", "inspect", IMAGE_NAME);
+  }
+
   /// Test Cargo commands
   #[ntex::test]
   async fn cargo() {
@@ -698,6 +712,19 @@ mod tests {
     );
   }
 
+  #[ntex::test]
+  async fn proxy() {
+    assert_cli_ok!("proxy", "stats");
+    assert_cli_ok!(
+      "proxy",
+      "stats",
+      "--since",
+      "24h",
+      "--group-by",
+      "upstream"
+    );
+  }
+
   #[ntex::test]
   async fn metric() {
     assert_cli_ok!("metric", "ls");
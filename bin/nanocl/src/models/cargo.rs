@@ -232,6 +232,9 @@ pub struct CargoStatsOpts {
   /// Disable streaming stats and only pull the first result
   #[clap(long)]
   pub no_stream: bool,
+  /// Summarize the samples of the resource usage since a duration eg: 1h
+  #[clap(long, value_parser = humantime::parse_duration)]
+  pub since: Option<std::time::Duration>,
   // TODO: Show all containers (default shows just running)
   // pub all: bool,
 }
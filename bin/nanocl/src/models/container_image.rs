@@ -0,0 +1,88 @@
+use chrono::TimeZone;
+use clap::{Parser, Subcommand};
+use tabled::Tabled;
+
+use nanocld_client::stubs::container_image::ContainerImageSummary;
+
+use super::{
+  GenericInspectOpts, GenericListOpts, GenericRemoveForceOpts,
+  GenericRemoveOpts,
+};
+
+/// `nanocl image` available commands
+#[derive(Clone, Subcommand)]
+pub enum ContainerImageCommand {
+  /// List container images
+  #[clap(alias("ls"))]
+  List(GenericListOpts),
+  /// Pull a container image ahead of time
+  Pull(ContainerImagePullOpts),
+  /// Inspect a container image
+  Inspect(GenericInspectOpts),
+  /// Remove container images not used by a cargo or a job
+  #[clap(alias("rm"))]
+  Remove(GenericRemoveOpts<GenericRemoveForceOpts>),
+  /// Remove container images not used by a cargo, a job or a container
+  Prune,
+}
+
+/// `nanocl image pull` available options
+#[derive(Clone, Parser)]
+pub struct ContainerImagePullOpts {
+  /// Secret with the credentials of the registry
+  #[clap(long)]
+  pub secret: Option<String>,
+  /// Names of the images with their tag
+  pub names: Vec<String>,
+}
+
+/// `nanocl image` available arguments
+#[derive(Clone, Parser)]
+pub struct ContainerImageArg {
+  /// Command to run
+  #[clap(subcommand)]
+  pub command: ContainerImageCommand,
+}
+
+/// A row of the container image table
+#[derive(Clone, Tabled)]
+#[tabled(rename_all = "UPPERCASE")]
+pub struct ContainerImageRow {
+  /// Short id of the image
+  pub id: String,
+  /// First tag of the image
+  pub name: String,
+  /// Size of the image
+  pub size: String,
+  /// Number of cargoes using the image
+  pub cargoes: usize,
+  /// Number of jobs using the image
+  pub jobs: usize,
+  #[tabled(rename = "CREATED AT")]
+  pub created_at: String,
+}
+
+/// Convert a ContainerImageSummary to a ContainerImageRow
+impl From<ContainerImageSummary> for ContainerImageRow {
+  fn from(item: ContainerImageSummary) -> Self {
+    let binding = chrono::Local::now();
+    let tz = binding.offset();
+    let created_at = tz
+      .timestamp_opt(item.created, 0)
+      .unwrap()
+      .format("%Y-%m-%d %H:%M:%S");
+    let id = item.id.trim_start_matches("sha256:");
+    Self {
+      id: id.chars().take(12).collect(),
+      name: item
+        .repo_tags
+        .first()
+        .cloned()
+        .unwrap_or("<none>".to_owned()),
+      size: format!("{} MB", item.size / 1024 / 1024),
+      cargoes: item.cargoes.len(),
+      jobs: item.jobs.len(),
+      created_at: created_at.to_string(),
+    }
+  }
+}
@@ -19,11 +19,27 @@ pub enum EventCommand {
   #[clap(alias("ls"))]
   List(GenericListOpts),
   /// Watch for new events in real time
-  Watch,
+  Watch(EventWatchOpts),
   /// Inspect a specific event
   Inspect(GenericInspectOpts),
 }
 
+/// Options of the watch of the events
+#[derive(Clone, Parser)]
+pub struct EventWatchOpts {
+  /// Replay the events since the cursor of an event before the new ones,
+  /// the watch is resumed when the connection to the daemon is lost
+  #[clap(long)]
+  pub since: Option<i64>,
+  /// Only show the matching events with key=value or a json condition.
+  /// Keys: action, kind, node, namespace, actor-kind, actor-key,
+  /// related-kind, related-key and attribute.<name>.
+  /// Filters on different keys must all match,
+  /// filters on the same key match any of their values
+  #[clap(long = "filter", short)]
+  pub filters: Vec<String>,
+}
+
 #[derive(Clone, Tabled)]
 #[tabled(rename_all = "UPPERCASE")]
 pub struct EventRow {
@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 
 mod backup;
 mod cargo;
+mod container_image;
 mod context;
 mod event;
 mod generic;
@@ -12,6 +13,7 @@ mod metric;
 mod namespace;
 mod node;
 mod process;
+mod proxy;
 mod resource;
 mod secret;
 mod state;
@@ -22,6 +24,7 @@ mod vm_image;
 
 pub use backup::*;
 pub use cargo::*;
+pub use container_image::*;
 pub use context::*;
 pub use event::*;
 pub use generic::*;
@@ -31,6 +34,7 @@ pub use metric::*;
 pub use namespace::*;
 pub use node::*;
 pub use process::*;
+pub use proxy::*;
 pub use resource::*;
 pub use secret::*;
 pub use state::*;
@@ -63,10 +67,14 @@ pub enum Command {
   Cargo(CargoArg),
   /// Manage virtual machines
   Vm(VmArg),
+  /// Manage container images
+  Image(ContainerImageArg),
   /// Manage resources
   Resource(ResourceArg),
   /// Manage metrics
   Metric(MetricArg),
+  /// Show analytics of the proxy
+  Proxy(ProxyArg),
   /// Manage contexts
   Context(ContextArg),
   /// Manage nodes (experimental)
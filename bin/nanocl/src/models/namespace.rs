@@ -1,8 +1,12 @@
+use std::collections::HashMap;
+
 use chrono::TimeZone;
 use clap::{Parser, Subcommand};
 use tabled::Tabled;
 
-use nanocld_client::stubs::namespace::NamespaceSummary;
+use nanocld_client::stubs::namespace::{
+  self, NamespaceNetwork, NamespacePartial, NamespaceQuota, NamespaceSummary,
+};
 
 use super::{GenericInspectOpts, GenericListOpts, GenericRemoveOpts};
 
@@ -15,10 +19,12 @@ pub enum NamespaceCommand {
   Inspect(GenericInspectOpts),
   /// Remove a namespace
   #[clap(alias("rm"))]
-  Remove(GenericRemoveOpts),
+  Remove(GenericRemoveOpts<NamespaceRemoveOpts>),
   /// List existing namespaces
   #[clap(alias("ls"))]
   List(GenericListOpts),
+  /// Clone a namespace with his cargoes, vms, proxy and dns rules
+  Clone(NamespaceCloneOpts),
 }
 
 /// `nanocl namespace delete` available options
@@ -31,6 +37,14 @@ pub struct NamespaceDeleteOpts {
   pub names: Vec<String>,
 }
 
+/// `nanocl namespace rm` available options
+#[derive(Clone, Parser)]
+pub struct NamespaceRemoveOpts {
+  /// remove the cargoes, vms, proxy and dns rules and volumes of the namespace
+  #[clap(long)]
+  pub cascade: bool,
+}
+
 /// `nanocl namespace` available arguments
 #[derive(Clone, Parser)]
 pub struct NamespaceArg {
@@ -43,6 +57,106 @@ pub struct NamespaceArg {
 pub struct NamespaceCreateOpts {
   /// name of the namespace to create
   pub name: String,
+  /// create a dedicated network for the namespace
+  #[clap(long)]
+  pub isolated: bool,
+  /// subnet of the dedicated network (eg: 10.10.0.0/16)
+  #[clap(long, requires = "isolated")]
+  pub subnet: Option<String>,
+  /// gateway of the dedicated network (eg: 10.10.0.1)
+  #[clap(long, requires = "isolated")]
+  pub gateway: Option<String>,
+  /// maximum number of cargoes
+  #[clap(long)]
+  pub max_cargoes: Option<usize>,
+  /// maximum number of cargo instances
+  #[clap(long)]
+  pub max_instances: Option<usize>,
+  /// maximum number of virtual machines
+  #[clap(long)]
+  pub max_vms: Option<usize>,
+  /// maximum total of cpus (eg: 1.5)
+  #[clap(long)]
+  pub max_cpus: Option<f64>,
+  /// maximum total of memory in MB
+  #[clap(long)]
+  pub max_memory: Option<i64>,
+}
+
+impl NamespaceCreateOpts {
+  /// Return the quotas set by the options if any
+  fn quota(&self) -> Option<NamespaceQuota> {
+    let quota = NamespaceQuota {
+      max_cargoes: self.max_cargoes,
+      max_instances: self.max_instances,
+      max_vms: self.max_vms,
+      max_nano_cpus: self.max_cpus.map(|cpus| (cpus * 1_000_000_000.0) as i64),
+      max_memory: self.max_memory.map(|memory| memory * 1024 * 1024),
+    };
+    if quota == NamespaceQuota::default() {
+      return None;
+    }
+    Some(quota)
+  }
+}
+
+/// Convert NamespaceCreateOpts to NamespacePartial
+impl From<NamespaceCreateOpts> for NamespacePartial {
+  fn from(opts: NamespaceCreateOpts) -> Self {
+    let quota = opts.quota();
+    let network = if opts.isolated {
+      Some(NamespaceNetwork {
+        isolated: true,
+        subnet: opts.subnet,
+        gateway: opts.gateway,
+      })
+    } else {
+      None
+    };
+    Self {
+      name: opts.name,
+      metadata: None,
+      network,
+      quota,
+      limit_range: None,
+    }
+  }
+}
+
+/// `nanocl namespace clone` available options
+#[derive(Clone, Parser)]
+pub struct NamespaceCloneOpts {
+  /// name of the namespace to clone
+  pub name: String,
+  /// name of the new namespace
+  pub target: String,
+  /// environment variables to set in every cargo (eg: APP_ENV=staging)
+  #[clap(short, long = "env")]
+  pub env: Vec<String>,
+  /// image to use for a cargo (eg: my-cargo=my-image:1.0)
+  #[clap(long = "image")]
+  pub images: Vec<String>,
+  /// snapshot the disks of the vms instead of using their base image
+  #[clap(long)]
+  pub snapshot_vms: bool,
+}
+
+/// Convert NamespaceCloneOpts to the api NamespaceCloneOpts
+impl From<NamespaceCloneOpts> for namespace::NamespaceCloneOpts {
+  fn from(opts: NamespaceCloneOpts) -> Self {
+    let images = opts
+      .images
+      .iter()
+      .filter_map(|image| image.split_once('='))
+      .map(|(cargo, image)| (cargo.to_owned(), image.to_owned()))
+      .collect::<HashMap<_, _>>();
+    Self {
+      name: opts.target,
+      env: (!opts.env.is_empty()).then_some(opts.env),
+      images: (!images.is_empty()).then_some(images),
+      snapshot_vms: opts.snapshot_vms.then_some(true),
+    }
+  }
 }
 
 /// A row of the namespace table
@@ -8,7 +8,7 @@ use bollard_next::{
 
 use nanocld_client::stubs::{
   generic::{GenericClause, GenericFilter},
-  process::{Process, ProcessStats},
+  process::{Process, ProcessStats, ProcessStatsSummary},
 };
 
 pub struct ProcessArg;
@@ -232,3 +232,38 @@ impl From<ProcessStats> for ProcessStatsRow {
     }
   }
 }
+
+/// A row of the summary of the sampled cargo stats
+#[derive(Tabled)]
+#[tabled(rename_all = "UPPERCASE")]
+pub struct ProcessStatsSummaryRow {
+  name: String,
+  samples: i64,
+  #[tabled(rename = "CPU AVG / P95 / MAX")]
+  cpu: String,
+  #[tabled(rename = "MEM AVG / P95 / MAX")]
+  mem: String,
+  #[tabled(rename = "MEM LIMIT")]
+  mem_limit: String,
+}
+
+impl From<ProcessStatsSummary> for ProcessStatsSummaryRow {
+  fn from(summary: ProcessStatsSummary) -> Self {
+    let to_mib = |bytes: f64| bytes / 1024.00 / 1024.00;
+    Self {
+      name: summary.name,
+      samples: summary.samples,
+      cpu: format!(
+        "{:.2}% / {:.2}% / {:.2}%",
+        summary.cpu_avg, summary.cpu_p95, summary.cpu_max
+      ),
+      mem: format!(
+        "{:.1}MiB / {:.1}MiB / {:.1}MiB",
+        to_mib(summary.memory_avg),
+        to_mib(summary.memory_p95),
+        to_mib(summary.memory_max)
+      ),
+      mem_limit: format!("{:.1}MiB", to_mib(summary.memory_limit)),
+    }
+  }
+}
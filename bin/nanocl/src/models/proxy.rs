@@ -0,0 +1,98 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use tabled::Tabled;
+
+use nanocld_client::stubs::metric::{HttpAnalytics, HttpAnalyticsGroupBy};
+
+/// Dimension used to split the analytics of the proxy
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum ProxyStatsGroupBy {
+  #[default]
+  Domain,
+  Location,
+  Upstream,
+}
+
+impl From<ProxyStatsGroupBy> for HttpAnalyticsGroupBy {
+  fn from(group_by: ProxyStatsGroupBy) -> Self {
+    match group_by {
+      ProxyStatsGroupBy::Domain => HttpAnalyticsGroupBy::Domain,
+      ProxyStatsGroupBy::Location => HttpAnalyticsGroupBy::Location,
+      ProxyStatsGroupBy::Upstream => HttpAnalyticsGroupBy::Upstream,
+    }
+  }
+}
+
+/// `nanocl proxy stats` available options
+#[derive(Clone, Parser)]
+pub struct ProxyStatsOpts {
+  /// Window of the analytics until now eg: 24h
+  #[clap(long, default_value = "1h", value_parser = humantime::parse_duration)]
+  pub since: std::time::Duration,
+  /// Split the analytics by domain, location or upstream
+  #[clap(long, value_enum, default_value_t)]
+  pub group_by: ProxyStatsGroupBy,
+  /// Only the requests of this domain
+  #[clap(long)]
+  pub domain: Option<String>,
+  /// Only the requests proxied by this node
+  #[clap(long)]
+  pub node: Option<String>,
+}
+
+/// `nanocl proxy` available commands
+#[derive(Clone, Subcommand)]
+pub enum ProxyCommand {
+  /// Show the traffic, error rates and latencies of the http requests
+  Stats(ProxyStatsOpts),
+}
+
+/// `nanocl proxy` available arguments
+#[derive(Clone, Parser)]
+pub struct ProxyArg {
+  #[clap(subcommand)]
+  pub command: ProxyCommand,
+}
+
+/// A row of the proxy stats table
+#[derive(Tabled)]
+#[tabled(rename_all = "UPPERCASE")]
+pub struct ProxyStatsRow {
+  name: String,
+  requests: i64,
+  #[tabled(rename = "REQ/S")]
+  rps: String,
+  #[tabled(rename = "4XX")]
+  client_errors: i64,
+  #[tabled(rename = "5XX")]
+  server_errors: i64,
+  #[tabled(rename = "ERROR %")]
+  error_rate: String,
+  #[tabled(rename = "P50")]
+  latency_p50: String,
+  #[tabled(rename = "P95")]
+  latency_p95: String,
+  #[tabled(rename = "P99")]
+  latency_p99: String,
+}
+
+impl From<HttpAnalytics> for ProxyStatsRow {
+  fn from(analytics: HttpAnalytics) -> Self {
+    let total = analytics.total;
+    let to_ms = |secs: f64| format!("{:.0}ms", secs * 1000.0);
+    Self {
+      name: if analytics.name.is_empty() {
+        "<none>".to_owned()
+      } else {
+        analytics.name
+      },
+      requests: total.requests,
+      rps: format!("{:.2}", total.rps),
+      client_errors: total.client_errors,
+      server_errors: total.server_errors,
+      error_rate: format!("{:.2}%", total.error_rate * 100.0),
+      latency_p50: to_ms(total.latency_p50),
+      latency_p95: to_ms(total.latency_p95),
+      latency_p99: to_ms(total.latency_p99),
+    }
+  }
+}
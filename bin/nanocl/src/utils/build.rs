@@ -0,0 +1,360 @@
+use std::{
+  io::Write,
+  path::{Path, PathBuf},
+};
+
+use futures::{channel::mpsc, SinkExt, StreamExt};
+use indicatif::ProgressBar;
+use ntex::util::Bytes;
+
+use nanocl_error::io::{FromIo, IoError, IoResult};
+use nanocld_client::{
+  stubs::container_image::{
+    ContainerBuild, ContainerImageBuildQuery, ContainerImageBuildStream,
+  },
+  NanocldClient,
+};
+
+use crate::models::StateRoot;
+
+/// Strip the tag of an image name to get the repository to build into
+pub fn image_repository(image: &str) -> String {
+  match image.rsplit_once(':') {
+    Some((repo, tag)) if !tag.contains('/') => repo.to_owned(),
+    _ => image.to_owned(),
+  }
+}
+
+/// Patterns of a `.dockerignore` file excluding files from a build context
+#[derive(Debug, Default)]
+pub struct DockerIgnore {
+  /// Compiled patterns with true when the pattern starts with `!`
+  patterns: Vec<(regex::Regex, bool)>,
+}
+
+impl DockerIgnore {
+  /// Parse the content of a `.dockerignore` file
+  pub fn parse(content: &str) -> IoResult<Self> {
+    let mut patterns = Vec::new();
+    for line in content.lines() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      let (pattern, exception) = match line.strip_prefix('!') {
+        Some(pattern) => (pattern.trim(), true),
+        None => (line, false),
+      };
+      let pattern = pattern
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/");
+      let regex =
+        regex::Regex::new(&Self::to_regex(&pattern)).map_err(|err| {
+          IoError::invalid_data(".dockerignore", err.to_string().as_str())
+        })?;
+      patterns.push((regex, exception));
+    }
+    Ok(Self { patterns })
+  }
+
+  /// Read the `.dockerignore` file of a build context if it exists
+  pub fn read(path: &Path) -> IoResult<Self> {
+    let path = path.join(".dockerignore");
+    if !path.exists() {
+      return Ok(Self::default());
+    }
+    let content = std::fs::read_to_string(&path)
+      .map_err(|err| err.map_err_context(|| path.display().to_string()))?;
+    Self::parse(&content)
+  }
+
+  /// Convert a pattern to a regex, `*` and `?` don't match a `/`
+  /// and `**` match any number of directories
+  fn to_regex(pattern: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+      match c {
+        '*' if chars.peek() == Some(&'*') => {
+          chars.next();
+          if chars.peek() == Some(&'/') {
+            chars.next();
+            regex.push_str("(.*/)?");
+          } else {
+            regex.push_str(".*");
+          }
+        }
+        '*' => regex.push_str("[^/]*"),
+        '?' => regex.push_str("[^/]"),
+        '[' => {
+          regex.push('[');
+          for c in chars.by_ref() {
+            regex.push(c);
+            if c == ']' {
+              break;
+            }
+          }
+        }
+        '\\' => {
+          if let Some(c) = chars.next() {
+            regex.push_str(&regex::escape(&c.to_string()));
+          }
+        }
+        c => regex.push_str(&regex::escape(&c.to_string())),
+      }
+    }
+    regex.push('$');
+    regex
+  }
+
+  /// Whether a path relative to the context is excluded,
+  /// a pattern matching a parent directory excludes its content
+  pub fn is_excluded(&self, path: &Path) -> bool {
+    let path = path.to_string_lossy();
+    let parts = path.split('/').collect::<Vec<_>>();
+    let mut excluded = false;
+    for (regex, exception) in &self.patterns {
+      if excluded != *exception {
+        continue;
+      }
+      let matched = (1..=parts.len())
+        .any(|depth| regex.is_match(&parts[..depth].join("/")));
+      if matched {
+        excluded = !exception;
+      }
+    }
+    excluded
+  }
+
+  /// Whether the content of an excluded directory can be skipped
+  fn has_exceptions(&self) -> bool {
+    self.patterns.iter().any(|(_, exception)| *exception)
+  }
+}
+
+/// List files and symlinks of a directory recursively in a sorted order
+/// without the paths excluded by the `.dockerignore`
+fn list_files(
+  root: &Path,
+  dir: &Path,
+  ignore: &DockerIgnore,
+  files: &mut Vec<PathBuf>,
+) -> IoResult<()> {
+  let mut entries = std::fs::read_dir(dir)
+    .map_err(|err| err.map_err_context(|| dir.display().to_string()))?
+    .map(|entry| entry.map(|entry| entry.path()))
+    .collect::<Result<Vec<_>, _>>()?;
+  entries.sort();
+  for path in entries {
+    let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+    let excluded = ignore.is_excluded(&relative);
+    let file_type = std::fs::symlink_metadata(&path)
+      .map_err(|err| err.map_err_context(|| path.display().to_string()))?
+      .file_type();
+    if file_type.is_dir() {
+      if !excluded || ignore.has_exceptions() {
+        list_files(root, &path, ignore, files)?;
+      }
+    } else if !excluded {
+      files.push(relative);
+    }
+  }
+  Ok(())
+}
+
+/// Size of the chunks of a build context sent to the daemon
+const CONTEXT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Stream of the chunks of a build context archive
+pub type ContextStream = mpsc::Receiver<std::io::Result<Bytes>>;
+
+/// Writer sending an archive in chunks to a channel,
+/// it waits for the previous chunks to be sent to keep the memory bounded
+struct ContextWriter {
+  buffer: Vec<u8>,
+  sender: mpsc::Sender<std::io::Result<Bytes>>,
+}
+
+impl ContextWriter {
+  fn send(&mut self, item: std::io::Result<Bytes>) -> std::io::Result<()> {
+    futures::executor::block_on(self.sender.send(item))
+      .map_err(|err| std::io::Error::new(std::io::ErrorKind::BrokenPipe, err))
+  }
+}
+
+impl Write for ContextWriter {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.buffer.extend_from_slice(buf);
+    if self.buffer.len() >= CONTEXT_CHUNK_SIZE {
+      self.flush()?;
+    }
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    if self.buffer.is_empty() {
+      return Ok(());
+    }
+    let chunk = Bytes::from(std::mem::take(&mut self.buffer));
+    self.send(Ok(chunk))
+  }
+}
+
+/// Write the tar archive of the given files of a build context
+fn write_context(
+  path: &Path,
+  files: &[PathBuf],
+  writer: &mut ContextWriter,
+) -> std::io::Result<()> {
+  let mut builder = tar::Builder::new(&mut *writer);
+  builder.mode(tar::HeaderMode::Deterministic);
+  builder.follow_symlinks(false);
+  for file in files {
+    builder
+      .append_path_with_name(path.join(file), file)
+      .map_err(|err| {
+        std::io::Error::new(err.kind(), format!("{}: {err}", file.display()))
+      })?;
+  }
+  builder.finish()?;
+  drop(builder);
+  writer.flush()
+}
+
+/// Create a reproducible tar archive of a build context
+/// Timestamps and ownership are cleared so the same sources give the same archive,
+/// symlinks are kept as links and the Dockerfile is always sent.
+/// The archive is written from a thread and streamed in chunks as it's created
+pub fn create_context(
+  path: &Path,
+  dockerfile: &str,
+) -> IoResult<ContextStream> {
+  let ignore = DockerIgnore::read(path)?;
+  let mut files = Vec::new();
+  list_files(path, path, &ignore, &mut files)?;
+  for file in [dockerfile, ".dockerignore"] {
+    let file = PathBuf::from(file);
+    if !files.contains(&file) && path.join(&file).exists() {
+      files.push(file);
+    }
+  }
+  let (sender, receiver) = mpsc::channel(4);
+  let path = path.to_path_buf();
+  std::thread::spawn(move || {
+    let mut writer = ContextWriter {
+      buffer: Vec::with_capacity(CONTEXT_CHUNK_SIZE),
+      sender,
+    };
+    if let Err(err) = write_context(&path, &files, &mut writer) {
+      let _ = writer.send(Err(err));
+    }
+  });
+  Ok(receiver)
+}
+
+/// Build an image from a Statefile build definition and return its tag
+pub async fn build_image(
+  client: &NanocldClient,
+  name: &str,
+  build: &ContainerBuild,
+  root: &StateRoot,
+  pg: &ProgressBar,
+) -> IoResult<String> {
+  let context_path = match root {
+    StateRoot::File(root) => root.join(&build.context),
+    _ => {
+      return Err(IoError::invalid_input(
+        "Build",
+        "context is only supported for local Statefiles",
+      ))
+    }
+  };
+  let dockerfile = build.dockerfile.clone().unwrap_or("Dockerfile".into());
+  let context = create_context(&context_path, &dockerfile)?;
+  let args = match &build.args {
+    None => None,
+    Some(args) => Some(
+      serde_json::to_string(args)
+        .map_err(|err| err.map_err_context(|| "Build args"))?,
+    ),
+  };
+  let query = ContainerImageBuildQuery {
+    name: name.to_owned(),
+    dockerfile: build.dockerfile.clone(),
+    args,
+    target: build.target.clone(),
+  };
+  let mut stream = client.build_image(&query, context).await?;
+  pg.set_message("(building)");
+  let mut image = None;
+  while let Some(item) = stream.next().await {
+    match item? {
+      ContainerImageBuildStream::Progress(progress) => {
+        let progress = progress.trim();
+        if !progress.is_empty() {
+          pg.set_message(format!("(building) {progress}"));
+        }
+      }
+      ContainerImageBuildStream::Done(tag) => image = Some(tag),
+    }
+  }
+  image.ok_or(IoError::interrupted("Build", "stream ended before done"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn dockerignore() {
+    let ignore = DockerIgnore::parse(
+      "# comment\n/target\n*.log\n**/node_modules\n!keep.log\ndocs/*.md\n",
+    )
+    .unwrap();
+    assert!(ignore.is_excluded(Path::new("target")));
+    assert!(ignore.is_excluded(Path::new("target/debug/app")));
+    assert!(ignore.is_excluded(Path::new("debug.log")));
+    assert!(!ignore.is_excluded(Path::new("keep.log")));
+    assert!(!ignore.is_excluded(Path::new("src/debug.log")));
+    assert!(ignore.is_excluded(Path::new("web/node_modules/lib.js")));
+    assert!(ignore.is_excluded(Path::new("docs/readme.md")));
+    assert!(!ignore.is_excluded(Path::new("docs/api/readme.md")));
+    assert!(!ignore.is_excluded(Path::new("src/main.rs")));
+  }
+
+  #[test]
+  fn context() {
+    let path = std::env::temp_dir()
+      .join(format!("nanocl-build-context-{}", std::process::id()));
+    std::fs::create_dir_all(&path).unwrap();
+    std::fs::write(path.join("Dockerfile"), "FROM scratch\n").unwrap();
+    std::fs::write(path.join(".dockerignore"), "*.log\n").unwrap();
+    std::fs::write(path.join("debug.log"), "ignored").unwrap();
+    std::fs::write(path.join("data"), vec![0; CONTEXT_CHUNK_SIZE * 2]).unwrap();
+    let stream = create_context(&path, "Dockerfile").unwrap();
+    let chunks = futures::executor::block_on(stream.collect::<Vec<_>>());
+    assert!(chunks.len() > 1);
+    let archive = chunks
+      .into_iter()
+      .map(|chunk| chunk.unwrap().to_vec())
+      .collect::<Vec<_>>()
+      .concat();
+    let mut archive = tar::Archive::new(archive.as_slice());
+    let files = archive
+      .entries()
+      .unwrap()
+      .map(|entry| entry.unwrap().path().unwrap().display().to_string())
+      .collect::<Vec<_>>();
+    assert_eq!(files, vec![".dockerignore", "Dockerfile", "data"]);
+    std::fs::remove_dir_all(&path).unwrap();
+  }
+
+  #[test]
+  fn repository() {
+    assert_eq!(image_repository("my/app:1.0"), "my/app");
+    assert_eq!(image_repository("localhost:5000/app"), "localhost:5000/app");
+    assert_eq!(image_repository("app"), "app");
+  }
+}
@@ -0,0 +1,143 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use nanocl_error::io::{IoError, IoResult};
+use nanocld_client::stubs::system::{
+  EventActorKind, EventCondition, EventKind, NativeEventAction,
+};
+
+/// Condition matching a single value of a filter key
+fn gen_condition(key: &str, value: &str) -> IoResult<EventCondition> {
+  let parse_actor_kind = |value: &str| {
+    serde_json::from_value::<EventActorKind>(value.into())
+      .map_err(|err| IoError::invalid_input("Filter", &err.to_string()))
+  };
+  let mut condition = EventCondition::default();
+  match key {
+    "action" => condition.action = vec![NativeEventAction::from_str(value)?],
+    "kind" => condition.kind = vec![EventKind::from_str(value)?],
+    "node" => condition.reporting_node = Some(value.to_owned()),
+    "namespace" => condition.namespace = Some(value.to_owned()),
+    "actor-kind" => condition.actor_kind = Some(parse_actor_kind(value)?),
+    "actor-key" => condition.actor_key = Some(value.to_owned()),
+    "related-kind" => condition.related_kind = Some(parse_actor_kind(value)?),
+    "related-key" => condition.related_key = Some(value.to_owned()),
+    _ => {
+      let Some(name) = key.strip_prefix("attribute.") else {
+        return Err(IoError::invalid_input(
+          "Filter",
+          format!("unknown key {key}").as_str(),
+        ));
+      };
+      condition.attributes = Some([(name.to_owned(), value.to_owned())].into());
+    }
+  }
+  Ok(condition)
+}
+
+/// Parse the `--filter` options of a watch into an event condition.
+/// The filters are `key=value` or a json condition,
+/// filters on different keys must all match
+/// and filters on the same key match any of their values.
+pub fn parse_filters(filters: &[String]) -> IoResult<Option<EventCondition>> {
+  if filters.is_empty() {
+    return Ok(None);
+  }
+  let mut all = Vec::new();
+  let mut values = BTreeMap::<&str, Vec<&str>>::new();
+  for filter in filters {
+    if filter.starts_with('{') {
+      let condition = serde_json::from_str::<EventCondition>(filter)
+        .map_err(|err| IoError::invalid_input("Filter", &err.to_string()))?;
+      all.push(condition);
+      continue;
+    }
+    let Some((key, value)) = filter.split_once('=') else {
+      return Err(IoError::invalid_input(
+        "Filter",
+        format!("expected key=value got {filter}").as_str(),
+      ));
+    };
+    values.entry(key).or_default().push(value);
+  }
+  for (key, values) in values {
+    let mut conditions = values
+      .into_iter()
+      .map(|value| gen_condition(key, value))
+      .collect::<IoResult<Vec<_>>>()?;
+    let condition = match conditions.len() {
+      1 => conditions.remove(0),
+      _ => EventCondition {
+        any: Some(conditions),
+        ..Default::default()
+      },
+    };
+    all.push(condition);
+  }
+  Ok(Some(EventCondition {
+    all: Some(all),
+    ..Default::default()
+  }))
+}
+
+#[cfg(test)]
+mod tests {
+  use nanocld_client::stubs::system::{Event, EventActor};
+
+  use super::*;
+
+  fn gen_event(action: &str, kind: EventKind, namespace: &str) -> Event {
+    Event {
+      key: Default::default(),
+      seq: 1,
+      created_at: Default::default(),
+      expires_at: Default::default(),
+      reporting_node: "node1".to_owned(),
+      reporting_controller: "nanocl.io/core".to_owned(),
+      kind,
+      action: action.to_owned(),
+      reason: "state_sync".to_owned(),
+      note: None,
+      actor: Some(EventActor {
+        key: Some(format!("my-cargo.{namespace}")),
+        kind: EventActorKind::Cargo,
+        attributes: Some(serde_json::json!({
+          "Namespace": namespace,
+          "io.nanocl.n": namespace,
+        })),
+      }),
+      related: None,
+      metadata: None,
+    }
+  }
+
+  #[test]
+  fn filters() {
+    let filters = [
+      "action=start",
+      "action=fail",
+      "kind=error",
+      "kind=warning",
+      "attribute.io.nanocl.n=prod",
+      "actor-kind=Cargo",
+    ]
+    .map(String::from);
+    let condition = parse_filters(&filters).unwrap().unwrap();
+    assert!(condition.matches(&gen_event("fail", EventKind::Error, "prod")));
+    assert!(condition.matches(&gen_event("start", EventKind::Warning, "prod")));
+    assert!(!condition.matches(&gen_event("start", EventKind::Normal, "prod")));
+    assert!(!condition.matches(&gen_event("stop", EventKind::Error, "prod")));
+    assert!(!condition.matches(&gen_event("fail", EventKind::Error, "dev")));
+    let filters = [
+      r#"{ "Any": [{ "Namespace": "dev" }, { "Kind": ["error"] }] }"#,
+      "node=node1",
+    ]
+    .map(String::from);
+    let condition = parse_filters(&filters).unwrap().unwrap();
+    assert!(condition.matches(&gen_event("start", EventKind::Normal, "dev")));
+    assert!(condition.matches(&gen_event("start", EventKind::Error, "prod")));
+    assert!(!condition.matches(&gen_event("start", EventKind::Normal, "prod")));
+    assert!(parse_filters(&["unknown=value".to_owned()]).is_err());
+    assert!(parse_filters(&["kind".to_owned()]).is_err());
+    assert!(parse_filters(&[]).unwrap().is_none());
+  }
+}
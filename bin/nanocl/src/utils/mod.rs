@@ -1,6 +1,8 @@
+pub mod build;
 pub mod context;
 pub mod dialog;
 pub mod docker;
+pub mod event;
 pub mod hash;
 pub mod installer;
 pub mod liquid;
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "tasks" (
+  "key" VARCHAR NOT NULL PRIMARY KEY,
+  "created_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "node_name" VARCHAR NOT NULL REFERENCES nodes("name"),
+  "action" VARCHAR NOT NULL,
+  "actor" JSONB NOT NULL
+);
+
+CREATE INDEX "tasks_created_at_idx" ON "tasks" ("created_at");
+CREATE INDEX "tasks_node_name_idx" ON "tasks" ("node_name");
+CREATE INDEX "tasks_action_idx" ON "tasks" ("action");
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE "tasks" ADD COLUMN IF NOT EXISTS "status" VARCHAR NOT NULL DEFAULT 'running';
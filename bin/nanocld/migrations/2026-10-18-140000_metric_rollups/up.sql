@@ -0,0 +1,18 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "metric_rollups" (
+  "resolution" BIGINT NOT NULL,
+  "bucket" TIMESTAMPTZ NOT NULL,
+  "node_name" VARCHAR NOT NULL,
+  "kind" VARCHAR NOT NULL,
+  "field" VARCHAR NOT NULL,
+  "count" BIGINT NOT NULL,
+  "sum" FLOAT8 NOT NULL,
+  "min" FLOAT8 NOT NULL,
+  "max" FLOAT8 NOT NULL,
+  "p95" FLOAT8 NOT NULL,
+  "expires_at" TIMESTAMPTZ NOT NULL,
+  PRIMARY KEY ("resolution", "field", "node_name", "kind", "bucket")
+) WITH (ttl_expiration_expression = 'expires_at');
+
+CREATE INDEX "metric_rollups_bucket_idx" ON "metric_rollups" ("bucket");
+CREATE INDEX "metric_rollups_expires_at_idx" ON "metric_rollups" ("expires_at");
@@ -0,0 +1,22 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "http_rollups" (
+  "resolution" BIGINT NOT NULL,
+  "bucket" TIMESTAMPTZ NOT NULL,
+  "node_name" VARCHAR NOT NULL,
+  "domain" VARCHAR NOT NULL,
+  "location" VARCHAR NOT NULL,
+  "upstream" VARCHAR NOT NULL,
+  "requests" BIGINT NOT NULL,
+  "client_errors" BIGINT NOT NULL,
+  "server_errors" BIGINT NOT NULL,
+  "bytes_sent" BIGINT NOT NULL,
+  "latency_sum" FLOAT8 NOT NULL,
+  "latency_buckets" BIGINT[] NOT NULL,
+  "expires_at" TIMESTAMPTZ NOT NULL,
+  PRIMARY KEY (
+    "resolution", "node_name", "domain", "location", "upstream", "bucket"
+  )
+) WITH (ttl_expiration_expression = 'expires_at');
+
+CREATE INDEX "http_rollups_bucket_idx" ON "http_rollups" ("resolution", "bucket");
+CREATE INDEX "http_rollups_expires_at_idx" ON "http_rollups" ("expires_at");
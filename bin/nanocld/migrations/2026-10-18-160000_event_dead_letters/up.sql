@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "event_dead_letters" (
+  "key" UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+  "created_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "node_name" VARCHAR NOT NULL,
+  "webhook" VARCHAR NOT NULL,
+  "url" VARCHAR NOT NULL,
+  "attempts" BIGINT NOT NULL,
+  "error" VARCHAR NOT NULL,
+  "event" JSONB NOT NULL
+);
+
+CREATE INDEX "event_dead_letters_created_at_idx" ON "event_dead_letters" ("created_at");
+CREATE INDEX "event_dead_letters_node_name_idx" ON "event_dead_letters" ("node_name");
+CREATE INDEX "event_dead_letters_webhook_idx" ON "event_dead_letters" ("webhook");
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "events" DROP COLUMN IF EXISTS "seq";
+DROP SEQUENCE IF EXISTS "events_seq";
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+CREATE SEQUENCE IF NOT EXISTS "events_seq";
+
+ALTER TABLE "events" ADD COLUMN IF NOT EXISTS "seq" INT8 NOT NULL DEFAULT nextval('events_seq');
+
+CREATE INDEX IF NOT EXISTS "events_seq_idx" ON "events" ("seq");
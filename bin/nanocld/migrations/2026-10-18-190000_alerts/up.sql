@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "alerts" (
+  "rule" VARCHAR NOT NULL PRIMARY KEY,
+  "updated_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "node_name" VARCHAR NOT NULL,
+  "status" VARCHAR NOT NULL,
+  "data" JSONB NOT NULL
+);
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "metric_rollups" ADD COLUMN IF NOT EXISTS "p95" FLOAT8 NOT NULL DEFAULT 0;
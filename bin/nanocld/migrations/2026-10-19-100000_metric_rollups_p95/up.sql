@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- A percentile can't be computed from the percentiles of the buckets
+ALTER TABLE "metric_rollups" DROP COLUMN IF EXISTS "p95";
@@ -1,10 +1,17 @@
-use nanocl_stubs::config::{DaemonConfig, DaemonConfigFile};
+use std::{str::FromStr, sync::OnceLock};
 
-use nanocl_error::io::{FromIo, IoResult};
-use nanocl_utils::unix;
+use nanocl_stubs::config::{
+  DaemonConfig, DaemonConfigFile, DaemonConfigReload,
+};
+
+use nanocl_error::io::{FromIo, IoError, IoResult};
+use nanocl_utils::{logger, unix};
 
 use crate::cli::Cli;
 
+/// Cli arguments of the daemon kept to merge them again on reload
+static ARGS: OnceLock<Cli> = OnceLock::new();
+
 /// Merge cli and config file together to generate the daemon config
 fn gen_daemon_conf(
   args: &Cli,
@@ -70,9 +77,104 @@ fn gen_daemon_conf(
     nodes: args.nodes.clone(),
     conf_dir: args.conf_dir.clone(),
     ssl: args.ssl.clone(),
+    image_gc: config.image_gc.clone(),
+    reconcile: config.reconcile.clone().unwrap_or_default(),
+    task_workers: config.task_workers.unwrap_or(8),
+    shutdown_timeout: config.shutdown_timeout.unwrap_or(30),
+    log_level: config.log_level.clone(),
+    retention: config.retention.clone().unwrap_or_default(),
+    process_stats: config.process_stats.clone().unwrap_or_default(),
+  })
+}
+
+/// Parse the log level of the config
+fn parse_log_level(log_level: &str) -> IoResult<log::LevelFilter> {
+  log::LevelFilter::from_str(log_level).map_err(|_| {
+    IoError::invalid_input(
+      "LogLevel",
+      format!("{log_level} is not valid").as_str(),
+    )
   })
 }
 
+/// Rebuild the filter of the logs with the level of the config
+/// Without level the logs are only filtered by the `LOG_LEVEL` environment variable
+pub fn set_log_level(config: &DaemonConfig) -> IoResult<()> {
+  let level = match &config.log_level {
+    Some(log_level) => Some(parse_log_level(log_level)?),
+    None => None,
+  };
+  logger::set_level(env!("CARGO_PKG_NAME"), level);
+  Ok(())
+}
+
+/// Compare the current config with a new one.
+/// Settings that can change while running are taken from the new config
+/// the others are kept and reported as requiring a restart
+fn diff(
+  current: &DaemonConfig,
+  new: &DaemonConfig,
+) -> (DaemonConfig, DaemonConfigReload) {
+  let mut config = current.clone();
+  let mut reload = DaemonConfigReload::default();
+  macro_rules! apply {
+    ($($field:ident),*) => {
+      $(
+        if current.$field != new.$field {
+          config.$field.clone_from(&new.$field);
+          reload.applied.push(stringify!($field).to_owned());
+        }
+      )*
+    };
+  }
+  macro_rules! restart {
+    ($($field:ident),*) => {
+      $(
+        if current.$field != new.$field {
+          reload.restart_required.push(stringify!($field).to_owned());
+        }
+      )*
+    };
+  }
+  apply!(
+    hosts,
+    ssl,
+    log_level,
+    image_gc,
+    reconcile,
+    shutdown_timeout,
+    retention,
+    process_stats
+  );
+  restart!(
+    docker_host,
+    store_addr,
+    state_dir,
+    gateway,
+    hostname,
+    advertise_addr,
+    task_workers
+  );
+  (config, reload)
+}
+
+/// Read the config file again and merge it with the cli arguments.
+/// Return the new config with the changes that can be applied live
+pub fn reload(
+  current: &DaemonConfig,
+) -> IoResult<(DaemonConfig, DaemonConfigReload)> {
+  let args = ARGS.get().cloned().unwrap_or_else(|| Cli {
+    conf_dir: current.conf_dir.clone(),
+    ..Default::default()
+  });
+  let file_config = read_config_file(&args.conf_dir)?;
+  let new = gen_daemon_conf(&args, &file_config)?;
+  if let Some(log_level) = &new.log_level {
+    parse_log_level(log_level)?;
+  }
+  Ok(diff(current, &new))
+}
+
 /// Read config file from config_dir
 fn read_config_file(config_dir: &str) -> IoResult<DaemonConfigFile> {
   let config_path = std::path::Path::new(&config_dir).join("nanocl.conf");
@@ -97,13 +199,22 @@ fn read_config_file(config_dir: &str) -> IoResult<DaemonConfigFile> {
 /// and parse Cli arguments we merge them together with a priority to Cli arguments
 pub fn init(args: &Cli) -> IoResult<DaemonConfig> {
   let file_config = read_config_file(&args.conf_dir)?;
-  gen_daemon_conf(args, &file_config)
+  let config = gen_daemon_conf(args, &file_config)?;
+  if let Some(log_level) = &config.log_level {
+    parse_log_level(log_level)?;
+  }
+  let _ = ARGS.set(args.clone());
+  Ok(config)
 }
 
 /// Config unit test
 #[cfg(test)]
 mod tests {
-  use std::os::unix::prelude::PermissionsExt;
+  use std::{collections::HashMap, os::unix::prelude::PermissionsExt};
+
+  use nanocl_stubs::config::{
+    ImageGcConfig, ProcessStatsConfig, ReconcileConfig, RetentionConfig,
+  };
 
   use super::*;
 
@@ -124,11 +235,38 @@ mod tests {
       store_addr: None,
       gateway: None,
       hostname: None,
+      image_gc: Some(ImageGcConfig {
+        keep_last: Some(3),
+        ..Default::default()
+      }),
+      reconcile: None,
+      task_workers: Some(4),
+      shutdown_timeout: Some(10),
+      log_level: Some(String::from("info")),
+      retention: Some(RetentionConfig {
+        metric_kinds: HashMap::from([(
+          String::from("ncproxy.io/http"),
+          86_400,
+        )]),
+        ..Default::default()
+      }),
+      process_stats: None,
     };
     let merged = gen_daemon_conf(&args, &config).unwrap();
     assert_eq!(merged.hosts, args.hosts.unwrap());
     assert_eq!(merged.state_dir, args.state_dir.unwrap());
     assert_eq!(merged.docker_host, args.docker_host.unwrap());
+    assert_eq!(merged.image_gc, config.image_gc);
+    assert_eq!(merged.reconcile, ReconcileConfig::default());
+    assert_eq!(merged.task_workers, 4);
+    assert_eq!(merged.shutdown_timeout, 10);
+    assert_eq!(merged.log_level, Some(String::from("info")));
+    assert_eq!(merged.retention.metric("ncproxy.io/http"), 86_400);
+    assert_eq!(
+      merged.retention.metric("nanocl.io/metrs"),
+      RetentionConfig::default().metrics
+    );
+    assert_eq!(merged.process_stats, ProcessStatsConfig::default());
   }
 
   /// Test read config file
@@ -177,6 +315,45 @@ mod tests {
     std::fs::remove_dir_all(&config_path).unwrap();
   }
 
+  /// Test diff of config on reload
+  #[test]
+  fn reload_diff() {
+    let current = DaemonConfig {
+      hosts: vec![String::from("unix:///run/nanocl/nanocl.sock")],
+      hostname: String::from("node-1"),
+      ..Default::default()
+    };
+    let new = DaemonConfig {
+      hosts: vec![
+        String::from("unix:///run/nanocl/nanocl.sock"),
+        String::from("tcp://0.0.0.0:8585"),
+      ],
+      hostname: String::from("node-2"),
+      image_gc: Some(ImageGcConfig::default()),
+      log_level: Some(String::from("warn")),
+      retention: RetentionConfig {
+        events: 3_600,
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    let (config, reload) = diff(&current, &new);
+    assert_eq!(config.hosts, new.hosts);
+    assert_eq!(config.image_gc, new.image_gc);
+    assert_eq!(config.log_level, new.log_level);
+    assert_eq!(config.hostname, current.hostname);
+    assert_eq!(config.retention, new.retention);
+    assert_eq!(
+      reload.applied,
+      vec!["hosts", "log_level", "image_gc", "retention"]
+    );
+    assert_eq!(reload.restart_required, vec!["hostname"]);
+    let (_, reload) = diff(&config, &config);
+    assert_eq!(reload, DaemonConfigReload::default());
+    assert!(parse_log_level("warn").is_ok());
+    assert!(parse_log_level("verbose").is_err());
+  }
+
   /// Test init config
   #[test]
   fn init_config() {
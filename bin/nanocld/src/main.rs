@@ -1,3 +1,6 @@
+// The middleware stack of the http server exceed the default limit
+#![recursion_limit = "256"]
+
 use clap::Parser;
 
 use nanocl_error::io::FromIo;
@@ -8,6 +11,7 @@ mod config;
 mod models;
 mod objects;
 mod repositories;
+mod runtime;
 mod schema;
 mod services;
 mod system;
@@ -38,7 +42,6 @@ async fn main() -> std::io::Result<()> {
       err.print_and_exit();
     }
     Ok(config) => config,
-    // gfg
   };
   // Boot internal dependencies (database, event bus, etc...)
   let daemon_state = match system::init(&config).await {
@@ -47,18 +50,47 @@ async fn main() -> std::io::Result<()> {
     }
     Ok(daemon_state) => daemon_state,
   };
+  if let Err(err) = config::set_log_level(&config) {
+    err.print_and_exit();
+  }
+  system::handle_signals(&daemon_state);
   // Start http server
-  match utils::server::gen(daemon_state).await {
+  let mut server = match utils::server::gen(daemon_state.clone()).await {
     Err(err) => {
       err.map_err_context(|| "Http server").print_and_exit();
     }
-    Ok(server) => {
-      // Start http server and wait for shutdown
-      // Server should never shutdown unless it's explicitly asked
-      if let Err(err) = server.await {
-        err.map_err_context(|| "Http server").print_and_exit();
-      }
+    Ok(server) => server,
+  };
+  loop {
+    let config = daemon_state.config();
+    daemon_state.set_server(&server);
+    // Start http server and wait for shutdown
+    // Server should never shutdown unless it's explicitly asked
+    // or restarted to apply a new config
+    if let Err(err) = server.await {
+      err.map_err_context(|| "Http server").print_and_exit();
+    }
+    if daemon_state.is_shutting_down() {
+      break;
     }
+    log::info!("main: restarting http server");
+    server = match utils::server::gen(daemon_state.clone()).await {
+      Ok(server) => server,
+      Err(err) => {
+        // Listen again with the previous hosts when the new ones are invalid
+        log::error!("main: {err} restoring the previous hosts");
+        let mut current = daemon_state.config().as_ref().clone();
+        current.hosts.clone_from(&config.hosts);
+        current.ssl.clone_from(&config.ssl);
+        daemon_state.set_config(current);
+        match utils::server::gen(daemon_state.clone()).await {
+          Ok(server) => server,
+          Err(err) => {
+            err.map_err_context(|| "Http server").print_and_exit();
+          }
+        }
+      }
+    };
   }
   log::info!("main: shutdown");
   Ok(())
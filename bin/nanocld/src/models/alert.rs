@@ -0,0 +1,46 @@
+use diesel::prelude::*;
+
+use nanocl_error::io::IoError;
+use nanocl_stubs::alert::Alert;
+
+use crate::schema::alerts;
+
+/// Pending or firing alert of a rule stored to survive the restarts
+/// and the changes of the node evaluating the rule
+#[derive(Clone, Debug, Queryable, Identifiable, Insertable, AsChangeset)]
+#[diesel(primary_key(rule))]
+#[diesel(table_name = alerts)]
+pub struct AlertDb {
+  /// Name of the alert rule
+  pub rule: String,
+  /// When the alert was last updated
+  pub updated_at: chrono::NaiveDateTime,
+  /// Node evaluating the rule
+  pub node_name: String,
+  /// Status of the alert
+  pub status: String,
+  /// The alert
+  pub data: serde_json::Value,
+}
+
+impl TryFrom<&Alert> for AlertDb {
+  type Error = IoError;
+
+  fn try_from(value: &Alert) -> Result<Self, Self::Error> {
+    Ok(AlertDb {
+      rule: value.rule.clone(),
+      updated_at: chrono::Utc::now().naive_utc(),
+      node_name: value.node_name.clone(),
+      status: value.status.to_string(),
+      data: serde_json::to_value(value)?,
+    })
+  }
+}
+
+impl TryFrom<AlertDb> for Alert {
+  type Error = IoError;
+
+  fn try_from(value: AlertDb) -> Result<Self, Self::Error> {
+    Ok(serde_json::from_value(value.data)?)
+  }
+}
@@ -3,9 +3,15 @@ use std::str::FromStr;
 use diesel::prelude::*;
 
 use nanocl_error::io::IoError;
-use nanocl_stubs::system::{Event, EventKind, EventPartial};
+use nanocl_stubs::{
+  system::{Event, EventKind, EventPartial},
+  webhook::EventDeadLetter,
+};
 
-use crate::schema::events;
+use crate::{
+  schema::{event_dead_letters, events},
+  utils,
+};
 
 #[derive(Debug, Queryable, Identifiable, Insertable)]
 #[diesel(primary_key(key))]
@@ -41,6 +47,9 @@ pub struct EventDb {
   pub related: Option<serde_json::Value>,
   /// Standard metadata.
   pub metadata: Option<serde_json::Value>,
+  /// Cursor of the event given by the `events_seq` sequence of the store
+  #[diesel(skip_insertion)]
+  pub seq: i64,
 }
 
 impl TryFrom<EventPartial> for EventDb {
@@ -61,16 +70,32 @@ impl TryFrom<EventPartial> for EventDb {
       actor: value.actor.map(serde_json::to_value).transpose()?,
       related: value.related.map(serde_json::to_value).transpose()?,
       metadata: value.metadata.map(serde_json::to_value).transpose()?,
+      seq: 0,
     })
   }
 }
 
+/// Create an event kept for a retention in seconds
+impl TryFrom<(EventPartial, u64)> for EventDb {
+  type Error = IoError;
+
+  fn try_from(
+    (value, retention): (EventPartial, u64),
+  ) -> Result<Self, Self::Error> {
+    let mut event = EventDb::try_from(value)?;
+    event.expires_at =
+      utils::store::gen_expires_at(event.created_at, retention);
+    Ok(event)
+  }
+}
+
 impl TryFrom<EventDb> for Event {
   type Error = IoError;
 
   fn try_from(value: EventDb) -> Result<Self, Self::Error> {
     Ok(Event {
       key: value.key,
+      seq: value.seq,
       created_at: value.created_at,
       expires_at: value.expires_at,
       reporting_node: value.reporting_node,
@@ -85,3 +110,43 @@ impl TryFrom<EventDb> for Event {
     })
   }
 }
+
+/// Event that could not be delivered to a `nanocl.io/event-webhook`
+#[derive(Debug, Queryable, Identifiable, Insertable)]
+#[diesel(primary_key(key))]
+#[diesel(table_name = event_dead_letters)]
+pub struct EventDeadLetterDb {
+  /// Unique identifier of the dead letter
+  pub key: uuid::Uuid,
+  /// When the event was dead-lettered
+  pub created_at: chrono::NaiveDateTime,
+  /// Node that tried to deliver the event
+  pub node_name: String,
+  /// Name of the webhook resource
+  pub webhook: String,
+  /// Url of the webhook when the event was sent
+  pub url: String,
+  /// Number of attempts made
+  pub attempts: i64,
+  /// Error of the last attempt
+  pub error: String,
+  /// The event not delivered
+  pub event: serde_json::Value,
+}
+
+impl TryFrom<EventDeadLetterDb> for EventDeadLetter {
+  type Error = IoError;
+
+  fn try_from(value: EventDeadLetterDb) -> Result<Self, Self::Error> {
+    Ok(EventDeadLetter {
+      key: value.key,
+      created_at: value.created_at,
+      node_name: value.node_name,
+      webhook: value.webhook,
+      url: value.url,
+      attempts: value.attempts,
+      error: value.error,
+      event: serde_json::from_value(value.event)?,
+    })
+  }
+}
@@ -0,0 +1,87 @@
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex},
+};
+
+/// Upper bounds in seconds of the buckets of the http latency histograms
+pub const HTTP_DURATION_BUCKETS: [f64; 11] = [
+  0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Latency histogram of an http handler
+#[derive(Clone, Debug, Default)]
+pub struct HttpHistogram {
+  /// Number of requests per bucket, not cumulative
+  pub buckets: [u64; HTTP_DURATION_BUCKETS.len()],
+  /// Number of requests
+  pub count: u64,
+  /// Sum of the durations in seconds
+  pub sum: f64,
+}
+
+impl HttpHistogram {
+  /// Add the requests of another histogram
+  pub fn merge(&mut self, other: &HttpHistogram) {
+    for (bucket, count) in self.buckets.iter_mut().zip(other.buckets) {
+      *bucket += count;
+    }
+    self.count += other.count;
+    self.sum += other.sum;
+  }
+
+  /// Estimate a quantile by interpolation inside the bucket reaching it,
+  /// the durations above the last bound are reported as the last bound
+  pub fn quantile(&self, quantile: f64) -> f64 {
+    if self.count == 0 {
+      return 0.0;
+    }
+    let rank = quantile * self.count as f64;
+    let mut seen = 0.0;
+    let mut lower = 0.0;
+    for (count, upper) in self.buckets.iter().zip(HTTP_DURATION_BUCKETS) {
+      let count = *count as f64;
+      if count > 0.0 && seen + count >= rank {
+        return lower + (upper - lower) * (rank - seen) / count;
+      }
+      seen += count;
+      lower = upper;
+    }
+    lower
+  }
+}
+
+/// Key of an histogram: method, route and status of the response
+pub type HttpMetricKey = (String, String, u16);
+
+/// Latency histograms of the http handlers
+#[derive(Clone, Default)]
+pub struct HttpMetrics {
+  inner: Arc<Mutex<HashMap<HttpMetricKey, HttpHistogram>>>,
+}
+
+impl HttpMetrics {
+  /// Record the duration in seconds of a request
+  pub fn observe(&self, key: HttpMetricKey, duration: f64) {
+    let Ok(mut inner) = self.inner.lock() else {
+      return;
+    };
+    let histogram = inner.entry(key).or_default();
+    if let Some(pos) = HTTP_DURATION_BUCKETS
+      .iter()
+      .position(|bound| duration <= *bound)
+    {
+      histogram.buckets[pos] += 1;
+    }
+    histogram.count += 1;
+    histogram.sum += duration;
+  }
+
+  /// Copy of the histograms
+  pub fn snapshot(&self) -> HashMap<HttpMetricKey, HttpHistogram> {
+    self
+      .inner
+      .lock()
+      .map(|inner| inner.clone())
+      .unwrap_or_default()
+  }
+}
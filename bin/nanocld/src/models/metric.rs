@@ -2,9 +2,11 @@ use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use nanocl_error::io::IoResult;
+use nanocl_error::io::{IoError, IoResult};
 
-use nanocl_stubs::metric::MetricPartial;
+use nanocl_stubs::{
+  config::RetentionConfig, metric::MetricPartial, process::ProcessStatsSummary,
+};
 
 use crate::{schema::metrics, utils};
 
@@ -46,16 +48,26 @@ pub struct MetricNodePartial {
   pub data: serde_json::Value,
   /// Optional note about the metric
   pub note: Option<String>,
+  /// When the metric will expire
+  pub expires_at: chrono::NaiveDateTime,
 }
 
 impl MetricNodePartial {
-  pub fn try_new_node(node_name: &str, item: &MetricPartial) -> IoResult<Self> {
+  pub fn try_new_node(
+    node_name: &str,
+    item: &MetricPartial,
+    retention: &RetentionConfig,
+  ) -> IoResult<Self> {
     utils::key::ensure_kind(&item.kind)?;
     Ok(MetricNodePartial {
       node_name: node_name.to_owned(),
       kind: item.kind.clone(),
       data: item.data.clone(),
       note: item.note.clone(),
+      expires_at: utils::store::gen_expires_at(
+        chrono::Utc::now().naive_utc(),
+        retention.metric(&item.kind),
+      ),
     })
   }
 }
@@ -65,8 +77,7 @@ impl From<&MetricNodePartial> for MetricDb {
     MetricDb {
       key: Uuid::new_v4(),
       created_at: chrono::Utc::now().naive_utc(),
-      expires_at: chrono::Utc::now().naive_utc()
-        + chrono::Duration::try_days(30).unwrap(),
+      expires_at: p.expires_at,
       node_name: p.node_name.clone(),
       kind: p.kind.clone(),
       data: p.data.clone(),
@@ -81,3 +92,168 @@ impl From<&MetricNodePartial> for MetricDb {
 pub struct MetricNodeDb {
   pub node_name: String,
 }
+
+/// Aggregated value of a time bucket computed by the store
+#[derive(Debug, QueryableByName)]
+pub struct MetricPointDb {
+  /// Start of the bucket
+  #[diesel(sql_type = diesel::sql_types::Timestamptz)]
+  pub bucket: chrono::NaiveDateTime,
+  /// Value of the group by column
+  #[diesel(sql_type = diesel::sql_types::Text)]
+  pub series: String,
+  /// Aggregated value
+  #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Double>)]
+  pub value: Option<f64>,
+  /// Number of values in the bucket
+  #[diesel(sql_type = diesel::sql_types::BigInt)]
+  pub count: i64,
+}
+
+/// Bucket of a rollup of the http requests of the proxy
+#[derive(Debug, QueryableByName)]
+pub struct HttpRollupDb {
+  /// Start of the step containing the bucket
+  #[diesel(sql_type = diesel::sql_types::Timestamptz)]
+  pub bucket: chrono::NaiveDateTime,
+  /// Value of the group by dimension
+  #[diesel(sql_type = diesel::sql_types::Text)]
+  pub series: String,
+  #[diesel(sql_type = diesel::sql_types::BigInt)]
+  pub requests: i64,
+  #[diesel(sql_type = diesel::sql_types::BigInt)]
+  pub client_errors: i64,
+  #[diesel(sql_type = diesel::sql_types::BigInt)]
+  pub server_errors: i64,
+  #[diesel(sql_type = diesel::sql_types::BigInt)]
+  pub bytes_sent: i64,
+  /// Sum of the `request_time` in seconds
+  #[diesel(sql_type = diesel::sql_types::Double)]
+  pub latency_sum: f64,
+  /// Number of requests by bucket of `HTTP_DURATION_BUCKETS`, not cumulative
+  #[diesel(sql_type = diesel::sql_types::Array<diesel::sql_types::BigInt>)]
+  pub latency_buckets: Vec<i64>,
+}
+
+/// Resource usage of a process summarized by the store from his samples
+#[derive(Debug, QueryableByName)]
+pub struct ProcessStatsSummaryDb {
+  #[diesel(sql_type = diesel::sql_types::Text)]
+  pub name: String,
+  #[diesel(sql_type = diesel::sql_types::BigInt)]
+  pub samples: i64,
+  #[diesel(sql_type = diesel::sql_types::Timestamptz)]
+  pub first_sample: chrono::NaiveDateTime,
+  #[diesel(sql_type = diesel::sql_types::Timestamptz)]
+  pub last_sample: chrono::NaiveDateTime,
+  #[diesel(sql_type = diesel::sql_types::Double)]
+  pub cpu_avg: f64,
+  #[diesel(sql_type = diesel::sql_types::Double)]
+  pub cpu_p95: f64,
+  #[diesel(sql_type = diesel::sql_types::Double)]
+  pub cpu_max: f64,
+  #[diesel(sql_type = diesel::sql_types::Double)]
+  pub memory_avg: f64,
+  #[diesel(sql_type = diesel::sql_types::Double)]
+  pub memory_p95: f64,
+  #[diesel(sql_type = diesel::sql_types::Double)]
+  pub memory_max: f64,
+  #[diesel(sql_type = diesel::sql_types::Double)]
+  pub memory_limit: f64,
+}
+
+impl From<ProcessStatsSummaryDb> for ProcessStatsSummary {
+  fn from(db: ProcessStatsSummaryDb) -> Self {
+    ProcessStatsSummary {
+      name: db.name,
+      samples: db.samples,
+      first_sample: db.first_sample,
+      last_sample: db.last_sample,
+      cpu_avg: db.cpu_avg,
+      cpu_p95: db.cpu_p95,
+      cpu_max: db.cpu_max,
+      memory_avg: db.memory_avg,
+      memory_p95: db.memory_p95,
+      memory_max: db.memory_max,
+      memory_limit: db.memory_limit,
+    }
+  }
+}
+
+/// Path of a value in the data of a metric eg: `Cpus.*.Usage`
+/// A `*` expand the array at the prefix and read the suffix in each element
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MetricField {
+  /// The path as given in the query
+  pub name: String,
+  /// Path of the array to expand or of the value without `*`
+  pub prefix: Vec<String>,
+  /// Path of the value in the elements of the array
+  pub suffix: Vec<String>,
+  /// Whether the prefix is an array to expand
+  pub is_array: bool,
+}
+
+impl std::str::FromStr for MetricField {
+  type Err = IoError;
+
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    let mut prefix = Vec::new();
+    let mut suffix = Vec::new();
+    let mut is_array = false;
+    for segment in name.split('.') {
+      if segment == "*" {
+        if is_array {
+          return Err(IoError::invalid_input(
+            "Metric field",
+            "only one * is allowed",
+          ));
+        }
+        is_array = true;
+        continue;
+      }
+      if segment.is_empty()
+        || !segment
+          .chars()
+          .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+      {
+        return Err(IoError::invalid_input(
+          "Metric field",
+          format!("invalid segment {segment:?} in {name}").as_str(),
+        ));
+      }
+      if is_array {
+        suffix.push(segment.to_owned());
+      } else {
+        prefix.push(segment.to_owned());
+      }
+    }
+    if prefix.is_empty() {
+      return Err(IoError::invalid_input(
+        "Metric field",
+        "must start with a key",
+      ));
+    }
+    Ok(Self {
+      name: name.to_owned(),
+      prefix,
+      suffix,
+      is_array,
+    })
+  }
+}
+
+/// Time range, size of the buckets and filters of a time series
+#[derive(Clone, Debug)]
+pub struct MetricRange {
+  /// Start of the range included
+  pub since: chrono::NaiveDateTime,
+  /// End of the range excluded
+  pub until: chrono::NaiveDateTime,
+  /// Duration of a bucket in seconds
+  pub step: i64,
+  /// Only the metrics of this kind
+  pub kind: Option<String>,
+  /// Only the metrics of this node
+  pub node_name: Option<String>,
+}
@@ -53,6 +53,15 @@ pub use raw_emitter::*;
 mod task_manager;
 pub use task_manager::*;
 
+mod http_metric;
+pub use http_metric::*;
+
+mod task;
+pub use task::*;
+
+mod alert;
+pub use alert::*;
+
 mod object_process_status;
 pub use object_process_status::*;
 
@@ -1,7 +1,13 @@
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use nanocl_stubs::namespace::{Namespace, NamespacePartial};
+use nanocl_stubs::{
+  namespace::{
+    Namespace, NamespaceLimitRange, NamespaceNetwork, NamespacePartial,
+    NamespaceQuota,
+  },
+  system::ObjPsStatusKind,
+};
 
 use crate::schema::namespaces;
 
@@ -21,6 +27,22 @@ pub struct NamespaceDb {
   pub created_at: chrono::NaiveDateTime,
   /// User defined metadata
   pub metadata: Option<serde_json::Value>,
+  /// Network settings
+  pub network: Option<serde_json::Value>,
+  /// Quotas
+  pub quota: Option<serde_json::Value>,
+  /// Limit range of containers
+  pub limit_range: Option<serde_json::Value>,
+  /// Status of the namespace, set during a cascading deletion
+  pub status: Option<String>,
+}
+
+/// This structure is used to update a namespace in the database.
+#[derive(Clone, Debug, Default, AsChangeset)]
+#[diesel(table_name = namespaces)]
+pub struct NamespaceUpdateDb {
+  /// Status of the namespace
+  pub status: Option<String>,
 }
 
 impl NamespaceDb {
@@ -30,8 +52,47 @@ impl NamespaceDb {
       name: name.to_owned(),
       created_at: chrono::Utc::now().naive_utc(),
       metadata: None,
+      network: None,
+      quota: None,
+      limit_range: None,
+      status: None,
     }
   }
+
+  /// Parse the network settings of the namespace
+  pub fn network(&self) -> Option<NamespaceNetwork> {
+    self
+      .network
+      .clone()
+      .and_then(|network| serde_json::from_value(network).ok())
+  }
+
+  /// Parse the quotas of the namespace
+  pub fn quota(&self) -> Option<NamespaceQuota> {
+    self
+      .quota
+      .clone()
+      .and_then(|quota| serde_json::from_value(quota).ok())
+  }
+
+  /// Parse the limit range of the namespace
+  pub fn limit_range(&self) -> Option<NamespaceLimitRange> {
+    self
+      .limit_range
+      .clone()
+      .and_then(|limit_range| serde_json::from_value(limit_range).ok())
+  }
+
+  /// Return true if the namespace is being deleted
+  pub fn is_destroying(&self) -> bool {
+    self.status.clone().unwrap_or_default()
+      == ObjPsStatusKind::Destroying.to_string()
+  }
+
+  /// Return true if the namespace have his own network
+  pub fn is_isolated(&self) -> bool {
+    self.network().map(|n| n.isolated).unwrap_or_default()
+  }
 }
 
 impl From<&NamespacePartial> for NamespaceDb {
@@ -40,16 +101,40 @@ impl From<&NamespacePartial> for NamespaceDb {
       name: p.name.clone(),
       created_at: chrono::Utc::now().naive_utc(),
       metadata: p.metadata.clone(),
+      network: p
+        .network
+        .as_ref()
+        .and_then(|network| serde_json::to_value(network).ok()),
+      quota: p
+        .quota
+        .as_ref()
+        .and_then(|quota| serde_json::to_value(quota).ok()),
+      limit_range: p
+        .limit_range
+        .as_ref()
+        .and_then(|limit_range| serde_json::to_value(limit_range).ok()),
+      status: None,
     }
   }
 }
 
 impl From<NamespaceDb> for Namespace {
   fn from(namespace: NamespaceDb) -> Self {
+    let network = namespace.network();
+    let quota = namespace.quota();
+    let limit_range = namespace.limit_range();
+    let status = namespace
+      .status
+      .as_deref()
+      .and_then(|status| status.parse().ok());
     Self {
       name: namespace.name,
       created_at: namespace.created_at,
       metadata: namespace.metadata,
+      network,
+      quota,
+      limit_range,
+      status,
     }
   }
 }
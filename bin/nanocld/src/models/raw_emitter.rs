@@ -33,19 +33,23 @@ impl Stream for RawEventReceiver {
   }
 }
 
+/// Sender of the events of a client with the conditions to stop the watch,
+/// the number of conditions met and the filter of the events to send
 #[derive(Clone)]
 pub struct RawEventSender(
   pub Sender<Bytes>,
   pub Option<Vec<EventCondition>>,
   pub usize,
+  pub Option<EventCondition>,
 );
 
 impl RawEventSender {
   pub fn new(
     condition: Option<Vec<EventCondition>>,
+    filter: Option<EventCondition>,
   ) -> (Self, RawEventReceiver) {
     let (tx, rx) = channel(100);
-    (Self(tx, condition, 0), RawEventReceiver(rx))
+    (Self(tx, condition, 0, filter), RawEventReceiver(rx))
   }
 }
 
@@ -128,7 +132,9 @@ impl RawEventEmitter {
     let mut new_clients = Vec::new();
     let msg = e.try_to_bytes()?;
     for client in clients {
-      let _ = client.0.try_send(msg.clone());
+      if client.3.as_ref().map(|f| f.matches(e)).unwrap_or(true) {
+        let _ = client.0.try_send(msg.clone());
+      }
       let conditions = client.1.clone().unwrap_or_default();
       if conditions.is_empty() {
         new_clients.push(client);
@@ -150,12 +156,34 @@ impl RawEventEmitter {
     Ok(())
   }
 
-  /// Subscribe to events
+  /// Number of clients subscribed
+  pub fn count(&self) -> usize {
+    self
+      .inner
+      .lock()
+      .map(|inner| inner.clients.len())
+      .unwrap_or_default()
+  }
+
+  /// Send a last event to all clients and disconnect them
+  pub fn close(&self, e: &Event) -> IoResult<()> {
+    let msg = e.try_to_bytes()?;
+    let clients = std::mem::take(&mut self.inner.lock()?.clients);
+    for client in clients {
+      if let Err(err) = client.0.try_send(msg.clone()) {
+        log::warn!("raw_emitter::close: {err}");
+      }
+    }
+    Ok(())
+  }
+
+  /// Subscribe to the events matching the filter
   pub async fn subscribe(
     &self,
     condition: Option<Vec<EventCondition>>,
+    filter: Option<EventCondition>,
   ) -> IoResult<RawEventReceiver> {
-    let (tx, rx) = RawEventSender::new(condition);
+    let (tx, rx) = RawEventSender::new(condition, filter);
     let inner = Arc::clone(&self.inner);
     web::block(move || {
       inner.lock()?.clients.push(tx);
@@ -1,22 +1,53 @@
-use std::sync::Arc;
+use std::{
+  collections::HashMap,
+  sync::{
+    atomic::{AtomicBool, AtomicUsize},
+    Arc, Mutex, RwLock,
+  },
+  time::Instant,
+};
 
 use futures::channel::mpsc;
-use ntex::rt;
+use ntex::{rt, server::Server};
 
-use nanocl_stubs::{config::DaemonConfig, system::Event};
+use nanocl_stubs::{
+  config::DaemonConfig,
+  process::ProcessStatsSample,
+  system::{Event, PruneStats},
+  webhook::{EventWebhookStatus, ResourceEventWebhook},
+};
 
-use super::{Pool, RawEventEmitter, TaskManager};
+use crate::runtime::ContainerRuntime;
+
+use metrsd_client::stubs::MetrsdEvent;
+
+use super::{HttpMetrics, Pool, RawEventEmitter, TaskManager};
+
+/// Event webhook resources read from the store by the dispatch of the events
+#[derive(Clone, Default)]
+pub struct EventWebhookCache {
+  /// Incremented when a resource change to invalidate the webhooks
+  pub version: u64,
+  /// When the webhooks were read with the version at that time
+  pub loaded_at: Option<(Instant, u64)>,
+  /// Webhooks by resource name
+  pub webhooks: Arc<Vec<(String, ResourceEventWebhook)>>,
+}
 
 /// This structure represent the state of the system.
 /// Used to share the state between the different handlers.
-/// It contains the database connection pool, the docker client, the config and the event emitter.
+/// It contains the database connection pool, the container runtime, the config and the event emitter.
 pub struct SystemStateInner {
   /// The database connection pool
   pub pool: Pool,
-  /// The docker client
-  pub docker_api: bollard_next::Docker,
-  /// The config of the daemon
-  pub config: DaemonConfig,
+  /// The container runtime used for the containers, images, networks and volumes
+  pub runtime: Arc<dyn ContainerRuntime>,
+  /// The config of the daemon replaced on reload, read it with `SystemState::config`
+  pub(crate) config: RwLock<Arc<DaemonConfig>>,
+  /// Handle of the http server to restart the listeners on reload
+  pub(crate) server: Mutex<Option<Server>>,
+  /// Hash of the certificates loaded by the http listeners
+  pub(crate) ssl_fingerprint: Mutex<Option<[u8; 32]>>,
   /// Manager of the tasks
   pub task_manager: TaskManager,
   /// Event emitter
@@ -25,6 +56,30 @@ pub struct SystemStateInner {
   pub(crate) event_emitter_raw: RawEventEmitter,
   /// task event loop
   pub(crate) arbiter: rt::Arbiter,
+  /// Set when the daemon is shutting down, mutations are refused
+  pub(crate) shutting_down: AtomicBool,
+  /// Number of events being saved in the background
+  pub(crate) pending_events: AtomicUsize,
+  /// Number of events waiting to be handled by the event loop
+  pub(crate) event_backlog: AtomicUsize,
+  /// Set while the daemon is subscribed to the metrics daemon
+  pub(crate) metrics_subscribed: AtomicBool,
+  /// Last metrics of the node received from the metrics daemon
+  pub(crate) node_metrics: Mutex<Option<MetrsdEvent>>,
+  /// Last resource usage of the processes of the node by process kind
+  pub(crate) process_samples: Mutex<Vec<ProcessStatsSample>>,
+  /// Latency of the http handlers
+  pub http_metrics: HttpMetrics,
+  /// Statistics of the pruning of the events and the metrics
+  pub(crate) prune_stats: Mutex<PruneStats>,
+  /// Deliveries of the event webhooks by webhook name
+  pub(crate) webhooks: Mutex<HashMap<String, EventWebhookStatus>>,
+  /// Event webhook resources, cleared when a resource change
+  pub(crate) webhook_cache: Mutex<EventWebhookCache>,
+  /// Number of webhook deliveries waiting for their next attempt
+  pub(crate) pending_deliveries: AtomicUsize,
+  /// Locks of the namespace quotas held between the check and the save of an object
+  pub(crate) quota_locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
 }
 
 #[derive(Clone)]
@@ -0,0 +1,74 @@
+use std::str::FromStr;
+
+use diesel::prelude::*;
+
+use nanocl_error::io::{FromIo, IoError};
+use nanocl_stubs::{
+  system::EventActor,
+  task::{Task, TaskStatus},
+};
+
+use crate::schema::tasks;
+
+/// Represents a task waiting or running in the database
+/// It is removed once the task is done and resumed at boot otherwise
+#[derive(Clone, Debug, Queryable, Identifiable, Insertable)]
+#[diesel(primary_key(key))]
+#[diesel(table_name = tasks)]
+pub struct TaskDb {
+  /// The unique key of the task
+  pub key: String,
+  /// The created at date
+  pub created_at: chrono::NaiveDateTime,
+  /// The node running the task
+  pub node_name: String,
+  /// The action of the task
+  pub action: String,
+  /// The object the task is about
+  pub actor: serde_json::Value,
+  /// The status of the task
+  pub status: String,
+}
+
+/// Used to update a task
+#[derive(Clone, Debug, Default, AsChangeset)]
+#[diesel(table_name = tasks)]
+pub struct TaskUpdateDb {
+  /// The status of the task
+  pub status: Option<String>,
+}
+
+impl TaskDb {
+  pub fn try_new(
+    key: &str,
+    node_name: &str,
+    action: &str,
+    actor: &EventActor,
+  ) -> Result<Self, IoError> {
+    Ok(TaskDb {
+      key: key.to_owned(),
+      created_at: chrono::Utc::now().naive_utc(),
+      node_name: node_name.to_owned(),
+      action: action.to_owned(),
+      actor: serde_json::to_value(actor)
+        .map_err(|err| err.map_err_context(|| "Task"))?,
+      status: TaskStatus::Pending.to_string(),
+    })
+  }
+}
+
+impl TryFrom<TaskDb> for Task {
+  type Error = IoError;
+
+  fn try_from(value: TaskDb) -> Result<Self, Self::Error> {
+    Ok(Task {
+      key: value.key,
+      created_at: value.created_at,
+      node_name: value.node_name,
+      action: value.action,
+      actor: serde_json::from_value(value.actor)
+        .map_err(|err| err.map_err_context(|| "Task"))?,
+      status: TaskStatus::from_str(&value.status)?,
+    })
+  }
+}
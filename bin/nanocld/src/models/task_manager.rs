@@ -1,19 +1,73 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+  collections::{HashMap, HashSet, VecDeque},
+  sync::Arc,
+};
 
+use futures::channel::oneshot;
 use futures_util::lock::Mutex;
 use ntex::rt;
 
 use nanocl_error::io::IoResult;
 
-use nanocl_stubs::system::NativeEventAction;
+use nanocl_stubs::system::{EventActor, NativeEventAction};
 
+use super::Pool;
+
+/// A task running for an object
 #[derive(Clone)]
 pub struct ObjTask {
+  /// Unique key of the task
+  pub id: String,
   pub kind: NativeEventAction,
   pub fut: Arc<rt::JoinHandle<IoResult<()>>>,
 }
 
-#[derive(Clone, Default)]
+/// A task waiting in the queue of his object
+#[derive(Clone, Debug)]
+pub struct QueuedTask {
+  /// Unique key of the task
+  pub id: String,
+  /// Order of arrival used to run tasks of the same priority in order
+  pub seq: u64,
+  pub action: NativeEventAction,
+  pub actor: EventActor,
+}
+
+/// Tasks of an object, they run one after the other
+#[derive(Default)]
+pub struct TaskQueue {
+  pub running: Option<ObjTask>,
+  pub pending: VecDeque<QueuedTask>,
+  /// Waiting for the tasks of the object to be done,
+  /// they are woken up when the queue is dropped
+  pub waiters: Vec<oneshot::Sender<()>>,
+}
+
+/// Queues of the task manager by object key
+#[derive(Default)]
+pub struct TaskQueues {
+  pub queues: HashMap<String, TaskQueue>,
+  /// Number of tasks running on all queues
+  pub running: usize,
+  /// Sequence of the last queued task
+  pub seq: u64,
+  /// No more tasks are started once the manager is draining
+  pub draining: bool,
+  /// Queues whose running task released his worker to wait for other tasks
+  pub waiting: HashSet<String>,
+}
+
+/// Keep track of the tasks of each object
+/// Tasks of the same object run in order and the number of tasks
+/// running at the same time is limited by `max_workers`.
+/// Tasks are persisted in the store until they are done
+#[derive(Clone)]
 pub struct TaskManager {
-  pub tasks: Arc<Mutex<HashMap<String, ObjTask>>>,
+  pub tasks: Arc<Mutex<TaskQueues>>,
+  /// Maximum number of tasks running at the same time
+  pub max_workers: usize,
+  /// Pool to persist the tasks
+  pub pool: Pool,
+  /// Name of the node running the tasks
+  pub node_name: String,
 }
@@ -42,8 +42,11 @@ impl ObjCreate for CargoDb {
       ));
     }
     let key = utils::key::gen_key(&obj.namespace, &obj.spec.name);
-    let new_spec =
-      SpecDb::try_from_cargo_partial(&key, &obj.version, &obj.spec)?;
+    let mut spec = obj.spec.clone();
+    let _quota =
+      utils::namespace::check_cargo(&obj.namespace, &key, &mut spec, state)
+        .await?;
+    let new_spec = SpecDb::try_from_cargo_partial(&key, &obj.version, &spec)?;
     let spec = SpecDb::create_from(new_spec, &state.inner.pool)
       .await?
       .try_to_cargo_spec()?;
@@ -120,6 +123,15 @@ impl ObjPutByPk for CargoDb {
     obj: &Self::ObjPutIn,
     state: &SystemState,
   ) -> HttpResult<Self::ObjPutOut> {
+    let cargo = CargoDb::transform_read_by_pk(pk, &state.inner.pool).await?;
+    let mut spec = obj.spec.clone();
+    let _quota = utils::namespace::check_cargo(
+      &cargo.namespace_name,
+      pk,
+      &mut spec,
+      state,
+    )
+    .await?;
     let status = ObjPsStatusDb::read_by_pk(pk, &state.inner.pool).await?;
     let new_status = ObjPsStatusUpdate {
       wanted: Some(ObjPsStatusKind::Start.to_string()),
@@ -128,7 +140,7 @@ impl ObjPutByPk for CargoDb {
       prev_actual: Some(status.actual),
     };
     ObjPsStatusDb::update_pk(pk, new_status, &state.inner.pool).await?;
-    CargoDb::update_from_spec(pk, &obj.spec, &obj.version, &state.inner.pool)
+    CargoDb::update_from_spec(pk, &spec, &obj.version, &state.inner.pool)
       .await
       .map_err(HttpError::from)
   }
@@ -255,6 +267,11 @@ impl ObjPatchByPk for CargoDb {
       } else {
         cargo.spec.image_pull_policy
       },
+      build: if obj.spec.build.is_some() {
+        obj.spec.build.clone()
+      } else {
+        cargo.spec.build
+      },
     };
     let obj = &CargoObjPutIn {
       spec,
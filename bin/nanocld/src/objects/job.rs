@@ -20,7 +20,9 @@ impl ObjCreate for JobDb {
     obj: &Self::ObjCreateIn,
     state: &crate::models::SystemState,
   ) -> HttpResult<Self::ObjCreateOut> {
-    let db_model = JobDb::try_from_partial(obj)?;
+    let mut obj = obj.clone();
+    let _quota = utils::namespace::check_job(&mut obj, state).await?;
+    let db_model = JobDb::try_from_partial(&obj)?;
     let status = ObjPsStatusPartial {
       key: obj.name.clone(),
       wanted: ObjPsStatusKind::Create,
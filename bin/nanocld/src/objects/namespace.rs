@@ -1,9 +1,15 @@
 use nanocl_error::http::{HttpError, HttpResult};
-use nanocl_stubs::namespace::{Namespace, NamespaceInspect, NamespacePartial};
+use nanocl_stubs::{
+  namespace::{
+    Namespace, NamespaceDeleteQuery, NamespaceInspect, NamespacePartial,
+  },
+  system::{EventActor, NativeEventAction, ObjPsStatusKind},
+};
 
 use crate::{
-  models::{CargoDb, NamespaceDb, SystemState},
+  models::{CargoDb, NamespaceDb, NamespaceUpdateDb, SystemState, VmDb},
   repositories::generic::*,
+  utils,
 };
 
 use super::generic::*;
@@ -25,10 +31,12 @@ impl ObjCreate for NamespaceDb {
         &obj.name
       )));
     }
-    let item = NamespaceDb::create_from(obj, &state.inner.pool)
-      .await?
-      .into();
-    Ok(item)
+    let item = NamespaceDb::create_from(obj, &state.inner.pool).await?;
+    if let Err(err) = utils::namespace::create_network(&item, state).await {
+      NamespaceDb::del_by_pk(&item.name, &state.inner.pool).await?;
+      return Err(err.into());
+    }
+    Ok(item.into())
   }
 }
 
@@ -49,28 +57,75 @@ impl ObjInspectByPk for NamespaceDb {
         CargoDb::inspect_obj_by_pk(&cargo.spec.cargo_key, state).await?;
       cargoes.push(cargo);
     }
+    let network = if namespace.is_isolated() {
+      utils::namespace::inspect_network(&namespace.name, state).await
+    } else {
+      None
+    };
     Ok(NamespaceInspect {
       name: namespace.name,
       cargoes,
+      network,
     })
   }
 }
 
 impl ObjDelByPk for NamespaceDb {
-  type ObjDelOpts = ();
+  type ObjDelOpts = NamespaceDeleteQuery;
   type ObjDelOut = Namespace;
 
   async fn fn_del_obj_by_pk(
     pk: &str,
-    _opts: &Self::ObjDelOpts,
+    opts: &Self::ObjDelOpts,
     state: &SystemState,
   ) -> HttpResult<Self::ObjDelOut> {
     let item = NamespaceDb::read_by_pk(pk, &state.inner.pool).await?;
-    CargoDb::delete_by_namespace(pk, state).await?;
+    let cascade = opts.cascade.unwrap_or_default();
+    if item.is_destroying() && !cascade {
+      return Err(HttpError::conflict(format!(
+        "Namespace {pk}: is being deleted, use cascade to resume the deletion"
+      )));
+    }
+    // The objects of the namespace are deleted in background by the namespace delete task
+    if cascade {
+      let update = NamespaceUpdateDb {
+        status: Some(ObjPsStatusKind::Destroying.to_string()),
+      };
+      let item = NamespaceDb::update_pk(pk, update, &state.inner.pool).await?;
+      return Ok(item.into());
+    }
+    let cargoes = CargoDb::read_by_namespace(pk, &state.inner.pool).await?;
+    let vms = VmDb::read_by_namespace(pk, &state.inner.pool).await?;
+    let objects =
+      utils::namespace::push_delete_objects(&cargoes, &vms, false, state)
+        .await?;
+    for (task_key, _) in objects {
+      state.inner.task_manager.wait_task(&task_key).await;
+    }
+    utils::namespace::ensure_empty(pk, state).await?;
     NamespaceDb::del_by_pk(pk, &state.inner.pool).await?;
-    if let Err(err) = state.inner.docker_api.remove_network(pk).await {
-      log::error!("Unable to remove network {} got error: {}", pk, err);
+    if item.is_isolated() {
+      utils::namespace::remove_network(pk, state).await;
     }
     Ok(item.into())
   }
+
+  /// A cascading deletion emit a `Destroying` event to start the namespace delete task
+  async fn del_obj_by_pk(
+    pk: &str,
+    opts: &Self::ObjDelOpts,
+    state: &SystemState,
+  ) -> HttpResult<Self::ObjDelOut>
+  where
+    Self::ObjDelOut: Into<EventActor> + Clone,
+  {
+    let obj = Self::fn_del_obj_by_pk(pk, opts, state).await?;
+    let action = if opts.cascade.unwrap_or_default() {
+      NativeEventAction::Destroying
+    } else {
+      Self::get_del_event()
+    };
+    state.emit_normal_native_action_sync(&obj, action).await;
+    Ok(obj)
+  }
 }
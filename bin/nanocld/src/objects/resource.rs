@@ -7,10 +7,26 @@ use nanocl_stubs::{
 use crate::{
   models::{ResourceDb, SpecDb, SystemState},
   repositories::generic::*,
+  utils, vars,
 };
 
 use super::generic::*;
 
+/// Validate the resources of the built-in kinds
+/// against the objects they refer to
+async fn validate(
+  resource: &ResourcePartial,
+  state: &SystemState,
+) -> HttpResult<()> {
+  match resource.kind.as_str() {
+    vars::EVENT_WEBHOOK_KIND => {
+      utils::event_webhook::validate(resource, state).await
+    }
+    vars::ALERT_RULE_KIND => utils::alert::validate(resource, state).await,
+    _ => Ok(()),
+  }
+}
+
 impl ObjCreate for ResourceDb {
   type ObjCreateIn = ResourcePartial;
   type ObjCreateOut = Resource;
@@ -29,6 +45,7 @@ impl ObjCreate for ResourceDb {
       )));
     }
     let obj = ResourceDb::hook_create(obj, &state.inner.pool).await?;
+    validate(&obj, state).await?;
     let resource =
       ResourceDb::create_from_spec(&obj, &state.inner.pool).await?;
     Ok(resource)
@@ -74,6 +91,7 @@ impl ObjPutByPk for ResourceDb {
   ) -> HttpResult<Self::ObjPutOut> {
     ResourceDb::read_by_pk(pk, &state.inner.pool).await?;
     let resource = ResourceDb::hook_create(obj, &state.inner.pool).await?;
+    validate(&resource, state).await?;
     let resource =
       ResourceDb::update_from_spec(&resource, &state.inner.pool).await?;
     Ok(resource)
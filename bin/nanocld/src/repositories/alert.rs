@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+
+use nanocl_error::io::{IoError, IoResult};
+use nanocl_stubs::{alert::Alert, generic::GenericFilter};
+
+use crate::{
+  gen_sql_multiple, gen_sql_order_by, gen_sql_query,
+  models::{AlertDb, ColumnType, Pool},
+  schema::alerts,
+  utils,
+};
+
+use super::generic::*;
+
+impl RepositoryBase for AlertDb {
+  fn get_columns<'a>() -> HashMap<&'a str, (ColumnType, &'a str)> {
+    HashMap::from([
+      ("rule", (ColumnType::Text, "alerts.rule")),
+      ("node_name", (ColumnType::Text, "alerts.node_name")),
+      ("status", (ColumnType::Text, "alerts.status")),
+      ("data", (ColumnType::Json, "alerts.data")),
+      ("updated_at", (ColumnType::Timestamptz, "alerts.updated_at")),
+    ])
+  }
+}
+
+impl RepositoryDelByPk for AlertDb {}
+
+impl RepositoryReadBy for AlertDb {
+  type Output = AlertDb;
+
+  fn get_pk() -> &'static str {
+    "rule"
+  }
+
+  fn gen_read_query(
+    filter: &GenericFilter,
+    is_multiple: bool,
+  ) -> impl diesel::query_dsl::methods::LoadQuery<
+    'static,
+    diesel::PgConnection,
+    Self::Output,
+  > {
+    let mut query = alerts::table.into_boxed();
+    let columns = Self::get_columns();
+    query = gen_sql_query!(query, filter, columns);
+    if let Some(orders) = &filter.order_by {
+      query = gen_sql_order_by!(query, orders, columns);
+    } else {
+      query = query.order(alerts::rule.asc());
+    }
+    if is_multiple {
+      gen_sql_multiple!(query, filter);
+    }
+    query
+  }
+}
+
+impl RepositoryReadByTransform for AlertDb {
+  type NewOutput = Alert;
+
+  fn transform(input: Self::Output) -> IoResult<Self::NewOutput> {
+    Self::NewOutput::try_from(input)
+  }
+}
+
+impl AlertDb {
+  /// Create or replace the stored alert of a rule
+  pub async fn upsert(alert: &Alert, pool: &Pool) -> IoResult<()> {
+    let item = AlertDb::try_from(alert)?;
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      diesel::insert_into(alerts::table)
+        .values(&item)
+        .on_conflict(alerts::rule)
+        .do_update()
+        .set(&item)
+        .execute(&mut conn)
+        .map_err(|err| IoError::interrupted("Alert", &err.to_string()))
+    })
+    .await
+    .map_err(|err| IoError::interrupted("Alert", &err.to_string()))??;
+    Ok(())
+  }
+}
@@ -2,13 +2,14 @@ use std::collections::HashMap;
 
 use diesel::prelude::*;
 
-use nanocl_error::io::IoResult;
-use nanocl_stubs::system::Event;
+use nanocl_error::io::{IoError, IoResult};
+use nanocl_stubs::{system::Event, webhook::EventDeadLetter};
 
 use crate::{
   gen_sql_multiple, gen_sql_order_by, gen_sql_query,
-  models::{ColumnType, EventDb},
-  schema::events,
+  models::{ColumnType, EventDb, EventDeadLetterDb, Pool},
+  schema::{event_dead_letters, events},
+  utils,
 };
 
 use super::generic::*;
@@ -90,3 +91,186 @@ impl RepositoryReadByTransform for EventDb {
     Self::NewOutput::try_from(input)
   }
 }
+
+impl EventDb {
+  /// Creation date of the event of a cursor,
+  /// or of the first event after it when the cursor event was pruned
+  pub async fn read_cursor_date(
+    since: i64,
+    pool: &Pool,
+  ) -> IoResult<Option<chrono::NaiveDateTime>> {
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let map_err = |err: diesel::result::Error| {
+        IoError::interrupted("Event cursor", &err.to_string())
+      };
+      let date = events::table
+        .filter(events::seq.eq(since))
+        .select(events::created_at)
+        .first::<chrono::NaiveDateTime>(&mut conn)
+        .optional()
+        .map_err(map_err)?;
+      if date.is_some() {
+        return Ok(date);
+      }
+      events::table
+        .filter(events::seq.gt(since))
+        .select(diesel::dsl::min(events::created_at))
+        .first::<Option<chrono::NaiveDateTime>>(&mut conn)
+        .map_err(map_err)
+    })
+    .await
+    .map_err(|err| IoError::interrupted("Event cursor", &err.to_string()))?
+  }
+
+  /// Read a batch of events created after a position
+  /// ordered by creation date then sequence
+  pub async fn read_after(
+    created_at: chrono::NaiveDateTime,
+    seq: i64,
+    limit: i64,
+    pool: &Pool,
+  ) -> IoResult<Vec<Event>> {
+    let pool = pool.clone();
+    let events = ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      events::table
+        .filter(
+          events::created_at
+            .gt(created_at)
+            .or(events::created_at.eq(created_at).and(events::seq.gt(seq))),
+        )
+        .order((events::created_at.asc(), events::seq.asc()))
+        .limit(limit)
+        .get_results::<EventDb>(&mut conn)
+        .map_err(|err| {
+          IoError::interrupted("Event read after", &err.to_string())
+        })
+    })
+    .await
+    .map_err(|err| {
+      IoError::interrupted("Event read after", &err.to_string())
+    })??;
+    events.into_iter().map(Event::try_from).collect()
+  }
+
+  /// Delete a batch of events created before a date or expired
+  pub async fn prune(
+    created_before: chrono::NaiveDateTime,
+    batch_size: i64,
+    pool: &Pool,
+  ) -> IoResult<usize> {
+    let pool = pool.clone();
+    let query = diesel::sql_query(
+      "DELETE FROM events WHERE key IN (
+        SELECT key FROM events
+        WHERE created_at < $1 OR expires_at < NOW()
+        LIMIT $2
+      )",
+    )
+    .bind::<diesel::sql_types::Timestamptz, _>(created_before)
+    .bind::<diesel::sql_types::BigInt, _>(batch_size);
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      query
+        .execute(&mut conn)
+        .map_err(|err| IoError::interrupted("Event prune", &err.to_string()))
+    })
+    .await
+    .map_err(|err| IoError::interrupted("Event prune", &err.to_string()))?
+  }
+}
+
+impl RepositoryBase for EventDeadLetterDb {
+  fn get_columns<'a>() -> HashMap<&'a str, (ColumnType, &'a str)> {
+    HashMap::from([
+      ("key", (ColumnType::Uuid, "event_dead_letters.key")),
+      (
+        "node_name",
+        (ColumnType::Text, "event_dead_letters.node_name"),
+      ),
+      ("webhook", (ColumnType::Text, "event_dead_letters.webhook")),
+      ("url", (ColumnType::Text, "event_dead_letters.url")),
+      ("error", (ColumnType::Text, "event_dead_letters.error")),
+      ("event", (ColumnType::Json, "event_dead_letters.event")),
+      (
+        "created_at",
+        (ColumnType::Timestamptz, "event_dead_letters.created_at"),
+      ),
+    ])
+  }
+}
+
+impl RepositoryCreate for EventDeadLetterDb {}
+
+impl RepositoryReadBy for EventDeadLetterDb {
+  type Output = EventDeadLetterDb;
+
+  fn get_pk() -> &'static str {
+    "key"
+  }
+
+  fn gen_read_query(
+    filter: &nanocl_stubs::generic::GenericFilter,
+    is_multiple: bool,
+  ) -> impl diesel::query_dsl::methods::LoadQuery<
+    'static,
+    diesel::PgConnection,
+    Self::Output,
+  >
+  where
+    Self::Output: Sized,
+  {
+    let mut query = event_dead_letters::table.into_boxed();
+    let columns = Self::get_columns();
+    query = gen_sql_query!(query, filter, columns);
+    if let Some(orders) = &filter.order_by {
+      query = gen_sql_order_by!(query, orders, columns);
+    } else {
+      query = query.order(event_dead_letters::created_at.desc());
+    }
+    if is_multiple {
+      gen_sql_multiple!(query, filter);
+    }
+    query
+  }
+}
+
+impl RepositoryReadByTransform for EventDeadLetterDb {
+  type NewOutput = EventDeadLetter;
+
+  fn transform(input: Self::Output) -> IoResult<Self::NewOutput> {
+    Self::NewOutput::try_from(input)
+  }
+}
+
+impl EventDeadLetterDb {
+  /// Delete a batch of dead letters created before a date
+  pub async fn prune(
+    created_before: chrono::NaiveDateTime,
+    batch_size: i64,
+    pool: &Pool,
+  ) -> IoResult<usize> {
+    let pool = pool.clone();
+    let query = diesel::sql_query(
+      "DELETE FROM event_dead_letters WHERE key IN (
+        SELECT key FROM event_dead_letters
+        WHERE created_at < $1
+        LIMIT $2
+      )",
+    )
+    .bind::<diesel::sql_types::Timestamptz, _>(created_before)
+    .bind::<diesel::sql_types::BigInt, _>(batch_size);
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      query.execute(&mut conn).map_err(|err| {
+        IoError::interrupted("Event dead letter prune", &err.to_string())
+      })
+    })
+    .await
+    .map_err(|err| {
+      IoError::interrupted("Event dead letter prune", &err.to_string())
+    })?
+  }
+}
@@ -142,6 +142,7 @@ impl JobDb {
       containers: p.containers.clone(),
       image_pull_secret: p.image_pull_secret.clone(),
       image_pull_policy: p.image_pull_policy.clone(),
+      build: p.build.clone(),
     })
   }
 
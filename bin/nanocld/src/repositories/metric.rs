@@ -1,14 +1,123 @@
-use diesel::{prelude::*, sql_query};
+use diesel::{
+  dsl::{max, min},
+  prelude::*,
+  sql_query,
+  sql_types::{Array, BigInt, Double, Nullable, Text, Timestamptz},
+};
 use nanocl_error::io::{IoError, IoResult};
-use nanocl_stubs::generic::{GenericClause, GenericFilter};
+use nanocl_stubs::{
+  generic::{GenericClause, GenericFilter},
+  metric::{HttpAnalyticsGroupBy, MetricAggregate, MetricGroupBy},
+  process::ProcessKind,
+};
 
 use crate::{
   gen_sql_multiple, gen_sql_order_by, gen_sql_query,
-  models::{ColumnType, MetricDb, MetricNodeDb, NodeDb, Pool},
-  schema::metrics,
+  models::{
+    ColumnType, HttpRollupDb, MetricDb, MetricField, MetricNodeDb,
+    MetricPointDb, MetricRange, NodeDb, Pool, ProcessStatsSummaryDb,
+    HTTP_DURATION_BUCKETS,
+  },
+  schema::{metric_rollups, metrics},
   utils,
 };
 
+/// Numbers can be saved as json strings by the controllers like ncproxy,
+/// only the values matching this pattern are casted
+const NUMBER_PATTERN: &str = "^-?[0-9]+([.][0-9]+)?([eE][-+]?[0-9]+)?$";
+
+/// Aggregation of the values of the raw metrics
+fn gen_aggregate_sql(aggregate: MetricAggregate) -> &'static str {
+  match aggregate {
+    MetricAggregate::Avg => "AVG(value)",
+    MetricAggregate::Min => "MIN(value)",
+    MetricAggregate::Max => "MAX(value)",
+    MetricAggregate::Sum => "SUM(value)",
+    MetricAggregate::P95 => {
+      "percentile_cont(0.95) WITHIN GROUP (ORDER BY value)"
+    }
+  }
+}
+
+/// Expression counting the requests of each bucket of the latency histogram
+fn gen_latency_buckets_sql() -> String {
+  let mut lower = None;
+  let counts = HTTP_DURATION_BUCKETS
+    .iter()
+    .map(|upper| {
+      let count = match lower {
+        None => format!("COUNT(*) FILTER (WHERE latency <= {upper})"),
+        Some(lower) => format!(
+          "COUNT(*) FILTER (WHERE latency > {lower} AND latency <= {upper})"
+        ),
+      };
+      lower = Some(upper);
+      count
+    })
+    .collect::<Vec<_>>();
+  format!("ARRAY[{}]", counts.join(", "))
+}
+
+/// Common table expression `points` with the numeric values of a field
+/// in the metrics of a range with their bucket, node and kind.
+/// Binds: $1 prefix, $2 suffix, $3 step, $4 since, $5 until, $6 kind, $7 node
+fn gen_points_sql(field: &MetricField) -> String {
+  let elements = if field.is_array {
+    "CASE WHEN jsonb_typeof(m.data #> $1) = 'array' \
+      THEN m.data #> $1 ELSE '[]'::JSONB END"
+  } else {
+    "jsonb_build_array(m.data #> $1)"
+  };
+  // The element is wrapped in an object so the path is never empty
+  format!(
+    "WITH samples AS (
+      SELECT
+        to_timestamp(floor(extract(epoch FROM m.created_at) / $3) * $3) AS bucket,
+        m.node_name,
+        m.kind,
+        jsonb_build_object('v', e.elem) #>> $2 AS raw
+      FROM metrics AS m
+      CROSS JOIN jsonb_array_elements({elements}) AS e(elem)
+      WHERE m.created_at >= $4 AND m.created_at < $5
+        AND ($6::VARCHAR IS NULL OR m.kind = $6)
+        AND ($7::VARCHAR IS NULL OR m.node_name = $7)
+    ), points AS (
+      SELECT bucket, node_name, kind, raw::FLOAT8 AS value
+      FROM samples
+      WHERE raw ~ '{NUMBER_PATTERN}'
+    )"
+  )
+}
+
+/// Column of the series for a group by
+fn gen_series_sql(group_by: Option<MetricGroupBy>) -> &'static str {
+  match group_by {
+    None => "''",
+    Some(MetricGroupBy::NodeName) => "node_name",
+    Some(MetricGroupBy::Kind) => "kind",
+  }
+}
+
+/// Run a time series query in a blocking thread
+async fn load_points(
+  query: diesel::query_builder::BoxedSqlQuery<
+    'static,
+    diesel::pg::Pg,
+    diesel::query_builder::SqlQuery,
+  >,
+  pool: &Pool,
+) -> IoResult<Vec<MetricPointDb>> {
+  let pool = pool.clone();
+  ntex::rt::spawn_blocking(move || {
+    let mut conn = utils::store::get_pool_conn(&pool)?;
+    query
+      .load::<MetricPointDb>(&mut conn)
+      .map_err(|err| IoError::interrupted("Metric query", &err.to_string()))
+  })
+  .await
+  .map_err(|err| IoError::interrupted("Metric query", &err.to_string()))?
+}
+
 use super::generic::*;
 
 impl RepositoryBase for MetricDb {
@@ -126,3 +235,472 @@ impl MetricDb {
     NodeDb::read_by(&filter, pool).await
   }
 }
+
+impl MetricDb {
+  /// Aggregate the values of a field in the raw metrics by bucket
+  pub async fn query_raw(
+    field: &MetricField,
+    range: &MetricRange,
+    aggregate: MetricAggregate,
+    group_by: Option<MetricGroupBy>,
+    pool: &Pool,
+  ) -> IoResult<Vec<MetricPointDb>> {
+    let query = format!(
+      "{points}
+      SELECT
+        bucket,
+        {series}::VARCHAR AS series,
+        ({aggregate})::FLOAT8 AS value,
+        COUNT(*)::INT8 AS count
+      FROM points
+      GROUP BY 1, 2
+      ORDER BY 2, 1",
+      points = gen_points_sql(field),
+      series = gen_series_sql(group_by),
+      aggregate = gen_aggregate_sql(aggregate),
+    );
+    let query = sql_query(query)
+      .into_boxed()
+      .bind::<Array<Text>, _>(field.prefix.clone())
+      .bind::<Array<Text>, _>(
+        [vec!["v".to_owned()], field.suffix.clone()].concat(),
+      )
+      .bind::<Double, _>(range.step as f64)
+      .bind::<Timestamptz, _>(range.since)
+      .bind::<Timestamptz, _>(range.until)
+      .bind::<Nullable<Text>, _>(range.kind.clone())
+      .bind::<Nullable<Text>, _>(range.node_name.clone());
+    load_points(query, pool).await
+  }
+
+  /// Aggregate all the values of a field in the raw metrics of a range,
+  /// nothing is returned without values
+  pub async fn aggregate_raw(
+    field: &MetricField,
+    range: &MetricRange,
+    aggregate: MetricAggregate,
+    pool: &Pool,
+  ) -> IoResult<Option<MetricPointDb>> {
+    let query = format!(
+      "{points}
+      SELECT
+        MIN(bucket) AS bucket,
+        ''::VARCHAR AS series,
+        ({aggregate})::FLOAT8 AS value,
+        COUNT(*)::INT8 AS count
+      FROM points
+      HAVING COUNT(*) > 0",
+      points = gen_points_sql(field),
+      aggregate = gen_aggregate_sql(aggregate),
+    );
+    let query = sql_query(query)
+      .into_boxed()
+      .bind::<Array<Text>, _>(field.prefix.clone())
+      .bind::<Array<Text>, _>(
+        [vec!["v".to_owned()], field.suffix.clone()].concat(),
+      )
+      .bind::<Double, _>(range.step as f64)
+      .bind::<Timestamptz, _>(range.since)
+      .bind::<Timestamptz, _>(range.until)
+      .bind::<Nullable<Text>, _>(range.kind.clone())
+      .bind::<Nullable<Text>, _>(range.node_name.clone());
+    Ok(load_points(query, pool).await?.into_iter().next())
+  }
+
+  /// Aggregate the buckets of a rollup in larger buckets,
+  /// the p95 can't be computed from the buckets and is refused
+  pub async fn query_rollup(
+    resolution: i64,
+    field: &MetricField,
+    range: &MetricRange,
+    aggregate: MetricAggregate,
+    group_by: Option<MetricGroupBy>,
+    pool: &Pool,
+  ) -> IoResult<Vec<MetricPointDb>> {
+    let aggregate = match aggregate {
+      MetricAggregate::Avg => "SUM(sum) / SUM(count)",
+      MetricAggregate::Min => "MIN(min)",
+      MetricAggregate::Max => "MAX(max)",
+      MetricAggregate::Sum => "SUM(sum)",
+      MetricAggregate::P95 => {
+        return Err(IoError::invalid_input(
+          "Metric query",
+          "p95 isn't available on rollups, query the raw metrics",
+        ))
+      }
+    };
+    let query = format!(
+      "SELECT
+        to_timestamp(floor(extract(epoch FROM bucket) / $3) * $3) AS bucket,
+        {series}::VARCHAR AS series,
+        ({aggregate})::FLOAT8 AS value,
+        SUM(count)::INT8 AS count
+      FROM metric_rollups
+      WHERE resolution = $1 AND field = $2
+        AND bucket >= $4 AND bucket < $5
+        AND ($6::VARCHAR IS NULL OR kind = $6)
+        AND ($7::VARCHAR IS NULL OR node_name = $7)
+      GROUP BY 1, 2
+      ORDER BY 2, 1",
+      series = gen_series_sql(group_by),
+    );
+    let query = sql_query(query)
+      .into_boxed()
+      .bind::<BigInt, _>(resolution)
+      .bind::<Text, _>(field.name.clone())
+      .bind::<Double, _>(range.step as f64)
+      .bind::<Timestamptz, _>(range.since)
+      .bind::<Timestamptz, _>(range.until)
+      .bind::<Nullable<Text>, _>(range.kind.clone())
+      .bind::<Nullable<Text>, _>(range.node_name.clone());
+    load_points(query, pool).await
+  }
+
+  /// Save the aggregations of a field by bucket of the range in the rollups,
+  /// buckets already saved are replaced
+  pub async fn rollup(
+    field: &MetricField,
+    range: &MetricRange,
+    ttl: i64,
+    pool: &Pool,
+  ) -> IoResult<usize> {
+    let query = format!(
+      "{points}
+      INSERT INTO metric_rollups
+        (resolution, bucket, node_name, kind, field,
+         count, sum, min, max, expires_at)
+      SELECT
+        $8, bucket, node_name, kind, $9,
+        COUNT(*), SUM(value), MIN(value), MAX(value),
+        bucket + $10 * INTERVAL '1 second'
+      FROM points
+      GROUP BY bucket, node_name, kind
+      ON CONFLICT (resolution, field, node_name, kind, bucket) DO UPDATE SET
+        count = excluded.count,
+        sum = excluded.sum,
+        min = excluded.min,
+        max = excluded.max,
+        expires_at = excluded.expires_at",
+      points = gen_points_sql(field),
+    );
+    let query = sql_query(query)
+      .into_boxed()
+      .bind::<Array<Text>, _>(field.prefix.clone())
+      .bind::<Array<Text>, _>(
+        [vec!["v".to_owned()], field.suffix.clone()].concat(),
+      )
+      .bind::<Double, _>(range.step as f64)
+      .bind::<Timestamptz, _>(range.since)
+      .bind::<Timestamptz, _>(range.until)
+      .bind::<Nullable<Text>, _>(range.kind.clone())
+      .bind::<Nullable<Text>, _>(range.node_name.clone())
+      .bind::<BigInt, _>(range.step)
+      .bind::<Text, _>(field.name.clone())
+      .bind::<Double, _>(ttl as f64);
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      query
+        .execute(&mut conn)
+        .map_err(|err| IoError::interrupted("Metric rollup", &err.to_string()))
+    })
+    .await
+    .map_err(|err| IoError::interrupted("Metric rollup", &err.to_string()))?
+  }
+
+  /// Start of the last bucket of a rollup of a node
+  /// or the date of his first metric when nothing was rolled up yet
+  pub async fn rollup_watermark(
+    resolution: i64,
+    node_name: &str,
+    pool: &Pool,
+  ) -> IoResult<Option<chrono::NaiveDateTime>> {
+    let pool = pool.clone();
+    let node_name = node_name.to_owned();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let last = metric_rollups::table
+        .filter(metric_rollups::resolution.eq(resolution))
+        .filter(metric_rollups::node_name.eq(&node_name))
+        .select(max(metric_rollups::bucket))
+        .first::<Option<chrono::NaiveDateTime>>(&mut conn)
+        .map_err(|err| {
+          IoError::interrupted("Metric rollup", &err.to_string())
+        })?;
+      if last.is_some() {
+        return Ok(last);
+      }
+      metrics::table
+        .filter(metrics::node_name.eq(&node_name))
+        .select(min(metrics::created_at))
+        .first::<Option<chrono::NaiveDateTime>>(&mut conn)
+        .map_err(|err| IoError::interrupted("Metric rollup", &err.to_string()))
+    })
+    .await
+    .map_err(|err| IoError::interrupted("Metric rollup", &err.to_string()))?
+  }
+}
+
+impl MetricDb {
+  /// Delete a batch of metrics of a kind created before a date,
+  /// without kind the metrics of the other kinds than `excluded_kinds`
+  /// and the expired ones are deleted
+  pub async fn prune(
+    kind: Option<String>,
+    excluded_kinds: Vec<String>,
+    created_before: chrono::NaiveDateTime,
+    batch_size: i64,
+    pool: &Pool,
+  ) -> IoResult<usize> {
+    // Without kind the metrics without a specific retention are pruned
+    let query = sql_query(
+      "DELETE FROM metrics WHERE key IN (
+        SELECT key FROM metrics
+        WHERE ($1::VARCHAR IS NOT NULL AND kind = $1 AND created_at < $3)
+          OR ($1::VARCHAR IS NULL AND (
+            (kind <> ALL($2) AND created_at < $3) OR expires_at < NOW()
+          ))
+        LIMIT $4
+      )",
+    )
+    .bind::<Nullable<Text>, _>(kind)
+    .bind::<Array<Text>, _>(excluded_kinds)
+    .bind::<Timestamptz, _>(created_before)
+    .bind::<BigInt, _>(batch_size);
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      query
+        .execute(&mut conn)
+        .map_err(|err| IoError::interrupted("Metric prune", &err.to_string()))
+    })
+    .await
+    .map_err(|err| IoError::interrupted("Metric prune", &err.to_string()))?
+  }
+
+  /// Delete a batch of expired buckets of the rollups
+  pub async fn prune_rollups(batch_size: i64, pool: &Pool) -> IoResult<usize> {
+    let query = sql_query(
+      "DELETE FROM metric_rollups
+      WHERE (resolution, field, node_name, kind, bucket) IN (
+        SELECT resolution, field, node_name, kind, bucket
+        FROM metric_rollups
+        WHERE expires_at < NOW()
+        LIMIT $1
+      )",
+    )
+    .bind::<BigInt, _>(batch_size);
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      query
+        .execute(&mut conn)
+        .map_err(|err| IoError::interrupted("Metric prune", &err.to_string()))
+    })
+    .await
+    .map_err(|err| IoError::interrupted("Metric prune", &err.to_string()))?
+  }
+}
+
+impl MetricDb {
+  /// Summarize by process the samples of the resource usage
+  /// of an object created since a date, the key alone is ambiguous
+  /// as a cargo and a vm of a namespace can share a name
+  pub async fn summarize_process_stats(
+    kind: &ProcessKind,
+    kind_key: &str,
+    since: chrono::NaiveDateTime,
+    pool: &Pool,
+  ) -> IoResult<Vec<ProcessStatsSummaryDb>> {
+    let query = sql_query(
+      "SELECT
+        data->>'Name' AS name,
+        COUNT(*) AS samples,
+        MIN(created_at) AS first_sample,
+        MAX(created_at) AS last_sample,
+        AVG((data->>'CpuPercent')::FLOAT8)::FLOAT8 AS cpu_avg,
+        percentile_cont(0.95) WITHIN GROUP (
+          ORDER BY (data->>'CpuPercent')::FLOAT8
+        ) AS cpu_p95,
+        MAX((data->>'CpuPercent')::FLOAT8) AS cpu_max,
+        AVG((data->>'MemoryUsage')::FLOAT8)::FLOAT8 AS memory_avg,
+        percentile_cont(0.95) WITHIN GROUP (
+          ORDER BY (data->>'MemoryUsage')::FLOAT8
+        ) AS memory_p95,
+        MAX((data->>'MemoryUsage')::FLOAT8) AS memory_max,
+        (array_agg((data->>'MemoryLimit')::FLOAT8 ORDER BY created_at DESC))[1]
+          AS memory_limit
+      FROM metrics
+      WHERE kind = $1 AND data->>'Kind' = $2 AND data->>'KindKey' = $3
+        AND created_at >= $4
+      GROUP BY data->>'Name'
+      ORDER BY name",
+    )
+    .bind::<Text, _>(crate::vars::PROCESS_STATS_KIND)
+    .bind::<Text, _>(kind.to_string())
+    .bind::<Text, _>(kind_key.to_owned())
+    .bind::<Timestamptz, _>(since);
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      query
+        .load::<ProcessStatsSummaryDb>(&mut conn)
+        .map_err(|err| {
+          IoError::interrupted("Process stats summary", &err.to_string())
+        })
+    })
+    .await
+    .map_err(|err| {
+      IoError::interrupted("Process stats summary", &err.to_string())
+    })?
+  }
+}
+
+impl MetricDb {
+  /// Save the traffic of the http requests of the proxy by bucket of the range
+  /// in the rollups, buckets already saved are replaced
+  pub async fn rollup_http(
+    range: &MetricRange,
+    ttl: i64,
+    pool: &Pool,
+  ) -> IoResult<usize> {
+    let query = format!(
+      "WITH requests AS (
+        SELECT
+          to_timestamp(floor(extract(epoch FROM created_at) / $1) * $1)
+            AS bucket,
+          node_name,
+          COALESCE(data->>'host', '') AS domain,
+          COALESCE(data->>'location', '') AS location,
+          COALESCE(data->>'upstream_addr', '') AS upstream,
+          CASE WHEN data->>'status' ~ '^[0-9]+$'
+            THEN (data->>'status')::INT8 ELSE 0 END AS status,
+          CASE WHEN data->>'bytes_sent' ~ '^[0-9]+$'
+            THEN (data->>'bytes_sent')::INT8 ELSE 0 END AS bytes_sent,
+          CASE WHEN data->>'request_time' ~ '{NUMBER_PATTERN}'
+            THEN (data->>'request_time')::FLOAT8 ELSE 0 END AS latency
+        FROM metrics
+        WHERE kind = 'ncproxy.io/http'
+          AND created_at >= $2 AND created_at < $3
+          AND ($4::VARCHAR IS NULL OR node_name = $4)
+      )
+      INSERT INTO http_rollups
+        (resolution, bucket, node_name, domain, location, upstream,
+         requests, client_errors, server_errors, bytes_sent,
+         latency_sum, latency_buckets, expires_at)
+      SELECT
+        $5, bucket, node_name, domain, location, upstream,
+        COUNT(*),
+        COUNT(*) FILTER (WHERE status >= 400 AND status < 500),
+        COUNT(*) FILTER (WHERE status >= 500),
+        SUM(bytes_sent),
+        SUM(latency),
+        {latency_buckets},
+        bucket + $6 * INTERVAL '1 second'
+      FROM requests
+      GROUP BY bucket, node_name, domain, location, upstream
+      ON CONFLICT (resolution, node_name, domain, location, upstream, bucket)
+      DO UPDATE SET
+        requests = excluded.requests,
+        client_errors = excluded.client_errors,
+        server_errors = excluded.server_errors,
+        bytes_sent = excluded.bytes_sent,
+        latency_sum = excluded.latency_sum,
+        latency_buckets = excluded.latency_buckets,
+        expires_at = excluded.expires_at",
+      latency_buckets = gen_latency_buckets_sql(),
+    );
+    let query = sql_query(query)
+      .into_boxed()
+      .bind::<Double, _>(range.step as f64)
+      .bind::<Timestamptz, _>(range.since)
+      .bind::<Timestamptz, _>(range.until)
+      .bind::<Nullable<Text>, _>(range.node_name.clone())
+      .bind::<BigInt, _>(range.step)
+      .bind::<Double, _>(ttl as f64);
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      query
+        .execute(&mut conn)
+        .map_err(|err| IoError::interrupted("Http rollup", &err.to_string()))
+    })
+    .await
+    .map_err(|err| IoError::interrupted("Http rollup", &err.to_string()))?
+  }
+
+  /// Buckets of a rollup of the http requests in a range
+  /// with the step containing them and their group by dimension
+  pub async fn query_http_rollup(
+    resolution: i64,
+    range: &MetricRange,
+    domain: Option<String>,
+    group_by: HttpAnalyticsGroupBy,
+    pool: &Pool,
+  ) -> IoResult<Vec<HttpRollupDb>> {
+    let series = match group_by {
+      HttpAnalyticsGroupBy::Domain => "domain",
+      HttpAnalyticsGroupBy::Location => "location",
+      HttpAnalyticsGroupBy::Upstream => "upstream",
+    };
+    let query = format!(
+      "SELECT
+        to_timestamp(floor(extract(epoch FROM bucket) / $2) * $2) AS bucket,
+        {series}::VARCHAR AS series,
+        requests,
+        client_errors,
+        server_errors,
+        bytes_sent,
+        latency_sum,
+        latency_buckets
+      FROM http_rollups
+      WHERE resolution = $1
+        AND bucket >= $3 AND bucket < $4
+        AND ($5::VARCHAR IS NULL OR domain = $5)
+        AND ($6::VARCHAR IS NULL OR node_name = $6)"
+    );
+    let query = sql_query(query)
+      .into_boxed()
+      .bind::<BigInt, _>(resolution)
+      .bind::<Double, _>(range.step as f64)
+      .bind::<Timestamptz, _>(range.since)
+      .bind::<Timestamptz, _>(range.until)
+      .bind::<Nullable<Text>, _>(domain)
+      .bind::<Nullable<Text>, _>(range.node_name.clone());
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      query
+        .load::<HttpRollupDb>(&mut conn)
+        .map_err(|err| IoError::interrupted("Http rollup", &err.to_string()))
+    })
+    .await
+    .map_err(|err| IoError::interrupted("Http rollup", &err.to_string()))?
+  }
+
+  /// Delete a batch of expired buckets of the http rollups
+  pub async fn prune_http_rollups(
+    batch_size: i64,
+    pool: &Pool,
+  ) -> IoResult<usize> {
+    let query = sql_query(
+      "DELETE FROM http_rollups
+      WHERE (resolution, node_name, domain, location, upstream, bucket) IN (
+        SELECT resolution, node_name, domain, location, upstream, bucket
+        FROM http_rollups
+        WHERE expires_at < NOW()
+        LIMIT $1
+      )",
+    )
+    .bind::<BigInt, _>(batch_size);
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      query
+        .execute(&mut conn)
+        .map_err(|err| IoError::interrupted("Metric prune", &err.to_string()))
+    })
+    .await
+    .map_err(|err| IoError::interrupted("Metric prune", &err.to_string()))?
+  }
+}
@@ -1,3 +1,4 @@
+mod alert;
 mod cargo;
 mod event;
 mod job;
@@ -10,6 +11,7 @@ mod resource;
 mod resource_kind;
 mod secret;
 mod spec;
+mod task;
 mod vm;
 mod vm_image;
 
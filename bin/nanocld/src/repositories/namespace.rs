@@ -7,7 +7,9 @@ use nanocl_stubs::{generic::GenericFilter, namespace::NamespaceSummary};
 
 use crate::{
   gen_sql_multiple, gen_sql_order_by, gen_sql_query,
-  models::{CargoDb, ColumnType, NamespaceDb, ProcessDb, SystemState},
+  models::{
+    CargoDb, ColumnType, NamespaceDb, NamespaceUpdateDb, ProcessDb, SystemState,
+  },
   schema::namespaces,
 };
 
@@ -17,6 +19,7 @@ impl RepositoryBase for NamespaceDb {
   fn get_columns<'a>() -> HashMap<&'a str, (ColumnType, &'a str)> {
     HashMap::from([
       ("name", (ColumnType::Text, "namespaces.name")),
+      ("status", (ColumnType::Text, "namespaces.status")),
       (
         "created_at",
         (ColumnType::Timestamptz, "namespaces.created_at"),
@@ -27,6 +30,10 @@ impl RepositoryBase for NamespaceDb {
 
 impl RepositoryCreate for NamespaceDb {}
 
+impl RepositoryUpdate for NamespaceDb {
+  type UpdateItem = NamespaceUpdateDb;
+}
+
 impl RepositoryDelByPk for NamespaceDb {}
 
 impl RepositoryReadBy for NamespaceDb {
@@ -10,7 +10,7 @@ use crate::{
   gen_sql_multiple, gen_sql_order_by, gen_sql_query,
   models::{ColumnType, NodeDb, Pool, SystemState},
   schema::nodes,
-  vars,
+  utils, vars,
 };
 
 use super::generic::*;
@@ -70,6 +70,61 @@ impl RepositoryCountBy for NodeDb {
 }
 
 impl NodeDb {
+  /// Acquire or renew a lease so a single node runs a cluster wide job.
+  /// The lease is taken over by another node once it expires,
+  /// return true when the node holds the lease for the given seconds
+  pub async fn acquire_lease(
+    name: &str,
+    node_name: &str,
+    ttl: u64,
+    pool: &Pool,
+  ) -> IoResult<bool> {
+    let pool = pool.clone();
+    let query = diesel::sql_query(
+      "INSERT INTO node_leases (name, node_name, expires_at)
+      VALUES ($1, $2, NOW() + $3 * INTERVAL '1 second')
+      ON CONFLICT (name) DO UPDATE
+      SET node_name = excluded.node_name, expires_at = excluded.expires_at
+      WHERE node_leases.node_name = excluded.node_name
+      OR node_leases.expires_at < NOW()",
+    )
+    .bind::<diesel::sql_types::Text, _>(name.to_owned())
+    .bind::<diesel::sql_types::Text, _>(node_name.to_owned())
+    .bind::<diesel::sql_types::BigInt, _>(ttl.min(i64::MAX as u64) as i64);
+    let count = ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      query
+        .execute(&mut conn)
+        .map_err(|err| IoError::interrupted("Node lease", &err.to_string()))
+    })
+    .await
+    .map_err(|err| IoError::interrupted("Node lease", &err.to_string()))??;
+    Ok(count > 0)
+  }
+
+  /// Release a lease held by the node so another node can take it right away
+  pub async fn release_lease(
+    name: &str,
+    node_name: &str,
+    pool: &Pool,
+  ) -> IoResult<()> {
+    let pool = pool.clone();
+    let query = diesel::sql_query(
+      "DELETE FROM node_leases WHERE name = $1 AND node_name = $2",
+    )
+    .bind::<diesel::sql_types::Text, _>(name.to_owned())
+    .bind::<diesel::sql_types::Text, _>(node_name.to_owned());
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      query
+        .execute(&mut conn)
+        .map_err(|err| IoError::interrupted("Node lease", &err.to_string()))
+    })
+    .await
+    .map_err(|err| IoError::interrupted("Node lease", &err.to_string()))??;
+    Ok(())
+  }
+
   pub async fn create_if_not_exists(
     node: &NodeDb,
     pool: &Pool,
@@ -82,19 +137,14 @@ impl NodeDb {
 
   pub async fn register(state: &SystemState) -> IoResult<()> {
     let ip_address =
-      state
-        .inner
-        .config
-        .gateway
-        .parse::<IpAddr>()
-        .map_err(|err| {
-          IoError::invalid_data("Invalid gateway", err.to_string().as_str())
-        })?;
+      state.config().gateway.parse::<IpAddr>().map_err(|err| {
+        IoError::invalid_data("Invalid gateway", err.to_string().as_str())
+      })?;
     let ip_address = ipnet::IpNet::from(ip_address);
     let node = NodeDb {
-      name: state.inner.config.hostname.clone(),
+      name: state.config().hostname.clone(),
       ip_address,
-      endpoint: state.inner.config.advertise_addr.clone(),
+      endpoint: state.config().advertise_addr.clone(),
       created_at: chrono::Utc::now().naive_utc(),
       version: vars::VERSION.to_owned(),
       metadata: None,
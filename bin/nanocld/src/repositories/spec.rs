@@ -155,6 +155,7 @@ impl SpecDb {
       replication: p.replication,
       image_pull_secret: p.image_pull_secret,
       image_pull_policy: p.image_pull_policy,
+      build: p.build,
     };
     Ok(spec)
   }
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+
+use nanocl_error::io::IoResult;
+use nanocl_stubs::{generic::GenericFilter, task::Task};
+
+use crate::{
+  gen_sql_multiple, gen_sql_order_by, gen_sql_query,
+  models::{ColumnType, TaskDb, TaskUpdateDb},
+  schema::tasks,
+};
+
+use super::generic::*;
+
+impl RepositoryBase for TaskDb {
+  fn get_columns<'a>() -> HashMap<&'a str, (ColumnType, &'a str)> {
+    HashMap::from([
+      ("key", (ColumnType::Text, "tasks.key")),
+      ("node_name", (ColumnType::Text, "tasks.node_name")),
+      ("action", (ColumnType::Text, "tasks.action")),
+      ("actor", (ColumnType::Json, "tasks.actor")),
+      ("status", (ColumnType::Text, "tasks.status")),
+      ("created_at", (ColumnType::Timestamptz, "tasks.created_at")),
+    ])
+  }
+}
+
+impl RepositoryCreate for TaskDb {}
+
+impl RepositoryDelByPk for TaskDb {}
+
+impl RepositoryUpdate for TaskDb {
+  type UpdateItem = TaskUpdateDb;
+}
+
+impl RepositoryReadBy for TaskDb {
+  type Output = TaskDb;
+
+  fn get_pk() -> &'static str {
+    "key"
+  }
+
+  fn gen_read_query(
+    filter: &GenericFilter,
+    is_multiple: bool,
+  ) -> impl diesel::query_dsl::methods::LoadQuery<
+    'static,
+    diesel::PgConnection,
+    Self::Output,
+  > {
+    let mut query = tasks::table.into_boxed();
+    let columns = Self::get_columns();
+    query = gen_sql_query!(query, filter, columns);
+    if let Some(orders) = &filter.order_by {
+      query = gen_sql_order_by!(query, orders, columns);
+    } else {
+      query = query.order(tasks::created_at.asc());
+    }
+    if is_multiple {
+      gen_sql_multiple!(query, filter);
+    }
+    query
+  }
+}
+
+impl RepositoryCountBy for TaskDb {
+  fn gen_count_query(
+    filter: &GenericFilter,
+  ) -> impl diesel::query_dsl::LoadQuery<'static, diesel::PgConnection, i64> {
+    let mut query = tasks::table.into_boxed();
+    let columns = Self::get_columns();
+    gen_sql_query!(query, filter, columns).count()
+  }
+}
+
+impl RepositoryReadByTransform for TaskDb {
+  type NewOutput = Task;
+
+  fn transform(input: Self::Output) -> IoResult<Self::NewOutput> {
+    Self::NewOutput::try_from(input)
+  }
+}
@@ -0,0 +1,344 @@
+use bollard_next::{
+  auth::DockerCredentials,
+  container::{
+    AttachContainerOptions, AttachContainerResults, Config,
+    CreateContainerOptions, InspectContainerOptions, KillContainerOptions,
+    ListContainersOptions, LogOutput, LogsOptions, RemoveContainerOptions,
+    RenameContainerOptions, RestartContainerOptions, StartContainerOptions,
+    Stats, StatsOptions, StopContainerOptions, WaitContainerOptions,
+  },
+  exec::{
+    CreateExecOptions, CreateExecResults, StartExecOptions, StartExecResults,
+  },
+  image::{
+    CreateImageOptions, ListImagesOptions, RemoveImageOptions, TagImageOptions,
+  },
+  network::{
+    ConnectNetworkOptions, CreateNetworkOptions, DisconnectNetworkOptions,
+    InspectNetworkOptions,
+  },
+  service::{
+    ContainerCreateResponse, ContainerInspectResponse, ContainerSummary,
+    ContainerWaitResponse, CreateImageInfo, EventMessage, ExecInspectResponse,
+    ImageDeleteResponseItem, ImageInspect, ImageSummary, Network,
+    NetworkCreateResponse, SystemInfo, VolumeListResponse,
+  },
+  system::EventsOptions,
+  volume::{ListVolumesOptions, RemoveVolumeOptions},
+  Docker,
+};
+use futures::{FutureExt, StreamExt};
+use nanocl_error::io::{FromIo, IoError, IoResult};
+
+use super::{ContainerRuntime, RuntimeFuture, RuntimeStream};
+
+/// Runtime using the docker api with bollard.
+/// It works with Docker and with Podman through his docker compatible socket.
+#[derive(Clone)]
+pub struct BollardRuntime {
+  docker: Docker,
+}
+
+/// Address of the docker daemon given by the `docker_host` option
+#[derive(Clone, Debug, PartialEq)]
+pub enum DockerHost {
+  /// Path of a unix socket, with or without the `unix://` scheme
+  Unix(String),
+  /// Address of a tcp socket given with the `tcp://` or `http://` scheme
+  Tcp(String),
+}
+
+impl DockerHost {
+  pub fn parse(host: &str) -> IoResult<Self> {
+    if let Some(addr) = ["tcp://", "http://"]
+      .iter()
+      .find_map(|scheme| host.strip_prefix(scheme))
+    {
+      return Ok(Self::Tcp(addr.trim_end_matches('/').to_owned()));
+    }
+    let path = host.strip_prefix("unix://").unwrap_or(host);
+    if path.contains("://") {
+      return Err(IoError::invalid_input(
+        "Docker host",
+        format!(
+          "{host} isn't supported, use a unix socket or a tcp:// address"
+        )
+        .as_str(),
+      ));
+    }
+    Ok(Self::Unix(path.to_owned()))
+  }
+}
+
+impl BollardRuntime {
+  pub fn new(docker: Docker) -> Self {
+    Self { docker }
+  }
+
+  /// Connect to the docker daemon listening on the given host
+  pub fn connect(host: &str) -> IoResult<Self> {
+    let docker = match DockerHost::parse(host)? {
+      DockerHost::Unix(path) => {
+        Docker::connect_with_unix(&path, 120, bollard_next::API_DEFAULT_VERSION)
+      }
+      DockerHost::Tcp(addr) => {
+        Docker::connect_with_http(&addr, 120, bollard_next::API_DEFAULT_VERSION)
+      }
+    }
+    .map_err(|err| err.map_err_context(|| "Docker"))?;
+    Ok(Self::new(docker))
+  }
+}
+
+impl ContainerRuntime for BollardRuntime {
+  fn create_container(
+    &self,
+    options: Option<CreateContainerOptions<String>>,
+    config: Config,
+  ) -> RuntimeFuture<'_, ContainerCreateResponse> {
+    self.docker.create_container(options, config).boxed()
+  }
+
+  fn start_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<StartContainerOptions<String>>,
+  ) -> RuntimeFuture<'a, ()> {
+    self.docker.start_container(name, options).boxed()
+  }
+
+  fn stop_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<StopContainerOptions>,
+  ) -> RuntimeFuture<'a, ()> {
+    self.docker.stop_container(name, options).boxed()
+  }
+
+  fn kill_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<KillContainerOptions<String>>,
+  ) -> RuntimeFuture<'a, ()> {
+    self.docker.kill_container(name, options).boxed()
+  }
+
+  fn restart_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<RestartContainerOptions>,
+  ) -> RuntimeFuture<'a, ()> {
+    self.docker.restart_container(name, options).boxed()
+  }
+
+  fn remove_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<RemoveContainerOptions>,
+  ) -> RuntimeFuture<'a, ()> {
+    self.docker.remove_container(name, options).boxed()
+  }
+
+  fn rename_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: RenameContainerOptions<String>,
+  ) -> RuntimeFuture<'a, ()> {
+    self.docker.rename_container(name, options).boxed()
+  }
+
+  fn inspect_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<InspectContainerOptions>,
+  ) -> RuntimeFuture<'a, ContainerInspectResponse> {
+    self.docker.inspect_container(name, options).boxed()
+  }
+
+  fn list_containers(
+    &self,
+    options: Option<ListContainersOptions<String>>,
+  ) -> RuntimeFuture<'_, Vec<ContainerSummary>> {
+    self.docker.list_containers(options).boxed()
+  }
+
+  fn wait_container(
+    &self,
+    name: &str,
+    options: Option<WaitContainerOptions<String>>,
+  ) -> RuntimeStream<ContainerWaitResponse> {
+    self.docker.wait_container(name, options).boxed()
+  }
+
+  fn logs(
+    &self,
+    name: &str,
+    options: Option<LogsOptions<String>>,
+  ) -> RuntimeStream<LogOutput> {
+    self.docker.logs(name, options).boxed()
+  }
+
+  fn stats(
+    &self,
+    name: &str,
+    options: Option<StatsOptions>,
+  ) -> RuntimeStream<Stats> {
+    self.docker.stats(name, options).boxed()
+  }
+
+  fn events(
+    &self,
+    options: Option<EventsOptions<String>>,
+  ) -> RuntimeStream<EventMessage> {
+    self.docker.events(options).boxed()
+  }
+
+  fn attach_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<AttachContainerOptions<String>>,
+  ) -> RuntimeFuture<'a, AttachContainerResults> {
+    self.docker.attach_container(name, options).boxed()
+  }
+
+  fn create_image(
+    &self,
+    options: Option<CreateImageOptions<'static, String>>,
+    credentials: Option<DockerCredentials>,
+  ) -> RuntimeStream<CreateImageInfo> {
+    self.docker.create_image(options, None, credentials).boxed()
+  }
+
+  fn inspect_image<'a>(
+    &'a self,
+    name: &'a str,
+  ) -> RuntimeFuture<'a, ImageInspect> {
+    self.docker.inspect_image(name).boxed()
+  }
+
+  fn list_images(
+    &self,
+    options: Option<ListImagesOptions<String>>,
+  ) -> RuntimeFuture<'_, Vec<ImageSummary>> {
+    self.docker.list_images(options).boxed()
+  }
+
+  fn remove_image<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<RemoveImageOptions>,
+  ) -> RuntimeFuture<'a, Vec<ImageDeleteResponseItem>> {
+    self.docker.remove_image(name, options, None).boxed()
+  }
+
+  fn tag_image<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<TagImageOptions<String>>,
+  ) -> RuntimeFuture<'a, ()> {
+    self.docker.tag_image(name, options).boxed()
+  }
+
+  fn create_exec<'a>(
+    &'a self,
+    name: &'a str,
+    options: CreateExecOptions,
+  ) -> RuntimeFuture<'a, CreateExecResults> {
+    self.docker.create_exec(name, options).boxed()
+  }
+
+  fn start_exec<'a>(
+    &'a self,
+    id: &'a str,
+    options: Option<StartExecOptions>,
+  ) -> RuntimeFuture<'a, StartExecResults> {
+    self.docker.start_exec(id, options).boxed()
+  }
+
+  fn inspect_exec<'a>(
+    &'a self,
+    id: &'a str,
+  ) -> RuntimeFuture<'a, ExecInspectResponse> {
+    self.docker.inspect_exec(id).boxed()
+  }
+
+  fn create_network(
+    &self,
+    options: CreateNetworkOptions<String>,
+  ) -> RuntimeFuture<'_, NetworkCreateResponse> {
+    self.docker.create_network(options).boxed()
+  }
+
+  fn inspect_network<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<InspectNetworkOptions<String>>,
+  ) -> RuntimeFuture<'a, Network> {
+    self.docker.inspect_network(name, options).boxed()
+  }
+
+  fn connect_network<'a>(
+    &'a self,
+    name: &'a str,
+    options: ConnectNetworkOptions<String>,
+  ) -> RuntimeFuture<'a, ()> {
+    self.docker.connect_network(name, options).boxed()
+  }
+
+  fn disconnect_network<'a>(
+    &'a self,
+    name: &'a str,
+    options: DisconnectNetworkOptions<String>,
+  ) -> RuntimeFuture<'a, ()> {
+    self.docker.disconnect_network(name, options).boxed()
+  }
+
+  fn remove_network<'a>(&'a self, name: &'a str) -> RuntimeFuture<'a, ()> {
+    self.docker.remove_network(name).boxed()
+  }
+
+  fn list_volumes(
+    &self,
+    options: Option<ListVolumesOptions<String>>,
+  ) -> RuntimeFuture<'_, VolumeListResponse> {
+    self.docker.list_volumes(options).boxed()
+  }
+
+  fn remove_volume<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<RemoveVolumeOptions>,
+  ) -> RuntimeFuture<'a, ()> {
+    self.docker.remove_volume(name, options).boxed()
+  }
+
+  fn info(&self) -> RuntimeFuture<'_, SystemInfo> {
+    self.docker.info().boxed()
+  }
+
+  fn ping(&self) -> RuntimeFuture<'_, String> {
+    self.docker.ping().boxed()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn docker_host() {
+    assert_eq!(
+      DockerHost::parse("/var/run/docker.sock").unwrap(),
+      DockerHost::Unix("/var/run/docker.sock".to_owned())
+    );
+    assert_eq!(
+      DockerHost::parse("unix:///run/podman/podman.sock").unwrap(),
+      DockerHost::Unix("/run/podman/podman.sock".to_owned())
+    );
+    assert_eq!(
+      DockerHost::parse("tcp://10.0.0.1:2375").unwrap(),
+      DockerHost::Tcp("10.0.0.1:2375".to_owned())
+    );
+    assert!(DockerHost::parse("ssh://user@host").is_err());
+  }
+}
@@ -0,0 +1,954 @@
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex},
+};
+
+use bollard_next::{
+  auth::DockerCredentials,
+  container::{
+    AttachContainerOptions, AttachContainerResults, Config,
+    CreateContainerOptions, InspectContainerOptions, KillContainerOptions,
+    ListContainersOptions, LogOutput, LogsOptions, RemoveContainerOptions,
+    RenameContainerOptions, RestartContainerOptions, StartContainerOptions,
+    Stats, StatsOptions, StopContainerOptions, WaitContainerOptions,
+  },
+  errors::Error,
+  exec::{
+    CreateExecOptions, CreateExecResults, StartExecOptions, StartExecResults,
+  },
+  image::{
+    CreateImageOptions, ListImagesOptions, RemoveImageOptions, TagImageOptions,
+  },
+  network::{
+    ConnectNetworkOptions, CreateNetworkOptions, DisconnectNetworkOptions,
+    InspectNetworkOptions,
+  },
+  service::{
+    ContainerConfig, ContainerCreateResponse, ContainerInspectResponse,
+    ContainerState, ContainerStateStatusEnum, ContainerSummary,
+    ContainerWaitResponse, CreateImageInfo, EndpointSettings, EventActor,
+    EventMessage, EventMessageTypeEnum, ExecInspectResponse,
+    ImageDeleteResponseItem, ImageInspect, ImageSummary, Network,
+    NetworkContainer, NetworkCreateResponse, NetworkSettings, SystemInfo,
+    VolumeListResponse,
+  },
+  system::EventsOptions,
+  volume::{ListVolumesOptions, RemoveVolumeOptions},
+};
+use futures::{channel::mpsc, future, FutureExt, StreamExt};
+
+use super::{ContainerRuntime, RuntimeFuture, RuntimeStream};
+
+/// In-memory runtime used by the tests to run without a container engine.
+/// Containers don't execute anything, they only change state
+/// and emit the same events as docker would.
+/// Images are registered without being downloaded and execs end right away.
+#[derive(Clone, Default)]
+pub struct FakeRuntime {
+  inner: Arc<Mutex<FakeRuntimeInner>>,
+}
+
+#[derive(Default)]
+struct FakeRuntimeInner {
+  containers: Vec<ContainerInspectResponse>,
+  images: Vec<ImageInspect>,
+  execs: Vec<ExecInspectResponse>,
+  networks: Vec<Network>,
+  subscribers: Vec<mpsc::UnboundedSender<EventMessage>>,
+}
+
+fn not_found(kind: &str, name: &str) -> Error {
+  Error::DockerResponseServerError {
+    status_code: 404,
+    message: format!("No such {kind}: {name}"),
+  }
+}
+
+fn conflict(message: String) -> Error {
+  Error::DockerResponseServerError {
+    status_code: 409,
+    message,
+  }
+}
+
+fn is_container(container: &ContainerInspectResponse, name: &str) -> bool {
+  container.id.as_deref() == Some(name)
+    || container.name.as_deref().map(|n| n.trim_start_matches('/'))
+      == Some(name.trim_start_matches('/'))
+}
+
+fn is_image(image: &ImageInspect, name: &str) -> bool {
+  let name = if name.contains(':') || name.starts_with("sha256") {
+    name.to_owned()
+  } else {
+    format!("{name}:latest")
+  };
+  image.id.as_deref() == Some(name.as_str())
+    || image
+      .repo_tags
+      .as_ref()
+      .map(|tags| tags.contains(&name))
+      .unwrap_or_default()
+}
+
+fn is_network(network: &Network, name: &str) -> bool {
+  network.id.as_deref() == Some(name) || network.name.as_deref() == Some(name)
+}
+
+fn is_running(container: &ContainerInspectResponse) -> bool {
+  container
+    .state
+    .as_ref()
+    .and_then(|state| state.running)
+    .unwrap_or_default()
+}
+
+impl FakeRuntimeInner {
+  fn find_mut(
+    &mut self,
+    name: &str,
+  ) -> Result<&mut ContainerInspectResponse, Error> {
+    self
+      .containers
+      .iter_mut()
+      .find(|container| is_container(container, name))
+      .ok_or_else(|| not_found("container", name))
+  }
+
+  fn find_image_mut(&mut self, name: &str) -> Result<&mut ImageInspect, Error> {
+    self
+      .images
+      .iter_mut()
+      .find(|image| is_image(image, name))
+      .ok_or_else(|| not_found("image", name))
+  }
+
+  fn find_network_mut(&mut self, name: &str) -> Result<&mut Network, Error> {
+    self
+      .networks
+      .iter_mut()
+      .find(|network| is_network(network, name))
+      .ok_or_else(|| not_found("network", name))
+  }
+
+  /// Add or remove a container from a network and his network settings
+  fn set_network(
+    &mut self,
+    network: &str,
+    container: &str,
+    connected: bool,
+  ) -> Result<(), Error> {
+    let network = self.find_network_mut(network)?.clone();
+    let network_name = network.name.clone().unwrap_or_default();
+    let container = self.find_mut(container)?;
+    let id = container.id.clone().unwrap_or_default();
+    let networks = container
+      .network_settings
+      .get_or_insert_with(NetworkSettings::default)
+      .networks
+      .get_or_insert_with(HashMap::new);
+    if connected {
+      networks.insert(
+        network_name.clone(),
+        EndpointSettings {
+          network_id: network.id.clone(),
+          ..Default::default()
+        },
+      );
+    } else {
+      networks.remove(&network_name);
+    }
+    let name = container.name.clone();
+    let network = self.find_network_mut(&network_name)?;
+    let containers = network.containers.get_or_insert_with(HashMap::new);
+    if connected {
+      containers.insert(
+        id,
+        NetworkContainer {
+          name,
+          ..Default::default()
+        },
+      );
+    } else {
+      containers.remove(&id);
+    }
+    Ok(())
+  }
+
+  fn emit(
+    &mut self,
+    container: &ContainerInspectResponse,
+    action: &str,
+    exit_code: Option<i64>,
+  ) {
+    let mut attributes = container
+      .config
+      .as_ref()
+      .and_then(|config| config.labels.clone())
+      .unwrap_or_default();
+    attributes.insert(
+      "name".to_owned(),
+      container
+        .name
+        .clone()
+        .unwrap_or_default()
+        .trim_start_matches('/')
+        .to_owned(),
+    );
+    if let Some(exit_code) = exit_code {
+      attributes.insert("exitCode".to_owned(), exit_code.to_string());
+    }
+    let event = EventMessage {
+      typ: Some(EventMessageTypeEnum::CONTAINER),
+      action: Some(action.to_owned()),
+      actor: Some(EventActor {
+        id: container.id.clone(),
+        attributes: Some(attributes),
+      }),
+      time: Some(chrono::Utc::now().timestamp()),
+      ..Default::default()
+    };
+    self
+      .subscribers
+      .retain(|subscriber| subscriber.unbounded_send(event.clone()).is_ok());
+  }
+
+  /// Change the state of a container and emit the matching events
+  fn set_state(
+    &mut self,
+    name: &str,
+    running: bool,
+    exit_code: i64,
+    actions: &[&str],
+  ) -> Result<(), Error> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let container = self.find_mut(name)?;
+    let state = container.state.get_or_insert_with(Default::default);
+    if running {
+      state.status = Some(ContainerStateStatusEnum::RUNNING);
+      state.started_at = Some(now);
+      state.exit_code = None;
+    } else {
+      state.status = Some(ContainerStateStatusEnum::EXITED);
+      state.finished_at = Some(now);
+      state.exit_code = Some(exit_code);
+    }
+    state.running = Some(running);
+    let container = container.clone();
+    for action in actions {
+      let exit_code = (*action == "die").then_some(exit_code);
+      self.emit(&container, action, exit_code);
+    }
+    Ok(())
+  }
+}
+
+impl FakeRuntime {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Make a running container exit like if his process ended with the given code
+  pub fn exit(&self, name: &str, exit_code: i64) -> Result<(), Error> {
+    let mut inner = self.inner.lock().unwrap();
+    inner.set_state(name, false, exit_code, &["die"])
+  }
+
+  fn subscribe(&self) -> mpsc::UnboundedReceiver<EventMessage> {
+    let (tx, rx) = mpsc::unbounded();
+    self.inner.lock().unwrap().subscribers.push(tx);
+    rx
+  }
+}
+
+impl ContainerRuntime for FakeRuntime {
+  fn create_container(
+    &self,
+    options: Option<CreateContainerOptions<String>>,
+    config: Config,
+  ) -> RuntimeFuture<'_, ContainerCreateResponse> {
+    let mut inner = self.inner.lock().unwrap();
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let name = options
+      .map(|options| options.name)
+      .unwrap_or_else(|| id[..12].to_owned());
+    if inner
+      .containers
+      .iter()
+      .any(|container| is_container(container, &name))
+    {
+      return future::ready(Err(conflict(format!(
+        "Conflict. The container name \"/{name}\" is already in use"
+      ))))
+      .boxed();
+    }
+    let container = ContainerInspectResponse {
+      id: Some(id.clone()),
+      name: Some(format!("/{name}")),
+      created: Some(chrono::Utc::now().to_rfc3339()),
+      image: config.image.clone(),
+      state: Some(ContainerState {
+        status: Some(ContainerStateStatusEnum::CREATED),
+        running: Some(false),
+        ..Default::default()
+      }),
+      host_config: config.host_config.clone(),
+      config: Some(ContainerConfig {
+        hostname: config.hostname,
+        env: config.env,
+        cmd: config.cmd,
+        image: config.image,
+        entrypoint: config.entrypoint,
+        working_dir: config.working_dir,
+        labels: config.labels,
+        tty: config.tty,
+        ..Default::default()
+      }),
+      ..Default::default()
+    };
+    inner.emit(&container, "create", None);
+    inner.containers.push(container);
+    future::ready(Ok(ContainerCreateResponse {
+      id,
+      warnings: Vec::new(),
+    }))
+    .boxed()
+  }
+
+  fn start_container<'a>(
+    &'a self,
+    name: &'a str,
+    _options: Option<StartContainerOptions<String>>,
+  ) -> RuntimeFuture<'a, ()> {
+    let mut inner = self.inner.lock().unwrap();
+    let res = inner.set_state(name, true, 0, &["start"]);
+    future::ready(res).boxed()
+  }
+
+  fn stop_container<'a>(
+    &'a self,
+    name: &'a str,
+    _options: Option<StopContainerOptions>,
+  ) -> RuntimeFuture<'a, ()> {
+    let mut inner = self.inner.lock().unwrap();
+    let res = inner.set_state(name, false, 0, &["die", "stop"]);
+    future::ready(res).boxed()
+  }
+
+  fn kill_container<'a>(
+    &'a self,
+    name: &'a str,
+    _options: Option<KillContainerOptions<String>>,
+  ) -> RuntimeFuture<'a, ()> {
+    let mut inner = self.inner.lock().unwrap();
+    let res = inner.set_state(name, false, 137, &["kill", "die"]);
+    future::ready(res).boxed()
+  }
+
+  fn restart_container<'a>(
+    &'a self,
+    name: &'a str,
+    _options: Option<RestartContainerOptions>,
+  ) -> RuntimeFuture<'a, ()> {
+    let mut inner = self.inner.lock().unwrap();
+    let res = inner.set_state(name, true, 0, &["restart"]);
+    future::ready(res).boxed()
+  }
+
+  fn remove_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<RemoveContainerOptions>,
+  ) -> RuntimeFuture<'a, ()> {
+    let mut inner = self.inner.lock().unwrap();
+    let force = options.map(|options| options.force).unwrap_or_default();
+    let res = match inner.find_mut(name).cloned() {
+      Err(err) => Err(err),
+      Ok(container) if is_running(&container) && !force => Err(conflict(
+        format!("You cannot remove a running container {name}"),
+      )),
+      Ok(container) => {
+        inner.containers.retain(|c| c.id != container.id);
+        inner.emit(&container, "destroy", None);
+        Ok(())
+      }
+    };
+    future::ready(res).boxed()
+  }
+
+  fn rename_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: RenameContainerOptions<String>,
+  ) -> RuntimeFuture<'a, ()> {
+    let mut inner = self.inner.lock().unwrap();
+    let res = inner.find_mut(name).map(|container| {
+      container.name = Some(format!("/{}", options.name));
+      container.clone()
+    });
+    let res = res.map(|container| inner.emit(&container, "rename", None));
+    future::ready(res).boxed()
+  }
+
+  fn inspect_container<'a>(
+    &'a self,
+    name: &'a str,
+    _options: Option<InspectContainerOptions>,
+  ) -> RuntimeFuture<'a, ContainerInspectResponse> {
+    let mut inner = self.inner.lock().unwrap();
+    let res = inner.find_mut(name).cloned();
+    future::ready(res).boxed()
+  }
+
+  fn list_containers(
+    &self,
+    options: Option<ListContainersOptions<String>>,
+  ) -> RuntimeFuture<'_, Vec<ContainerSummary>> {
+    let inner = self.inner.lock().unwrap();
+    let options = options.unwrap_or_default();
+    let labels = options.filters.get("label").cloned().unwrap_or_default();
+    let containers = inner
+      .containers
+      .iter()
+      .filter(|container| options.all || is_running(container))
+      .filter(|container| {
+        let container_labels = container
+          .config
+          .as_ref()
+          .and_then(|config| config.labels.clone())
+          .unwrap_or_default();
+        labels.iter().all(|label| match label.split_once('=') {
+          Some((key, value)) => {
+            container_labels.get(key).map(String::as_str) == Some(value)
+          }
+          None => container_labels.contains_key(label),
+        })
+      })
+      .map(|container| {
+        let state = container.state.clone().unwrap_or_default();
+        ContainerSummary {
+          id: container.id.clone(),
+          names: container.name.clone().map(|name| vec![name]),
+          image: container.image.clone(),
+          labels: container
+            .config
+            .as_ref()
+            .and_then(|config| config.labels.clone()),
+          state: state.status.map(|status| status.to_string()),
+          ..Default::default()
+        }
+      })
+      .collect();
+    future::ready(Ok(containers)).boxed()
+  }
+
+  fn wait_container(
+    &self,
+    name: &str,
+    options: Option<WaitContainerOptions<String>>,
+  ) -> RuntimeStream<ContainerWaitResponse> {
+    let next_exit = options
+      .map(|options| options.condition == "next-exit")
+      .unwrap_or_default();
+    let rx = self.subscribe();
+    let container = self.inner.lock().unwrap().find_mut(name).cloned();
+    let container = match container {
+      Err(err) => {
+        return futures::stream::once(future::ready(Err(err))).boxed()
+      }
+      Ok(container) => container,
+    };
+    let to_response = |exit_code: i64| {
+      if exit_code == 0 {
+        Ok(ContainerWaitResponse {
+          status_code: exit_code,
+          error: None,
+        })
+      } else {
+        Err(Error::DockerContainerWaitError {
+          error: String::new(),
+          code: exit_code,
+        })
+      }
+    };
+    if !is_running(&container) && !next_exit {
+      let exit_code = container
+        .state
+        .and_then(|state| state.exit_code)
+        .unwrap_or_default();
+      return futures::stream::once(future::ready(to_response(exit_code)))
+        .boxed();
+    }
+    rx.filter_map(move |event| {
+      let actor = event.actor.unwrap_or_default();
+      let res =
+        if event.action.as_deref() == Some("die") && actor.id == container.id {
+          let exit_code = actor
+            .attributes
+            .unwrap_or_default()
+            .get("exitCode")
+            .and_then(|code| code.parse::<i64>().ok())
+            .unwrap_or_default();
+          Some(to_response(exit_code))
+        } else {
+          None
+        };
+      future::ready(res)
+    })
+    .take(1)
+    .boxed()
+  }
+
+  fn logs(
+    &self,
+    _name: &str,
+    _options: Option<LogsOptions<String>>,
+  ) -> RuntimeStream<LogOutput> {
+    futures::stream::empty().boxed()
+  }
+
+  fn stats(
+    &self,
+    _name: &str,
+    _options: Option<StatsOptions>,
+  ) -> RuntimeStream<Stats> {
+    futures::stream::empty().boxed()
+  }
+
+  fn events(
+    &self,
+    _options: Option<EventsOptions<String>>,
+  ) -> RuntimeStream<EventMessage> {
+    self.subscribe().map(Ok).boxed()
+  }
+
+  fn attach_container<'a>(
+    &'a self,
+    name: &'a str,
+    _options: Option<AttachContainerOptions<String>>,
+  ) -> RuntimeFuture<'a, AttachContainerResults> {
+    let res = self.inner.lock().unwrap().find_mut(name).map(|_| {
+      AttachContainerResults {
+        output: futures::stream::empty().boxed(),
+        input: Box::pin(tokio::io::sink()),
+      }
+    });
+    future::ready(res).boxed()
+  }
+
+  fn create_image(
+    &self,
+    options: Option<CreateImageOptions<'static, String>>,
+    _credentials: Option<DockerCredentials>,
+  ) -> RuntimeStream<CreateImageInfo> {
+    let mut inner = self.inner.lock().unwrap();
+    let options = options.unwrap_or_default();
+    let tag = if options.tag.is_empty() {
+      "latest"
+    } else {
+      &options.tag
+    };
+    let name = format!("{}:{tag}", options.from_image);
+    if inner.find_image_mut(&name).is_err() {
+      inner.images.push(ImageInspect {
+        id: Some(format!("sha256:{}", uuid::Uuid::new_v4().simple())),
+        repo_tags: Some(vec![name.clone()]),
+        size: Some(0),
+        ..Default::default()
+      });
+    }
+    let info = CreateImageInfo {
+      status: Some(format!("Downloaded newer image for {name}")),
+      ..Default::default()
+    };
+    futures::stream::once(future::ready(Ok(info))).boxed()
+  }
+
+  fn inspect_image<'a>(
+    &'a self,
+    name: &'a str,
+  ) -> RuntimeFuture<'a, ImageInspect> {
+    let res = self.inner.lock().unwrap().find_image_mut(name).cloned();
+    future::ready(res).boxed()
+  }
+
+  fn list_images(
+    &self,
+    _options: Option<ListImagesOptions<String>>,
+  ) -> RuntimeFuture<'_, Vec<ImageSummary>> {
+    let inner = self.inner.lock().unwrap();
+    let images = inner
+      .images
+      .iter()
+      .map(|image| ImageSummary {
+        id: image.id.clone().unwrap_or_default(),
+        repo_tags: image.repo_tags.clone().unwrap_or_default(),
+        size: image.size.unwrap_or_default(),
+        containers: inner
+          .containers
+          .iter()
+          .filter(|container| container.image == image.id)
+          .count() as i64,
+        ..Default::default()
+      })
+      .collect();
+    future::ready(Ok(images)).boxed()
+  }
+
+  fn remove_image<'a>(
+    &'a self,
+    name: &'a str,
+    _options: Option<RemoveImageOptions>,
+  ) -> RuntimeFuture<'a, Vec<ImageDeleteResponseItem>> {
+    let mut inner = self.inner.lock().unwrap();
+    let res = inner.find_image_mut(name).cloned().map(|image| {
+      inner.images.retain(|i| i.id != image.id);
+      vec![ImageDeleteResponseItem {
+        deleted: image.id,
+        ..Default::default()
+      }]
+    });
+    future::ready(res).boxed()
+  }
+
+  fn tag_image<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<TagImageOptions<String>>,
+  ) -> RuntimeFuture<'a, ()> {
+    let mut inner = self.inner.lock().unwrap();
+    let options = options.unwrap_or_default();
+    let tag = if options.tag.is_empty() {
+      "latest"
+    } else {
+      &options.tag
+    };
+    let new_tag = format!("{}:{tag}", options.repo);
+    let res = inner.find_image_mut(name).map(|image| {
+      image.repo_tags.get_or_insert_with(Vec::new).push(new_tag);
+    });
+    future::ready(res).boxed()
+  }
+
+  fn create_exec<'a>(
+    &'a self,
+    name: &'a str,
+    _options: CreateExecOptions,
+  ) -> RuntimeFuture<'a, CreateExecResults> {
+    let mut inner = self.inner.lock().unwrap();
+    let res = inner.find_mut(name).cloned().map(|container| {
+      let id = uuid::Uuid::new_v4().simple().to_string();
+      inner.execs.push(ExecInspectResponse {
+        id: Some(id.clone()),
+        container_id: container.id,
+        running: Some(false),
+        ..Default::default()
+      });
+      CreateExecResults { id }
+    });
+    future::ready(res).boxed()
+  }
+
+  fn start_exec<'a>(
+    &'a self,
+    id: &'a str,
+    options: Option<StartExecOptions>,
+  ) -> RuntimeFuture<'a, StartExecResults> {
+    let mut inner = self.inner.lock().unwrap();
+    let res = match inner
+      .execs
+      .iter_mut()
+      .find(|exec| exec.id.as_deref() == Some(id))
+    {
+      None => Err(not_found("exec instance", id)),
+      Some(exec) => {
+        exec.exit_code = Some(0);
+        let detach = options.map(|options| options.detach).unwrap_or_default();
+        if detach {
+          Ok(StartExecResults::Detached)
+        } else {
+          Ok(StartExecResults::Attached {
+            output: futures::stream::empty().boxed(),
+            input: Box::pin(tokio::io::sink()),
+          })
+        }
+      }
+    };
+    future::ready(res).boxed()
+  }
+
+  fn inspect_exec<'a>(
+    &'a self,
+    id: &'a str,
+  ) -> RuntimeFuture<'a, ExecInspectResponse> {
+    let inner = self.inner.lock().unwrap();
+    let res = inner
+      .execs
+      .iter()
+      .find(|exec| exec.id.as_deref() == Some(id))
+      .cloned()
+      .ok_or_else(|| not_found("exec instance", id));
+    future::ready(res).boxed()
+  }
+
+  fn create_network(
+    &self,
+    options: CreateNetworkOptions<String>,
+  ) -> RuntimeFuture<'_, NetworkCreateResponse> {
+    let mut inner = self.inner.lock().unwrap();
+    if inner.find_network_mut(&options.name).is_ok() {
+      return future::ready(Err(conflict(format!(
+        "network with name {} already exists",
+        options.name
+      ))))
+      .boxed();
+    }
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    inner.networks.push(Network {
+      id: Some(id.clone()),
+      name: Some(options.name),
+      driver: Some(options.driver),
+      ipam: Some(options.ipam),
+      internal: Some(options.internal),
+      attachable: Some(options.attachable),
+      labels: Some(options.labels),
+      containers: Some(HashMap::new()),
+      ..Default::default()
+    });
+    future::ready(Ok(NetworkCreateResponse {
+      id: Some(id),
+      warning: None,
+    }))
+    .boxed()
+  }
+
+  fn inspect_network<'a>(
+    &'a self,
+    name: &'a str,
+    _options: Option<InspectNetworkOptions<String>>,
+  ) -> RuntimeFuture<'a, Network> {
+    let res = self.inner.lock().unwrap().find_network_mut(name).cloned();
+    future::ready(res).boxed()
+  }
+
+  fn connect_network<'a>(
+    &'a self,
+    name: &'a str,
+    options: ConnectNetworkOptions<String>,
+  ) -> RuntimeFuture<'a, ()> {
+    let mut inner = self.inner.lock().unwrap();
+    let res = inner.set_network(name, &options.container, true);
+    future::ready(res).boxed()
+  }
+
+  fn disconnect_network<'a>(
+    &'a self,
+    name: &'a str,
+    options: DisconnectNetworkOptions<String>,
+  ) -> RuntimeFuture<'a, ()> {
+    let mut inner = self.inner.lock().unwrap();
+    let res = inner.set_network(name, &options.container, false);
+    future::ready(res).boxed()
+  }
+
+  fn remove_network<'a>(&'a self, name: &'a str) -> RuntimeFuture<'a, ()> {
+    let mut inner = self.inner.lock().unwrap();
+    let res = match inner.find_network_mut(name).cloned() {
+      Err(err) => Err(err),
+      Ok(network)
+        if !network.containers.clone().unwrap_or_default().is_empty() =>
+      {
+        Err(Error::DockerResponseServerError {
+          status_code: 403,
+          message: format!(
+            "error while removing network: network {name} has active endpoints"
+          ),
+        })
+      }
+      Ok(network) => {
+        inner.networks.retain(|n| n.id != network.id);
+        Ok(())
+      }
+    };
+    future::ready(res).boxed()
+  }
+
+  fn list_volumes(
+    &self,
+    _options: Option<ListVolumesOptions<String>>,
+  ) -> RuntimeFuture<'_, VolumeListResponse> {
+    future::ready(Ok(VolumeListResponse {
+      volumes: Some(Vec::new()),
+      warnings: None,
+    }))
+    .boxed()
+  }
+
+  fn remove_volume<'a>(
+    &'a self,
+    name: &'a str,
+    _options: Option<RemoveVolumeOptions>,
+  ) -> RuntimeFuture<'a, ()> {
+    future::ready(Err(not_found("volume", name))).boxed()
+  }
+
+  fn info(&self) -> RuntimeFuture<'_, SystemInfo> {
+    let inner = self.inner.lock().unwrap();
+    let running = inner.containers.iter().filter(|c| is_running(c)).count();
+    let info = SystemInfo {
+      name: Some("fake".to_owned()),
+      containers: Some(inner.containers.len() as i64),
+      containers_running: Some(running as i64),
+      images: Some(inner.images.len() as i64),
+      ..Default::default()
+    };
+    future::ready(Ok(info)).boxed()
+  }
+
+  fn ping(&self) -> RuntimeFuture<'_, String> {
+    future::ready(Ok("OK".to_owned())).boxed()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[ntex::test]
+  async fn lifecycle() {
+    let runtime = FakeRuntime::new();
+    let mut events = runtime.events(None);
+    let res = runtime
+      .create_container(
+        Some(CreateContainerOptions {
+          name: "fake-test".to_owned(),
+          ..Default::default()
+        }),
+        Config {
+          image: Some("busybox:latest".to_owned()),
+          labels: Some(HashMap::from([(
+            "io.nanocl".to_owned(),
+            "enabled".to_owned(),
+          )])),
+          ..Default::default()
+        },
+      )
+      .await
+      .unwrap();
+    let event = events.next().await.unwrap().unwrap();
+    assert_eq!(event.action.as_deref(), Some("create"));
+    runtime.start_container("fake-test", None).await.unwrap();
+    let inspect = runtime.inspect_container(&res.id, None).await.unwrap();
+    assert!(is_running(&inspect));
+    let filters = HashMap::from([(
+      "label".to_owned(),
+      vec!["io.nanocl=enabled".to_owned()],
+    )]);
+    let containers = runtime
+      .list_containers(Some(ListContainersOptions {
+        filters,
+        ..Default::default()
+      }))
+      .await
+      .unwrap();
+    assert_eq!(containers.len(), 1);
+    assert!(runtime.remove_container(&res.id, None).await.is_err());
+    let wait = runtime.wait_container("fake-test", None);
+    runtime.exit("fake-test", 1).unwrap();
+    let res = wait.collect::<Vec<_>>().await;
+    assert!(matches!(
+      res.as_slice(),
+      [Err(Error::DockerContainerWaitError { code: 1, .. })]
+    ));
+    runtime.remove_container("fake-test", None).await.unwrap();
+    let err = runtime.inspect_container("fake-test", None).await;
+    assert!(matches!(
+      err,
+      Err(Error::DockerResponseServerError {
+        status_code: 404,
+        ..
+      })
+    ));
+  }
+
+  #[ntex::test]
+  async fn images_and_networks() {
+    let runtime = FakeRuntime::new();
+    let res = runtime
+      .create_image(
+        Some(CreateImageOptions {
+          from_image: "busybox".to_owned(),
+          ..Default::default()
+        }),
+        None,
+      )
+      .collect::<Vec<_>>()
+      .await;
+    assert_eq!(res.len(), 1);
+    let image = runtime.inspect_image("busybox").await.unwrap();
+    runtime
+      .tag_image(
+        "busybox:latest",
+        Some(TagImageOptions {
+          repo: "nanocl-busybox".to_owned(),
+          tag: "dev".to_owned(),
+        }),
+      )
+      .await
+      .unwrap();
+    let tagged = runtime.inspect_image("nanocl-busybox:dev").await.unwrap();
+    assert_eq!(tagged.id, image.id);
+    assert_eq!(runtime.list_images(None).await.unwrap().len(), 1);
+    runtime
+      .create_network(CreateNetworkOptions {
+        name: "fake-net".to_owned(),
+        ..Default::default()
+      })
+      .await
+      .unwrap();
+    runtime
+      .create_container(
+        Some(CreateContainerOptions {
+          name: "fake-net-test".to_owned(),
+          ..Default::default()
+        }),
+        Config::default(),
+      )
+      .await
+      .unwrap();
+    runtime
+      .connect_network(
+        "fake-net",
+        ConnectNetworkOptions {
+          container: "fake-net-test".to_owned(),
+          ..Default::default()
+        },
+      )
+      .await
+      .unwrap();
+    let inspect = runtime.inspect_container("fake-net-test", None).await;
+    let networks = inspect
+      .unwrap()
+      .network_settings
+      .and_then(|settings| settings.networks)
+      .unwrap_or_default();
+    assert!(networks.contains_key("fake-net"));
+    assert!(runtime.remove_network("fake-net").await.is_err());
+    runtime
+      .disconnect_network(
+        "fake-net",
+        DisconnectNetworkOptions {
+          container: "fake-net-test".to_owned(),
+          force: true,
+        },
+      )
+      .await
+      .unwrap();
+    runtime.remove_network("fake-net").await.unwrap();
+    runtime
+      .remove_image(&image.id.unwrap(), None)
+      .await
+      .unwrap();
+    assert!(runtime.inspect_image("busybox").await.is_err());
+  }
+}
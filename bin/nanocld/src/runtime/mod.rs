@@ -0,0 +1,248 @@
+use bollard_next::{
+  auth::DockerCredentials,
+  container::{
+    AttachContainerOptions, AttachContainerResults, Config,
+    CreateContainerOptions, InspectContainerOptions, KillContainerOptions,
+    ListContainersOptions, LogOutput, LogsOptions, RemoveContainerOptions,
+    RenameContainerOptions, RestartContainerOptions, StartContainerOptions,
+    Stats, StatsOptions, StopContainerOptions, WaitContainerOptions,
+  },
+  errors::Error,
+  exec::{
+    CreateExecOptions, CreateExecResults, StartExecOptions, StartExecResults,
+  },
+  image::{
+    CreateImageOptions, ListImagesOptions, RemoveImageOptions, TagImageOptions,
+  },
+  network::{
+    ConnectNetworkOptions, CreateNetworkOptions, DisconnectNetworkOptions,
+    InspectNetworkOptions,
+  },
+  service::{
+    ContainerCreateResponse, ContainerInspectResponse, ContainerSummary,
+    ContainerWaitResponse, CreateImageInfo, EventMessage, ExecInspectResponse,
+    ImageDeleteResponseItem, ImageInspect, ImageSummary, Network,
+    NetworkCreateResponse, SystemInfo, VolumeListResponse,
+  },
+  system::EventsOptions,
+  volume::{ListVolumesOptions, RemoveVolumeOptions},
+};
+use futures::{future::BoxFuture, stream::BoxStream};
+
+mod bollard;
+pub use bollard::*;
+
+#[cfg(test)]
+mod fake;
+#[cfg(test)]
+pub use fake::*;
+
+/// Result of a container runtime operation
+pub type RuntimeFuture<'a, T> = BoxFuture<'a, Result<T, Error>>;
+/// Stream of a container runtime operation
+pub type RuntimeStream<T> = BoxStream<'static, Result<T, Error>>;
+
+/// Container engine used to run the processes.
+/// Every container, image, exec, network and volume operation of the daemon goes through it
+/// so we can run against Docker, Podman or an in-memory fake in tests.
+/// Image builds use their own client, see `utils::build`.
+/// The types are the ones of the docker api to keep the implementations close to it.
+pub trait ContainerRuntime: Send + Sync {
+  /// Create a container
+  fn create_container(
+    &self,
+    options: Option<CreateContainerOptions<String>>,
+    config: Config,
+  ) -> RuntimeFuture<'_, ContainerCreateResponse>;
+
+  /// Start a container
+  fn start_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<StartContainerOptions<String>>,
+  ) -> RuntimeFuture<'a, ()>;
+
+  /// Stop a container
+  fn stop_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<StopContainerOptions>,
+  ) -> RuntimeFuture<'a, ()>;
+
+  /// Send a signal to a container
+  fn kill_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<KillContainerOptions<String>>,
+  ) -> RuntimeFuture<'a, ()>;
+
+  /// Restart a container
+  fn restart_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<RestartContainerOptions>,
+  ) -> RuntimeFuture<'a, ()>;
+
+  /// Remove a container
+  fn remove_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<RemoveContainerOptions>,
+  ) -> RuntimeFuture<'a, ()>;
+
+  /// Rename a container
+  fn rename_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: RenameContainerOptions<String>,
+  ) -> RuntimeFuture<'a, ()>;
+
+  /// Inspect a container by his id or name
+  fn inspect_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<InspectContainerOptions>,
+  ) -> RuntimeFuture<'a, ContainerInspectResponse>;
+
+  /// List the containers
+  fn list_containers(
+    &self,
+    options: Option<ListContainersOptions<String>>,
+  ) -> RuntimeFuture<'_, Vec<ContainerSummary>>;
+
+  /// Wait for a container to reach a condition
+  fn wait_container(
+    &self,
+    name: &str,
+    options: Option<WaitContainerOptions<String>>,
+  ) -> RuntimeStream<ContainerWaitResponse>;
+
+  /// Stream the logs of a container
+  fn logs(
+    &self,
+    name: &str,
+    options: Option<LogsOptions<String>>,
+  ) -> RuntimeStream<LogOutput>;
+
+  /// Stream the resource usage of a container
+  fn stats(
+    &self,
+    name: &str,
+    options: Option<StatsOptions>,
+  ) -> RuntimeStream<Stats>;
+
+  /// Stream the events of the runtime
+  fn events(
+    &self,
+    options: Option<EventsOptions<String>>,
+  ) -> RuntimeStream<EventMessage>;
+
+  /// Attach to the input and output of a container
+  fn attach_container<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<AttachContainerOptions<String>>,
+  ) -> RuntimeFuture<'a, AttachContainerResults>;
+
+  /// Pull an image and stream the progress
+  fn create_image(
+    &self,
+    options: Option<CreateImageOptions<'static, String>>,
+    credentials: Option<DockerCredentials>,
+  ) -> RuntimeStream<CreateImageInfo>;
+
+  /// Inspect an image by his id or name
+  fn inspect_image<'a>(
+    &'a self,
+    name: &'a str,
+  ) -> RuntimeFuture<'a, ImageInspect>;
+
+  /// List the images
+  fn list_images(
+    &self,
+    options: Option<ListImagesOptions<String>>,
+  ) -> RuntimeFuture<'_, Vec<ImageSummary>>;
+
+  /// Remove an image
+  fn remove_image<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<RemoveImageOptions>,
+  ) -> RuntimeFuture<'a, Vec<ImageDeleteResponseItem>>;
+
+  /// Tag an image with a new repository and tag
+  fn tag_image<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<TagImageOptions<String>>,
+  ) -> RuntimeFuture<'a, ()>;
+
+  /// Create an exec command in a container
+  fn create_exec<'a>(
+    &'a self,
+    name: &'a str,
+    options: CreateExecOptions,
+  ) -> RuntimeFuture<'a, CreateExecResults>;
+
+  /// Start an exec command
+  fn start_exec<'a>(
+    &'a self,
+    id: &'a str,
+    options: Option<StartExecOptions>,
+  ) -> RuntimeFuture<'a, StartExecResults>;
+
+  /// Inspect an exec command
+  fn inspect_exec<'a>(
+    &'a self,
+    id: &'a str,
+  ) -> RuntimeFuture<'a, ExecInspectResponse>;
+
+  /// Create a network
+  fn create_network(
+    &self,
+    options: CreateNetworkOptions<String>,
+  ) -> RuntimeFuture<'_, NetworkCreateResponse>;
+
+  /// Inspect a network by his id or name
+  fn inspect_network<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<InspectNetworkOptions<String>>,
+  ) -> RuntimeFuture<'a, Network>;
+
+  /// Connect a container to a network
+  fn connect_network<'a>(
+    &'a self,
+    name: &'a str,
+    options: ConnectNetworkOptions<String>,
+  ) -> RuntimeFuture<'a, ()>;
+
+  /// Disconnect a container from a network
+  fn disconnect_network<'a>(
+    &'a self,
+    name: &'a str,
+    options: DisconnectNetworkOptions<String>,
+  ) -> RuntimeFuture<'a, ()>;
+
+  /// Remove a network
+  fn remove_network<'a>(&'a self, name: &'a str) -> RuntimeFuture<'a, ()>;
+
+  /// List the volumes
+  fn list_volumes(
+    &self,
+    options: Option<ListVolumesOptions<String>>,
+  ) -> RuntimeFuture<'_, VolumeListResponse>;
+
+  /// Remove a volume
+  fn remove_volume<'a>(
+    &'a self,
+    name: &'a str,
+    options: Option<RemoveVolumeOptions>,
+  ) -> RuntimeFuture<'a, ()>;
+
+  /// Information about the runtime and his host
+  fn info(&self) -> RuntimeFuture<'_, SystemInfo>;
+
+  /// Check that the runtime is reachable
+  fn ping(&self) -> RuntimeFuture<'_, String>;
+}
@@ -1,5 +1,15 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    alerts (rule) {
+        rule -> Varchar,
+        updated_at -> Timestamptz,
+        node_name -> Varchar,
+        status -> Varchar,
+        data -> Jsonb,
+    }
+}
+
 diesel::table! {
     cargoes (key) {
         key -> Varchar,
@@ -11,6 +21,19 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    event_dead_letters (key) {
+        key -> Uuid,
+        created_at -> Timestamptz,
+        node_name -> Varchar,
+        webhook -> Varchar,
+        url -> Varchar,
+        attempts -> Int8,
+        error -> Varchar,
+        event -> Jsonb,
+    }
+}
+
 diesel::table! {
     events (key) {
         key -> Uuid,
@@ -25,6 +48,25 @@ diesel::table! {
         actor -> Nullable<Jsonb>,
         related -> Nullable<Jsonb>,
         metadata -> Nullable<Jsonb>,
+        seq -> Int8,
+    }
+}
+
+diesel::table! {
+    http_rollups (resolution, node_name, domain, location, upstream, bucket) {
+        resolution -> Int8,
+        bucket -> Timestamptz,
+        node_name -> Varchar,
+        domain -> Varchar,
+        location -> Varchar,
+        upstream -> Varchar,
+        requests -> Int8,
+        client_errors -> Int8,
+        server_errors -> Int8,
+        bytes_sent -> Int8,
+        latency_sum -> Float8,
+        latency_buckets -> Array<Int8>,
+        expires_at -> Timestamptz,
     }
 }
 
@@ -39,6 +81,21 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    metric_rollups (resolution, field, node_name, kind, bucket) {
+        resolution -> Int8,
+        bucket -> Timestamptz,
+        node_name -> Varchar,
+        kind -> Varchar,
+        field -> Varchar,
+        count -> Int8,
+        sum -> Float8,
+        min -> Float8,
+        max -> Float8,
+        expires_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     metrics (key) {
         key -> Uuid,
@@ -56,6 +113,10 @@ diesel::table! {
         name -> Varchar,
         created_at -> Timestamptz,
         metadata -> Nullable<Jsonb>,
+        network -> Nullable<Jsonb>,
+        quota -> Nullable<Jsonb>,
+        limit_range -> Nullable<Jsonb>,
+        status -> Nullable<Varchar>,
     }
 }
 
@@ -73,6 +134,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    node_leases (name) {
+        name -> Varchar,
+        node_name -> Varchar,
+        expires_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     nodes (name) {
         name -> Varchar,
@@ -150,6 +219,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    tasks (key) {
+        key -> Varchar,
+        created_at -> Timestamptz,
+        node_name -> Varchar,
+        action -> Varchar,
+        actor -> Jsonb,
+        status -> Varchar,
+    }
+}
+
 diesel::table! {
     vm_images (name) {
         name -> Varchar,
@@ -184,19 +264,25 @@ diesel::joinable!(node_group_links -> nodes (node_name));
 diesel::joinable!(processes -> nodes (node_name));
 diesel::joinable!(resource_kinds -> specs (spec_key));
 diesel::joinable!(resources -> specs (spec_key));
+diesel::joinable!(tasks -> nodes (node_name));
 diesel::joinable!(vm_images -> nodes (node_name));
 diesel::joinable!(vms -> namespaces (namespace_name));
 diesel::joinable!(vms -> object_process_statuses (status_key));
 diesel::joinable!(vms -> specs (spec_key));
 
 diesel::allow_tables_to_appear_in_same_query!(
+  alerts,
   cargoes,
+  event_dead_letters,
   events,
+  http_rollups,
   jobs,
+  metric_rollups,
   metrics,
   namespaces,
   node_group_links,
   node_groups,
+  node_leases,
   nodes,
   object_process_statuses,
   processes,
@@ -204,6 +290,7 @@ diesel::allow_tables_to_appear_in_same_query!(
   resources,
   secrets,
   specs,
+  tasks,
   vm_images,
   vms,
 );
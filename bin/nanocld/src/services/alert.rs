@@ -0,0 +1,44 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+
+use crate::{models::SystemState, utils};
+
+/// Get the pending and firing alerts of the cluster
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Alerts",
+  path = "/alerts",
+  responses(
+    (status = 200, description = "List of alerts", body = Vec<Alert>),
+  ),
+))]
+#[web::get("/alerts")]
+pub async fn list_alert(
+  state: web::types::State<SystemState>,
+) -> HttpResult<web::HttpResponse> {
+  let alerts = utils::alert::list(&state).await?;
+  Ok(web::HttpResponse::Ok().json(&alerts))
+}
+
+pub fn ntex_config(config: &mut web::ServiceConfig) {
+  config.service(list_alert);
+}
+
+#[cfg(test)]
+mod tests {
+  use ntex::http;
+
+  use nanocl_stubs::alert::Alert;
+
+  use crate::utils::tests::*;
+
+  #[ntex::test]
+  async fn list() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let mut res = client.send_get("/alerts", None::<String>).await;
+    test_status_code!(res.status(), http::StatusCode::OK, "list alerts");
+    let _ = res.json::<Vec<Alert>>().await.unwrap();
+  }
+}
@@ -298,13 +298,21 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
 
 #[cfg(test)]
 mod tests {
+  use std::collections::HashMap;
+
+  use bollard_next::container::ListContainersOptions;
   use ntex::http;
 
   use nanocl_stubs::cargo::{
     Cargo, CargoDeleteQuery, CargoInspect, CargoKillOptions, CargoSummary,
   };
   use nanocl_stubs::cargo_spec::{CargoSpec, CargoSpecPartial};
+  use nanocl_stubs::generic::ImagePullPolicy;
+  use nanocl_stubs::system::{EventActor, EventActorKind, ObjPsStatusKind};
 
+  use crate::models::{ObjPsStatusDb, TaskManager};
+  use crate::repositories::generic::*;
+  use crate::runtime::ContainerRuntime;
   use crate::utils::tests::*;
 
   const ENDPOINT: &str = "/cargoes";
@@ -488,4 +496,166 @@ mod tests {
     ntex::time::sleep(std::time::Duration::from_secs(1)).await;
     system.state.wait_event_loop().await;
   }
+
+  /// Test to start a cargo without docker using the fake runtime
+  #[ntex::test]
+  async fn fake_runtime() {
+    const CARGO_NAME: &str = "fake-runtime-cargo";
+    let (system, runtime) = gen_fake_test_system().await;
+    let client = system.client;
+    let res = client
+      .send_post(
+        ENDPOINT,
+        Some(&CargoSpecPartial {
+          name: CARGO_NAME.to_owned(),
+          container: bollard_next::container::Config {
+            image: Some("fake:1.0".to_owned()),
+            ..Default::default()
+          },
+          image_pull_policy: Some(ImagePullPolicy::Never),
+          ..Default::default()
+        }),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "create");
+    let res = client
+      .send_post(
+        &format!("/processes/cargo/{CARGO_NAME}/start"),
+        None::<String>,
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "start");
+    let filters = HashMap::from([(
+      "label".to_owned(),
+      vec![format!("io.nanocl.c={CARGO_NAME}.global")],
+    )]);
+    let mut containers = Vec::new();
+    for _ in 0..10 {
+      containers = runtime
+        .list_containers(Some(ListContainersOptions {
+          filters: filters.clone(),
+          ..Default::default()
+        }))
+        .await
+        .unwrap();
+      if !containers.is_empty() {
+        break;
+      }
+      ntex::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+    assert_eq!(containers.len(), 1, "Expect the cargo to be running");
+    let res = client
+      .send_delete(
+        &format!("{ENDPOINT}/{CARGO_NAME}"),
+        Some(CargoDeleteQuery {
+          force: Some(true),
+          ..Default::default()
+        }),
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "delete");
+    system.state.wait_event_loop().await;
+  }
+
+  /// Test that a failed update keeps the old instances running
+  #[ntex::test]
+  async fn update_rollback() {
+    const CARGO_NAME: &str = "update-rollback-cargo";
+    let (system, runtime) = gen_fake_test_system().await;
+    let client = system.client;
+    let spec = CargoSpecPartial {
+      name: CARGO_NAME.to_owned(),
+      container: bollard_next::container::Config {
+        image: Some("fake:1.0".to_owned()),
+        ..Default::default()
+      },
+      image_pull_policy: Some(ImagePullPolicy::Never),
+      ..Default::default()
+    };
+    let res = client
+      .send_post(ENDPOINT, Some(&spec), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "create");
+    let res = client
+      .send_post(
+        &format!("/processes/cargo/{CARGO_NAME}/start"),
+        None::<String>,
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "start");
+    let filters = HashMap::from([(
+      "label".to_owned(),
+      vec![format!("io.nanocl.c={CARGO_NAME}.global")],
+    )]);
+    let list_options = Some(ListContainersOptions {
+      all: true,
+      filters,
+      ..Default::default()
+    });
+    let mut containers = Vec::new();
+    for _ in 0..10 {
+      containers = runtime.list_containers(list_options.clone()).await.unwrap();
+      if containers.first().and_then(|c| c.state.as_deref()) == Some("running")
+      {
+        break;
+      }
+      ntex::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+    assert_eq!(containers.len(), 1, "Expect the cargo to be running");
+    let names = containers[0].names.clone();
+    // The image can't be pulled so the creation of the new instances fail
+    let res = client
+      .send_put(
+        &format!("{ENDPOINT}/{CARGO_NAME}"),
+        Some(&CargoSpecPartial {
+          container: bollard_next::container::Config {
+            image: Some("localhost:1/update-rollback:2.0".to_owned()),
+            ..Default::default()
+          },
+          image_pull_policy: Some(ImagePullPolicy::Always),
+          ..spec
+        }),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "update");
+    let key = format!("{CARGO_NAME}.global");
+    for _ in 0..20 {
+      let status = ObjPsStatusDb::read_by_pk(&key, &system.state.inner.pool)
+        .await
+        .unwrap();
+      if status.actual != ObjPsStatusKind::Updating.to_string() {
+        break;
+      }
+      ntex::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+    let task_key = TaskManager::gen_key(&EventActor {
+      key: Some(key),
+      kind: EventActorKind::Cargo,
+      attributes: None,
+    });
+    system.state.inner.task_manager.wait_task(&task_key).await;
+    let containers = runtime.list_containers(list_options).await.unwrap();
+    assert_eq!(containers.len(), 1, "Expect the old instance to be kept");
+    assert_eq!(containers[0].names, names, "Expect the old instance name");
+    assert_eq!(
+      containers[0].state.as_deref(),
+      Some("running"),
+      "Expect the old instance to be running"
+    );
+    let res = client
+      .send_delete(
+        &format!("{ENDPOINT}/{CARGO_NAME}"),
+        Some(CargoDeleteQuery {
+          force: Some(true),
+          ..Default::default()
+        }),
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "delete");
+    system.state.wait_event_loop().await;
+  }
 }
@@ -0,0 +1,213 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+
+use nanocl_stubs::container_image::{
+  ContainerImageBuildQuery, ContainerImageDeleteQuery, ContainerImagePullOpts,
+};
+
+use crate::{models::SystemState, utils};
+
+/// List container images with the cargoes and jobs using them
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "ContainerImages",
+  path = "/images",
+  responses(
+    (status = 200, description = "List of container images", body = [ContainerImageSummary]),
+  ),
+))]
+#[web::get("/images")]
+pub async fn list_images(
+  state: web::types::State<SystemState>,
+) -> HttpResult<web::HttpResponse> {
+  let images = utils::container::image::list(&state).await?;
+  Ok(web::HttpResponse::Ok().json(&images))
+}
+
+/// Pull a container image ahead of time
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  request_body = ContainerImagePullOpts,
+  tag = "ContainerImages",
+  path = "/images/pull",
+  responses(
+    (status = 200, description = "Detailed information about the pulled image", body = ImageInspect),
+    (status = 404, description = "Secret or image is not existing", body = ApiError),
+  ),
+))]
+#[web::post("/images/pull")]
+pub async fn pull_image(
+  state: web::types::State<SystemState>,
+  payload: web::types::Json<ContainerImagePullOpts>,
+) -> HttpResult<web::HttpResponse> {
+  let image = utils::container::image::pull_image(
+    &payload.name,
+    payload.secret.clone(),
+    &state,
+  )
+  .await?;
+  Ok(web::HttpResponse::Ok().json(&image))
+}
+
+/// Build a container image from a tar archive of his context
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  request_body = String,
+  tag = "ContainerImages",
+  path = "/images/build",
+  params(
+    ("name" = String, Query, description = "Repository of the image, the tag is a hash of the context"),
+    ("dockerfile" = Option<String>, Query, description = "Path of the Dockerfile inside the context"),
+    ("args" = Option<String>, Query, description = "Build arguments as a json object"),
+    ("target" = Option<String>, Query, description = "Stage of a multi-stage Dockerfile to build"),
+  ),
+  responses(
+    (status = 200, description = "Stream of the build output", body = ContainerImageBuildStream),
+    (status = 400, description = "Invalid name or build arguments", body = ApiError),
+  ),
+))]
+#[web::post("/images/build")]
+pub async fn build_image(
+  state: web::types::State<SystemState>,
+  qs: web::types::Query<ContainerImageBuildQuery>,
+  payload: web::types::Payload,
+) -> HttpResult<web::HttpResponse> {
+  let rx = utils::container::image::build(&qs, payload, &state).await?;
+  Ok(web::HttpResponse::Ok().streaming(rx))
+}
+
+/// Remove the container images not used by a cargo, a job or a container
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "ContainerImages",
+  path = "/images/prune",
+  responses(
+    (status = 200, description = "Removed images", body = ContainerImagePruneResult),
+  ),
+))]
+#[web::post("/images/prune")]
+pub async fn prune_images(
+  state: web::types::State<SystemState>,
+) -> HttpResult<web::HttpResponse> {
+  let result = utils::container::image::prune(&state).await?;
+  Ok(web::HttpResponse::Ok().json(&result))
+}
+
+/// Get detailed information about a container image
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "ContainerImages",
+  path = "/images/{name}",
+  params(
+    ("name" = String, Path, description = "Name or id of the image"),
+  ),
+  responses(
+    (status = 200, description = "Detailed information about the image", body = ImageInspect),
+    (status = 404, description = "Image is not existing", body = ApiError),
+  ),
+))]
+#[web::get("/images/{name}*")]
+pub async fn inspect_image(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+) -> HttpResult<web::HttpResponse> {
+  let image = state.inner.runtime.inspect_image(&path.1).await?;
+  Ok(web::HttpResponse::Ok().json(&image))
+}
+
+/// Remove a container image not used by a cargo or a job
+#[cfg_attr(feature = "dev", utoipa::path(
+  delete,
+  tag = "ContainerImages",
+  path = "/images/{name}",
+  params(
+    ("name" = String, Path, description = "Name or id of the image"),
+    ("force" = Option<bool>, Query, description = "Remove the image even if it's tagged multiple times or used by stopped containers"),
+  ),
+  responses(
+    (status = 202, description = "Image have been removed"),
+    (status = 404, description = "Image is not existing", body = ApiError),
+    (status = 409, description = "Image is used by a cargo or a job", body = ApiError),
+  ),
+))]
+#[web::delete("/images/{name}*")]
+pub async fn delete_image(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  qs: web::types::Query<ContainerImageDeleteQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let force = qs.force.unwrap_or_default();
+  utils::container::image::remove(&path.1, force, &state).await?;
+  Ok(web::HttpResponse::Accepted().into())
+}
+
+pub fn ntex_config(config: &mut web::ServiceConfig) {
+  config.service(list_images);
+  config.service(pull_image);
+  config.service(build_image);
+  config.service(prune_images);
+  config.service(inspect_image);
+  config.service(delete_image);
+}
+
+#[cfg(test)]
+mod tests {
+  use ntex::http;
+
+  use bollard_next::service::ImageInspect;
+  use nanocl_stubs::container_image::{
+    ContainerImagePullOpts, ContainerImageSummary,
+  };
+
+  use crate::utils::tests::*;
+
+  const ENDPOINT: &str = "/images";
+
+  #[ntex::test]
+  async fn basic() {
+    const IMAGE: &str = "ghcr.io/next-hat/nanocl-get-started:latest";
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let res = client
+      .send_post(
+        &format!("{ENDPOINT}/pull"),
+        Some(&ContainerImagePullOpts {
+          name: IMAGE.to_owned(),
+          secret: None,
+        }),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "pull");
+    let image = TestClient::res_json::<ImageInspect>(res).await;
+    assert!(
+      image
+        .repo_tags
+        .unwrap_or_default()
+        .contains(&IMAGE.to_owned()),
+      "Expect pulled image to be tagged {IMAGE}"
+    );
+    let res = client
+      .send_get(&format!("{ENDPOINT}/{IMAGE}"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "inspect");
+    let res = client.send_get(ENDPOINT, None::<String>).await;
+    test_status_code!(res.status(), http::StatusCode::OK, "list");
+    let images = TestClient::res_json::<Vec<ContainerImageSummary>>(res).await;
+    assert!(
+      images
+        .iter()
+        .any(|image| image.repo_tags.contains(&IMAGE.to_owned())),
+      "Expect {IMAGE} to be listed"
+    );
+    let res = client
+      .send_get(&format!("{ENDPOINT}/not-existing:latest"), None::<String>)
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::NOT_FOUND,
+      "inspect not existing"
+    );
+  }
+}
@@ -1,13 +1,13 @@
 use ntex::web;
 
-use nanocl_error::http::HttpResult;
+use nanocl_error::http::{HttpError, HttpResult};
 use nanocl_stubs::{
   generic::{GenericCount, GenericListQuery},
-  system::EventCondition,
+  system::{EventCondition, EventWatchQuery},
 };
 
 use crate::{
-  models::{EventDb, SystemState},
+  models::{EventDb, EventDeadLetterDb, SystemState},
   repositories::generic::*,
   utils,
 };
@@ -55,29 +55,93 @@ pub async fn inspect_event(
   Ok(web::HttpResponse::Ok().json(&event))
 }
 
+/// Respond with the events of a watch in the transport asked by the request.
+/// The `Last-Event-ID` header of a reconnecting server-sent events client
+/// is used as the `since` cursor
+async fn gen_watch_response(
+  req: web::HttpRequest,
+  qs: EventWatchQuery,
+  condition: Option<Vec<EventCondition>>,
+  state: &SystemState,
+) -> Result<web::HttpResponse, web::Error> {
+  let filter = qs
+    .filter
+    .as_deref()
+    .map(serde_json::from_str::<EventCondition>)
+    .transpose()
+    .map_err(|err| HttpError::bad_request(format!("Invalid filter: {err}")))?;
+  let since = qs.since.or_else(|| {
+    req
+      .headers()
+      .get("Last-Event-ID")
+      .and_then(|id| id.to_str().ok())
+      .and_then(|id| id.parse().ok())
+  });
+  let stream = utils::event::watch(since, filter, condition, state)
+    .await
+    .map_err(HttpError::from)?;
+  utils::stream::respond(
+    req,
+    "application/vdn.nanocl.raw-stream",
+    stream,
+    Some(utils::event::gen_sse_id),
+  )
+  .await
+}
+
 /// Watch on new events of all peer nodes with optional condition to stop the stream
+/// The stored events since the `since` cursor are sent first to resume a watch
+/// and only the events matching the `filter` condition are sent.
+/// The events are sent as server-sent events with `Accept: text/event-stream`,
+/// the events replayed by the overlap of a resume are sent again with the same `Key`
 #[cfg_attr(feature = "dev", utoipa::path(
   post,
   tag = "Events",
   path = "/events/watch",
   request_body = Option<Vec<EventCondition>>,
+  params(
+    ("since" = Option<i64>, Query, description = "Replay the stored events since the event of this cursor minus the overlap, the events sent twice have the same key"),
+    ("filter" = Option<String>, Query, description = "Event condition as json", example = "{ \"Kind\": [\"error\", \"warning\"], \"Namespace\": \"prod\" }"),
+  ),
   responses(
-    (status = 200, description = "Event stream", body = String),
+    (status = 200, description = "Event stream", content_type = ["application/vdn.nanocl.raw-stream", "text/event-stream"], body = String),
   ),
 ))]
 #[web::post("/events/watch")]
 pub async fn watch_event(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
+  qs: web::types::Query<EventWatchQuery>,
   condition: Option<web::types::Json<Vec<EventCondition>>>,
-) -> HttpResult<web::HttpResponse> {
-  let stream = state
-    .subscribe_raw(condition.map(|c| c.into_inner()))
-    .await?;
-  Ok(
-    web::HttpResponse::Ok()
-      .content_type("text/event-stream")
-      .streaming(stream),
-  )
+) -> Result<web::HttpResponse, web::Error> {
+  let condition = condition.map(|c| c.into_inner());
+  gen_watch_response(req, qs.into_inner(), condition, &state).await
+}
+
+/// Watch on new events of all peer nodes from a browser or `curl -N`
+/// Same as the POST without stop conditions, the events are sent
+/// as server-sent events with `Accept: text/event-stream`
+/// or as text messages when the connection is upgraded to a websocket
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Events",
+  path = "/events/watch",
+  params(
+    ("since" = Option<i64>, Query, description = "Replay the stored events since the event of this cursor minus the overlap, the events sent twice have the same key"),
+    ("filter" = Option<String>, Query, description = "Event condition as json", example = "{ \"Kind\": [\"error\", \"warning\"], \"Namespace\": \"prod\" }"),
+  ),
+  responses(
+    (status = 101, description = "Websocket connection"),
+    (status = 200, description = "Event stream", content_type = ["application/vdn.nanocl.raw-stream", "text/event-stream"], body = String),
+  ),
+))]
+#[web::get("/events/watch")]
+pub async fn follow_event(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  qs: web::types::Query<EventWatchQuery>,
+) -> Result<web::HttpResponse, web::Error> {
+  gen_watch_response(req, qs.into_inner(), None, &state).await
 }
 
 /// Count events
@@ -102,26 +166,73 @@ pub async fn count_event(
   Ok(web::HttpResponse::Ok().json(&GenericCount { count }))
 }
 
+/// Get the deliveries of the event webhooks by the node since it started
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Events",
+  path = "/events/webhooks",
+  responses(
+    (status = 200, description = "Deliveries of the webhooks", body = Vec<EventWebhookStatus>),
+  ),
+))]
+#[web::get("/events/webhooks")]
+pub async fn list_event_webhook(
+  state: web::types::State<SystemState>,
+) -> HttpResult<web::HttpResponse> {
+  let webhooks = utils::event_webhook::list_status(&state)?;
+  Ok(web::HttpResponse::Ok().json(&webhooks))
+}
+
+/// Get the events that could not be delivered to their webhook
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Events",
+  path = "/events/dead-letters",
+  params(
+    ("filter" = Option<String>, Query, description = "Generic filter", example = "{ \"filter\": { \"where\": { \"webhook\": { \"eq\": \"my-webhook\" } } } }"),
+  ),
+  responses(
+    (status = 200, description = "List of dead letters", body = Vec<EventDeadLetter>),
+  ),
+))]
+#[web::get("/events/dead-letters")]
+pub async fn list_event_dead_letter(
+  state: web::types::State<SystemState>,
+  qs: web::types::Query<GenericListQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let filter = utils::query_string::parse_qs_filter(&qs)?;
+  let dead_letters =
+    EventDeadLetterDb::transform_read_by(&filter, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Ok().json(&dead_letters))
+}
+
 pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(list_event);
   config.service(watch_event);
+  config.service(follow_event);
   config.service(inspect_event);
   config.service(count_event);
+  config.service(list_event_webhook);
+  config.service(list_event_dead_letter);
 }
 
 #[cfg(test)]
 mod tests {
+  use std::str::FromStr;
+
   use bollard_next::container::Config;
   use futures::{StreamExt, TryStreamExt};
   use nanocl_stubs::{
     cargo_spec::CargoSpecPartial,
     system::{
-      Event, EventActorKind, EventCondition, EventKind, NativeEventAction,
+      Event, EventActorKind, EventCondition, EventKind, EventPartial,
+      EventWatchQuery, NativeEventAction,
     },
+    webhook::{EventDeadLetter, EventWebhookStatus},
   };
-  use ntex::{http, rt};
+  use ntex::{http, rt, ws};
 
-  use crate::utils::tests::*;
+  use crate::{utils::tests::*, vars};
 
   #[ntex::test]
   async fn basic() {
@@ -140,6 +251,20 @@ mod tests {
     resp.json::<Event>().await.unwrap();
   }
 
+  #[ntex::test]
+  async fn webhooks() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let mut res = client.send_get("/events/webhooks", None::<String>).await;
+    test_status_code!(res.status(), http::StatusCode::OK, "list webhooks");
+    let _ = res.json::<Vec<EventWebhookStatus>>().await.unwrap();
+    let mut res = client
+      .send_get("/events/dead-letters", None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "list dead letters");
+    let _ = res.json::<Vec<EventDeadLetter>>().await.unwrap();
+  }
+
   #[ntex::test]
   async fn watch_events() {
     let system = gen_default_test_system().await;
@@ -150,6 +275,167 @@ mod tests {
     test_status_code!(res.status(), http::StatusCode::OK, "watch events");
   }
 
+  #[ntex::test]
+  async fn watch_events_since() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let mut res = client.send_get("/events", None::<String>).await;
+    let events = res.json::<Vec<Event>>().await.unwrap();
+    let event = events.last().unwrap();
+    let res = client
+      .send_post(
+        "/events/watch",
+        None::<String>,
+        Some(EventWatchQuery {
+          since: Some(event.seq),
+          filter: None,
+        }),
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "watch events since");
+    // The cursor event is in the overlap replayed again
+    let mut stream = res.into_stream();
+    let mut is_replayed = false;
+    while let Some(bytes) = stream.next().await {
+      let bytes = bytes.unwrap();
+      is_replayed = bytes
+        .split(|b| *b == b'\n')
+        .filter_map(|line| serde_json::from_slice::<Event>(line).ok())
+        .any(|replayed| replayed.key == event.key);
+      if is_replayed {
+        break;
+      }
+    }
+    assert!(is_replayed);
+  }
+
+  #[ntex::test]
+  async fn watch_events_filter() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let mut res = client.send_get("/events", None::<String>).await;
+    let events = res.json::<Vec<Event>>().await.unwrap();
+    let event = events.last().unwrap();
+    let filter = EventCondition {
+      action: [NativeEventAction::from_str(&event.action).unwrap()].to_vec(),
+      reporting_node: Some(event.reporting_node.clone()),
+      ..Default::default()
+    };
+    let res = client
+      .send_post(
+        "/events/watch",
+        None::<String>,
+        Some(EventWatchQuery {
+          since: Some(event.seq - 1),
+          filter: Some(serde_json::to_string(&filter).unwrap()),
+        }),
+      )
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::OK,
+      "watch events filter"
+    );
+    let mut stream = res.into_stream();
+    let bytes = stream.next().await.unwrap().unwrap();
+    let line = bytes.split(|b| *b == b'\n').next().unwrap();
+    let replayed = serde_json::from_slice::<Event>(line).unwrap();
+    assert!(filter.matches(&replayed));
+    let res = client
+      .send_post(
+        "/events/watch",
+        None::<String>,
+        Some(EventWatchQuery {
+          since: None,
+          filter: Some("{".to_owned()),
+        }),
+      )
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::BAD_REQUEST,
+      "watch events invalid filter"
+    );
+  }
+
+  #[ntex::test]
+  async fn watch_events_sse() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let mut res = client.send_get("/events", None::<String>).await;
+    let events = res.json::<Vec<Event>>().await.unwrap();
+    let event = events.last().unwrap();
+    let res = client
+      .get("/events/watch")
+      .header("Accept", "text/event-stream")
+      .header("Last-Event-ID", event.seq.to_string())
+      .send()
+      .await
+      .unwrap();
+    test_status_code!(res.status(), http::StatusCode::OK, "watch events sse");
+    assert_eq!(
+      res.headers().get("Content-Type").unwrap(),
+      "text/event-stream"
+    );
+    // The cursor event is in the overlap replayed again
+    let mut stream = res.into_stream();
+    let mut is_replayed = false;
+    while let Some(bytes) = stream.next().await {
+      let bytes = String::from_utf8(bytes.unwrap().to_vec()).unwrap();
+      let mut lines = bytes.lines();
+      let Some(id) = lines.next().and_then(|id| id.strip_prefix("id: ")) else {
+        continue;
+      };
+      let data = lines.next().unwrap().strip_prefix("data: ").unwrap();
+      let replayed = serde_json::from_str::<Event>(data).unwrap();
+      assert_eq!(id, replayed.seq.to_string());
+      if replayed.key == event.key {
+        is_replayed = true;
+        break;
+      }
+    }
+    assert!(is_replayed);
+  }
+
+  #[ntex::test]
+  async fn watch_events_ws() {
+    let system = gen_default_test_system().await;
+    let con = system.client.ws("/events/watch").await.unwrap();
+    test_status_code!(
+      con.response().status(),
+      http::StatusCode::SWITCHING_PROTOCOLS,
+      "watch events ws"
+    );
+    let rx = con.receiver();
+    let state = system.state.clone();
+    rt::spawn(async move {
+      state
+        .emit_event(EventPartial {
+          reporting_controller: vars::CONTROLLER_NAME.to_owned(),
+          reporting_node: state.config().hostname.clone(),
+          action: "watch_ws".to_owned(),
+          reason: "test".to_owned(),
+          kind: EventKind::Normal,
+          actor: None,
+          related: None,
+          metadata: None,
+          note: None,
+        })
+        .await
+        .unwrap();
+    });
+    while let Some(frame) = rx.recv().await {
+      let ws::Frame::Text(text) = frame.unwrap() else {
+        continue;
+      };
+      let event = serde_json::from_slice::<Event>(&text).unwrap();
+      if event.action == "watch_ws" {
+        return;
+      }
+    }
+    panic!("watch_ws event not received");
+  }
+
   #[ntex::test]
   async fn watch_events_condition() {
     const CARGO_NAME: &str = "event-condition";
@@ -4,7 +4,7 @@ use nanocl_error::http::{HttpError, HttpResult};
 
 use nanocl_stubs::{
   generic::{GenericCount, GenericListQuery},
-  metric::MetricPartial,
+  metric::{HttpAnalyticsQuery, MetricPartial, MetricQuery},
 };
 
 use crate::{
@@ -75,8 +75,11 @@ pub async fn create_metric(
   if payload.kind.starts_with("nanocl.io") {
     return Err(HttpError::bad_request("reserved kind nanocl.io"));
   }
-  let new_metric =
-    MetricNodePartial::try_new_node(&state.inner.config.hostname, &payload)?;
+  let new_metric = MetricNodePartial::try_new_node(
+    &state.config().hostname,
+    &payload,
+    &state.config().retention,
+  )?;
   let metric = MetricDb::create_from(&new_metric, &state.inner.pool).await?;
   Ok(web::HttpResponse::Created().json(&metric))
 }
@@ -103,18 +106,80 @@ pub async fn count_metric(
   Ok(web::HttpResponse::Ok().json(&GenericCount { count }))
 }
 
+/// Time series of a field of the metrics aggregated by bucket
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Metrics",
+  path = "/metrics/query",
+  params(
+    ("field" = String, Query, description = "Path of the value in the data, a `*` expand an array", example = "Cpus.*.Usage"),
+    ("kind" = Option<String>, Query, description = "Kind of the metrics", example = "nanocl.io/metrs"),
+    ("node_name" = Option<String>, Query, description = "Only the metrics of this node"),
+    ("since" = Option<i64>, Query, description = "Start of the range as a unix timestamp, default to one hour ago"),
+    ("until" = Option<i64>, Query, description = "End of the range as a unix timestamp, default to now"),
+    ("step" = Option<i64>, Query, description = "Duration of a bucket in seconds, default to 60"),
+    ("aggregate" = Option<MetricAggregate>, Query, description = "Aggregation of the values of a bucket, default to avg"),
+    ("group_by" = Option<MetricGroupBy>, Query, description = "Split the result in a series by node or kind"),
+    ("source" = Option<MetricSource>, Query, description = "Read the raw metrics or a rollup (5m, 1h) of a rolled up field without p95, default to raw"),
+  ),
+  responses(
+    (status = 200, description = "Series of the query", body = Vec<MetricSeries>),
+  ),
+))]
+#[web::get("/metrics/query")]
+pub async fn query_metric(
+  state: web::types::State<SystemState>,
+  qs: web::types::Query<MetricQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let series = utils::metric::query(&qs, &state).await?;
+  Ok(web::HttpResponse::Ok().json(&series))
+}
+
+/// Requests per second, error rates and latency percentiles
+/// of the http requests of the proxy from the rollups
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Metrics",
+  path = "/metrics/http",
+  params(
+    ("since" = Option<i64>, Query, description = "Start of the window as a unix timestamp, default to one hour ago"),
+    ("until" = Option<i64>, Query, description = "End of the window as a unix timestamp, default to now"),
+    ("step" = Option<i64>, Query, description = "Duration of a point in seconds, default to the resolution of the source"),
+    ("group_by" = Option<HttpAnalyticsGroupBy>, Query, description = "Split the analytics by domain, location or upstream, default to domain"),
+    ("domain" = Option<String>, Query, description = "Only the requests of this domain"),
+    ("node_name" = Option<String>, Query, description = "Only the requests proxied by this node"),
+    ("source" = Option<MetricSource>, Query, description = "Rollup to read (5m, 1h), default to 5m"),
+  ),
+  responses(
+    (status = 200, description = "Analytics by group", body = Vec<HttpAnalytics>),
+  ),
+))]
+#[web::get("/metrics/http")]
+pub async fn http_analytics_metric(
+  state: web::types::State<SystemState>,
+  qs: web::types::Query<HttpAnalyticsQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let analytics = utils::metric::http_analytics(&qs, &state).await?;
+  Ok(web::HttpResponse::Ok().json(&analytics))
+}
+
 pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(list_metric);
   config.service(create_metric);
   config.service(inspect_metric);
   config.service(count_metric);
+  config.service(query_metric);
+  config.service(http_analytics_metric);
 }
 
 #[cfg(test)]
 mod tests {
   use nanocl_stubs::{
     generic::{GenericClause, GenericFilter, GenericListQuery},
-    metric::{Metric, MetricPartial},
+    metric::{
+      HttpAnalytics, HttpAnalyticsGroupBy, HttpAnalyticsQuery, Metric,
+      MetricAggregate, MetricPartial, MetricQuery, MetricSeries, MetricSource,
+    },
   };
   use ntex::http;
 
@@ -170,5 +235,79 @@ mod tests {
       )
       .await;
     test_status_code!(res.status(), http::StatusCode::OK, "inspect metric");
+    let qs = MetricQuery {
+      field: "test".to_owned(),
+      kind: Some("test.io/test".to_owned()),
+      ..Default::default()
+    };
+    let mut res = client
+      .send_get(&format!("{ENDPOINT}/query"), Some(&qs))
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "query metric");
+    let _ = res.json::<Vec<MetricSeries>>().await.unwrap();
+    let qs = MetricQuery {
+      field: "test.*.*".to_owned(),
+      ..Default::default()
+    };
+    let res = client
+      .send_get(&format!("{ENDPOINT}/query"), Some(&qs))
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::BAD_REQUEST,
+      "query metric with an invalid field"
+    );
+    let qs = MetricQuery {
+      field: "test".to_owned(),
+      source: Some(MetricSource::FiveMinutes),
+      step: Some(300),
+      ..Default::default()
+    };
+    let res = client
+      .send_get(&format!("{ENDPOINT}/query"), Some(&qs))
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::BAD_REQUEST,
+      "query a rollup with a field not rolled up"
+    );
+    let qs = MetricQuery {
+      field: "request_time".to_owned(),
+      aggregate: Some(MetricAggregate::P95),
+      ..qs
+    };
+    let res = client
+      .send_get(&format!("{ENDPOINT}/query"), Some(&qs))
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::BAD_REQUEST,
+      "query the p95 of a rollup"
+    );
+    let mut res = client
+      .send_get(
+        &format!("{ENDPOINT}/http"),
+        Some(&HttpAnalyticsQuery {
+          group_by: Some(HttpAnalyticsGroupBy::Location),
+          ..Default::default()
+        }),
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "http analytics");
+    let _ = res.json::<Vec<HttpAnalytics>>().await.unwrap();
+    let res = client
+      .send_get(
+        &format!("{ENDPOINT}/http"),
+        Some(&HttpAnalyticsQuery {
+          source: Some(MetricSource::Raw),
+          ..Default::default()
+        }),
+      )
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::BAD_REQUEST,
+      "http analytics from the raw metrics"
+    );
   }
 }
@@ -5,7 +5,9 @@ use nanocl_error::http::{HttpError, HttpResult};
 #[cfg(feature = "dev")]
 mod openapi;
 
+mod alert;
 mod cargo;
+mod container_image;
 mod event;
 mod exec;
 mod job;
@@ -13,10 +15,12 @@ mod metric;
 mod namespace;
 mod node;
 mod process;
+mod prometheus;
 mod resource;
 mod resource_kind;
 mod secret;
 mod system;
+mod task;
 mod vm;
 mod vm_image;
 
@@ -44,6 +48,8 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
         .configure(swagger::register),
     );
   }
+  // Scraped without version like any other Prometheus target
+  prometheus::ntex_config(config);
   config.service(
     web::scope("/{version}")
       .wrap(
@@ -56,13 +62,16 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
       .configure(system::ntex_config)
       .configure(resource::ntex_config)
       .configure(cargo::ntex_config)
+      .configure(container_image::ntex_config)
       .configure(vm_image::ntex_config)
       .configure(vm::ntex_config)
       .configure(metric::ntex_config)
+      .configure(alert::ntex_config)
       .configure(secret::ntex_config)
       .configure(process::ntex_config)
       .configure(job::ntex_config)
       .configure(event::ntex_config)
+      .configure(task::ntex_config)
       .configure(resource_kind::ntex_config),
   );
 }
@@ -4,7 +4,7 @@ use nanocl_error::http::HttpResult;
 
 use nanocl_stubs::{
   generic::{GenericCount, GenericListQuery},
-  namespace::NamespacePartial,
+  namespace::{NamespaceCloneOpts, NamespaceDeleteQuery, NamespacePartial},
 };
 
 use crate::{
@@ -84,7 +84,8 @@ pub async fn create_namespace(
   tag = "Namespaces",
   path = "/namespaces/{name}",
   params(
-    ("name" = String, Path, description = "Name of the namespace to delete")
+    ("name" = String, Path, description = "Name of the namespace to delete"),
+    ("cascade" = Option<bool>, Query, description = "If true delete the cargoes, vms, proxy and dns rules and volumes of the namespace in background"),
   ),
   responses(
     (status = 202, description = "Namespace have been deleted"),
@@ -95,11 +96,38 @@ pub async fn create_namespace(
 pub async fn delete_namespace(
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
+  qs: web::types::Query<NamespaceDeleteQuery>,
 ) -> HttpResult<web::HttpResponse> {
-  NamespaceDb::del_obj_by_pk(&path.1, &(), &state).await?;
+  NamespaceDb::del_obj_by_pk(&path.1, &qs, &state).await?;
   Ok(web::HttpResponse::Accepted().into())
 }
 
+/// Clone a namespace with his cargoes, virtual machines, proxy and dns rules
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  request_body = NamespaceCloneOpts,
+  tag = "Namespaces",
+  path = "/namespaces/{name}/clone",
+  params(
+    ("name" = String, Path, description = "Name of the namespace to clone"),
+  ),
+  responses(
+    (status = 201, description = "The created namespace with the proxy rules that weren't cloned", body = NamespaceCloned),
+    (status = 404, description = "Namespace is not existing", body = ApiError),
+    (status = 409, description = "Namespace already exist or a virtual machine to snapshot is running", body = ApiError),
+  ),
+))]
+#[web::post("/namespaces/{name}/clone")]
+pub async fn clone_namespace(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  payload: web::types::Json<NamespaceCloneOpts>,
+) -> HttpResult<web::HttpResponse> {
+  let item =
+    utils::namespace::clone_namespace(&path.1, &payload, &state).await?;
+  Ok(web::HttpResponse::Created().json(&item))
+}
+
 /// Count namespaces
 #[cfg_attr(feature = "dev", utoipa::path(
   get,
@@ -127,14 +155,26 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(create_namespace);
   config.service(inspect_namespace);
   config.service(delete_namespace);
+  config.service(clone_namespace);
   config.service(count_namespace);
 }
 
 #[cfg(test)]
 mod test_namespace {
+  use futures::StreamExt;
+  use ntex::http;
   use serde_json::json;
 
-  use nanocl_stubs::namespace::{Namespace, NamespacePartial};
+  use nanocl_stubs::{
+    cargo_spec::CargoSpecPartial,
+    generic::GenericNspQuery,
+    namespace::{
+      Namespace, NamespaceCloneOpts, NamespaceDeleteQuery, NamespaceInspect,
+      NamespaceNetwork, NamespacePartial, NamespaceQuota,
+    },
+    resource::ResourcePartial,
+    system::{Event, EventCondition, NativeEventAction},
+  };
 
   use crate::utils::tests::*;
 
@@ -147,10 +187,7 @@ mod test_namespace {
   }
 
   async fn create(client: &TestClient) {
-    let new_namespace = NamespacePartial {
-      name: String::from("controller-default"),
-      metadata: None,
-    };
+    let new_namespace = test_namespace("controller-default");
     let res = client
       .send_post(ENDPOINT, Some(new_namespace), None::<String>)
       .await;
@@ -204,4 +241,227 @@ mod test_namespace {
     delete(&client).await;
     system.state.wait_event_loop().await;
   }
+
+  #[ntex::test]
+  async fn isolated() {
+    const NAME: &str = "controller-isolated";
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let new_namespace = NamespacePartial {
+      network: Some(NamespaceNetwork {
+        isolated: true,
+        ..Default::default()
+      }),
+      ..test_namespace(NAME)
+    };
+    let res = client
+      .send_post(ENDPOINT, Some(new_namespace), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "create");
+    let namespace = TestClient::res_json::<Namespace>(res).await;
+    assert!(
+      namespace.network.unwrap_or_default().isolated,
+      "Expect namespace to be isolated"
+    );
+    let res = client
+      .send_get(&format!("{ENDPOINT}/{NAME}/inspect"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "inspect");
+    let namespace = TestClient::res_json::<NamespaceInspect>(res).await;
+    let network = namespace.network.expect("Expect a network");
+    assert_eq!(network.name, Some(format!("nanocl.{NAME}")));
+    let res = client
+      .send_delete(&format!("{ENDPOINT}/{NAME}"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "delete");
+    system.state.wait_event_loop().await;
+  }
+
+  #[ntex::test]
+  async fn quota() {
+    const NAME: &str = "controller-quota";
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let new_namespace = NamespacePartial {
+      quota: Some(NamespaceQuota {
+        max_cargoes: Some(0),
+        ..Default::default()
+      }),
+      ..test_namespace(NAME)
+    };
+    let res = create_test_namespace_cargo(
+      &client,
+      &new_namespace,
+      &test_cargo("quota-exceeded"),
+    )
+    .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::FORBIDDEN,
+      "create cargo over quota"
+    );
+    let res = client
+      .send_delete(&format!("{ENDPOINT}/{NAME}"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "delete");
+    system.state.wait_event_loop().await;
+  }
+
+  #[ntex::test]
+  async fn registry_policy() {
+    const NAME: &str = "controller-registry-policy";
+    const POLICY: &str = "controller-registry-policy";
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let res = client
+      .send_post(ENDPOINT, Some(test_namespace(NAME)), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "create");
+    let policy = ResourcePartial {
+      name: POLICY.to_owned(),
+      kind: "nanocl.io/registry-policy".to_owned(),
+      data: json!({
+        "Namespace": NAME,
+        "Allow": ["ghcr.io/next-hat/*"],
+        "RejectLatest": true,
+      }),
+      metadata: None,
+    };
+    let res = client
+      .send_post("/resources", Some(&policy), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "policy");
+    for image in ["nginx:1.25", "ghcr.io/next-hat/nanocl-get-started:latest"] {
+      let res = client
+        .send_post(
+          "/cargoes",
+          Some(&CargoSpecPartial {
+            container: bollard_next::container::Config {
+              image: Some(image.to_owned()),
+              ..Default::default()
+            },
+            ..test_cargo("registry-policy")
+          }),
+          Some(&GenericNspQuery::new(Some(NAME))),
+        )
+        .await;
+      test_status_code!(
+        res.status(),
+        http::StatusCode::BAD_REQUEST,
+        "create cargo with a rejected image"
+      );
+    }
+    let res = client
+      .send_delete(&format!("/resources/{POLICY}"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "policy");
+    let res = client
+      .send_delete(&format!("{ENDPOINT}/{NAME}"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "delete");
+    system.state.wait_event_loop().await;
+  }
+
+  #[ntex::test]
+  async fn cascade() {
+    const NAME: &str = "controller-cascade";
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let res = create_test_namespace_cargo(
+      &client,
+      &test_namespace(NAME),
+      &test_cargo("cascade"),
+    )
+    .await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "create cargo");
+    let filter = EventCondition {
+      actor_key: Some(format!("cascade.{NAME}")),
+      action: vec![NativeEventAction::Destroying],
+      ..Default::default()
+    };
+    let mut events = system
+      .state
+      .subscribe_raw(None, Some(filter))
+      .await
+      .unwrap();
+    let res = client
+      .send_delete(
+        &format!("{ENDPOINT}/{NAME}"),
+        Some(&NamespaceDeleteQuery {
+          cascade: Some(true),
+        }),
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "delete");
+    // The cargo is deleted like the api does
+    loop {
+      let bytes = events.next().await.unwrap().unwrap();
+      if serde_json::from_slice::<Event>(&bytes).is_ok() {
+        break;
+      }
+    }
+    let mut status = http::StatusCode::OK;
+    for _ in 0..50 {
+      let res = client
+        .send_get(&format!("{ENDPOINT}/{NAME}/inspect"), None::<String>)
+        .await;
+      status = res.status();
+      if status == http::StatusCode::NOT_FOUND {
+        break;
+      }
+      ntex::time::sleep(std::time::Duration::from_millis(200)).await;
+    }
+    test_status_code!(status, http::StatusCode::NOT_FOUND, "cascade deleted");
+    system.state.wait_event_loop().await;
+  }
+
+  #[ntex::test]
+  async fn clone() {
+    const NAME: &str = "controller-clone-src";
+    const TARGET: &str = "controller-clone-dst";
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let res = create_test_namespace_cargo(
+      &client,
+      &test_namespace(NAME),
+      &test_cargo("clone"),
+    )
+    .await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "create cargo");
+    let res = client
+      .send_post(
+        &format!("{ENDPOINT}/{NAME}/clone"),
+        Some(&NamespaceCloneOpts {
+          name: TARGET.to_owned(),
+          env: Some(vec!["APP_ENV=clone".to_owned()]),
+          ..Default::default()
+        }),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "clone");
+    let res = client
+      .send_get(&format!("{ENDPOINT}/{TARGET}/inspect"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "inspect clone");
+    let namespace = TestClient::res_json::<NamespaceInspect>(res).await;
+    let cargo = namespace.cargoes.first().expect("Expect a cloned cargo");
+    let env = cargo.spec.container.env.clone().unwrap_or_default();
+    assert!(
+      env.contains(&"APP_ENV=clone".to_owned()),
+      "Expect env override"
+    );
+    for name in [NAME, TARGET] {
+      let res = client
+        .send_delete(
+          &format!("{ENDPOINT}/{name}"),
+          Some(&NamespaceDeleteQuery {
+            cascade: Some(true),
+          }),
+        )
+        .await;
+      test_status_code!(res.status(), http::StatusCode::ACCEPTED, "delete");
+    }
+    system.state.wait_event_loop().await;
+  }
 }
@@ -74,7 +74,7 @@ async fn node_ws_service(
   let (tx, rx) = oneshot::channel();
   let con_state = Rc::new(RefCell::new(WsConState::new()));
   rt::spawn(utils::ws::heartbeat(con_state.clone(), sink.clone(), rx));
-  let message = format!("[SERVER] hello i'm {}", state.inner.config.hostname);
+  let message = format!("[SERVER] hello i'm {}", state.config().hostname);
   let _ = sink
     .send(ws::Message::Text(ByteString::from(message)))
     .await;
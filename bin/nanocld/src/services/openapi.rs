@@ -18,7 +18,8 @@ use bollard_next::service::{
   GenericResourcesInner, GenericResourcesInnerDiscreteResourceSpec,
   GenericResourcesInnerNamedResourceSpec, Health, HealthConfig,
   HealthStatusEnum, HealthcheckResult, HostConfig, HostConfigCgroupnsModeEnum,
-  HostConfigIsolationEnum, HostConfigLogConfig, IndexInfo, Ipam, IpamConfig,
+  HostConfigIsolationEnum, HostConfigLogConfig, ImageInspect,
+  ImageInspectMetadata, ImageInspectRootFs, IndexInfo, Ipam, IpamConfig,
   LocalNodeState, Mount, MountBindOptions, MountBindOptionsPropagationEnum,
   MountPoint, MountPointTypeEnum, MountTmpfsOptions, MountTypeEnum,
   MountVolumeOptions, MountVolumeOptionsDriverConfig, Network,
@@ -35,6 +36,11 @@ use bollard_next::service::{
   TlsInfo,
 };
 
+use nanocl_stubs::alert::{
+  Alert, AlertCondition, AlertEventCondition, AlertMetricCondition,
+  AlertOperator, AlertProcessCondition, AlertSeverity, AlertSink, AlertStatus,
+  ResourceAlertRule,
+};
 use nanocl_stubs::cargo::{
   Cargo, CargoInspect, CargoKillOptions, CargoSummary, CreateExecOptions,
 };
@@ -42,18 +48,33 @@ use nanocl_stubs::cargo_spec::{
   CargoSpec, CargoSpecPartial, CargoSpecUpdate, ReplicationMode,
   ReplicationStatic,
 };
-use nanocl_stubs::config::DaemonConfig;
+use nanocl_stubs::config::{
+  DaemonConfig, DaemonConfigReload, ImageGcConfig, ProcessStatsConfig,
+  ReconcileConfig, RetentionConfig,
+};
+use nanocl_stubs::container_image::{
+  ContainerBuild, ContainerImageBuildStream, ContainerImagePruneResult,
+  ContainerImagePullOpts, ContainerImageSummary, ResourceRegistryPolicy,
+};
 use nanocl_stubs::dns::{DnsEntry, ResourceDnsRule};
 use nanocl_stubs::generic::{
   GenericClause, GenericCount, GenericFilter, GenericWhere, ImagePullPolicy,
 };
 use nanocl_stubs::job::{Job, JobInspect, JobPartial, JobSummary};
-use nanocl_stubs::metric::{Metric, MetricPartial};
+use nanocl_stubs::metric::{
+  HttpAnalytics, HttpAnalyticsGroupBy, HttpAnalyticsPoint, Metric,
+  MetricAggregate, MetricGroupBy, MetricPartial, MetricPoint, MetricSeries,
+  MetricSource,
+};
 use nanocl_stubs::namespace::{
-  Namespace, NamespaceInspect, NamespacePartial, NamespaceSummary,
+  Namespace, NamespaceCloneOpts, NamespaceCloneSkippedRule, NamespaceCloned,
+  NamespaceInspect, NamespaceLimitRange, NamespaceNetwork, NamespacePartial,
+  NamespaceQuota, NamespaceSummary, ResourceNetworkPolicy,
 };
 use nanocl_stubs::node::Node;
-use nanocl_stubs::process::{Process, ProcessKind, ProcessStats};
+use nanocl_stubs::process::{
+  Process, ProcessKind, ProcessStats, ProcessStatsSample, ProcessStatsSummary,
+};
 use nanocl_stubs::proxy::{
   HttpTarget, LimitReq, LimitReqZone, LocationTarget, ProxyHttpLocation,
   ProxyRule, ProxyRuleHttp, ProxyRuleStream, ProxySsl, ProxySslConfig,
@@ -74,19 +95,24 @@ use nanocl_stubs::statefile::{
 };
 use nanocl_stubs::system::{
   BinaryInfo, Event, EventActor, EventActorKind, EventCondition, EventKind,
-  HostInfo, NativeEventAction, ObjPsStatus, ObjPsStatusKind, SslConfig,
+  HealthCheck, HealthReport, HealthStatus, HostInfo, NativeEventAction,
+  ObjPsStatus, ObjPsStatusKind, PruneStats, SslConfig,
 };
+use nanocl_stubs::task::{Task, TaskStats, TaskStatus};
 use nanocl_stubs::vm::{Vm, VmInspect, VmSummary};
 use nanocl_stubs::vm_image::{VmImage, VmImageResizePayload};
 use nanocl_stubs::vm_spec::{
   VmDisk, VmHostConfig, VmSpec, VmSpecPartial, VmSpecUpdate,
 };
+use nanocl_stubs::webhook::{
+  EventDeadLetter, EventWebhookStatus, ResourceEventWebhook,
+};
 
 use crate::vars;
 
 use super::{
-  cargo, event, exec, job, metric, namespace, node, process, resource,
-  resource_kind, secret, system, vm, vm_image,
+  alert, cargo, container_image, event, exec, job, metric, namespace, node,
+  process, resource, resource_kind, secret, system, task, vm, vm_image,
 };
 
 /// When returning a [HttpError](nanocl_error::http::HttpError)
@@ -236,11 +262,16 @@ impl Modify for VersionModifier {
     system::get_info,
     system::get_version,
     system::get_ping,
+    system::get_healthz,
+    system::get_readyz,
+    system::reload_config,
+    system::get_prune_stats,
     // Namespace
     namespace::list_namespace,
     namespace::inspect_namespace,
     namespace::create_namespace,
     namespace::delete_namespace,
+    namespace::clone_namespace,
     namespace::count_namespace,
     // Secret
     secret::list_secret,
@@ -249,6 +280,11 @@ impl Modify for VersionModifier {
     secret::delete_secret,
     secret::patch_secret,
     secret::count_secret,
+    // Task
+    task::list_task,
+    task::count_task,
+    task::stats_task,
+    task::cancel_task,
     // Job
     job::list_job,
     job::delete_job,
@@ -269,6 +305,13 @@ impl Modify for VersionModifier {
     exec::create_exec_command,
     exec::start_exec_command,
     exec::inspect_exec_command,
+    // Container Image
+    container_image::list_images,
+    container_image::pull_image,
+    container_image::build_image,
+    container_image::prune_images,
+    container_image::inspect_image,
+    container_image::delete_image,
     // VM Image
     vm_image::list_vm_images,
     vm_image::import_vm_image,
@@ -305,6 +348,10 @@ impl Modify for VersionModifier {
     metric::create_metric,
     metric::inspect_metric,
     metric::count_metric,
+    metric::query_metric,
+    metric::http_analytics_metric,
+    // Alert
+    alert::list_alert,
     // Process
     process::logs_processes,
     process::logs_process,
@@ -315,12 +362,16 @@ impl Modify for VersionModifier {
     process::kill_processes,
     process::wait_processes,
     process::stats_processes,
+    process::stats_summary_processes,
     process::count_process,
     // Event
     event::list_event,
     event::watch_event,
+    event::follow_event,
     event::inspect_event,
     event::count_event,
+    event::list_event_webhook,
+    event::list_event_dead_letter,
   ),
   components(schemas(
     // Node
@@ -329,9 +380,16 @@ impl Modify for VersionModifier {
     Secret,
     SecretPartial,
     SecretUpdate,
+    // Task
+    Task,
+    TaskStats,
+    TaskStatus,
     // System
     BinaryInfo,
     HostInfo,
+    HealthStatus,
+    HealthCheck,
+    HealthReport,
     SystemInfo,
     Commit,
     Runtime,
@@ -367,11 +425,20 @@ impl Modify for VersionModifier {
     NamespacePartial,
     NamespaceInspect,
     NamespaceSummary,
+    NamespaceNetwork,
+    NamespaceQuota,
+    NamespaceLimitRange,
+    NamespaceCloneOpts,
+    NamespaceCloned,
+    NamespaceCloneSkippedRule,
+    ResourceNetworkPolicy,
     // Process
     Process,
     ProcessKind,
     Stats,
     ProcessStats,
+    ProcessStatsSample,
+    ProcessStatsSummary,
     ObjPsStatus,
     ObjPsStatusKind,
     // Job
@@ -454,6 +521,16 @@ impl Modify for VersionModifier {
     GraphDriverData,
     // Network
     Network,
+    // Container Image
+    ContainerImageSummary,
+    ContainerImagePullOpts,
+    ContainerImagePruneResult,
+    ContainerImageBuildStream,
+    ContainerBuild,
+    ResourceRegistryPolicy,
+    ImageInspect,
+    ImageInspectMetadata,
+    ImageInspectRootFs,
     // Vm Image
     VmImage,
     VmImageResizePayload,
@@ -510,8 +587,33 @@ impl Modify for VersionModifier {
     // Metric
     Metric,
     MetricPartial,
+    MetricAggregate,
+    MetricGroupBy,
+    MetricSource,
+    MetricPoint,
+    MetricSeries,
+    HttpAnalyticsGroupBy,
+    HttpAnalyticsPoint,
+    HttpAnalytics,
+    // Alert
+    Alert,
+    AlertStatus,
+    AlertSeverity,
+    AlertSink,
+    AlertOperator,
+    AlertCondition,
+    AlertMetricCondition,
+    AlertEventCondition,
+    AlertProcessCondition,
+    ResourceAlertRule,
     // Daemon
     DaemonConfig,
+    ImageGcConfig,
+    ReconcileConfig,
+    DaemonConfigReload,
+    RetentionConfig,
+    ProcessStatsConfig,
+    PruneStats,
     // Error
     ApiError,
     // Generic Types
@@ -530,19 +632,25 @@ impl Modify for VersionModifier {
     EventKind,
     EventCondition,
     NativeEventAction,
+    ResourceEventWebhook,
+    EventWebhookStatus,
+    EventDeadLetter,
   )),
   tags(
     (name = "Namespaces", description = "Namespaces management endpoints."),
     (name = "Nodes", description = "Nodes management endpoints."),
     (name = "Resources", description = "Resources management endpoints."),
     (name = "System", description = "General system endpoints."),
+    (name = "ContainerImages", description = "Container images management endpoints."),
     (name = "VmImages", description = "Virtual machine images management endpoints."),
     (name = "Vms", description = "Virtual machines management endpoints."),
     (name = "Metrics", description = "Metrics management endpoints."),
+    (name = "Alerts", description = "Alerts management endpoints."),
     (name = "Processes", description = "Processes management endpoints."),
     (name = "Secrets", description = "Secrets management endpoints."),
     (name = "Jobs", description = "Jobs management endpoints."),
     (name = "Events", description = "Events management endpoints."),
+    (name = "Tasks", description = "Tasks management endpoints."),
   ),
   modifiers(&VersionModifier),
 )]
@@ -14,7 +14,7 @@ use nanocl_stubs::{
   generic::{GenericCount, GenericListQuery, GenericNspQuery},
   process::{
     ProcessLogQuery, ProcessOutputLog, ProcessStats, ProcessStatsQuery,
-    ProcessWaitQuery, ProcessWaitResponse,
+    ProcessStatsSummaryQuery, ProcessWaitQuery, ProcessWaitResponse,
   },
 };
 
@@ -63,20 +63,23 @@ pub async fn list_processes(
     ("tail" = Option<String>, Query, description = "Only return the n last (integer) or all (\"all\") logs"),
   ),
   responses(
-    (status = 200, description = "Process instances logs", content_type = "application/vdn.nanocl.raw-stream"),
+    (status = 101, description = "Websocket connection"),
+    (status = 200, description = "Process instances logs", content_type = ["application/vdn.nanocl.raw-stream", "text/event-stream"]),
   ),
 ))]
 #[web::get("/processes/{kind}/{name}/logs")]
 async fn logs_processes(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String, String)>,
   qs: web::types::Query<ProcessLogQuery>,
-) -> HttpResult<web::HttpResponse> {
+) -> Result<web::HttpResponse, web::Error> {
   let (_, kind, name) = path.into_inner();
   let kind = kind.parse().map_err(HttpError::bad_request)?;
   let kind_key = utils::key::gen_kind_key(&kind, &name, &qs.namespace);
-  let processes =
-    ProcessDb::read_by_kind_key(&kind_key, &state.inner.pool).await?;
+  let processes = ProcessDb::read_by_kind_key(&kind_key, &state.inner.pool)
+    .await
+    .map_err(HttpError::from)?;
   log::debug!("process::logs_process: {kind_key}");
   let options: LogsOptions<String> = qs.into_inner().into();
   let futures = processes
@@ -87,7 +90,7 @@ async fn logs_processes(
     .map(|process| {
       state
         .inner
-        .docker_api
+        .runtime
         .logs(
           &process.data.id.unwrap_or_default(),
           Some(LogsOptions::<String> {
@@ -109,12 +112,11 @@ async fn logs_processes(
   let stream = utils::stream::transform_stream::<
     ProcessOutputLog,
     ProcessOutputLog,
-  >(stream);
-  Ok(
-    web::HttpResponse::Ok()
-      .content_type("application/vdn.nanocl.raw-stream")
-      .streaming(stream),
-  )
+  >(stream)
+  .map_err(web::Error::from)
+  .boxed_local();
+  utils::stream::respond(req, "application/vdn.nanocl.raw-stream", stream, None)
+    .await
 }
 
 /// Get logs of a single process instance by it's name or id
@@ -131,21 +133,23 @@ async fn logs_processes(
     ("tail" = Option<String>, Query, description = "Only return the n last (integer) or all (\"all\") logs"),
   ),
   responses(
-    (status = 200, description = "Process instances logs", content_type = "application/vdn.nanocl.raw-stream"),
+    (status = 101, description = "Websocket connection"),
+    (status = 200, description = "Process instances logs", content_type = ["application/vdn.nanocl.raw-stream", "text/event-stream"]),
   ),
 ))]
 #[web::get("/processes/{name}/logs")]
 async fn logs_process(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   qs: web::types::Query<ProcessLogQuery>,
-) -> HttpResult<web::HttpResponse> {
+) -> Result<web::HttpResponse, web::Error> {
   let (_, name) = path.into_inner();
   log::debug!("process::logs_process: {name}");
   let options: LogsOptions<String> = qs.into_inner().into();
   let stream = state
     .inner
-    .docker_api
+    .runtime
     .logs(
       &name,
       Some(LogsOptions::<String> {
@@ -164,12 +168,11 @@ async fn logs_process(
   let stream = utils::stream::transform_stream::<
     ProcessOutputLog,
     ProcessOutputLog,
-  >(stream);
-  Ok(
-    web::HttpResponse::Ok()
-      .content_type("application/vdn.nanocl.raw-stream")
-      .streaming(stream),
-  )
+  >(stream)
+  .map_err(web::Error::from)
+  .boxed_local();
+  utils::stream::respond(req, "application/vdn.nanocl.raw-stream", stream, None)
+    .await
 }
 
 /// Start process by it's pk
@@ -194,7 +197,7 @@ pub async fn start_process_by_pk(
   let process = ProcessDb::read_by_pk(&pk, &state.inner.pool).await?;
   state
     .inner
-    .docker_api
+    .runtime
     .start_container(&process.key, None::<StartContainerOptions<String>>)
     .await?;
   Ok(web::HttpResponse::Accepted().finish())
@@ -334,7 +337,7 @@ pub async fn wait_processes(
   let kind = kind.parse().map_err(HttpError::bad_request)?;
   let kind_pk = utils::key::gen_kind_key(&kind, &name, &qs.namespace);
   let opts = WaitContainerOptions {
-    condition: qs.condition.clone().unwrap_or_default(),
+    condition: qs.condition.clone().unwrap_or_default().to_string(),
   };
   let processes =
     ProcessDb::read_by_kind_key(&kind_pk, &state.inner.pool).await?;
@@ -343,7 +346,7 @@ pub async fn wait_processes(
     let options = Some(opts.clone());
     let stream = state
       .inner
-      .docker_api
+      .runtime
       .wait_container(&process.key, options)
       .map(move |wait_result| match wait_result {
         Err(err) => {
@@ -395,45 +398,77 @@ pub async fn wait_processes(
     ("one_shot" = Option<bool>, Query, description = "Return stats only once"),
   ),
   responses(
-    (status = 200, description = "Process stats", content_type = "application/vdn.nanocl.raw-stream", body = ProcessStats),
+    (status = 101, description = "Websocket connection"),
+    (status = 200, description = "Process stats", content_type = ["application/vdn.nanocl.raw-stream", "text/event-stream"], body = ProcessStats),
     (status = 404, description = "Process does not exist"),
   ),
 ))]
 #[web::get("/processes/{kind}/{name}/stats")]
 pub async fn stats_processes(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String, String)>,
   qs: web::types::Query<ProcessStatsQuery>,
-) -> HttpResult<web::HttpResponse> {
+) -> Result<web::HttpResponse, web::Error> {
   let (_, kind, name) = path.into_inner();
   let kind = kind.parse().map_err(HttpError::bad_request)?;
   let kind_key = utils::key::gen_kind_key(&kind, &name, &qs.namespace);
   let opts: StatsOptions = qs.clone().into();
-  let processes =
-    ProcessDb::read_by_kind_key(&kind_key, &state.inner.pool).await?;
-  let streams =
-    processes
-      .into_iter()
-      .map(|process| {
-        state.inner.docker_api.stats(&process.key, Some(opts)).map(
-          move |elem| match elem {
-            Err(err) => Err(err),
-            Ok(stats) => Ok(ProcessStats {
-              name: process.name.clone(),
-              stats,
-            }),
-          },
-        )
-      })
-      .collect::<Vec<_>>();
+  let processes = ProcessDb::read_by_kind_key(&kind_key, &state.inner.pool)
+    .await
+    .map_err(HttpError::from)?;
+  let streams = processes
+    .into_iter()
+    .map(|process| {
+      state
+        .inner
+        .runtime
+        .stats(&process.key, Some(opts))
+        .map(move |elem| match elem {
+          Err(err) => Err(err),
+          Ok(stats) => Ok(ProcessStats {
+            name: process.name.clone(),
+            stats,
+          }),
+        })
+    })
+    .collect::<Vec<_>>();
   let stream = select_all(streams).into_stream();
-  Ok(
-    web::HttpResponse::Ok()
-      .content_type("application/vdn.nanocl.raw-stream")
-      .streaming(
-        utils::stream::transform_stream::<ProcessStats, ProcessStats>(stream),
-      ),
-  )
+  let stream =
+    utils::stream::transform_stream::<ProcessStats, ProcessStats>(stream)
+      .map_err(web::Error::from)
+      .boxed_local();
+  utils::stream::respond(req, "application/vdn.nanocl.raw-stream", stream, None)
+    .await
+}
+
+/// Summarize the sampled resource usage of the processes of a cargo, vm or job
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Processes",
+  path = "/processes/{kind}/{name}/stats/summary",
+  params(
+    ("kind" = String, Path, description = "Kind of process", example = "cargo"),
+    ("name" = String, Path, description = "Name of the process group", example = "deploy-example"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the cargo belongs"),
+    ("since" = Option<i64>, Query, description = "Unix timestamp of the first sample, default to one hour ago"),
+  ),
+  responses(
+    (status = 200, description = "Summary by process", body = [ProcessStatsSummary]),
+  ),
+))]
+#[web::get("/processes/{kind}/{name}/stats/summary")]
+pub async fn stats_summary_processes(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String, String)>,
+  qs: web::types::Query<ProcessStatsSummaryQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let (_, kind, name) = path.into_inner();
+  let kind = kind.parse().map_err(HttpError::bad_request)?;
+  let kind_key = utils::key::gen_kind_key(&kind, &name, &qs.namespace);
+  let summaries =
+    utils::process_stats::summary(&kind, &kind_key, qs.since, &state).await?;
+  Ok(web::HttpResponse::Ok().json(&summaries))
 }
 
 /// Count processes
@@ -468,6 +503,7 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(kill_processes);
   config.service(wait_processes);
   config.service(stats_processes);
+  config.service(stats_summary_processes);
   config.service(count_process);
 }
 
@@ -475,11 +511,14 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
 mod tests {
   use ntex::http;
 
-  use crate::utils::tests::*;
+  use crate::utils::{self, tests::*};
 
   use nanocl_stubs::{
     generic::{GenericClause, GenericFilter, GenericListQuery},
-    process::{Process, ProcessStatsQuery},
+    process::{
+      Process, ProcessStats, ProcessStatsQuery, ProcessStatsSummary,
+      ProcessStatsSummaryQuery,
+    },
   };
 
   #[ntex::test]
@@ -508,6 +547,68 @@ mod tests {
     test_status_code!(res.status(), http::StatusCode::OK, "basic cargo stats");
   }
 
+  #[ntex::test]
+  async fn stats_sse() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let mut res = client
+      .get("/processes/cargo/nstore/stats")
+      .query(&ProcessStatsQuery {
+        namespace: Some("system".to_owned()),
+        stream: Some(false),
+        one_shot: Some(true),
+      })
+      .unwrap()
+      .header("Accept", "text/event-stream")
+      .send()
+      .await
+      .unwrap();
+    test_status_code!(res.status(), http::StatusCode::OK, "cargo stats sse");
+    let body = res.body().await.unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    let data = body.lines().next().unwrap().strip_prefix("data: ").unwrap();
+    let stats = serde_json::from_str::<ProcessStats>(data).unwrap();
+    assert_eq!(stats.name, "nstore.system.c");
+  }
+
+  #[ntex::test]
+  async fn stats_summary() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let count = utils::process_stats::sample(&system.state).await.unwrap();
+    assert!(count > 0);
+    let mut res = client
+      .send_get(
+        "/processes/cargo/nstore/stats/summary",
+        Some(ProcessStatsSummaryQuery {
+          namespace: Some("system".to_owned()),
+          since: None,
+        }),
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "stats summary");
+    let summaries = res.json::<Vec<ProcessStatsSummary>>().await.unwrap();
+    let summary = summaries
+      .iter()
+      .find(|summary| summary.name == "nstore.system.c")
+      .unwrap();
+    assert!(summary.samples > 0);
+    assert!(summary.memory_max > 0.0);
+    // A vm with the same key doesn't get the samples of the cargo
+    let mut res = client
+      .send_get(
+        "/processes/vm/nstore/stats/summary",
+        Some(ProcessStatsSummaryQuery {
+          namespace: Some("system".to_owned()),
+          since: None,
+        }),
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "vm stats summary");
+    let summaries = res.json::<Vec<ProcessStatsSummary>>().await.unwrap();
+    assert!(summaries.is_empty());
+  }
+
   #[ntex::test]
   async fn list_by() {
     let system = gen_default_test_system().await;
@@ -0,0 +1,24 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+
+use crate::{models::SystemState, utils};
+
+/// Metrics of the node, processes, objects, tasks and http handlers
+/// in the OpenMetrics text format to be scraped by Prometheus.
+/// Not in the api spec since it's not versioned and it isn't json
+#[web::get("/metrics")]
+pub async fn get_prometheus(
+  state: web::types::State<SystemState>,
+) -> HttpResult<web::HttpResponse> {
+  let metrics = utils::prometheus::render(&state).await?;
+  Ok(
+    web::HttpResponse::Ok()
+      .content_type(utils::prometheus::CONTENT_TYPE)
+      .body(metrics),
+  )
+}
+
+pub fn ntex_config(config: &mut web::ServiceConfig) {
+  config.service(get_prometheus);
+}
@@ -16,7 +16,7 @@ use crate::{
   models::{SecretDb, SystemState},
   objects::generic::*,
   repositories::generic::*,
-  utils,
+  utils, vars,
 };
 
 /// List secret
@@ -94,6 +94,10 @@ pub async fn create_secret(
       serde_json::from_value::<DockerCredentials>(payload.data.clone())
         .map_err(|e| HttpError::bad_request(e.to_string()))?;
     }
+    vars::WEBHOOK_SECRET_KIND => {
+      serde_json::from_value::<String>(payload.data.clone())
+        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+    }
     _ => {}
   }
   let secret = SecretDb::create_obj(&payload, &state).await?;
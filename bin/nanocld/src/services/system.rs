@@ -3,10 +3,10 @@ use ntex::web;
 
 use nanocl_error::http::HttpResult;
 
-use nanocl_stubs::system::HostInfo;
+use nanocl_stubs::system::{HealthStatus, HostInfo};
 
 use crate::models::SystemState;
-use crate::vars;
+use crate::{utils, vars};
 
 /// Get version information
 #[cfg_attr(feature = "dev", utoipa::path(
@@ -54,31 +54,122 @@ pub async fn get_version() -> web::HttpResponse {
 pub async fn get_info(
   state: web::types::State<SystemState>,
 ) -> HttpResult<web::HttpResponse> {
-  let docker = state.inner.docker_api.info().await?;
-  let host_gateway = state.inner.config.gateway.clone();
+  let docker = state.inner.runtime.info().await?;
+  let host_gateway = state.config().gateway.clone();
   let network = state
     .inner
-    .docker_api
-    .inspect_network("nanoclbr0", None::<InspectNetworkOptions<String>>)
+    .runtime
+    .inspect_network(
+      vars::DEFAULT_NETWORK,
+      None::<InspectNetworkOptions<String>>,
+    )
     .await?;
   let info = HostInfo {
     docker,
     host_gateway,
     network,
-    config: state.inner.config.clone(),
+    config: state.config().as_ref().clone(),
   };
   Ok(web::HttpResponse::Ok().json(&info))
 }
 
+/// Liveness of the daemon with the health of each of its dependencies.
+/// Fail only when the daemon is stuck and must be restarted
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "System",
+  path = "/healthz",
+  responses(
+    (status = 200, description = "Daemon is alive", body = HealthReport),
+    (status = 503, description = "Daemon is stuck", body = HealthReport),
+  ),
+))]
+#[web::get("/healthz")]
+pub async fn get_healthz(
+  state: web::types::State<SystemState>,
+) -> HttpResult<web::HttpResponse> {
+  let report = utils::health::report(&state).await;
+  let is_stuck = report.checks.iter().any(|check| {
+    check.name == "event_loop" && check.status == HealthStatus::Down
+  });
+  if is_stuck {
+    return Ok(web::HttpResponse::ServiceUnavailable().json(&report));
+  }
+  Ok(web::HttpResponse::Ok().json(&report))
+}
+
+/// Readiness of the daemon with the health of each of its dependencies.
+/// Fail when a critical dependency is down or when the daemon is shutting down
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "System",
+  path = "/readyz",
+  responses(
+    (status = 200, description = "Daemon is ready", body = HealthReport),
+    (status = 503, description = "Daemon isn't ready", body = HealthReport),
+  ),
+))]
+#[web::get("/readyz")]
+pub async fn get_readyz(
+  state: web::types::State<SystemState>,
+) -> HttpResult<web::HttpResponse> {
+  let report = utils::health::report(&state).await;
+  if report.status == HealthStatus::Down || state.is_shutting_down() {
+    return Ok(web::HttpResponse::ServiceUnavailable().json(&report));
+  }
+  Ok(web::HttpResponse::Ok().json(&report))
+}
+
+/// Reload the config of the daemon and apply the settings that can change live
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "System",
+  path = "/system/config/reload",
+  responses(
+    (status = 200, description = "Settings applied and requiring a restart", body = DaemonConfigReload),
+  ),
+))]
+#[web::post("/system/config/reload")]
+pub async fn reload_config(
+  state: web::types::State<SystemState>,
+) -> HttpResult<web::HttpResponse> {
+  let reload = utils::system::reload_config(&state).await?;
+  Ok(web::HttpResponse::Ok().json(&reload))
+}
+
+/// Statistics of the pruning of the events and the metrics past their retention
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "System",
+  path = "/system/prune/stats",
+  responses(
+    (status = 200, description = "Statistics of the last prune", body = PruneStats),
+  ),
+))]
+#[web::get("/system/prune/stats")]
+pub async fn get_prune_stats(
+  state: web::types::State<SystemState>,
+) -> HttpResult<web::HttpResponse> {
+  let stats = utils::retention::stats(&state);
+  Ok(web::HttpResponse::Ok().json(&stats))
+}
+
 pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(get_ping);
   config.service(get_version);
   config.service(get_info);
+  config.service(get_healthz);
+  config.service(get_readyz);
+  config.service(reload_config);
+  config.service(get_prune_stats);
 }
 
 #[cfg(test)]
 mod tests {
-  use nanocl_stubs::system::HostInfo;
+  use nanocl_stubs::{
+    config::DaemonConfigReload,
+    system::{HealthReport, HealthStatus, HostInfo, PruneStats},
+  };
   use ntex::http;
 
   use crate::services::ntex_config;
@@ -93,6 +184,49 @@ mod tests {
     let _ = res.json::<HostInfo>().await.unwrap();
   }
 
+  #[ntex::test]
+  async fn health() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let mut res = client.send_get("/healthz", None::<String>).await;
+    test_status_code!(res.status(), http::StatusCode::OK, "healthz");
+    let report = res.json::<HealthReport>().await.unwrap();
+    let store = report
+      .checks
+      .iter()
+      .find(|check| check.name == "store")
+      .expect("Expect a store check");
+    assert_eq!(store.status, HealthStatus::Up);
+    let mut res = client.send_get("/readyz", None::<String>).await;
+    test_status_code!(res.status(), http::StatusCode::OK, "readyz");
+    let report = res.json::<HealthReport>().await.unwrap();
+    assert_ne!(report.status, HealthStatus::Down);
+  }
+
+  #[ntex::test]
+  async fn reload_config() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let mut res = client
+      .send_post("/system/config/reload", None::<String>, None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "reload config");
+    let _ = res.json::<DaemonConfigReload>().await.unwrap();
+  }
+
+  #[ntex::test]
+  async fn prune() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let stats = crate::utils::retention::prune(&system.state).await.unwrap();
+    assert!(stats.last_prune.is_some());
+    assert!(stats.deleted.contains_key("metrics"));
+    let mut res = client.send_get("/system/prune/stats", None::<String>).await;
+    test_status_code!(res.status(), http::StatusCode::OK, "prune stats");
+    let res = res.json::<PruneStats>().await.unwrap();
+    assert_eq!(res.last_prune, stats.last_prune);
+  }
+
   #[ntex::test]
   async fn wrong_version() {
     let client = gen_test_system(ntex_config, "13.44").await.client;
@@ -0,0 +1,143 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+
+use nanocl_stubs::generic::{GenericCount, GenericListQuery};
+
+use crate::{
+  models::{SystemState, TaskDb},
+  repositories::generic::*,
+  utils,
+};
+
+/// List tasks in progress
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Tasks",
+  path = "/tasks",
+  params(
+    ("filter" = Option<String>, Query, description = "Generic filter", example = "{ \"filter\": { \"where\": { \"action\": { \"eq\": \"updating\" } } } }"),
+  ),
+  responses(
+    (status = 200, description = "List of tasks", body = [Task]),
+  ),
+))]
+#[web::get("/tasks")]
+pub async fn list_task(
+  state: web::types::State<SystemState>,
+  qs: web::types::Query<GenericListQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let filter = utils::query_string::parse_qs_filter(&qs)?;
+  let items = TaskDb::transform_read_by(&filter, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Ok().json(&items))
+}
+
+/// Count tasks in progress
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Tasks",
+  path = "/tasks/count",
+  params(
+    ("filter" = Option<String>, Query, description = "Generic filter", example = "{ \"filter\": { \"where\": { \"action\": { \"eq\": \"updating\" } } } }"),
+  ),
+  responses(
+    (status = 200, description = "Count result", body = GenericCount),
+  ),
+))]
+#[web::get("/tasks/count")]
+pub async fn count_task(
+  state: web::types::State<SystemState>,
+  qs: web::types::Query<GenericListQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let filter = utils::query_string::parse_qs_filter(&qs)?;
+  let count = TaskDb::count_by(&filter, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Ok().json(&GenericCount { count }))
+}
+
+/// Get the number of tasks running and waiting in the queues of this node
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Tasks",
+  path = "/tasks/stats",
+  responses(
+    (status = 200, description = "Stats of the task queues", body = TaskStats),
+  ),
+))]
+#[web::get("/tasks/stats")]
+pub async fn stats_task(
+  state: web::types::State<SystemState>,
+) -> HttpResult<web::HttpResponse> {
+  let stats = state.inner.task_manager.stats().await;
+  Ok(web::HttpResponse::Ok().json(&stats))
+}
+
+/// Cancel a task in progress
+#[cfg_attr(feature = "dev", utoipa::path(
+  delete,
+  tag = "Tasks",
+  path = "/tasks/{key}",
+  params(
+    ("key" = String, Path, description = "Key of the task")
+  ),
+  responses(
+    (status = 202, description = "Task have been cancelled"),
+    (status = 404, description = "Task don't exists", body = ApiError),
+  ),
+))]
+#[web::delete("/tasks/{key}")]
+pub async fn cancel_task(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+) -> HttpResult<web::HttpResponse> {
+  utils::task::cancel(&path.1, &state).await?;
+  Ok(web::HttpResponse::Accepted().into())
+}
+
+pub fn ntex_config(config: &mut web::ServiceConfig) {
+  config.service(list_task);
+  config.service(count_task);
+  config.service(stats_task);
+  config.service(cancel_task);
+}
+
+#[cfg(test)]
+mod tests {
+  use ntex::http;
+
+  use nanocl_stubs::{
+    generic::GenericCount,
+    task::{Task, TaskStats},
+  };
+
+  use crate::utils::tests::*;
+
+  const ENDPOINT: &str = "/tasks";
+
+  #[ntex::test]
+  async fn basic() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let mut res = client.send_get(ENDPOINT, None::<String>).await;
+    test_status_code!(res.status(), http::StatusCode::OK, "list tasks");
+    let _ = res.json::<Vec<Task>>().await.unwrap();
+    let mut res = client
+      .send_get(&format!("{ENDPOINT}/count"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "count tasks");
+    let _ = res.json::<GenericCount>().await.unwrap();
+    let mut res = client
+      .send_get(&format!("{ENDPOINT}/stats"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "stats tasks");
+    let stats = res.json::<TaskStats>().await.unwrap();
+    assert!(stats.max_workers > 0);
+    let res = client
+      .send_delete(&format!("{ENDPOINT}/Cargo@unknown.global"), None::<String>)
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::NOT_FOUND,
+      "cancel unknown task"
+    );
+  }
+}
@@ -214,7 +214,7 @@ async fn ws_attach_service(
   let (scmd, mut rcmd) = mpsc::channel::<Result<Bytes, web::Error>>();
   let stream = state
     .inner
-    .docker_api
+    .runtime
     .attach_container(
       &format!("{key}.v"),
       Some(AttachContainerOptions::<String> {
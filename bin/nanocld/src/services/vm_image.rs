@@ -65,7 +65,7 @@ pub async fn import_vm_image(
   {
     return Err(HttpError::conflict(format!("Vm image {name} already used")));
   }
-  let state_dir = state.inner.config.state_dir.clone();
+  let state_dir = state.config().state_dir.clone();
   let vm_images_dir = format!("{state_dir}/vms/images");
   let filepath = format!("{vm_images_dir}/{name}.img");
   let fp = filepath.clone();
@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use ntex::{rt, time};
+
+use crate::{models::SystemState, utils};
+
+/// Delay between two evaluations of the alert rules in seconds
+const EVALUATE_DELAY: u64 = 15;
+
+/// Spawn a background thread that periodically evaluate the alert rules
+/// of the node and the unassigned ones when it holds the lease.
+pub fn spawn(state: &SystemState) {
+  let state = state.clone();
+  rt::spawn(async move {
+    loop {
+      time::sleep(Duration::from_secs(EVALUATE_DELAY)).await;
+      if state.is_shutting_down() {
+        break;
+      }
+      log::trace!("alert::spawn: evaluating rules");
+      if let Err(err) = utils::alert::evaluate(&state).await {
+        log::warn!("alert::spawn: {err}");
+      }
+    }
+  });
+}
@@ -58,7 +58,7 @@ async fn exec_docker(
   let action = action.as_str();
   let mut event = EventPartial {
     reporting_controller: vars::CONTROLLER_NAME.to_owned(),
-    reporting_node: state.inner.config.hostname.clone(),
+    reporting_node: state.config().hostname.clone(),
     kind: EventKind::Normal,
     action: NativeEventAction::Destroy.to_string(),
     related: Some(EventActor {
@@ -170,7 +170,7 @@ async fn exec_docker(
   state.spawn_emit_event(event);
   let instance = state
     .inner
-    .docker_api
+    .runtime
     .inspect_container(&id, None::<InspectContainerOptions>)
     .await
     .map_err(|err| err.map_err_context(|| "Docker event"))?;
@@ -193,7 +193,7 @@ pub fn analyze(state: &SystemState) {
     rt::spawn(async move {
       loop {
         let mut streams =
-          state.inner.docker_api.events(None::<EventsOptions<String>>);
+          state.inner.runtime.events(None::<EventsOptions<String>>);
         log::info!("event::analyze_docker: stream connected");
         while let Some(event) = streams.next().await {
           match event {
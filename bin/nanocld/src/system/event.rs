@@ -12,14 +12,15 @@ use nanocl_stubs::{
 };
 
 use crate::{
-  models::{CargoDb, JobDb, ObjPsStatusDb, ProcessDb, SystemState, VmDb},
+  models::{
+    CargoDb, JobDb, ObjPsStatusDb, ProcessDb, SystemState, TaskManager,
+  },
   objects::generic::*,
   repositories::generic::*,
-  tasks::generic::*,
-  utils,
+  utils, vars,
 };
 
-/// Remove a job after when finished and ttl is set
+/// Remove a job when finished and ttl is set
 async fn job_ttl(actor: &EventActor, state: &SystemState) -> IoResult<()> {
   let attributes = actor.attributes.clone().unwrap_or_default();
   let job_id = match attributes.get("io.nanocl.j") {
@@ -81,131 +82,71 @@ async fn job_ttl(actor: &EventActor, state: &SystemState) -> IoResult<()> {
   Ok(())
 }
 
-fn starting(
-  key: &str,
+/// Apply network policies again when a `nanocl.io/network-policy` resource
+/// is created, updated or deleted
+fn network_policy(
   actor: &EventActor,
+  action: &NativeEventAction,
   state: &SystemState,
-) -> Option<ObjTaskFuture> {
-  match actor.kind {
-    EventActorKind::Job => {
-      let task = JobDb::create_start_task(key, state);
-      Some(task)
-    }
-    EventActorKind::Cargo => {
-      let task = CargoDb::create_start_task(key, state);
-      Some(task)
-    }
-    EventActorKind::Vm => {
-      let task = VmDb::create_start_task(key, state);
-      Some(task)
-    }
-    _ => None,
+) {
+  if actor.kind != EventActorKind::Resource {
+    return;
   }
-}
-
-/// Handle delete event for living objects (job, cargo, vm)
-/// by checking if the event is `NativeEventAction::Deleting`
-/// and pushing into the task manager the task to delete the object
-fn destroying(
-  key: &str,
-  actor: &EventActor,
-  state: &SystemState,
-) -> Option<ObjTaskFuture> {
-  match actor.kind {
-    EventActorKind::Cargo => {
-      log::debug!("handling delete event for cargo {key}");
-      let task = CargoDb::create_delete_task(key, state);
-      Some(task)
-    }
-    EventActorKind::Vm => {
-      let task = VmDb::create_delete_task(key, state);
-      Some(task)
-    }
-    EventActorKind::Job => {
-      let task = JobDb::create_delete_task(key, state);
-      Some(task)
-    }
-    _ => None,
+  match action {
+    NativeEventAction::Create
+    | NativeEventAction::Update
+    | NativeEventAction::Destroy => {}
+    _ => return,
   }
-}
-
-fn updating(
-  key: &str,
-  actor: &EventActor,
-  state: &SystemState,
-) -> Option<ObjTaskFuture> {
-  match actor.kind {
-    EventActorKind::Cargo => {
-      let task = CargoDb::create_update_task(key, state);
-      Some(task)
-    }
-    EventActorKind::Vm => {
-      let task = VmDb::create_update_task(key, state);
-      Some(task)
-    }
-    _ => None,
+  let attributes = actor.attributes.clone().unwrap_or_default();
+  let kind = attributes
+    .get("Kind")
+    .and_then(|kind| kind.as_str())
+    .unwrap_or_default();
+  if kind != vars::NETWORK_POLICY_KIND {
+    return;
   }
+  let state = state.clone();
+  rt::spawn(async move {
+    if let Err(err) = utils::namespace::sync_network_policies(&state).await {
+      log::error!("event::network_policy: {err}");
+    }
+  });
 }
 
+/// When a secret is updated the cargoes using it are updated too
 async fn update(
   key: &str,
   actor: &EventActor,
   state: &SystemState,
-) -> Option<ObjTaskFuture> {
-  match actor.kind {
-    // If a secret is updated we check for the cargoes using it and fire an update for them
-    EventActorKind::Secret => {
-      log::debug!("handling update event for secret {key}");
-      let filter = GenericFilter::new().r#where(
-        "data",
-        GenericClause::Contains(serde_json::json!({
-          "Secrets": [
-            key
-          ]
-        })),
-      );
-      let cargoes = CargoDb::transform_read_by(&filter, &state.inner.pool)
-        .await
-        .unwrap();
-      log::debug!("found {} cargoes using secret {key}", cargoes.len());
-      for cargo in &cargoes {
-        ObjPsStatusDb::update_actual_status(
-          &cargo.spec.cargo_key,
-          &ObjPsStatusKind::Updating,
-          &state.inner.pool,
-        )
-        .await
-        .ok();
-        state
-          .emit_normal_native_action_sync(cargo, NativeEventAction::Updating)
-          .await;
-      }
-      None
-    }
-    _ => None,
+) -> IoResult<()> {
+  if actor.kind != EventActorKind::Secret {
+    return Ok(());
   }
-}
-
-fn stopping(
-  key: &str,
-  actor: &EventActor,
-  state: &SystemState,
-) -> Option<ObjTaskFuture> {
-  match actor.kind {
-    EventActorKind::Cargo => {
-      let task = CargoDb::create_stop_task(key, state);
-      Some(task)
-    }
-    EventActorKind::Vm => {
-      let task = VmDb::create_stop_task(key, state);
-      Some(task)
-    }
-    EventActorKind::Job => {
-      let task = JobDb::create_stop_task(key, state);
-      Some(task)
-    }
-    _ => None,
+  log::debug!("handling update event for secret {key}");
+  let filter = GenericFilter::new().r#where(
+    "data",
+    GenericClause::Contains(serde_json::json!({
+      "Secrets": [
+        key
+      ]
+    })),
+  );
+  let cargoes = CargoDb::transform_read_by(&filter, &state.inner.pool).await?;
+  log::debug!("found {} cargoes using secret {key}", cargoes.len());
+  for cargo in &cargoes {
+    ObjPsStatusDb::update_actual_status(
+      &cargo.spec.cargo_key,
+      &ObjPsStatusKind::Updating,
+      &state.inner.pool,
+    )
+    .await
+    .ok();
+    state
+      .emit_normal_native_action_sync(cargo, NativeEventAction::Updating)
+      .await;
   }
+  Ok(())
 }
 
 /// Take action when event is received
@@ -227,64 +168,25 @@ pub async fn exec_event(e: &Event, state: &SystemState) -> IoResult<()> {
     e.action,
     actor.key.clone().unwrap_or_default()
   );
-  // Specific key of the task for this object
-  // If a task is already running for this object, we wait for it to finish
-  // This is to avoid data races conditions when manipulating an object
-  let task_key = format!("{}@{key}", &actor.kind);
   let action = NativeEventAction::from_str(e.action.as_str())?;
-  match (&actor.kind, &action) {
-    (EventActorKind::Cargo | EventActorKind::Vm, _) => {
-      state.inner.task_manager.wait_task(&task_key).await;
-    }
-    (EventActorKind::Job, NativeEventAction::Destroying) => {
-      log::debug!("Removing task for job {key}");
-      state.inner.task_manager.remove_task(&task_key).await;
+  network_policy(actor, &action, state);
+  match action {
+    NativeEventAction::Update => update(&key, actor, state).await?,
+    NativeEventAction::Die => job_ttl(actor, state).await?,
+    _ if TaskManager::is_handled(actor, &action) => {
+      // Destroying a job stop his running task instead of waiting for it
+      if actor.kind == EventActorKind::Job
+        && action == NativeEventAction::Destroying
+      {
+        log::debug!("Removing task for job {key}");
+        let task_key = TaskManager::gen_key(actor);
+        state.inner.task_manager.remove_task(&task_key, state).await;
+      }
+      // Tasks of the same object are queued to avoid data races
+      // when manipulating an object
+      state.inner.task_manager.push(actor, action, state).await;
     }
     _ => {}
   }
-  let task: Option<ObjTaskFuture> = match action {
-    NativeEventAction::Starting => starting(&key, actor, state),
-    NativeEventAction::Stopping => stopping(&key, actor, state),
-    NativeEventAction::Updating => updating(&key, actor, state),
-    NativeEventAction::Update => update(&key, actor, state).await,
-    NativeEventAction::Destroying => destroying(&key, actor, state),
-    NativeEventAction::Die => {
-      job_ttl(actor, state).await?;
-      None
-    }
-    _ => None,
-  };
-  let Some(task) = task else { return Ok(()) };
-  // push the task into the task manager
-  let state_ptr = state.clone();
-  let actor = actor.clone();
-  state
-    .inner
-    .task_manager
-    .add_task(&task_key, action.clone(), task, |err| async move {
-      log::error!(
-        "exec_event add_task: error {action} {} {:#?} {err}",
-        actor.kind,
-        actor.key
-      );
-      let action = match action {
-        NativeEventAction::Starting => NativeEventAction::Start,
-        NativeEventAction::Stopping => NativeEventAction::Stop,
-        NativeEventAction::Updating => NativeEventAction::Update,
-        NativeEventAction::Destroying => NativeEventAction::Destroy,
-        _ => return Ok(()),
-      };
-      let state = state_ptr.clone();
-      let key = actor.key.clone().unwrap_or_default();
-      ObjPsStatusDb::update_actual_status(
-        &key,
-        &ObjPsStatusKind::Fail,
-        &state.inner.pool,
-      )
-      .await?;
-      state_ptr.emit_error_native_action(&actor, action, Some(err.to_string()));
-      Ok(())
-    })
-    .await;
   Ok(())
 }
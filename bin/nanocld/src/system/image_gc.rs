@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use ntex::{rt, time};
+
+use crate::{models::SystemState, utils};
+
+/// Delay before checking again a policy that isn't set
+const IDLE_DELAY: u64 = 60;
+
+/// Spawn a background thread that periodically remove the container images
+/// according to the garbage collection policy of the daemon config.
+/// The policy is read before each collection to follow the reloads of the config,
+/// nothing is collected while it isn't set.
+pub fn spawn(state: &SystemState) {
+  let state = state.clone();
+  rt::spawn(async move {
+    loop {
+      let delay = state
+        .config()
+        .image_gc
+        .as_ref()
+        .map(|config| config.interval.max(1))
+        .unwrap_or(IDLE_DELAY);
+      time::sleep(Duration::from_secs(delay)).await;
+      let Some(config) = state.config().image_gc.clone() else {
+        continue;
+      };
+      log::trace!("image_gc::spawn: collecting");
+      match utils::container::image::gc(&config, &state).await {
+        Ok(res) if !res.images_deleted.is_empty() => {
+          log::info!(
+            "image_gc::spawn: removed {} images reclaiming {} bytes",
+            res.images_deleted.len(),
+            res.space_reclaimed
+          );
+        }
+        Ok(_) => {}
+        Err(err) => log::warn!("image_gc::spawn: {err}"),
+      }
+    }
+  });
+}
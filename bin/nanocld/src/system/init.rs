@@ -125,10 +125,13 @@ pub async fn init(conf: &DaemonConfig) -> IoResult<SystemState> {
   NodeDb::register(&system_ptr).await?;
   utils::system::register_namespace("global", &system_ptr).await?;
   utils::system::register_namespace("system", &system_ptr).await?;
+  utils::system::register_resource_kinds(&system_ptr).await?;
   rt::spawn(async move {
     let fut = async move {
       utils::system::sync_processes(&system_ptr).await?;
       utils::system::sync_vm_images(&system_ptr).await?;
+      utils::system::resume_namespaces_deletion(&system_ptr).await?;
+      utils::task::resume(&system_ptr).await?;
       Ok::<_, IoError>(())
     };
     if let Err(err) = fut.await {
@@ -137,7 +140,13 @@ pub async fn init(conf: &DaemonConfig) -> IoResult<SystemState> {
     Ok::<_, IoError>(())
   });
   super::docker_event::analyze(&system_state);
+  super::alert::spawn(&system_state);
   super::metric::spawn(&system_state);
+  super::image_gc::spawn(&system_state);
+  super::process_stats::spawn(&system_state);
+  super::reconcile::spawn(&system_state);
+  super::retention::spawn(&system_state);
+  super::rollup::spawn(&system_state);
   Ok(system_state)
 }
 
@@ -177,7 +186,7 @@ mod tests {
     // Test state
     let state = init(&config).await.unwrap();
     let state_ptr = state.clone();
-    let mut raw_sub = state.subscribe_raw(None).await.unwrap();
+    let mut raw_sub = state.subscribe_raw(None, None).await.unwrap();
     rt::spawn(async move {
       ntex::time::sleep(std::time::Duration::from_secs(1)).await;
       let actor = Resource::default();
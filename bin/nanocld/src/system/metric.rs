@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{sync::atomic::Ordering, time::Duration};
 
 use futures::StreamExt;
 use ntex::{rt, time::interval};
@@ -8,20 +8,17 @@ use nanocl_error::io::IoResult;
 use metrsd_client::{stubs::MetrsdEvent, MetrsdClient};
 
 use crate::{
-  models::{MetricDb, MetricNodePartial, Pool, SystemState},
+  models::{MetricDb, MetricNodePartial, SystemState},
   repositories::generic::*,
+  utils,
 };
 
 /// Save metric event send by [metrsd](http://github.com/next-hat/metrs) to the database
 /// The event can be a `CPU`, `MEMORY`, `DISK` or `NETWORK` event.
 /// The metric is saved for the current node.
 /// This allow us to know what node is the most used.
-async fn save_metric(
-  node: &str,
-  ev: &MetrsdEvent,
-  pool: &Pool,
-) -> IoResult<()> {
-  let node_name = node.to_owned();
+async fn save_metric(ev: &MetrsdEvent, state: &SystemState) -> IoResult<()> {
+  let node_name = state.config().hostname.clone();
   let kind = "nanocl.io/metrs";
   let data = serde_json::to_value(ev)?;
   let mut cpu_percent = ev.cpus.iter().fold(0.0, |acc, cpu| acc + cpu.usage);
@@ -46,8 +43,12 @@ async fn save_metric(
     node_name,
     kind: kind.to_owned(),
     note: Some(display),
+    expires_at: utils::store::gen_expires_at(
+      chrono::Utc::now().naive_utc(),
+      state.config().retention.metric(kind),
+    ),
   };
-  MetricDb::create_from(&metric, pool).await?;
+  MetricDb::create_from(&metric, &state.inner.pool).await?;
   Ok(())
 }
 
@@ -64,16 +65,15 @@ pub fn spawn(state: &SystemState) {
         match client.subscribe().await {
           Ok(mut stream) => {
             log::info!("metrics::spawn_logger: subscribed");
+            state.inner.metrics_subscribed.store(true, Ordering::SeqCst);
             while let Some(res) = stream.next().await {
               match res {
                 Ok(ev) => {
-                  if let Err(err) = save_metric(
-                    &state.inner.config.hostname,
-                    &ev,
-                    &state.inner.pool,
-                  )
-                  .await
+                  if let Ok(mut node_metrics) = state.inner.node_metrics.lock()
                   {
+                    *node_metrics = Some(ev.clone());
+                  }
+                  if let Err(err) = save_metric(&ev, &state).await {
                     log::warn!("metrics::spawn_logger: {err}");
                   }
                 }
@@ -88,6 +88,10 @@ pub fn spawn(state: &SystemState) {
             log::warn!("metrics::spawn_logger: {err}")
           }
         }
+        state
+          .inner
+          .metrics_subscribed
+          .store(false, Ordering::SeqCst);
         log::warn!("metrics::spawn_logger: reconnecting in 2 seconds...");
         interval(Duration::from_secs(2)).tick().await;
       }
@@ -1,8 +1,16 @@
+mod alert;
 mod docker_event;
 mod event;
+mod image_gc;
 mod init;
 mod metric;
+mod process_stats;
+mod reconcile;
+mod retention;
+mod rollup;
+mod signal;
 mod system_state;
 
 pub use event::exec_event;
 pub use init::init;
+pub use signal::handle_signals;
@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+use ntex::{rt, time};
+
+use crate::{models::SystemState, utils};
+
+/// Delay before checking again a sampling that is disabled
+const IDLE_DELAY: u64 = 60;
+
+/// Spawn a background thread that periodically sample the resource usage
+/// of the processes running on the node and save them as metrics.
+/// The interval is read before each sampling to follow the reloads of the config,
+/// nothing is sampled while it's set to 0.
+pub fn spawn(state: &SystemState) {
+  let state = state.clone();
+  rt::spawn(async move {
+    loop {
+      let interval = state.config().process_stats.interval;
+      let delay = if interval == 0 { IDLE_DELAY } else { interval };
+      time::sleep(Duration::from_secs(delay)).await;
+      if state.config().process_stats.interval == 0 {
+        continue;
+      }
+      log::trace!("process_stats::spawn: sampling");
+      match utils::process_stats::sample(&state).await {
+        Ok(count) => {
+          log::debug!("process_stats::spawn: saved {count} samples");
+        }
+        Err(err) => log::warn!("process_stats::spawn: {err}"),
+      }
+    }
+  });
+}
@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use ntex::{rt, time};
+
+use crate::{models::SystemState, utils};
+
+/// Delay before checking again a reconciliation that is disabled
+const IDLE_DELAY: u64 = 60;
+
+/// Spawn a background thread that periodically repair the drifts
+/// between the wanted state of the objects and their processes.
+/// The interval is read before each reconciliation to follow the reloads of the config,
+/// nothing is reconciled while it's set to 0.
+pub fn spawn(state: &SystemState) {
+  let state = state.clone();
+  rt::spawn(async move {
+    loop {
+      // The processes are synced at boot so we wait before the first run
+      let interval = state.config().reconcile.interval;
+      let delay = if interval == 0 { IDLE_DELAY } else { interval };
+      time::sleep(Duration::from_secs(delay)).await;
+      if state.config().reconcile.interval == 0 {
+        continue;
+      }
+      log::trace!("reconcile::spawn: reconciling");
+      match utils::reconcile::reconcile(&state).await {
+        Ok(count) if count > 0 => {
+          log::info!("reconcile::spawn: corrected {count} drifts");
+        }
+        Ok(_) => {}
+        Err(err) => log::warn!("reconcile::spawn: {err}"),
+      }
+    }
+  });
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use ntex::{rt, time};
+
+use crate::{
+  models::{NodeDb, SystemState},
+  utils,
+};
+
+/// Name of the lease electing the node pruning the store
+const LEASE: &str = "retention";
+
+/// Spawn a background thread that periodically delete the events
+/// and the metrics past their retention according to the daemon config.
+/// The config is read before each prune to follow the reloads.
+/// The store is shared so a single node holding the lease prunes it.
+pub fn spawn(state: &SystemState) {
+  let state = state.clone();
+  rt::spawn(async move {
+    loop {
+      let delay = state.config().retention.interval.max(1);
+      time::sleep(Duration::from_secs(delay)).await;
+      if state.is_shutting_down() {
+        break;
+      }
+      // The lease outlives a few intervals so the pruning node keeps it
+      match NodeDb::acquire_lease(
+        LEASE,
+        &state.config().hostname,
+        delay.saturating_mul(3),
+        &state.inner.pool,
+      )
+      .await
+      {
+        Ok(true) => {}
+        Ok(false) => {
+          log::trace!("retention::spawn: pruned by another node");
+          continue;
+        }
+        Err(err) => {
+          log::warn!("retention::spawn: {err}");
+          continue;
+        }
+      }
+      log::trace!("retention::spawn: pruning");
+      match utils::retention::prune(&state).await {
+        Ok(stats) => {
+          let deleted = stats.deleted.values().sum::<u64>();
+          if deleted > 0 {
+            log::info!(
+              "retention::spawn: pruned {deleted} rows in {}ms",
+              stats.duration
+            );
+          }
+        }
+        Err(err) => log::warn!("retention::spawn: {err}"),
+      }
+    }
+  });
+}
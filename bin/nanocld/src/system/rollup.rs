@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use ntex::{rt, time};
+
+use crate::{models::SystemState, utils};
+
+/// Delay between two rollups in seconds
+const ROLLUP_DELAY: u64 = 300;
+
+/// Spawn a background thread that periodically roll up the metrics of the node
+/// in coarser buckets to keep them cheaply once the raw metrics expired.
+pub fn spawn(state: &SystemState) {
+  let state = state.clone();
+  rt::spawn(async move {
+    loop {
+      time::sleep(Duration::from_secs(ROLLUP_DELAY)).await;
+      if state.is_shutting_down() {
+        break;
+      }
+      log::trace!("rollup::spawn: rolling up");
+      match utils::metric::rollup(&state).await {
+        Ok(count) => log::debug!("rollup::spawn: {count} buckets saved"),
+        Err(err) => log::warn!("rollup::spawn: {err}"),
+      }
+    }
+  });
+}
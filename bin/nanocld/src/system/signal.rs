@@ -0,0 +1,45 @@
+use ntex::rt;
+use tokio::signal::unix::{signal, SignalKind};
+
+use crate::{models::SystemState, utils};
+
+/// Wait for the signals of the daemon:
+/// - SIGHUP reload the config
+/// - SIGTERM or SIGINT shutdown the daemon gracefully before stopping the http server
+async fn wait_signals(state: SystemState) {
+  let signals = (
+    signal(SignalKind::hangup()),
+    signal(SignalKind::terminate()),
+    signal(SignalKind::interrupt()),
+  );
+  let (mut sighup, mut sigterm, mut sigint) = match signals {
+    (Ok(sighup), Ok(sigterm), Ok(sigint)) => (sighup, sigterm, sigint),
+    (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => {
+      log::error!("signal::wait_signals: {err}");
+      return;
+    }
+  };
+  loop {
+    tokio::select! {
+      _ = sighup.recv() => {
+        log::info!("signal::wait_signals: SIGHUP");
+        if let Err(err) = utils::system::reload_config(&state).await {
+          log::error!("signal::wait_signals: reload {err}");
+        }
+        continue;
+      }
+      _ = sigterm.recv() => log::info!("signal::wait_signals: SIGTERM"),
+      _ = sigint.recv() => log::info!("signal::wait_signals: SIGINT"),
+    }
+    break;
+  }
+  utils::shutdown::graceful(&state).await;
+  if let Some(server) = state.server() {
+    server.stop(true).await;
+  }
+}
+
+/// Spawn the handler of the signals of the daemon
+pub fn handle_signals(state: &SystemState) {
+  rt::spawn(wait_signals(state.clone()));
+}
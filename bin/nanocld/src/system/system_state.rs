@@ -1,50 +1,80 @@
-use std::sync::Arc;
+use std::{
+  collections::HashMap,
+  sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Mutex, RwLock,
+  },
+  time::Duration,
+};
 
 use futures::channel::mpsc;
 use futures_util::{SinkExt, StreamExt};
-use ntex::rt;
+use ntex::{rt, server::Server};
 
-use nanocl_error::io::{FromIo, IoError, IoResult};
+use nanocl_error::io::{IoError, IoResult};
 
 use nanocl_stubs::{
   config::DaemonConfig,
   system::{
     Event, EventActor, EventCondition, EventKind, EventPartial,
-    NativeEventAction,
+    NativeEventAction, PruneStats,
   },
 };
 
 use crate::{
   models::{
-    EventDb, RawEventEmitter, RawEventReceiver, SystemState, SystemStateInner,
-    TaskManager,
+    EventDb, EventWebhookCache, HttpMetrics, RawEventEmitter, RawEventReceiver,
+    SystemState, SystemStateInner, TaskManager,
   },
   repositories::generic::*,
+  runtime::{BollardRuntime, ContainerRuntime},
   utils, vars,
 };
 
 impl SystemState {
   /// Create a new instance of the system state
-  /// It will create the database connection pool and the docker client
+  /// It will create the database connection pool and the docker runtime
   /// and the event emitter
   pub async fn new(conf: &DaemonConfig) -> IoResult<Self> {
-    let docker = bollard_next::Docker::connect_with_unix(
-      &conf.docker_host,
-      120,
-      bollard_next::API_DEFAULT_VERSION,
-    )
-    .map_err(|err| err.map_err_context(|| "Docker"))?;
+    let runtime = Arc::new(BollardRuntime::connect(&conf.docker_host)?);
+    Self::with_runtime(conf, runtime).await
+  }
+
+  /// Create a new instance of the system state
+  /// with the given container runtime instead of the docker one
+  pub async fn with_runtime(
+    conf: &DaemonConfig,
+    runtime: Arc<dyn ContainerRuntime>,
+  ) -> IoResult<Self> {
     let pool = utils::store::init(conf).await?;
     let (sx, rx) = mpsc::unbounded();
     let system_state = SystemState {
       inner: Arc::new(SystemStateInner {
+        task_manager: TaskManager::new(
+          &pool,
+          &conf.hostname,
+          conf.task_workers,
+        ),
         pool,
-        docker_api: docker.clone(),
-        config: conf.to_owned(),
+        runtime,
+        config: RwLock::new(Arc::new(conf.to_owned())),
+        server: Mutex::new(None),
+        ssl_fingerprint: Mutex::new(None),
         event_emitter: sx,
         event_emitter_raw: RawEventEmitter::new(),
-        task_manager: TaskManager::new(),
         arbiter: rt::Arbiter::new(),
+        shutting_down: AtomicBool::new(false),
+        pending_events: AtomicUsize::new(0),
+        event_backlog: AtomicUsize::new(0),
+        metrics_subscribed: AtomicBool::new(false),
+        node_metrics: Mutex::new(None),
+        process_samples: Mutex::new(Vec::new()),
+        http_metrics: HttpMetrics::default(),
+        prune_stats: Mutex::new(PruneStats::default()),
+        webhooks: Mutex::new(HashMap::new()),
+        webhook_cache: Mutex::new(EventWebhookCache::default()),
+        pending_deliveries: AtomicUsize::new(0),
+        quota_locks: Mutex::new(HashMap::new()),
       }),
     };
     system_state.clone().run(rx);
@@ -54,6 +84,7 @@ impl SystemState {
   /// Start the system event loop
   /// It will handle events and execute some actions
   /// It will also emit the event to the raw event emitter for the http clients
+  /// and send it to the matching event webhooks
   fn run(self, mut rx: mpsc::UnboundedReceiver<Event>) {
     self.inner.arbiter.clone().exec_fn(move || {
       rt::spawn(async move {
@@ -61,6 +92,8 @@ impl SystemState {
           if let Err(err) = super::exec_event(&e, &self).await {
             log::error!("system::run: exec_event {err}");
           }
+          self.inner.event_backlog.fetch_sub(1, Ordering::SeqCst);
+          utils::event_webhook::dispatch(&e, &self);
           let event_emitter_raw = self.inner.event_emitter_raw.clone();
           rt::spawn(async move {
             if let Err(err) = event_emitter_raw.emit(&e) {
@@ -74,37 +107,113 @@ impl SystemState {
 
   /// Emit an event to the system event loop
   pub async fn emit_event(&self, new_ev: EventPartial) -> IoResult<()> {
-    let ev: Event = EventDb::create_try_from(new_ev, &self.inner.pool)
-      .await?
-      .try_into()?;
-    self
-      .inner
-      .event_emitter
-      .clone()
-      .send(ev)
-      .await
-      .map_err(|err| {
-        IoError::interrupted("Event Emitter", err.to_string().as_str())
-      })?;
+    let retention = self.config().retention.events;
+    let ev: Event =
+      EventDb::create_try_from((new_ev, retention), &self.inner.pool)
+        .await?
+        .try_into()?;
+    self.inner.event_backlog.fetch_add(1, Ordering::SeqCst);
+    if let Err(err) = self.inner.event_emitter.clone().send(ev).await {
+      self.inner.event_backlog.fetch_sub(1, Ordering::SeqCst);
+      return Err(IoError::interrupted(
+        "Event Emitter",
+        err.to_string().as_str(),
+      ));
+    }
     Ok(())
   }
 
   /// Emit an event in the background to the system event loop
   pub fn spawn_emit_event(&self, event: EventPartial) {
     let self_ptr = self.clone();
+    self.inner.pending_events.fetch_add(1, Ordering::SeqCst);
     rt::spawn(async move {
       if let Err(err) = self_ptr.emit_event(event).await {
         log::warn!("system::spawn_emit_event: {err}");
       }
+      self_ptr.inner.pending_events.fetch_sub(1, Ordering::SeqCst);
     });
   }
 
+  /// Wait for the events emitted in the background to be saved in the store
+  /// Return false if some events are still pending after the timeout
+  pub async fn flush_events(&self, timeout: Duration) -> bool {
+    let wait = async {
+      while self.inner.pending_events.load(Ordering::SeqCst) > 0 {
+        ntex::time::sleep(Duration::from_millis(50)).await;
+      }
+    };
+    ntex::time::timeout(ntex::time::Millis::from(timeout), wait)
+      .await
+      .is_ok()
+  }
+
+  /// Get the current config of the daemon with the reloaded settings
+  pub fn config(&self) -> Arc<DaemonConfig> {
+    match self.inner.config.read() {
+      Ok(config) => config.clone(),
+      Err(err) => err.into_inner().clone(),
+    }
+  }
+
+  /// Replace the current config of the daemon
+  pub fn set_config(&self, config: DaemonConfig) {
+    match self.inner.config.write() {
+      Ok(mut current) => *current = Arc::new(config),
+      Err(err) => *err.into_inner() = Arc::new(config),
+    }
+  }
+
+  /// Keep the handle of the running http server
+  pub fn set_server(&self, server: &Server) {
+    if let Ok(mut current) = self.inner.server.lock() {
+      *current = Some(server.clone());
+    }
+  }
+
+  /// Keep the hash of the certificates loaded by the http listeners
+  pub fn set_ssl_fingerprint(&self, fingerprint: Option<[u8; 32]>) {
+    if let Ok(mut current) = self.inner.ssl_fingerprint.lock() {
+      *current = fingerprint;
+    }
+  }
+
+  /// Get the hash of the certificates loaded by the http listeners
+  pub fn ssl_fingerprint(&self) -> Option<[u8; 32]> {
+    self
+      .inner
+      .ssl_fingerprint
+      .lock()
+      .ok()
+      .and_then(|fingerprint| *fingerprint)
+  }
+
+  /// Get the handle of the running http server
+  pub fn server(&self) -> Option<Server> {
+    self
+      .inner
+      .server
+      .lock()
+      .ok()
+      .and_then(|server| server.clone())
+  }
+
+  /// Return true once the daemon started to shutdown
+  pub fn is_shutting_down(&self) -> bool {
+    self.inner.shutting_down.load(Ordering::SeqCst)
+  }
+
   /// Subscribe an http client to the event loop
   pub async fn subscribe_raw(
     &self,
     condition: Option<Vec<EventCondition>>,
+    filter: Option<EventCondition>,
   ) -> IoResult<RawEventReceiver> {
-    self.inner.event_emitter_raw.subscribe(condition).await
+    self
+      .inner
+      .event_emitter_raw
+      .subscribe(condition, filter)
+      .await
   }
 
   pub async fn emit_action_sync(
@@ -118,7 +227,7 @@ impl SystemState {
   ) {
     let event = EventPartial {
       reporting_controller: vars::CONTROLLER_NAME.to_owned(),
-      reporting_node: self.inner.config.hostname.clone(),
+      reporting_node: self.config().hostname.clone(),
       kind,
       action: action.to_string(),
       related: None,
@@ -143,7 +252,7 @@ impl SystemState {
   ) {
     let event = EventPartial {
       reporting_controller: vars::CONTROLLER_NAME.to_owned(),
-      reporting_node: self.inner.config.hostname.clone(),
+      reporting_node: self.config().hostname.clone(),
       kind,
       action: action.to_string(),
       related: None,
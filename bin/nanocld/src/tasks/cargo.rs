@@ -10,7 +10,6 @@ use nanocl_error::{
   io::IoError,
 };
 use nanocl_stubs::{
-  cargo_spec::ReplicationMode,
   process::ProcessKind,
   system::{NativeEventAction, ObjPsStatusKind},
 };
@@ -40,10 +39,12 @@ impl ObjTaskStart for CargoDb {
       //   MetricDb::find_best_nodes(90.0, 90.0, 100, &state.inner.pool).await?;
       // log::debug!("BEST NODES FOR CARGO {key}: {nodes:?}");
       if processes.is_empty() {
-        let number = match &cargo.spec.replication {
-          Some(ReplicationMode::Static(replication)) => replication.number,
-          _ => 1,
-        };
+        let number = utils::container::cargo::replicas(
+          &cargo.spec.replication,
+          &state.config().hostname,
+        )
+        .map(|replicas| replicas.number())
+        .unwrap_or(1);
         utils::container::cargo::create(&cargo, number, &state).await?;
       }
       utils::container::process::start_instances(
@@ -68,12 +69,12 @@ impl ObjTaskDelete for CargoDb {
       for process in processes {
         let _ = state
           .inner
-          .docker_api
+          .runtime
           .stop_container(&process.key, None::<StopContainerOptions>)
           .await;
         let _ = state
           .inner
-          .docker_api
+          .runtime
           .remove_container(&process.key, None::<RemoveContainerOptions>)
           .await;
       }
@@ -102,7 +103,7 @@ impl ObjTaskUpdate for CargoDb {
       processes
         .iter()
         .map(|process| {
-          let docker_api = state.inner.docker_api.clone();
+          let runtime = state.inner.runtime.clone();
           async move {
             if process
               .data
@@ -112,15 +113,15 @@ impl ObjTaskUpdate for CargoDb {
               .restarting
               .unwrap_or_default()
             {
-              docker_api
+              runtime
                 .stop_container(&process.name, None::<StopContainerOptions>)
                 .await?;
             }
             let new_name = format!("tmp-{}", process.name);
-            docker_api
+            runtime
               .rename_container(
                 &process.key,
-                RenameContainerOptions { name: &new_name },
+                RenameContainerOptions { name: new_name },
               )
               .await?;
             Ok::<_, HttpError>(())
@@ -131,22 +132,27 @@ impl ObjTaskUpdate for CargoDb {
         .await
         .into_iter()
         .collect::<HttpResult<Vec<_>>>()?;
-      let number = match &cargo.spec.replication {
-        Some(ReplicationMode::Static(replication)) => replication.number,
-        _ => 1,
-      };
+      let number = utils::container::cargo::replicas(
+        &cargo.spec.replication,
+        &state.config().hostname,
+      )
+      .map(|replicas| replicas.number())
+      .unwrap_or(1);
       // Create instance with the new spec
-      let new_instances =
-        match utils::container::cargo::create(&cargo, number, &state).await {
-          Err(err) => {
-            log::error!(
-              "Unable to create cargo instance {} : {err}",
-              cargo.spec.cargo_key
-            );
-            return Err(err.into());
-          }
-          Ok(instances) => instances,
-        };
+      if let Err(err) =
+        utils::container::cargo::create(&cargo, number, &state).await
+      {
+        log::error!(
+          "Unable to create cargo instance {} : {err}",
+          cargo.spec.cargo_key
+        );
+        if let Err(rollback_err) =
+          utils::container::cargo::rollback_update(&key, &state).await
+        {
+          log::error!("Unable to restore cargo instances: {rollback_err}");
+        }
+        return Err(err.into());
+      }
       // start created containers
       match utils::container::process::start_instances(
         &key,
@@ -160,38 +166,10 @@ impl ObjTaskUpdate for CargoDb {
             "Unable to start cargo instance {} : {err}",
             cargo.spec.cargo_key
           );
-          let state_ptr_ptr = state.clone();
-          let _ = utils::container::process::delete_instances(
-            &new_instances
-              .iter()
-              .map(|p| p.key.clone())
-              .collect::<Vec<_>>(),
-            &state_ptr_ptr,
-          )
-          .await;
-          let res = processes
-            .iter()
-            .map(|process| {
-              let docker_api = state_ptr_ptr.inner.docker_api.clone();
-              async move {
-                docker_api
-                  .rename_container(
-                    &process.key,
-                    RenameContainerOptions {
-                      name: &process.name,
-                    },
-                  )
-                  .await?;
-                Ok::<_, HttpError>(())
-              }
-            })
-            .collect::<FuturesUnordered<_>>()
-            .collect::<Vec<_>>()
-            .await
-            .into_iter()
-            .collect::<HttpResult<Vec<_>>>();
-          if let Err(err) = res {
-            log::error!("Unable to rename containers back: {err}");
+          if let Err(err) =
+            utils::container::cargo::rollback_update(&key, &state).await
+          {
+            log::error!("Unable to restore cargo instances: {err}");
           }
         }
         Ok(_) => {
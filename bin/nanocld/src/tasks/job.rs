@@ -41,14 +41,14 @@ impl ObjTaskStart for JobDb {
       for process in processes {
         let _ = state
           .inner
-          .docker_api
+          .runtime
           .start_container(&process.key, None::<StartContainerOptions<String>>)
           .await;
         // We currently run a sequential order so we wait for the container to finish to start the next one.
-        let mut stream = state.inner.docker_api.wait_container(
+        let mut stream = state.inner.runtime.wait_container(
           &process.key,
           Some(WaitContainerOptions {
-            condition: "not-running",
+            condition: "not-running".to_owned(),
           }),
         );
         while let Some(stream) = stream.next().await {
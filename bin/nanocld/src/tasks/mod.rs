@@ -2,5 +2,6 @@ pub mod generic;
 
 mod cargo;
 mod job;
+mod namespace;
 mod task_manager;
 mod vm;
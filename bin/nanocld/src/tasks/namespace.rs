@@ -0,0 +1,105 @@
+use nanocl_error::io::IoError;
+use nanocl_stubs::{
+  namespace::Namespace,
+  system::{
+    EventActor, EventActorKind, EventKind, EventPartial, NativeEventAction,
+  },
+};
+
+use crate::{
+  models::{CargoDb, NamespaceDb, ResourceDb, SystemState, TaskManager, VmDb},
+  objects::generic::*,
+  repositories::generic::*,
+  utils, vars,
+};
+
+use super::generic::*;
+
+/// Emit the progress of a cascading deletion
+/// The related actor is the object that have been removed
+fn emit_progress(
+  namespace: &Namespace,
+  related: EventActor,
+  step: usize,
+  total: usize,
+  state: &SystemState,
+) {
+  let event = EventPartial {
+    reporting_controller: vars::CONTROLLER_NAME.to_owned(),
+    reporting_node: state.config().hostname.clone(),
+    action: NativeEventAction::DestroyProgress.to_string(),
+    reason: "state_sync".to_owned(),
+    kind: EventKind::Normal,
+    actor: Some(namespace.clone().into()),
+    related: Some(related),
+    metadata: Some(serde_json::json!({
+      "Step": step,
+      "Total": total,
+    })),
+    note: Some(format!("Namespace {} {step}/{total}", namespace.name)),
+  };
+  state.spawn_emit_event(event);
+}
+
+impl ObjTaskDelete for NamespaceDb {
+  /// Delete everything inside a namespace in dependency order:
+  /// proxy and dns rules, cargoes, virtual machines, volumes and network.
+  /// Cargoes and virtual machines are deleted by their own tasks
+  /// awaited by this one.
+  /// Already removed objects are not listed anymore
+  /// so the task can be resumed after a restart of the daemon.
+  fn create_delete_task(key: &str, state: &SystemState) -> ObjTaskFuture {
+    let key = key.to_owned();
+    let state = state.clone();
+    Box::pin(async move {
+      let item = NamespaceDb::read_by_pk(&key, &state.inner.pool).await?;
+      let namespace: Namespace = item.clone().into();
+      let resources = utils::namespace::list_resources(&key, &state).await?;
+      let cargoes = CargoDb::read_by_namespace(&key, &state.inner.pool).await?;
+      let vms = VmDb::read_by_namespace(&key, &state.inner.pool).await?;
+      let volumes =
+        utils::namespace::list_volumes(&key, &cargoes, &state).await?;
+      let total = resources.len() + cargoes.len() + vms.len() + volumes.len();
+      let mut step = 0;
+      log::debug!("task::namespace: {key} deleting {total} objects");
+      for resource in resources {
+        step += 1;
+        ResourceDb::del_obj_by_pk(&resource.spec.resource_key, &(), &state)
+          .await?;
+        emit_progress(&namespace, resource.into(), step, total, &state);
+      }
+      let objects =
+        utils::namespace::push_delete_objects(&cargoes, &vms, true, &state)
+          .await?;
+      let namespace_key = TaskManager::gen_key(&namespace.clone().into());
+      for (task_key, actor) in objects {
+        state
+          .inner
+          .task_manager
+          .wait_tasks(&namespace_key, &[task_key], &state)
+          .await;
+        step += 1;
+        emit_progress(&namespace, actor, step, total, &state);
+      }
+      utils::namespace::ensure_empty(&key, &state).await?;
+      for volume in volumes {
+        step += 1;
+        utils::namespace::remove_volume(&volume, &state).await;
+        let actor = EventActor {
+          key: Some(volume.clone()),
+          kind: EventActorKind::Volume,
+          attributes: None,
+        };
+        emit_progress(&namespace, actor, step, total, &state);
+      }
+      if item.is_isolated() {
+        utils::namespace::remove_network(&key, &state).await;
+      }
+      NamespaceDb::del_by_pk(&key, &state.inner.pool).await?;
+      state
+        .emit_normal_native_action_sync(&namespace, NativeEventAction::Destroy)
+        .await;
+      Ok::<_, IoError>(())
+    })
+  }
+}
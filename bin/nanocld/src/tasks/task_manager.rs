@@ -1,93 +1,449 @@
-use nanocl_error::io::IoError;
-use nanocl_stubs::system::NativeEventAction;
+use std::{pin::Pin, sync::Arc, time::Duration};
 
-use crate::models::{ObjTask, TaskManager};
+use futures::channel::oneshot;
+use futures_util::Future;
+use ntex::{rt, time};
 
-use super::generic::ObjTaskFuture;
+use nanocl_error::io::{IoError, IoResult};
+use nanocl_stubs::{
+  system::{EventActor, EventActorKind, NativeEventAction, ObjPsStatusKind},
+  task::{TaskStats, TaskStatus},
+};
 
-use std::{sync::Arc, time::Duration};
+use crate::{
+  models::{
+    CargoDb, JobDb, NamespaceDb, ObjPsStatusDb, ObjTask, Pool, QueuedTask,
+    SystemState, TaskDb, TaskManager, TaskUpdateDb, VmDb,
+  },
+  repositories::generic::*,
+};
 
-use futures_util::Future;
-use ntex::{rt, time};
+use super::generic::*;
 
-use nanocl_error::io::IoResult;
+/// Function creating the task of an object from his key
+type ObjTaskFactory = fn(&str, &SystemState) -> ObjTaskFuture;
+
+/// Get the function creating the task of an action for a kind of object
+fn get_factory(
+  kind: &EventActorKind,
+  action: &NativeEventAction,
+) -> Option<ObjTaskFactory> {
+  let factory: ObjTaskFactory = match (action, kind) {
+    (NativeEventAction::Starting, EventActorKind::Cargo) => {
+      CargoDb::create_start_task
+    }
+    (NativeEventAction::Starting, EventActorKind::Vm) => {
+      VmDb::create_start_task
+    }
+    (NativeEventAction::Starting, EventActorKind::Job) => {
+      JobDb::create_start_task
+    }
+    (NativeEventAction::Stopping, EventActorKind::Cargo) => {
+      CargoDb::create_stop_task
+    }
+    (NativeEventAction::Stopping, EventActorKind::Vm) => VmDb::create_stop_task,
+    (NativeEventAction::Stopping, EventActorKind::Job) => {
+      JobDb::create_stop_task
+    }
+    (NativeEventAction::Updating, EventActorKind::Cargo) => {
+      CargoDb::create_update_task
+    }
+    (NativeEventAction::Updating, EventActorKind::Vm) => {
+      VmDb::create_update_task
+    }
+    (NativeEventAction::Destroying, EventActorKind::Cargo) => {
+      CargoDb::create_delete_task
+    }
+    (NativeEventAction::Destroying, EventActorKind::Vm) => {
+      VmDb::create_delete_task
+    }
+    (NativeEventAction::Destroying, EventActorKind::Job) => {
+      JobDb::create_delete_task
+    }
+    (NativeEventAction::Destroying, EventActorKind::Namespace) => {
+      NamespaceDb::create_delete_task
+    }
+    _ => return None,
+  };
+  Some(factory)
+}
+
+/// Mark the object as failed when his task return an error
+async fn on_error(
+  actor: &EventActor,
+  action: &NativeEventAction,
+  err: &IoError,
+  state: &SystemState,
+) -> IoResult<()> {
+  let action = match action {
+    NativeEventAction::Starting => NativeEventAction::Start,
+    NativeEventAction::Stopping => NativeEventAction::Stop,
+    NativeEventAction::Updating => NativeEventAction::Update,
+    NativeEventAction::Destroying => NativeEventAction::Destroy,
+    _ => return Ok(()),
+  };
+  let key = actor.key.clone().unwrap_or_default();
+  // A namespace stay in his destroying state to be resumed later
+  if actor.kind != EventActorKind::Namespace {
+    ObjPsStatusDb::update_actual_status(
+      &key,
+      &ObjPsStatusKind::Fail,
+      &state.inner.pool,
+    )
+    .await?;
+  }
+  state.emit_error_native_action(actor, action, Some(err.to_string()));
+  Ok(())
+}
 
 impl ObjTask {
-  pub fn new<F>(kind: NativeEventAction, task: F) -> Self
+  pub fn new<F>(id: &str, kind: NativeEventAction, task: F) -> Self
   where
     F: Future<Output = IoResult<()>> + 'static,
   {
     let fut = Arc::new(rt::spawn(task));
-    Self { kind, fut }
-  }
-
-  pub async fn wait(&self) {
-    loop {
-      if self.fut.is_finished() {
-        log::debug!("Task finished: {}", self.kind);
-        break;
-      }
-      time::sleep(Duration::from_micros(10)).await;
+    Self {
+      id: id.to_owned(),
+      kind,
+      fut,
     }
   }
 }
 
 impl TaskManager {
-  pub fn new() -> Self {
-    Self::default()
+  pub fn new(pool: &Pool, node_name: &str, max_workers: usize) -> Self {
+    Self {
+      tasks: Arc::default(),
+      max_workers: max_workers.max(1),
+      pool: pool.clone(),
+      node_name: node_name.to_owned(),
+    }
+  }
+
+  /// Generate the key of the queue of an object
+  pub fn gen_key(actor: &EventActor) -> String {
+    format!("{}@{}", actor.kind, actor.key.clone().unwrap_or_default())
+  }
+
+  /// Priority of an action, deletions run before the other actions
+  fn priority(action: &NativeEventAction) -> u8 {
+    match action {
+      NativeEventAction::Destroying => 3,
+      NativeEventAction::Stopping => 2,
+      NativeEventAction::Updating => 1,
+      _ => 0,
+    }
+  }
+
+  /// Return true if a task exists for this action and kind of object
+  pub fn is_handled(actor: &EventActor, action: &NativeEventAction) -> bool {
+    get_factory(&actor.kind, action).is_some()
   }
 
-  pub async fn add_task<EC, F>(
+  /// Queue the task of an action for an object.
+  /// The task run once the previous tasks of the object are done
+  /// and a worker is available.
+  pub async fn push(
     &self,
-    key: &str,
-    kind: NativeEventAction,
-    task: ObjTaskFuture,
-    on_error: EC,
-  ) where
-    EC: FnOnce(IoError) -> F + 'static,
-    F: Future<Output = IoResult<()>> + 'static,
-  {
-    let key = key.to_owned();
-    let key_ptr = key.clone();
-    let tasks = self.tasks.clone();
-    log::debug!("Creating task: {key} {}", kind);
-    let new_task = ObjTask::new(kind.clone(), async move {
-      if let Err(err) = task.await {
-        log::error!("Task failed: {kind} {key_ptr} {}", err);
-        tasks.lock().await.remove(&key_ptr);
-        if let Err(err) = on_error(err.clone()).await {
-          log::error!("on_error failed: {kind} {key_ptr} {}", err);
-          return Err(err);
+    actor: &EventActor,
+    action: NativeEventAction,
+    state: &SystemState,
+  ) {
+    if !Self::is_handled(actor, &action) {
+      return;
+    }
+    let key = Self::gen_key(actor);
+    let id = uuid::Uuid::new_v4().to_string();
+    log::debug!("Queuing task: {key} {action}");
+    match TaskDb::try_new(&id, &self.node_name, &action.to_string(), actor) {
+      Ok(item) => {
+        if let Err(err) = TaskDb::create_from(item, &self.pool).await {
+          log::warn!("Unable to persist task {key}: {err}");
         }
-        return Err(err);
+      }
+      Err(err) => log::warn!("Unable to persist task {key}: {err}"),
+    }
+    {
+      let mut tasks = self.tasks.lock().await;
+      tasks.seq += 1;
+      let seq = tasks.seq;
+      tasks
+        .queues
+        .entry(key)
+        .or_default()
+        .pending
+        .push_back(QueuedTask {
+          id,
+          seq,
+          action,
+          actor: actor.clone(),
+        });
+    }
+    self.schedule(state).await;
+  }
+
+  /// Run the pending tasks with the highest priority while workers are available
+  /// Only one task run at a time for an object
+  pub fn schedule(
+    &self,
+    state: &SystemState,
+  ) -> Pin<Box<dyn Future<Output = ()>>> {
+    let manager = self.clone();
+    let state = state.clone();
+    Box::pin(async move {
+      let mut tasks = manager.tasks.lock().await;
+      while !tasks.draining && tasks.running < manager.max_workers {
+        let next = tasks
+          .queues
+          .iter()
+          .filter(|(_, queue)| queue.running.is_none())
+          .filter_map(|(key, queue)| {
+            queue.pending.front().map(|task| (key, task))
+          })
+          .max_by_key(|(_, task)| {
+            (Self::priority(&task.action), std::cmp::Reverse(task.seq))
+          })
+          .map(|(key, _)| key.clone());
+        let Some(key) = next else {
+          break;
+        };
+        let Some(queue) = tasks.queues.get_mut(&key) else {
+          break;
+        };
+        let Some(task) = queue.pending.pop_front() else {
+          break;
+        };
+        let Some(factory) = get_factory(&task.actor.kind, &task.action) else {
+          continue;
+        };
+        log::debug!("Running task: {key} {}", task.action);
+        let object_key = task.actor.key.clone().unwrap_or_default();
+        let fut = factory(&object_key, &state);
+        let manager_ptr = manager.clone();
+        let state_ptr = state.clone();
+        let id = task.id.clone();
+        let queue_key = key.clone();
+        let action = task.action.clone();
+        let actor = task.actor.clone();
+        queue.running = Some(ObjTask::new(&task.id, task.action, async move {
+          let _ = TaskDb::update_pk(
+            &id,
+            TaskUpdateDb {
+              status: Some(TaskStatus::Running.to_string()),
+            },
+            &state_ptr.inner.pool,
+          )
+          .await;
+          let res = fut.await;
+          let _ = TaskDb::del_by_pk(&id, &state_ptr.inner.pool).await;
+          if let Err(err) = &res {
+            log::error!("Task failed: {action} {queue_key} {err}");
+            if let Err(err) = on_error(&actor, &action, err, &state_ptr).await {
+              log::error!("on_error failed: {action} {queue_key} {err}");
+            }
+          } else {
+            log::debug!("Task completed: {action} {queue_key}");
+          }
+          manager_ptr.finish(&queue_key, &id, &state_ptr).await;
+          res
+        }));
+        tasks.running += 1;
+      }
+    })
+  }
+
+  /// Release the worker of a finished task and schedule the next ones
+  async fn finish(&self, key: &str, id: &str, state: &SystemState) {
+    {
+      let mut tasks = self.tasks.lock().await;
+      let Some(queue) = tasks.queues.get_mut(key) else {
+        return;
       };
-      log::debug!("Task completed: {kind} {key_ptr}");
-      tasks.lock().await.remove(&key_ptr);
-      Ok::<_, IoError>(())
-    });
-    self.tasks.lock().await.insert(key, new_task);
+      match &queue.running {
+        Some(task) if task.id == id => queue.running = None,
+        _ => return,
+      }
+      if queue.pending.is_empty() {
+        tasks.queues.remove(key);
+      }
+      tasks.running -= 1;
+    }
+    self.schedule(state).await;
   }
 
-  pub async fn remove_task(&self, key: &str) {
-    let mut tasks = self.tasks.lock().await;
-    let task = tasks.get(key);
-    if let Some(task) = task {
-      task.fut.abort();
+  /// Abort the running task and drop the pending tasks of an object
+  pub async fn remove_task(&self, key: &str, state: &SystemState) {
+    let queue = {
+      let mut tasks = self.tasks.lock().await;
+      let queue = tasks.queues.remove(key);
+      let is_waiting = tasks.waiting.remove(key);
+      if let Some(true) = queue.as_ref().map(|queue| queue.running.is_some()) {
+        if !is_waiting {
+          tasks.running -= 1;
+        }
+      }
+      queue
+    };
+    let Some(queue) = queue else {
+      return;
+    };
+    if let Some(task) = &queue.running {
       log::debug!("Removing task: {key} {}", task.kind);
-      tasks.remove(key);
+      task.fut.abort();
+      let _ = TaskDb::del_by_pk(&task.id, &self.pool).await;
+    }
+    for task in &queue.pending {
+      let _ = TaskDb::del_by_pk(&task.id, &self.pool).await;
+    }
+    self.schedule(state).await;
+  }
+
+  /// Cancel a task by his unique key.
+  /// A running task is aborted and a pending one is removed from his queue
+  /// Return false if the task isn't managed by this node
+  pub async fn cancel_task(&self, id: &str, state: &SystemState) -> bool {
+    let found = {
+      let mut tasks = self.tasks.lock().await;
+      let mut found = None;
+      for (key, queue) in tasks.queues.iter_mut() {
+        let is_running = queue
+          .running
+          .as_ref()
+          .map(|task| task.id == id)
+          .unwrap_or_default();
+        if is_running {
+          if let Some(task) = queue.running.take() {
+            task.fut.abort();
+          }
+          found = Some((key.clone(), true));
+          break;
+        }
+        if let Some(pos) = queue.pending.iter().position(|task| task.id == id) {
+          queue.pending.remove(pos);
+          found = Some((key.clone(), false));
+          break;
+        }
+      }
+      if let Some((key, was_running)) = &found {
+        if *was_running && !tasks.waiting.remove(key) {
+          tasks.running -= 1;
+        }
+        let is_empty = tasks
+          .queues
+          .get(key)
+          .map(|queue| queue.running.is_none() && queue.pending.is_empty())
+          .unwrap_or_default();
+        if is_empty {
+          tasks.queues.remove(key);
+        }
+      }
+      found.is_some()
+    };
+    let _ = TaskDb::del_by_pk(id, &self.pool).await;
+    if found {
+      self.schedule(state).await;
     }
+    found
   }
 
-  pub async fn get_task(&self, key: &str) -> Option<ObjTask> {
+  /// Return true if a task is running or waiting for the object
+  pub async fn has_task(&self, key: &str) -> bool {
     let tasks = self.tasks.lock().await;
-    tasks.get(key).cloned()
+    tasks.queues.contains_key(key)
   }
 
+  /// Wait until the tasks of an object are done
   pub async fn wait_task(&self, key: &str) {
-    if let Some(task) = self.get_task(key).await {
-      task.wait().await;
-      log::debug!("Task finished: {key} {} removing it", task.kind);
-      self.remove_task(key).await;
+    loop {
+      let rx = {
+        let mut tasks = self.tasks.lock().await;
+        let Some(queue) = tasks.queues.get_mut(key) else {
+          break;
+        };
+        let (tx, rx) = oneshot::channel();
+        queue.waiters.push(tx);
+        rx
+      };
+      // The sender is dropped with the queue once his tasks are done
+      let _ = rx.await;
+    }
+    log::debug!("Tasks finished: {key}");
+  }
+
+  /// Wait from a running task until the tasks of other objects are done.
+  /// The worker of the running task of the queue `key` is released while waiting
+  /// so the awaited tasks can run even when every worker is busy
+  pub async fn wait_tasks(
+    &self,
+    key: &str,
+    keys: &[String],
+    state: &SystemState,
+  ) {
+    {
+      let mut tasks = self.tasks.lock().await;
+      let is_running = tasks
+        .queues
+        .get(key)
+        .map(|queue| queue.running.is_some())
+        .unwrap_or_default();
+      if is_running && tasks.waiting.insert(key.to_owned()) {
+        tasks.running -= 1;
+      }
+    }
+    self.schedule(state).await;
+    for key in keys {
+      self.wait_task(key).await;
+    }
+    let mut tasks = self.tasks.lock().await;
+    if tasks.waiting.remove(key) {
+      tasks.running += 1;
+    }
+  }
+
+  /// Stop starting new tasks and wait for the running ones to finish.
+  /// Tasks still running after the timeout and the ones waiting for tasks
+  /// that will not start anymore are aborted,
+  /// they stay in the store with the pending ones to be resumed at boot.
+  /// Return the number of aborted tasks
+  pub async fn drain(&self, timeout: Duration) -> usize {
+    self.tasks.lock().await.draining = true;
+    let deadline = time::Millis::from(timeout);
+    let wait = async {
+      while self.tasks.lock().await.running > 0 {
+        time::sleep(Duration::from_millis(100)).await;
+      }
+    };
+    let _ = time::timeout(deadline, wait).await;
+    let mut tasks = self.tasks.lock().await;
+    tasks.waiting.clear();
+    let mut aborted = 0;
+    for (key, queue) in tasks.queues.iter_mut() {
+      if let Some(task) = queue.running.take() {
+        log::warn!("Aborting task: {key} {}", task.kind);
+        task.fut.abort();
+        aborted += 1;
+      }
+    }
+    tasks.running = 0;
+    aborted
+  }
+
+  /// Number of tasks running and waiting
+  pub async fn stats(&self) -> TaskStats {
+    let tasks = self.tasks.lock().await;
+    let queues = tasks
+      .queues
+      .iter()
+      .map(|(key, queue)| {
+        let running = usize::from(queue.running.is_some());
+        (key.clone(), running + queue.pending.len())
+      })
+      .collect();
+    TaskStats {
+      max_workers: self.max_workers,
+      running: tasks.running,
+      pending: tasks.queues.values().map(|queue| queue.pending.len()).sum(),
+      queues,
     }
   }
 }
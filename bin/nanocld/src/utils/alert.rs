@@ -0,0 +1,603 @@
+use std::{
+  collections::HashMap,
+  io::{ErrorKind, Write},
+  process::{Child, Command, ExitStatus, Stdio},
+  time::{Duration, Instant},
+};
+
+use ntex::{http::Client, rt, time::Millis};
+
+use nanocl_error::{
+  http::{HttpError, HttpResult},
+  io::{IoError, IoResult},
+};
+use nanocl_stubs::{
+  alert::{
+    Alert, AlertCondition, AlertSeverity, AlertSink, AlertStatus,
+    ResourceAlertRule,
+  },
+  generic::{GenericClause, GenericFilter},
+  resource::{Resource, ResourcePartial},
+  system::{EventKind, EventPartial, NativeEventAction, ObjPsStatusKind},
+};
+
+use crate::{
+  models::{
+    AlertDb, EventDb, MetricDb, MetricField, MetricRange, NodeDb,
+    ObjPsStatusDb, ResourceDb, SystemState,
+  },
+  repositories::generic::*,
+  utils, vars,
+};
+
+/// Default duration of the window of a condition in seconds
+const DEFAULT_WINDOW: u64 = 300;
+
+/// Seconds before a notification is abandoned
+const NOTIFY_TIMEOUT: u64 = 10;
+
+/// Name of the lease electing the node evaluating the rules without `Node`
+const LEASE: &str = "alert";
+
+/// Seconds the lease is kept without being renewed by an evaluation
+const LEASE_TTL: u64 = 45;
+
+/// Evaluate a condition, return whether it's true
+/// with the value of the metric or the number of events
+async fn eval_condition(
+  condition: &AlertCondition,
+  state: &SystemState,
+) -> IoResult<(bool, Option<f64>)> {
+  let pool = &state.inner.pool;
+  let now = chrono::Utc::now().naive_utc();
+  match condition {
+    AlertCondition::Metric(condition) => {
+      let field = condition.field.parse::<MetricField>()?;
+      let window = condition.window.unwrap_or(DEFAULT_WINDOW) as i64;
+      let range = MetricRange {
+        since: now - chrono::Duration::seconds(window),
+        until: now,
+        step: window,
+        kind: Some(condition.kind.clone()),
+        node_name: condition.node_name.clone(),
+      };
+      let aggregate = condition.aggregate.unwrap_or_default();
+      let value = MetricDb::aggregate_raw(&field, &range, aggregate, pool)
+        .await?
+        .and_then(|point| point.value);
+      // Without values the condition can't be true
+      let Some(value) = value else {
+        return Ok((false, None));
+      };
+      Ok((
+        condition.operator.compare(value, condition.threshold),
+        Some(value),
+      ))
+    }
+    AlertCondition::Event(condition) => {
+      let window = condition.window.unwrap_or(DEFAULT_WINDOW) as i64;
+      let since = now - chrono::Duration::seconds(window);
+      let mut filter = GenericFilter::new()
+        .r#where("created_at", GenericClause::Gt(since.to_string()));
+      if let Some(kind) = &condition.kind {
+        filter = filter.r#where("kind", GenericClause::Eq(kind.clone()));
+      }
+      match &condition.action {
+        Some(action) => {
+          filter = filter.r#where("action", GenericClause::Eq(action.clone()));
+        }
+        // The alerts emitted by the rules aren't counted
+        None => {
+          filter = filter.r#where("reason", GenericClause::Ne("alert".into()));
+        }
+      }
+      let mut actor = serde_json::Map::new();
+      if let Some(kind) = &condition.actor_kind {
+        actor.insert("Kind".to_owned(), kind.clone().into());
+      }
+      if let Some(key) = &condition.actor_key {
+        actor.insert("Key".to_owned(), key.clone().into());
+      }
+      if !actor.is_empty() {
+        filter = filter.r#where(
+          "actor",
+          GenericClause::Contains(serde_json::Value::Object(actor)),
+        );
+      }
+      let count = EventDb::count_by(&filter, pool).await? as u64;
+      Ok((count >= condition.count.unwrap_or(1), Some(count as f64)))
+    }
+    AlertCondition::Process(condition) => {
+      let key = utils::key::gen_kind_key(
+        &condition.kind,
+        &condition.name,
+        &condition.namespace,
+      );
+      let status = ObjPsStatusDb::read_by_pk(&key, pool).await?;
+      let actual = status.actual.parse::<ObjPsStatusKind>()?;
+      Ok((actual == condition.status, None))
+    }
+  }
+}
+
+/// Description of an alert when the rule doesn't have a summary
+fn gen_summary(name: &str, rule: &ResourceAlertRule) -> String {
+  if let Some(summary) = &rule.summary {
+    return summary.clone();
+  }
+  match &rule.condition {
+    AlertCondition::Metric(condition) => format!(
+      "{name}: {} of {} is {:?} {}",
+      condition.field, condition.kind, condition.operator, condition.threshold
+    ),
+    AlertCondition::Event(condition) => format!(
+      "{name}: at least {} {} events in {}s",
+      condition.count.unwrap_or(1),
+      condition.action.as_deref().unwrap_or("matching"),
+      condition.window.unwrap_or(DEFAULT_WINDOW)
+    ),
+    AlertCondition::Process(condition) => format!(
+      "{name}: {} {} is {}",
+      condition.kind, condition.name, condition.status
+    ),
+  }
+}
+
+/// Next state of the alert of a rule after an evaluation of his condition.
+/// Return the alert to keep, none when the condition is false,
+/// and the alert to notify when it fire or resolve
+fn next_alert(
+  current: Option<Alert>,
+  name: &str,
+  rule: &ResourceAlertRule,
+  node_name: &str,
+  active: bool,
+  value: Option<f64>,
+  now: chrono::NaiveDateTime,
+) -> (Option<Alert>, Option<Alert>) {
+  if !active {
+    return match current {
+      Some(alert) if alert.status == AlertStatus::Firing => {
+        let resolved = Alert {
+          status: AlertStatus::Resolved,
+          resolved_at: Some(now),
+          value,
+          ..alert
+        };
+        (None, Some(resolved))
+      }
+      _ => (None, None),
+    };
+  }
+  let mut alert = current.unwrap_or_else(|| Alert {
+    rule: name.to_owned(),
+    node_name: node_name.to_owned(),
+    status: AlertStatus::Pending,
+    severity: rule.severity.unwrap_or_default(),
+    summary: gen_summary(name, rule),
+    value,
+    active_since: now,
+    fired_at: None,
+    resolved_at: None,
+  });
+  alert.value = value;
+  node_name.clone_into(&mut alert.node_name);
+  let pending_for = (now - alert.active_since).num_seconds();
+  if alert.status == AlertStatus::Pending
+    && pending_for >= rule.r#for.unwrap_or_default() as i64
+  {
+    alert.status = AlertStatus::Firing;
+    alert.fired_at = Some(now);
+    return (Some(alert.clone()), Some(alert));
+  }
+  (Some(alert), None)
+}
+
+/// Wait for a child process, kill it once the timeout is reached
+fn wait_child(mut child: Child, timeout: Duration) -> IoResult<ExitStatus> {
+  let deadline = Instant::now() + timeout;
+  loop {
+    if let Some(status) = child.try_wait()? {
+      return Ok(status);
+    }
+    if Instant::now() >= deadline {
+      child.kill()?;
+      child.wait()?;
+      return Err(IoError::interrupted(
+        "Alert exec",
+        format!("killed after {}s", timeout.as_secs()).as_str(),
+      ));
+    }
+    std::thread::sleep(Duration::from_millis(50));
+  }
+}
+
+/// Send an alert to a sink
+async fn notify(sink: &AlertSink, alert: &Alert) -> IoResult<()> {
+  let mut payload = serde_json::to_vec(alert)?;
+  match sink {
+    AlertSink::Webhook(url) => {
+      let client = Client::build()
+        .timeout(Millis::from_secs(NOTIFY_TIMEOUT as u32))
+        .finish();
+      let res = client.post(url).send_json(alert).await.map_err(|err| {
+        IoError::interrupted("Alert webhook", &err.to_string())
+      })?;
+      if !res.status().is_success() {
+        return Err(IoError::interrupted(
+          "Alert webhook",
+          format!("{url} responded {}", res.status()).as_str(),
+        ));
+      }
+    }
+    AlertSink::Unix(path) => {
+      let path = path.clone();
+      payload.push(b'\n');
+      rt::spawn_blocking(move || {
+        let mut stream = std::os::unix::net::UnixStream::connect(&path)?;
+        stream.set_write_timeout(Some(Duration::from_secs(NOTIFY_TIMEOUT)))?;
+        stream.write_all(&payload)?;
+        Ok::<_, IoError>(())
+      })
+      .await
+      .map_err(|err| IoError::interrupted("Alert unix", &err.to_string()))??;
+    }
+    AlertSink::Exec(command) => {
+      let Some((program, args)) = command.split_first() else {
+        return Err(IoError::invalid_input("Alert exec", "empty command"));
+      };
+      let mut command = Command::new(program);
+      command
+        .args(args)
+        .env("NANOCL_ALERT_RULE", &alert.rule)
+        .env("NANOCL_ALERT_STATUS", alert.status.to_string())
+        .env("NANOCL_ALERT_SEVERITY", alert.severity.to_string())
+        .env("NANOCL_ALERT_SUMMARY", &alert.summary)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+      let status = rt::spawn_blocking(move || {
+        let mut child = command.spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+          // The command may exit without reading the alert
+          match stdin.write_all(&payload) {
+            Err(err) if err.kind() != ErrorKind::BrokenPipe => {
+              child.kill()?;
+              child.wait()?;
+              return Err(err.into());
+            }
+            _ => {}
+          }
+        }
+        wait_child(child, Duration::from_secs(NOTIFY_TIMEOUT))
+      })
+      .await
+      .map_err(|err| IoError::interrupted("Alert exec", &err.to_string()))??;
+      if !status.success() {
+        return Err(IoError::interrupted(
+          "Alert exec",
+          format!("{program} exited with {status}").as_str(),
+        ));
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Record an alert firing or resolving as an event and send it to the sinks
+fn emit_alert(
+  resource: &Resource,
+  rule: &ResourceAlertRule,
+  alert: Alert,
+  state: &SystemState,
+) -> IoResult<()> {
+  let kind = match (alert.status, alert.severity) {
+    (AlertStatus::Resolved, _) | (_, AlertSeverity::Info) => EventKind::Normal,
+    (_, AlertSeverity::Warning) => EventKind::Warning,
+    (_, AlertSeverity::Critical) => EventKind::Error,
+  };
+  state.spawn_emit_event(EventPartial {
+    reporting_controller: vars::CONTROLLER_NAME.to_owned(),
+    reporting_node: state.config().hostname.clone(),
+    action: NativeEventAction::Other(format!("alert_{}", alert.status))
+      .to_string(),
+    reason: "alert".to_owned(),
+    kind,
+    actor: Some(resource.clone().into()),
+    related: None,
+    metadata: Some(serde_json::to_value(&alert)?),
+    note: Some(alert.summary.clone()),
+  });
+  for sink in rule.sinks.clone().unwrap_or_default() {
+    let alert = alert.clone();
+    rt::spawn(async move {
+      if let Err(err) = notify(&sink, &alert).await {
+        log::warn!("alert::notify: {}: {err}", alert.rule);
+      }
+    });
+  }
+  Ok(())
+}
+
+/// Evaluate the alert rules of the cluster, fire the alerts of the conditions
+/// true for their `For` duration and resolve the ones that are false again.
+/// The rules without `Node` are evaluated by the node holding the lease
+/// and the alerts are stored so they survive the restarts.
+pub async fn evaluate(state: &SystemState) -> IoResult<()> {
+  let pool = &state.inner.pool;
+  let node_name = state.config().hostname.clone();
+  let is_leader =
+    NodeDb::acquire_lease(LEASE, &node_name, LEASE_TTL, pool).await?;
+  let filter = GenericFilter::new()
+    .r#where("kind", GenericClause::Eq(vars::ALERT_RULE_KIND.to_owned()));
+  let resources = ResourceDb::transform_read_by(&filter, pool).await?;
+  let mut alerts = AlertDb::transform_read_by(&GenericFilter::new(), pool)
+    .await?
+    .into_iter()
+    .map(|alert| (alert.rule.clone(), alert))
+    .collect::<HashMap<_, _>>();
+  let mut names = Vec::new();
+  for resource in resources {
+    let name = resource.spec.resource_key.clone();
+    names.push(name.clone());
+    let rule = match serde_json::from_value::<ResourceAlertRule>(
+      resource.spec.data.clone(),
+    ) {
+      Ok(rule) => rule,
+      Err(err) => {
+        log::warn!("alert::evaluate: {name}: {err}");
+        continue;
+      }
+    };
+    let is_evaluator = match &rule.node {
+      Some(node) => node == &node_name,
+      None => is_leader,
+    };
+    if !is_evaluator {
+      continue;
+    }
+    let (active, value) = match eval_condition(&rule.condition, state).await {
+      Ok(res) => res,
+      Err(err) => {
+        log::warn!("alert::evaluate: {name}: {err}");
+        continue;
+      }
+    };
+    let now = chrono::Utc::now().naive_utc();
+    let current = alerts.remove(&name);
+    let (alert, notification) = next_alert(
+      current.clone(),
+      &name,
+      &rule,
+      &node_name,
+      active,
+      value,
+      now,
+    );
+    let res = match &alert {
+      Some(alert) if current.as_ref() != Some(alert) => {
+        AlertDb::upsert(alert, pool).await
+      }
+      None if current.is_some() => AlertDb::del_by_pk(&name, pool).await,
+      _ => Ok(()),
+    };
+    // The notification is sent again by the next evaluation
+    if let Err(err) = res {
+      log::warn!("alert::evaluate: {name}: {err}");
+      continue;
+    }
+    if let Some(alert) = notification {
+      log::info!("alert::evaluate: {name} is {}", alert.status);
+      if let Err(err) = emit_alert(&resource, &rule, alert, state) {
+        log::warn!("alert::evaluate: {name}: {err}");
+      }
+    }
+  }
+  // The alerts of the deleted rules are forgotten
+  if is_leader {
+    for rule in alerts.keys().filter(|rule| !names.contains(rule)) {
+      if let Err(err) = AlertDb::del_by_pk(rule, pool).await {
+        log::warn!("alert::evaluate: {rule}: {err}");
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Check the condition of an alert rule resource before it's saved
+pub async fn validate(
+  resource: &ResourcePartial,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let rule = serde_json::from_value::<ResourceAlertRule>(resource.data.clone())
+    .map_err(|err| {
+      HttpError::bad_request(format!("Invalid alert rule {err}"))
+    })?;
+  match &rule.condition {
+    AlertCondition::Metric(condition) => {
+      condition.field.parse::<MetricField>().map_err(|err| {
+        HttpError::bad_request(format!("Invalid alert rule field {err}"))
+      })?;
+    }
+    AlertCondition::Process(condition) => {
+      let key = utils::key::gen_kind_key(
+        &condition.kind,
+        &condition.name,
+        &condition.namespace,
+      );
+      if ObjPsStatusDb::read_by_pk(&key, &state.inner.pool)
+        .await
+        .is_err()
+      {
+        return Err(HttpError::bad_request(format!(
+          "Invalid alert rule {} {} doesn't exist",
+          condition.kind, condition.name
+        )));
+      }
+    }
+    AlertCondition::Event(_) => {}
+  }
+  Ok(())
+}
+
+/// Pending and firing alerts of the cluster
+pub async fn list(state: &SystemState) -> IoResult<Vec<Alert>> {
+  AlertDb::transform_read_by(&GenericFilter::new(), &state.inner.pool).await
+}
+
+#[cfg(test)]
+mod tests {
+  use nanocl_stubs::{
+    alert::{
+      AlertEventCondition, AlertMetricCondition, AlertOperator,
+      AlertProcessCondition,
+    },
+    process::ProcessKind,
+  };
+
+  use super::*;
+
+  use crate::{models::SpecDb, objects::generic::*, utils::tests::*};
+
+  fn gen_rule(r#for: Option<u64>) -> ResourceAlertRule {
+    ResourceAlertRule {
+      condition: AlertCondition::Event(AlertEventCondition {
+        action: Some("alert_test".to_owned()),
+        ..Default::default()
+      }),
+      r#for,
+      severity: Some(AlertSeverity::Critical),
+      summary: None,
+      node: None,
+      sinks: None,
+    }
+  }
+
+  #[test]
+  fn transitions() {
+    let rule = gen_rule(Some(60));
+    let now = chrono::Utc::now().naive_utc();
+    let (alert, notification) =
+      next_alert(None, "test", &rule, "node", true, Some(1.0), now);
+    let alert = alert.unwrap();
+    assert_eq!(alert.status, AlertStatus::Pending);
+    assert!(notification.is_none());
+    let later = now + chrono::Duration::seconds(61);
+    let (alert, notification) =
+      next_alert(Some(alert), "test", &rule, "node", true, Some(2.0), later);
+    let alert = alert.unwrap();
+    assert_eq!(alert.status, AlertStatus::Firing);
+    assert_eq!(notification.unwrap().value, Some(2.0));
+    let (alert, notification) =
+      next_alert(Some(alert), "test", &rule, "node", false, None, later);
+    assert!(alert.is_none());
+    let resolved = notification.unwrap();
+    assert_eq!(resolved.status, AlertStatus::Resolved);
+    assert_eq!(resolved.resolved_at, Some(later));
+    // A pending alert isn't notified when the condition is false again
+    let (alert, _) = next_alert(None, "test", &rule, "node", true, None, now);
+    let (alert, notification) =
+      next_alert(alert, "test", &rule, "node", false, None, now);
+    assert!(alert.is_none() && notification.is_none());
+    // Without `For` the alert fire on the first evaluation
+    let rule = gen_rule(None);
+    let (_, notification) =
+      next_alert(None, "test", &rule, "node", true, None, now);
+    assert_eq!(notification.unwrap().status, AlertStatus::Firing);
+    assert!(AlertOperator::Ge.compare(1.0, 1.0));
+    assert!(!AlertOperator::Gt.compare(1.0, 1.0));
+  }
+
+  #[test]
+  fn exec_timeout() {
+    let child = Command::new("sleep").arg("30").spawn().unwrap();
+    let started = Instant::now();
+    let res = wait_child(child, Duration::from_millis(200));
+    assert!(res.is_err());
+    assert!(started.elapsed() < Duration::from_secs(5));
+    let child = Command::new("true").spawn().unwrap();
+    let status = wait_child(child, Duration::from_secs(5)).unwrap();
+    assert!(status.success());
+  }
+
+  #[ntex::test]
+  async fn validate_rule() {
+    let system = gen_default_test_system().await;
+    let state = &system.state;
+    let mut rule = gen_rule(None);
+    rule.condition = AlertCondition::Metric(AlertMetricCondition {
+      kind: "nanocl.io/metrs".to_owned(),
+      field: "Cpus.*.*".to_owned(),
+      aggregate: None,
+      window: None,
+      node_name: None,
+      operator: AlertOperator::Gt,
+      threshold: 90.0,
+    });
+    let resource = ResourcePartial {
+      name: "test-invalid-alert-rule".to_owned(),
+      kind: vars::ALERT_RULE_KIND.to_owned(),
+      data: serde_json::to_value(&rule).unwrap(),
+      metadata: None,
+    };
+    let err = ResourceDb::create_obj(&resource, state).await.unwrap_err();
+    assert_eq!(err.status, ntex::http::StatusCode::BAD_REQUEST);
+    rule.condition = AlertCondition::Process(AlertProcessCondition {
+      kind: ProcessKind::Cargo,
+      name: "test-missing-alert-cargo".to_owned(),
+      namespace: None,
+      status: ObjPsStatusKind::Fail,
+    });
+    let resource = ResourcePartial {
+      data: serde_json::to_value(&rule).unwrap(),
+      ..resource
+    };
+    let err = ResourceDb::create_obj(&resource, state).await.unwrap_err();
+    assert_eq!(err.status, ntex::http::StatusCode::BAD_REQUEST);
+  }
+
+  #[ntex::test]
+  async fn evaluate_rule() {
+    let system = gen_default_test_system().await;
+    let state = &system.state;
+    let resource = ResourcePartial {
+      name: "test-alert-rule".to_owned(),
+      kind: vars::ALERT_RULE_KIND.to_owned(),
+      data: serde_json::to_value(gen_rule(None)).unwrap(),
+      metadata: None,
+    };
+    let resource = ResourceDb::create_from_spec(&resource, &state.inner.pool)
+      .await
+      .unwrap();
+    state
+      .emit_event(EventPartial {
+        reporting_controller: vars::CONTROLLER_NAME.to_owned(),
+        reporting_node: state.config().hostname.clone(),
+        action: "alert_test".to_owned(),
+        reason: "test".to_owned(),
+        kind: EventKind::Normal,
+        actor: None,
+        related: None,
+        metadata: None,
+        note: None,
+      })
+      .await
+      .unwrap();
+    evaluate(state).await.unwrap();
+    let alerts = list(state).await.unwrap();
+    let alert = alerts
+      .iter()
+      .find(|alert| alert.rule == "test-alert-rule")
+      .unwrap();
+    assert_eq!(alert.status, AlertStatus::Firing);
+    ResourceDb::del_by_pk(&resource.spec.resource_key, &state.inner.pool)
+      .await
+      .unwrap();
+    SpecDb::del_by_kind_key(&resource.spec.resource_key, &state.inner.pool)
+      .await
+      .unwrap();
+    evaluate(state).await.unwrap();
+    let alerts = list(state).await.unwrap();
+    assert!(!alerts.iter().any(|alert| alert.rule == "test-alert-rule"));
+  }
+}
@@ -1,5 +1,8 @@
 use bollard_next::{
-  container::{StartContainerOptions, WaitContainerOptions},
+  container::{
+    InspectContainerOptions, RenameContainerOptions, StartContainerOptions,
+    WaitContainerOptions,
+  },
   secret::{HostConfig, RestartPolicy, RestartPolicyNameEnum},
 };
 use futures::{stream::FuturesUnordered, StreamExt};
@@ -9,16 +12,71 @@ use nanocl_error::{
 };
 use nanocl_stubs::{
   cargo::Cargo,
+  cargo_spec::ReplicationMode,
   generic::{GenericClause, GenericFilter},
   process::{Process, ProcessKind},
 };
 
 use crate::{
-  models::{SecretDb, SystemState},
+  models::{ProcessDb, SecretDb, SystemState},
   repositories::generic::*,
   utils,
 };
 
+/// Placement of the instances of a cargo defined by its replication mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Replicas {
+  /// The instances all run on the node that started the cargo
+  Single(usize),
+  /// Each node runs this number of instances
+  PerNode(usize),
+}
+
+impl Replicas {
+  /// Number of instances to run on a node
+  pub fn number(&self) -> usize {
+    match self {
+      Replicas::Single(number) | Replicas::PerNode(number) => *number,
+    }
+  }
+}
+
+/// Placement of the instances of a cargo seen from the given node.
+/// None when the mode depends on node groups the daemon doesn't know.
+pub fn replicas(
+  replication: &Option<ReplicationMode>,
+  node: &str,
+) -> Option<Replicas> {
+  let named = |names: &[String], number: usize| {
+    let number = if names.iter().any(|name| name == node) {
+      number
+    } else {
+      0
+    };
+    Some(Replicas::PerNode(number))
+  };
+  match replication {
+    None | Some(ReplicationMode::Auto | ReplicationMode::Unique) => {
+      Some(Replicas::Single(1))
+    }
+    Some(ReplicationMode::Static(replication)) => {
+      Some(Replicas::Single(replication.number))
+    }
+    Some(ReplicationMode::UniqueByNode) => Some(Replicas::PerNode(1)),
+    Some(ReplicationMode::StaticByNodes(replication)) => {
+      Some(Replicas::PerNode(replication.number))
+    }
+    Some(ReplicationMode::UniqueByNodeNames { names }) => named(names, 1),
+    Some(ReplicationMode::StaticByNodeNames { names, number }) => {
+      named(names, usize::try_from(*number).unwrap_or_default())
+    }
+    Some(
+      ReplicationMode::UniqueByNodeGroups { .. }
+      | ReplicationMode::StaticByNodeGroups { .. },
+    ) => None,
+  }
+}
+
 /// Container to execute before the cargo instances
 async fn execute_before(cargo: &Cargo, state: &SystemState) -> HttpResult<()> {
   match cargo.spec.init_container.clone() {
@@ -28,8 +86,10 @@ async fn execute_before(cargo: &Cargo, state: &SystemState) -> HttpResult<()> {
         .clone()
         .unwrap_or(cargo.spec.container.image.clone().unwrap());
       before.image = Some(image.clone());
+      let network =
+        utils::namespace::get_network(&cargo.namespace_name, state).await?;
       before.host_config = Some(HostConfig {
-        network_mode: Some("nanoclbr0".to_owned()),
+        network_mode: Some(network),
         ..before.host_config.unwrap_or_default()
       });
       super::image::download(
@@ -64,13 +124,13 @@ async fn execute_before(cargo: &Cargo, state: &SystemState) -> HttpResult<()> {
       .await?;
       state
         .inner
-        .docker_api
+        .runtime
         .start_container(&name, None::<StartContainerOptions<String>>)
         .await?;
       let options = Some(WaitContainerOptions {
-        condition: "not-running",
+        condition: "not-running".to_owned(),
       });
-      let mut stream = state.inner.docker_api.wait_container(&name, options);
+      let mut stream = state.inner.runtime.wait_container(&name, options);
       while let Some(wait_status) = stream.next().await {
         log::trace!("init_container: wait {wait_status:?}");
         match wait_status {
@@ -132,11 +192,14 @@ pub async fn create(
     // Flatten the secrets to have envs in a single vector
     secret_envs = secrets.into_iter().flatten().collect();
   }
-  (0..number)
+  let network =
+    utils::namespace::get_network(&cargo.namespace_name, state).await?;
+  let processes = (0..number)
     .collect::<Vec<usize>>()
     .into_iter()
     .map(move |current| {
       let secret_envs = secret_envs.clone();
+      let network = network.clone();
       async move {
         let ordinal_index = if current > 0 {
           current.to_string()
@@ -176,8 +239,8 @@ pub async fn create(
         let mut env = container.env.unwrap_or_default();
         // merge cargo env with secret env
         env.extend(secret_envs);
-        env.push(format!("NANOCL_NODE={}", state.inner.config.hostname));
-        env.push(format!("NANOCL_NODE_ADDR={}", state.inner.config.gateway));
+        env.push(format!("NANOCL_NODE={}", state.config().hostname));
+        env.push(format!("NANOCL_NODE_ADDR={}", state.config().gateway));
         env.push(format!("NANOCL_CARGO_KEY={}", cargo.spec.cargo_key.to_owned()));
         env.push(format!("NANOCL_CARGO_NAMESPACE={}", cargo.namespace_name));
         env.push(format!("NANOCL_CARGO_INSTANCE={}", current));
@@ -196,7 +259,7 @@ pub async fn create(
           env: Some(env),
           host_config: Some(HostConfig {
             restart_policy,
-            network_mode: Some("nanoclbr0".to_owned()),
+            network_mode: Some(network),
             ..host_config
           }),
           ..container
@@ -214,5 +277,118 @@ pub async fn create(
     .collect::<Vec<HttpResult<Process>>>()
     .await
     .into_iter()
-    .collect::<HttpResult<Vec<Process>>>()
+    .collect::<HttpResult<Vec<Process>>>()?;
+  // Attach the instances to the networks of the namespaces allowing us
+  utils::namespace::connect_processes(&cargo.namespace_name, &processes, state)
+    .await?;
+  Ok(processes)
+}
+
+/// Restore the instances of a failed or interrupted cargo update.
+/// The instances created by the update are removed and the old instances
+/// renamed with a `tmp-` prefix get back their name and are started again.
+/// Return the number of restored instances
+pub async fn rollback_update(
+  key: &str,
+  state: &SystemState,
+) -> HttpResult<usize> {
+  let processes = ProcessDb::read_by_kind_key(key, &state.inner.pool).await?;
+  let mut old_instances = Vec::new();
+  let mut new_instances = Vec::new();
+  for process in processes {
+    // The name in the store follows the runtime events and can be late
+    let container = match state
+      .inner
+      .runtime
+      .inspect_container(&process.key, None::<InspectContainerOptions>)
+      .await
+    {
+      Ok(container) => container,
+      Err(err) => {
+        log::warn!("cargo::rollback_update: {} {err}", process.key);
+        continue;
+      }
+    };
+    let name = container.name.unwrap_or_default();
+    let running = container
+      .state
+      .and_then(|state| state.running)
+      .unwrap_or_default();
+    match name.trim_start_matches('/').strip_prefix("tmp-") {
+      Some(name) => old_instances.push((process.key, name.to_owned(), running)),
+      None => new_instances.push(process.key),
+    }
+  }
+  if old_instances.is_empty() {
+    return Ok(0);
+  }
+  super::process::delete_instances(&new_instances, state).await?;
+  for (process_key, name, running) in &old_instances {
+    state
+      .inner
+      .runtime
+      .rename_container(
+        process_key,
+        RenameContainerOptions { name: name.clone() },
+      )
+      .await?;
+    if !running {
+      state
+        .inner
+        .runtime
+        .start_container(process_key, None::<StartContainerOptions<String>>)
+        .await?;
+    }
+  }
+  Ok(old_instances.len())
+}
+
+#[cfg(test)]
+mod tests {
+  use nanocl_stubs::cargo_spec::ReplicationStatic;
+
+  use super::*;
+
+  #[test]
+  fn replicas_by_mode() {
+    assert_eq!(replicas(&None, "node"), Some(Replicas::Single(1)));
+    assert_eq!(
+      replicas(
+        &Some(ReplicationMode::Static(ReplicationStatic { number: 3 })),
+        "node"
+      ),
+      Some(Replicas::Single(3))
+    );
+    assert_eq!(
+      replicas(
+        &Some(ReplicationMode::StaticByNodes(ReplicationStatic {
+          number: 2
+        })),
+        "node"
+      ),
+      Some(Replicas::PerNode(2))
+    );
+    let names = vec!["node".to_owned()];
+    assert_eq!(
+      replicas(
+        &Some(ReplicationMode::StaticByNodeNames {
+          names: names.clone(),
+          number: 2,
+        }),
+        "node"
+      ),
+      Some(Replicas::PerNode(2))
+    );
+    assert_eq!(
+      replicas(&Some(ReplicationMode::UniqueByNodeNames { names }), "other"),
+      Some(Replicas::PerNode(0))
+    );
+    assert_eq!(
+      replicas(
+        &Some(ReplicationMode::UniqueByNodeGroups { groups: vec![] }),
+        "node"
+      ),
+      None
+    );
+  }
 }
@@ -1,19 +1,54 @@
-use bollard_next::auth::DockerCredentials;
-use futures::StreamExt;
+use std::{
+  cell::RefCell,
+  collections::{HashMap, HashSet},
+  rc::Rc,
+};
+
+use bollard_next::{
+  auth::DockerCredentials,
+  container::ListContainersOptions,
+  image::{ListImagesOptions, RemoveImageOptions, TagImageOptions},
+  service::{BuildInfo, ImageInspect, ImageSummary},
+};
+use futures::{Stream, StreamExt};
 use nanocl_error::http::{HttpError, HttpResult};
 use nanocl_stubs::{
-  generic::ImagePullPolicy,
+  cargo_spec::CargoSpecPartial,
+  config::ImageGcConfig,
+  container_image::{
+    ContainerImageBuildQuery, ContainerImageBuildStream,
+    ContainerImagePruneResult, ContainerImageSummary,
+  },
+  generic::{GenericClause, GenericFilter, ImagePullPolicy},
   system::{
     EventActor, EventActorKind, EventKind, EventPartial, NativeEventAction,
   },
 };
+use ntex::{
+  channel::mpsc::Receiver, http, rt, service::fn_service, util::Bytes,
+};
+use serde::Serialize;
 
 use crate::{
-  models::{SecretDb, SystemState},
+  models::{CargoDb, JobDb, SecretDb, SpecDb, SystemState},
   repositories::generic::*,
+  runtime::DockerHost,
   vars,
 };
 
+/// Cargoes and jobs using a container image
+#[derive(Clone, Debug, Default)]
+pub struct ImageUsers {
+  pub cargoes: Vec<String>,
+  pub jobs: Vec<String>,
+}
+
+impl ImageUsers {
+  pub fn is_empty(&self) -> bool {
+    self.cargoes.is_empty() && self.jobs.is_empty()
+  }
+}
+
 /// Get the docker credentials to authenticate with the registry from the secret
 async fn get_credentials(
   secret: Option<String>,
@@ -30,7 +65,7 @@ async fn get_credentials(
   })
 }
 
-fn emit_download_status(
+fn emit_image_event(
   actor: Option<EventActor>,
   related: Option<EventActor>,
   note: &str,
@@ -41,7 +76,7 @@ fn emit_download_status(
 ) {
   let event = EventPartial {
     reporting_controller: vars::CONTROLLER_NAME.to_owned(),
-    reporting_node: state.inner.config.hostname.clone(),
+    reporting_node: state.config().hostname.clone(),
     action: action.to_string(),
     reason: "state_sync".to_owned(),
     kind,
@@ -78,7 +113,7 @@ where
   match policy {
     ImagePullPolicy::Always => {}
     ImagePullPolicy::IfNotPresent => {
-      if state.inner.docker_api.inspect_image(image).await.is_ok() {
+      if state.inner.runtime.inspect_image(image).await.is_ok() {
         return Ok(());
       }
     }
@@ -86,27 +121,33 @@ where
       return Ok(());
     }
   }
+  pull(image, secret, Some(actor.clone().into()), state).await
+}
+
+/// Pull the image from his registry and emit the progress as events
+/// The related actor is the object that need the image if any
+pub async fn pull(
+  image: &str,
+  secret: Option<String>,
+  related: Option<EventActor>,
+  state: &SystemState,
+) -> HttpResult<()> {
   let credentials = get_credentials(secret, state).await?;
   let (name, tag) = parse_name(image)?;
-  let mut stream = state.inner.docker_api.create_image(
+  let mut stream = state.inner.runtime.create_image(
     Some(bollard_next::image::CreateImageOptions {
       from_image: name.clone(),
       tag: tag.clone(),
       ..Default::default()
     }),
-    None,
     credentials,
   );
-  let event_actor = Some(EventActor {
-    key: Some(image.to_owned()),
-    kind: EventActorKind::ContainerImage,
-    attributes: None,
-  });
-  let event_related_actor: Option<EventActor> = Some(actor.clone().into());
+  let event_actor = Some(image_actor(image));
+  let event_related_actor = related;
   while let Some(chunk) = stream.next().await {
     let chunk = match chunk {
       Err(err) => {
-        emit_download_status(
+        emit_image_event(
           event_actor.clone(),
           event_related_actor.clone(),
           &format!("{err}"),
@@ -119,7 +160,7 @@ where
       }
       Ok(chunk) => chunk,
     };
-    emit_download_status(
+    emit_image_event(
       event_actor.clone(),
       event_related_actor.clone(),
       &format!("{name}:{tag}"),
@@ -131,7 +172,7 @@ where
       state,
     );
   }
-  emit_download_status(
+  emit_image_event(
     event_actor.clone(),
     event_related_actor.clone(),
     &format!("{name}:{tag}"),
@@ -142,3 +183,711 @@ where
   );
   Ok(())
 }
+
+/// Event actor of a container image
+fn image_actor(image: &str) -> EventActor {
+  EventActor {
+    key: Some(image.to_owned()),
+    kind: EventActorKind::ContainerImage,
+    attributes: None,
+  }
+}
+
+/// Normalize an image reference to compare it with the tags of the docker images
+/// `nginx` and `docker.io/library/nginx:latest` both become `nginx:latest`
+pub fn normalize_name(image: &str) -> String {
+  let image = image
+    .trim_start_matches("docker.io/")
+    .trim_start_matches("library/");
+  let name = image.rsplit('/').next().unwrap_or_default();
+  if name.contains(':') || name.contains('@') {
+    return image.to_owned();
+  }
+  format!("{image}:latest")
+}
+
+/// List the images used by the cargoes and jobs by normalized name
+pub async fn list_users(
+  state: &SystemState,
+) -> HttpResult<HashMap<String, ImageUsers>> {
+  let mut users: HashMap<String, ImageUsers> = HashMap::new();
+  let filter = GenericFilter::new();
+  let cargoes = CargoDb::transform_read_by(&filter, &state.inner.pool).await?;
+  for cargo in cargoes {
+    let init_image = cargo
+      .spec
+      .init_container
+      .as_ref()
+      .and_then(|init| init.image.clone());
+    let images = [cargo.spec.container.image.clone(), init_image];
+    for image in images.into_iter().flatten() {
+      users
+        .entry(normalize_name(&image))
+        .or_default()
+        .cargoes
+        .push(cargo.spec.cargo_key.clone());
+    }
+  }
+  let jobs = JobDb::transform_read_by(&filter, &state.inner.pool).await?;
+  for job in jobs {
+    for container in &job.containers {
+      let Some(image) = &container.image else {
+        continue;
+      };
+      users
+        .entry(normalize_name(image))
+        .or_default()
+        .jobs
+        .push(job.name.clone());
+    }
+  }
+  for user in users.values_mut() {
+    user.cargoes.dedup();
+    user.jobs.dedup();
+  }
+  Ok(users)
+}
+
+/// Merge the users of every tag of an image
+fn get_image_users(
+  repo_tags: &[String],
+  users: &HashMap<String, ImageUsers>,
+) -> ImageUsers {
+  let mut image_users = ImageUsers::default();
+  for tag in repo_tags {
+    if let Some(user) = users.get(&normalize_name(tag)) {
+      image_users.cargoes.extend(user.cargoes.clone());
+      image_users.jobs.extend(user.jobs.clone());
+    }
+  }
+  image_users
+}
+
+/// List the container images with the cargoes and jobs using them
+pub async fn list(
+  state: &SystemState,
+) -> HttpResult<Vec<ContainerImageSummary>> {
+  let users = list_users(state).await?;
+  let images = state
+    .inner
+    .runtime
+    .list_images(None::<ListImagesOptions<String>>)
+    .await?;
+  let images = images
+    .into_iter()
+    .map(|image| {
+      let image_users = get_image_users(&image.repo_tags, &users);
+      ContainerImageSummary {
+        id: image.id,
+        repo_tags: image.repo_tags,
+        size: image.size,
+        created: image.created,
+        cargoes: image_users.cargoes,
+        jobs: image_users.jobs,
+      }
+    })
+    .collect();
+  Ok(images)
+}
+
+/// Pull an image ahead of time and return his detailed information
+pub async fn pull_image(
+  image: &str,
+  secret: Option<String>,
+  state: &SystemState,
+) -> HttpResult<ImageInspect> {
+  pull(image, secret, None, state).await?;
+  let image = state.inner.runtime.inspect_image(image).await?;
+  Ok(image)
+}
+
+/// Remove an image, images used by a cargo or a job are protected
+pub async fn remove(
+  image: &str,
+  force: bool,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let inspect = state.inner.runtime.inspect_image(image).await?;
+  let mut repo_tags = inspect.repo_tags.unwrap_or_default();
+  repo_tags.push(image.to_owned());
+  let users = get_image_users(&repo_tags, &list_users(state).await?);
+  if !users.is_empty() {
+    let used_by = users
+      .cargoes
+      .iter()
+      .map(|cargo| format!("cargo {cargo}"))
+      .chain(users.jobs.iter().map(|job| format!("job {job}")))
+      .collect::<Vec<_>>()
+      .join(", ");
+    return Err(HttpError::conflict(format!(
+      "Image {image} is used by {used_by}"
+    )));
+  }
+  state
+    .inner
+    .runtime
+    .remove_image(
+      image,
+      Some(RemoveImageOptions {
+        force,
+        ..Default::default()
+      }),
+    )
+    .await?;
+  emit_image_event(
+    Some(image_actor(image)),
+    None,
+    image,
+    NativeEventAction::Destroy,
+    EventKind::Normal,
+    None,
+    state,
+  );
+  Ok(())
+}
+
+/// Remove the images that aren't used by a cargo, a job or a container
+pub async fn prune(
+  state: &SystemState,
+) -> HttpResult<ContainerImagePruneResult> {
+  let users = list_users(state).await?;
+  let containers = state
+    .inner
+    .runtime
+    .list_containers(Some(ListContainersOptions::<String> {
+      all: true,
+      ..Default::default()
+    }))
+    .await?;
+  let used_ids = containers
+    .into_iter()
+    .filter_map(|container| container.image_id)
+    .collect::<HashSet<_>>();
+  let images = state
+    .inner
+    .runtime
+    .list_images(None::<ListImagesOptions<String>>)
+    .await?;
+  let mut result = ContainerImagePruneResult::default();
+  for image in images {
+    if used_ids.contains(&image.id)
+      || !get_image_users(&image.repo_tags, &users).is_empty()
+    {
+      continue;
+    }
+    if let Err(err) = state
+      .inner
+      .runtime
+      .remove_image(&image.id, None::<RemoveImageOptions>)
+      .await
+    {
+      log::warn!("image::prune: {} {err}", image.id);
+      continue;
+    }
+    let name = image.repo_tags.first().unwrap_or(&image.id);
+    emit_image_event(
+      Some(image_actor(name)),
+      None,
+      name,
+      NativeEventAction::Destroy,
+      EventKind::Normal,
+      None,
+      state,
+    );
+    result.space_reclaimed += image.size;
+    result.images_deleted.push(image.id);
+  }
+  Ok(result)
+}
+
+/// Get the repository of an image tag or digest, `None` for dangling images
+fn tag_repository(tag: &str) -> Option<String> {
+  if tag == "<none>:<none>" || tag == "<none>@<none>" {
+    return None;
+  }
+  if let Some((repo, _)) = tag.split_once('@') {
+    return Some(repo.to_owned());
+  }
+  match tag.rsplit_once(':') {
+    Some((repo, tag)) if !tag.contains('/') => Some(repo.to_owned()),
+    _ => Some(tag.to_owned()),
+  }
+}
+
+/// Repositories an image is counted in for the `keep_last` policy.
+/// Untagged images are counted in the repositories of their digests,
+/// images without tag nor digest are counted together
+fn image_repositories(image: &ImageSummary) -> HashSet<String> {
+  let repositories = image
+    .repo_tags
+    .iter()
+    .filter_map(|tag| tag_repository(tag))
+    .collect::<HashSet<_>>();
+  if !repositories.is_empty() {
+    return repositories;
+  }
+  let repositories = image
+    .repo_digests
+    .iter()
+    .filter_map(|digest| tag_repository(digest))
+    .collect::<HashSet<_>>();
+  if !repositories.is_empty() {
+    return repositories;
+  }
+  HashSet::from(["<none>".to_owned()])
+}
+
+/// Split the images not protected in the ones in excess of the `keep_last` policy
+/// and the ones kept, both sorted from the newest to the oldest
+fn select_keep_last(
+  mut images: Vec<ImageSummary>,
+  keep_last: Option<usize>,
+  protected: &HashSet<String>,
+  used_ids: &HashSet<String>,
+) -> (Vec<ImageSummary>, Vec<ImageSummary>) {
+  images.sort_by(|a, b| b.created.cmp(&a.created));
+  let mut candidates = Vec::new();
+  let mut kept = Vec::new();
+  let mut per_repository: HashMap<String, usize> = HashMap::new();
+  for image in images {
+    let is_protected = used_ids.contains(&image.id)
+      || image
+        .repo_tags
+        .iter()
+        .any(|tag| protected.contains(&normalize_name(tag)));
+    let in_excess = match keep_last {
+      None => false,
+      Some(keep_last) => {
+        let mut in_excess = true;
+        for repository in image_repositories(&image) {
+          let count = per_repository.entry(repository).or_default();
+          if *count < keep_last {
+            in_excess = false;
+          }
+          *count += 1;
+        }
+        in_excess
+      }
+    };
+    if is_protected {
+      continue;
+    }
+    if in_excess {
+      candidates.push(image);
+    } else {
+      kept.push(image);
+    }
+  }
+  (candidates, kept)
+}
+
+/// Percentage of the disk used by the filesystem holding the given path
+fn disk_usage(path: &str) -> HttpResult<u8> {
+  let stat = nix::sys::statvfs::statvfs(path).map_err(|err| {
+    HttpError::internal_server_error(format!("{path}: {err}"))
+  })?;
+  let total = stat.blocks() as f64;
+  if total == 0.0 {
+    return Ok(0);
+  }
+  let used = total - stat.blocks_free() as f64;
+  Ok(((used / total) * 100.0) as u8)
+}
+
+/// List the normalized images of every cargo history
+async fn list_history_images(
+  state: &SystemState,
+) -> HttpResult<HashSet<String>> {
+  let filter = GenericFilter::new()
+    .r#where("kind_name", GenericClause::Eq("Cargo".to_owned()));
+  let specs = SpecDb::read_by(&filter, &state.inner.pool).await?;
+  let images = specs
+    .into_iter()
+    .filter_map(|spec| {
+      serde_json::from_value::<CargoSpecPartial>(spec.data).ok()
+    })
+    .flat_map(|spec| {
+      let init_image = spec.init_container.and_then(|init| init.image);
+      [spec.container.image, init_image]
+    })
+    .flatten()
+    .map(|image| normalize_name(&image))
+    .collect();
+  Ok(images)
+}
+
+/// Remove an image collected by the garbage collector and emit his event
+async fn gc_remove(
+  image: &ImageSummary,
+  policy: &str,
+  state: &SystemState,
+) -> bool {
+  if let Err(err) = state
+    .inner
+    .runtime
+    .remove_image(&image.id, None::<RemoveImageOptions>)
+    .await
+  {
+    log::warn!("image::gc: {} {err}", image.id);
+    return false;
+  }
+  let name = image.repo_tags.first().unwrap_or(&image.id);
+  emit_image_event(
+    Some(image_actor(name)),
+    None,
+    name,
+    NativeEventAction::Destroy,
+    EventKind::Normal,
+    Some(serde_json::json!({
+      "ImageGc": policy,
+    })),
+    state,
+  );
+  true
+}
+
+/// Collect the images according to the garbage collection policy
+/// Images used by a cargo, a job, a cargo history or a container are kept
+pub async fn gc(
+  config: &ImageGcConfig,
+  state: &SystemState,
+) -> HttpResult<ContainerImagePruneResult> {
+  let mut protected =
+    list_users(state).await?.into_keys().collect::<HashSet<_>>();
+  protected.extend(list_history_images(state).await?);
+  let containers = state
+    .inner
+    .runtime
+    .list_containers(Some(ListContainersOptions::<String> {
+      all: true,
+      ..Default::default()
+    }))
+    .await?;
+  let used_ids = containers
+    .into_iter()
+    .filter_map(|container| container.image_id)
+    .collect::<HashSet<_>>();
+  let images = state
+    .inner
+    .runtime
+    .list_images(None::<ListImagesOptions<String>>)
+    .await?;
+  let (candidates, kept) =
+    select_keep_last(images, config.keep_last, &protected, &used_ids);
+  let mut result = ContainerImagePruneResult::default();
+  for image in candidates {
+    if gc_remove(&image, "KeepLast", state).await {
+      result.space_reclaimed += image.size;
+      result.images_deleted.push(image.id);
+    }
+  }
+  let Some(high_water_mark) = config.high_water_mark else {
+    return Ok(result);
+  };
+  let low_water_mark = config
+    .low_water_mark
+    .unwrap_or(high_water_mark.saturating_sub(10));
+  let info = state.inner.runtime.info().await?;
+  let path = info
+    .docker_root_dir
+    .filter(|dir| std::path::Path::new(dir).exists())
+    .unwrap_or(state.config().state_dir.clone());
+  if disk_usage(&path)? <= high_water_mark {
+    return Ok(result);
+  }
+  // Oldest images are removed first until we reach the low water mark
+  for image in kept.into_iter().rev() {
+    if disk_usage(&path)? <= low_water_mark {
+      break;
+    }
+    if gc_remove(&image, "HighWaterMark", state).await {
+      result.space_reclaimed += image.size;
+      result.images_deleted.push(image.id);
+    }
+  }
+  Ok(result)
+}
+
+/// Hash of a build context and his options streamed to docker,
+/// used as the tag of the built image
+type BuildHasher = Rc<RefCell<Option<openssl::sha::Sha256>>>;
+
+/// Start the hash of a build with his options
+fn build_hasher(query: &ContainerImageBuildQuery) -> BuildHasher {
+  let mut hasher = openssl::sha::Sha256::new();
+  for option in [&query.dockerfile, &query.args, &query.target] {
+    hasher.update(option.as_deref().unwrap_or_default().as_bytes());
+    hasher.update(&[0]);
+  }
+  Rc::new(RefCell::new(Some(hasher)))
+}
+
+/// Compute the tag of a build once his context have been streamed
+/// The same context built with the same options always get the same tag
+fn build_tag(hasher: &BuildHasher) -> String {
+  hasher
+    .borrow_mut()
+    .take()
+    .unwrap_or_default()
+    .finish()
+    .iter()
+    .take(6)
+    .fold(String::new(), |acc, byte| format!("{acc}{byte:02x}"))
+}
+
+/// Query of the docker build endpoint
+/// The build is sent without bollard to stream the context and set the target
+#[derive(Serialize)]
+struct DockerBuildQuery<'a> {
+  dockerfile: &'a str,
+  t: &'a str,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  target: Option<&'a str>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  buildargs: Option<&'a str>,
+  rm: bool,
+  forcerm: bool,
+}
+
+/// Http client connected to the docker daemon and the url of his build endpoint
+/// without request timeout since a build response only starts once the context is received
+fn docker_client(
+  state: &SystemState,
+) -> HttpResult<(http::client::Client, String)> {
+  let host = DockerHost::parse(&state.config().docker_host)
+    .map_err(|err| HttpError::internal_server_error(err.to_string()))?;
+  let client = http::client::Client::build().disable_timeout();
+  let res = match host {
+    DockerHost::Unix(socket) => (
+      client
+        .connector(
+          http::client::Connector::default()
+            .connector(fn_service(move |_| {
+              let socket = socket.clone();
+              async move { Ok::<_, _>(rt::unix_connect(socket).await?) }
+            }))
+            .finish(),
+        )
+        .finish(),
+      "http://localhost/build".to_owned(),
+    ),
+    DockerHost::Tcp(addr) => (client.finish(), format!("http://{addr}/build")),
+  };
+  Ok(res)
+}
+
+/// Serialize a build stream item as a json line
+fn build_chunk(item: &ContainerImageBuildStream) -> HttpResult<Bytes> {
+  let item = serde_json::to_string(item).map_err(|err| {
+    HttpError::internal_server_error(format!(
+      "Failed stringify stream item: {err}"
+    ))
+  })?;
+  Ok(Bytes::from(item + "\r\n"))
+}
+
+/// Build an image from a stream of a tar archive of his context
+/// and stream the output.
+/// The context is forwarded to docker as it is received, the image is built
+/// under a temporary tag then tagged with a hash of the context and the options.
+pub async fn build<S, E>(
+  query: &ContainerImageBuildQuery,
+  context: S,
+  state: &SystemState,
+) -> HttpResult<Receiver<HttpResult<Bytes>>>
+where
+  S: Stream<Item = Result<Bytes, E>> + Unpin + 'static,
+  E: std::error::Error + 'static,
+{
+  let name = query.name.to_ascii_lowercase();
+  if name.is_empty()
+    || name.rsplit('/').next().unwrap_or_default().contains(':')
+  {
+    return Err(HttpError::bad_request(format!(
+      "Invalid image name {name}: the tag is generated from the context"
+    )));
+  }
+  if let Some(args) = &query.args {
+    serde_json::from_str::<HashMap<String, String>>(args).map_err(|err| {
+      HttpError::bad_request(format!("Invalid build args: {err}"))
+    })?;
+  }
+  let hasher = build_hasher(query);
+  let context = context.map({
+    let hasher = hasher.clone();
+    move |item| {
+      if let (Ok(bytes), Some(hasher)) = (&item, hasher.borrow_mut().as_mut()) {
+        hasher.update(bytes);
+      }
+      item
+    }
+  });
+  let build_image = format!("{name}:build-{}", uuid::Uuid::new_v4().simple());
+  let docker_query = DockerBuildQuery {
+    dockerfile: query.dockerfile.as_deref().unwrap_or("Dockerfile"),
+    t: &build_image,
+    target: query.target.as_deref(),
+    buildargs: query.args.as_deref(),
+    rm: true,
+    forcerm: true,
+  };
+  let (client, url) = docker_client(state)?;
+  let mut res = client
+    .post(url)
+    .query(&docker_query)
+    .map_err(|err| HttpError::bad_request(format!("Invalid build: {err}")))?
+    .header(http::header::CONTENT_TYPE, "application/x-tar")
+    .send_stream(context)
+    .await
+    .map_err(|err| {
+      HttpError::internal_server_error(format!("Unable to build: {err}"))
+    })?;
+  if !res.status().is_success() {
+    let body = res.body().await.unwrap_or_default();
+    let message = serde_json::from_slice::<serde_json::Value>(&body)
+      .ok()
+      .and_then(|body| body["message"].as_str().map(str::to_owned))
+      .unwrap_or_else(|| String::from_utf8_lossy(&body).to_string());
+    return Err(HttpError::bad_request(message));
+  }
+  let (tx, rx) = ntex::channel::mpsc::channel::<HttpResult<Bytes>>();
+  let state = state.clone();
+  rt::spawn(async move {
+    let mut buffer = Vec::new();
+    while let Some(bytes) = res.next().await {
+      let bytes = match bytes {
+        Err(err) => {
+          let _ = tx.send(Err(HttpError::internal_server_error(format!(
+            "Unable to read build output: {err}"
+          ))));
+          return;
+        }
+        Ok(bytes) => bytes,
+      };
+      buffer.extend_from_slice(&bytes);
+      while let Some(pos) = buffer.iter().position(|byte| *byte == b'\n') {
+        let line = buffer.drain(..=pos).collect::<Vec<u8>>();
+        let Ok(info) = serde_json::from_slice::<BuildInfo>(&line) else {
+          continue;
+        };
+        if let Some(error) = info.error {
+          emit_image_event(
+            Some(image_actor(&build_image)),
+            None,
+            &error,
+            NativeEventAction::Create,
+            EventKind::Error,
+            None,
+            &state,
+          );
+          let _ = tx.send(Err(HttpError::bad_request(error)));
+          return;
+        }
+        let Some(output) = info.stream.or(info.status) else {
+          continue;
+        };
+        let item = ContainerImageBuildStream::Progress(output);
+        if tx.send(build_chunk(&item)).is_err() {
+          log::warn!("image::build: {build_image} client disconnected");
+        }
+      }
+    }
+    let tag = build_tag(&hasher);
+    let image = format!("{name}:{tag}");
+    let options = TagImageOptions {
+      repo: name.clone(),
+      tag,
+    };
+    if let Err(err) = state
+      .inner
+      .runtime
+      .tag_image(&build_image, Some(options))
+      .await
+    {
+      let _ = tx.send(Err(err.into()));
+      return;
+    }
+    let options = RemoveImageOptions {
+      noprune: true,
+      ..Default::default()
+    };
+    if let Err(err) = state
+      .inner
+      .runtime
+      .remove_image(&build_image, Some(options))
+      .await
+    {
+      log::warn!("image::build: {build_image} {err}");
+    }
+    emit_image_event(
+      Some(image_actor(&image)),
+      None,
+      &image,
+      NativeEventAction::Create,
+      EventKind::Normal,
+      None,
+      &state,
+    );
+    let _ = tx.send(build_chunk(&ContainerImageBuildStream::Done(image)));
+  });
+  Ok(rx)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn image(
+    id: &str,
+    created: i64,
+    tags: &[&str],
+    digests: &[&str],
+  ) -> ImageSummary {
+    ImageSummary {
+      id: id.to_owned(),
+      created,
+      repo_tags: tags.iter().map(|tag| tag.to_string()).collect(),
+      repo_digests: digests.iter().map(|digest| digest.to_string()).collect(),
+      ..Default::default()
+    }
+  }
+
+  fn ids(images: &[ImageSummary]) -> Vec<&str> {
+    images.iter().map(|image| image.id.as_str()).collect()
+  }
+
+  #[test]
+  fn gc_keep_last() {
+    let images = vec![
+      image("app-1", 1, &["app:1"], &[]),
+      image("app-2", 2, &["app:2"], &[]),
+      image("app-3", 3, &["app:3"], &[]),
+      image("api-1", 1, &["api:1"], &[]),
+      image("app-old", 0, &[], &["app@sha256:aaa"]),
+      image("web-old", 0, &[], &["web@sha256:bbb"]),
+      image("dangling-1", 1, &["<none>:<none>"], &[]),
+      image("dangling-2", 2, &[], &[]),
+      image("used", 0, &["app:0"], &[]),
+    ];
+    let protected = HashSet::new();
+    let used_ids = HashSet::from(["used".to_owned()]);
+    let (candidates, kept) =
+      select_keep_last(images.clone(), Some(2), &protected, &used_ids);
+    assert_eq!(ids(&candidates), vec!["app-1", "app-old"]);
+    assert_eq!(
+      ids(&kept),
+      vec![
+        "app-3",
+        "app-2",
+        "dangling-2",
+        "api-1",
+        "dangling-1",
+        "web-old"
+      ]
+    );
+    let (candidates, kept) =
+      select_keep_last(images, None, &protected, &HashSet::new());
+    assert!(candidates.is_empty());
+    assert_eq!(kept.len(), 9);
+  }
+}
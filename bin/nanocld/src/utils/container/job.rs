@@ -5,7 +5,7 @@ use nanocl_stubs::{
   process::{Process, ProcessKind},
 };
 
-use crate::{models::SystemState, utils};
+use crate::{models::SystemState, utils, vars};
 
 /// Create process (container) for a job
 async fn create_job_instance(
@@ -21,7 +21,9 @@ async fn create_job_instance(
   let host_config = container.host_config.clone().unwrap_or_default();
   container.host_config = Some(HostConfig {
     network_mode: Some(
-      host_config.network_mode.unwrap_or("nanoclbr0".to_owned()),
+      host_config
+        .network_mode
+        .unwrap_or(vars::DEFAULT_NETWORK.to_owned()),
     ),
     ..host_config
   });
@@ -34,10 +34,10 @@ pub async fn create(
   config.labels = Some(labels);
   let res = state
     .inner
-    .docker_api
+    .runtime
     .create_container(
       Some(CreateContainerOptions {
-        name,
+        name: name.to_owned(),
         ..Default::default()
       }),
       config,
@@ -45,7 +45,7 @@ pub async fn create(
     .await?;
   let inspect = state
     .inner
-    .docker_api
+    .runtime
     .inspect_container(&res.id, None::<InspectContainerOptions>)
     .await?;
   let created_at = inspect.created.clone().unwrap_or_default();
@@ -55,7 +55,7 @@ pub async fn create(
     kind: kind.clone(),
     data: serde_json::to_value(&inspect)
       .map_err(|err| err.map_err_context(|| "CreateProcess"))?,
-    node_name: state.inner.config.hostname.clone(),
+    node_name: state.config().hostname.clone(),
     kind_key: kind_key.to_owned(),
     created_at: Some(
       chrono::NaiveDateTime::parse_from_str(
@@ -78,7 +78,7 @@ pub async fn delete_instance(
   opts: Option<RemoveContainerOptions>,
   state: &SystemState,
 ) -> HttpResult<()> {
-  match state.inner.docker_api.remove_container(pk, opts).await {
+  match state.inner.runtime.remove_container(pk, opts).await {
     Ok(_) => {}
     Err(err) => match &err {
       bollard_next::errors::Error::DockerResponseServerError {
@@ -136,7 +136,7 @@ pub async fn kill_by_kind_key(
   for process in processes {
     state
       .inner
-      .docker_api
+      .runtime
       .kill_container(&process.key, Some(opts.clone().into()))
       .await?;
   }
@@ -155,7 +155,7 @@ pub async fn restart_instances(
   for process in processes {
     state
       .inner
-      .docker_api
+      .runtime
       .restart_container(&process.key, None)
       .await?;
   }
@@ -177,7 +177,7 @@ pub async fn stop_instances(
   for process in processes {
     state
       .inner
-      .docker_api
+      .runtime
       .stop_container(
         &process.data.id.unwrap_or_default(),
         None::<StopContainerOptions>,
@@ -207,7 +207,7 @@ pub async fn start_instances(
   for process in processes {
     state
       .inner
-      .docker_api
+      .runtime
       .start_container(
         &process.data.id.unwrap_or_default(),
         None::<StartContainerOptions<String>>,
@@ -10,7 +10,7 @@ use nanocl_stubs::{
 
 use crate::{
   models::{SystemState, VmImageDb},
-  vars,
+  utils, vars,
 };
 
 /// Create a VM instance from a VM image
@@ -21,7 +21,7 @@ pub async fn create_vm_instance(
   state: &SystemState,
 ) -> HttpResult<Process> {
   let mut labels: HashMap<String, String> = HashMap::new();
-  let img_path = format!("{}/vms/images", state.inner.config.state_dir);
+  let img_path = format!("{}/vms/images", state.config().state_dir);
   labels.insert("io.nanocl.v".to_owned(), vm.spec.vm_key.clone());
   labels.insert("io.nanocl.n".to_owned(), vm.namespace_name.clone());
   let mut args: Vec<String> =
@@ -93,6 +93,10 @@ pub async fn create_vm_instance(
     state,
   )
   .await?;
+  let network = match &vm.spec.host_config.runtime_network {
+    Some(network) => network.to_owned(),
+    None => utils::namespace::get_network(&vm.namespace_name, state).await?,
+  };
   let spec = bollard_next::container::Config {
     image: Some(image),
     tty: Some(true),
@@ -105,13 +109,7 @@ pub async fn create_vm_instance(
     attach_stdout: Some(true),
     open_stdin: Some(true),
     host_config: Some(HostConfig {
-      network_mode: Some(
-        vm.spec
-          .host_config
-          .runtime_network
-          .clone()
-          .unwrap_or("nanoclbr0".to_owned()),
-      ),
+      network_mode: Some(network),
       binds: Some(vec![format!("{img_path}:{img_path}")]),
       devices: Some(devices),
       cap_add: Some(vec!["NET_ADMIN".into()]),
@@ -128,5 +126,11 @@ pub async fn create_vm_instance(
     state,
   )
   .await?;
+  utils::namespace::connect_processes(
+    &vm.namespace_name,
+    std::slice::from_ref(&process),
+    state,
+  )
+  .await?;
   Ok(process)
 }
@@ -92,6 +92,18 @@ impl CtrlClient {
     Ok(body)
   }
 
+  /// Check that the controller accept connections
+  /// Any response means the controller is reachable
+  pub async fn ping(&self) -> Result<(), HttpClientError> {
+    self
+      .client
+      .head(self.format_url("/"))
+      .send()
+      .await
+      .map_err(|err| err.map_err_context(|| self.name.to_owned()))?;
+    Ok(())
+  }
+
   /// Call apply rule method on controller
   pub async fn apply_rule(
     &self,
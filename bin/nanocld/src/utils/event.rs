@@ -0,0 +1,275 @@
+use std::{
+  collections::HashSet,
+  sync::{Arc, Mutex},
+};
+
+use futures::{stream, StreamExt};
+use ntex::{util::Bytes, web};
+
+use nanocl_error::{http::HttpError, io::IoResult};
+use nanocl_stubs::system::{Event, EventCondition, EVENT_WATCH_OVERLAP};
+
+use crate::{
+  models::{EventDb, SystemState},
+  utils::stream::ByteStream,
+};
+
+/// Number of stored events read at once when replaying a watch
+const REPLAY_BATCH: i64 = 500;
+
+/// Id of the server-sent event of an event, the cursor used to resume
+/// the watch with the `Last-Event-ID` header
+pub fn gen_sse_id(bytes: &[u8]) -> Option<String> {
+  let event = serde_json::from_slice::<serde_json::Value>(bytes).ok()?;
+  event["Seq"].as_i64().map(|seq| seq.to_string())
+}
+
+/// Convert an event to bytes to send to the http clients
+fn event_to_bytes(event: &Event) -> IoResult<Bytes> {
+  let mut data = serde_json::to_vec(event)?;
+  data.push(b'\n');
+  Ok(Bytes::from(data))
+}
+
+/// State of a watch resumed from a cursor shared by the replay
+/// and the live events
+#[derive(Default)]
+struct ResumeState {
+  /// Keys of the replayed events that may also be received live
+  replayed: HashSet<uuid::Uuid>,
+  /// When the replay ended, the live events created after it weren't replayed
+  replayed_until: Option<chrono::NaiveDateTime>,
+  /// Conditions to stop the watch
+  conditions: Vec<EventCondition>,
+  /// Number of events that met a condition
+  met: usize,
+}
+
+impl ResumeState {
+  fn is_stopped(&self) -> bool {
+    !self.conditions.is_empty() && self.met >= self.conditions.len()
+  }
+
+  /// Count an event meeting a condition, return true when the watch stop
+  fn meet(&mut self, event: &Event) -> bool {
+    if self.conditions.iter().any(|c| c == event) {
+      self.met += 1;
+    }
+    self.is_stopped()
+  }
+
+  /// Live event to send, none to skip it.
+  /// The events already replayed are skipped and the replayed keys are
+  /// forgotten once the live events are past the end of the replay
+  fn next_live(
+    &mut self,
+    event: &Event,
+    filter: Option<&EventCondition>,
+  ) -> bool {
+    if self.replayed.remove(&event.key) {
+      return false;
+    }
+    let is_past = self
+      .replayed_until
+      .is_some_and(|until| event.created_at > until);
+    if is_past && !self.replayed.is_empty() {
+      self.replayed = HashSet::new();
+    }
+    self.meet(event);
+    filter.map(|f| f.matches(event)).unwrap_or(true)
+  }
+}
+
+/// Watch the new events matching the filter, when a cursor is given the
+/// stored events created since the cursor event minus `EVENT_WATCH_OVERLAP`
+/// are replayed first. The watch is subscribed before the replay so the
+/// events emitted in between are sent once after the replay.
+/// The stop conditions are met by the replayed events too.
+pub async fn watch(
+  since: Option<i64>,
+  filter: Option<EventCondition>,
+  condition: Option<Vec<EventCondition>>,
+  state: &SystemState,
+) -> IoResult<ByteStream> {
+  let Some(since) = since else {
+    let live = state.subscribe_raw(condition, filter).await?;
+    return Ok(live.boxed_local());
+  };
+  let overlap = chrono::Duration::seconds(EVENT_WATCH_OVERLAP);
+  // The live events are created after this date minus the time to store them
+  let subscribed_at = chrono::Utc::now().naive_utc() - overlap;
+  let live = state.subscribe_raw(None, None).await?;
+  let pool = state.inner.pool.clone();
+  let position = EventDb::read_cursor_date(since, &pool)
+    .await?
+    .map(|date| (date - overlap, i64::MIN));
+  let resume = Arc::new(Mutex::new(ResumeState {
+    conditions: condition.unwrap_or_default(),
+    ..Default::default()
+  }));
+  let resume_ptr = resume.clone();
+  let replay_filter = filter.clone();
+  let replay = stream::unfold(position, move |position| {
+    let pool = pool.clone();
+    let resume = resume_ptr.clone();
+    let filter = replay_filter.clone();
+    async move {
+      let end_replay = || {
+        if let Ok(mut resume) = resume.lock() {
+          resume.replayed_until = Some(chrono::Utc::now().naive_utc());
+        }
+      };
+      let Some((created_at, seq)) = position else {
+        end_replay();
+        return None;
+      };
+      let events =
+        match EventDb::read_after(created_at, seq, REPLAY_BATCH, &pool).await {
+          Ok(events) => events,
+          Err(err) => {
+            end_replay();
+            let err = web::Error::from(HttpError::from(err));
+            return Some((vec![Err(err)], None));
+          }
+        };
+      let mut next = events
+        .last()
+        .filter(|_| events.len() as i64 == REPLAY_BATCH)
+        .map(|event| (event.created_at, event.seq));
+      let mut items = Vec::new();
+      if let Ok(mut resume) = resume.lock() {
+        for event in &events {
+          let is_stopped = resume.meet(event);
+          if filter.as_ref().map(|f| f.matches(event)).unwrap_or(true) {
+            if event.created_at >= subscribed_at {
+              resume.replayed.insert(event.key);
+            }
+            items.push(
+              event_to_bytes(event)
+                .map_err(|err| web::Error::from(HttpError::from(err))),
+            );
+          }
+          if is_stopped {
+            next = None;
+            break;
+          }
+        }
+      }
+      if next.is_none() {
+        end_replay();
+      }
+      Some((items, next))
+    }
+  })
+  .flat_map(stream::iter);
+  let live = live
+    .scan(resume, move |resume, item| {
+      let item = match resume.lock() {
+        Err(_) => None,
+        Ok(resume) if resume.is_stopped() => None,
+        Ok(mut resume) => {
+          // The keep alive messages aren't events
+          match item
+            .as_ref()
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Event>(bytes).ok())
+          {
+            None => Some(Some(item)),
+            Some(event) => {
+              Some(resume.next_live(&event, filter.as_ref()).then_some(item))
+            }
+          }
+        }
+      };
+      async move { item }
+    })
+    .filter_map(|item| async move { item });
+  Ok(replay.chain(live).boxed_local())
+}
+
+#[cfg(test)]
+mod tests {
+  use nanocl_stubs::system::{EventActor, EventActorKind, EventKind};
+
+  use super::*;
+
+  fn gen_event(action: &str) -> Event {
+    Event {
+      key: uuid::Uuid::new_v4(),
+      seq: 0,
+      created_at: chrono::Utc::now().naive_utc(),
+      expires_at: chrono::Utc::now().naive_utc(),
+      reporting_node: "test".to_owned(),
+      reporting_controller: "test".to_owned(),
+      kind: EventKind::Normal,
+      action: action.to_owned(),
+      reason: "test".to_owned(),
+      note: None,
+      actor: Some(EventActor {
+        key: Some("test".to_owned()),
+        kind: EventActorKind::Cargo,
+        attributes: None,
+      }),
+      related: None,
+      metadata: None,
+    }
+  }
+
+  fn gen_stop_condition(action: &str) -> EventCondition {
+    EventCondition {
+      actor_key: Some("test".to_owned()),
+      actor_kind: Some(EventActorKind::Cargo),
+      kind: vec![EventKind::Normal],
+      action: vec![action.parse().unwrap()],
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn stop_condition() {
+    let event = gen_event("delete");
+    assert!(gen_stop_condition("delete") == event);
+    assert!(gen_stop_condition("create") != event);
+    // The fields not set don't match as a stop condition but as a filter
+    let condition = EventCondition {
+      action: vec!["delete".parse().unwrap()],
+      ..Default::default()
+    };
+    assert!(condition != event);
+    assert!(condition.matches(&event));
+    let condition = EventCondition {
+      reporting_node: Some("other".to_owned()),
+      ..gen_stop_condition("delete")
+    };
+    assert!(condition != event);
+  }
+
+  #[test]
+  fn resume_live() {
+    let replayed = gen_event("create");
+    let mut resume = ResumeState {
+      conditions: vec![gen_stop_condition("delete")],
+      ..Default::default()
+    };
+    resume.replayed.insert(replayed.key);
+    resume.replayed_until = Some(replayed.created_at);
+    // A replayed event received live is skipped once
+    assert!(!resume.next_live(&replayed, None));
+    assert!(resume.next_live(&replayed, None));
+    // The replayed keys are forgotten past the end of the replay
+    let old = gen_event("create");
+    resume.replayed.insert(old.key);
+    let mut next = gen_event("start");
+    next.created_at += chrono::Duration::seconds(1);
+    assert!(resume.next_live(&next, None));
+    assert!(resume.replayed.is_empty());
+    // The filter doesn't change the stop conditions
+    let filter = EventCondition {
+      action: vec!["start".parse().unwrap()],
+      ..Default::default()
+    };
+    let delete = gen_event("delete");
+    assert!(!resume.next_live(&delete, Some(&filter)));
+    assert!(resume.is_stopped());
+  }
+}
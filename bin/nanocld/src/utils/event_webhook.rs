@@ -0,0 +1,535 @@
+use std::{
+  sync::{atomic::Ordering, Arc},
+  time::{Duration, Instant},
+};
+
+use ntex::{http::Client, rt, time};
+use openssl::{hash::MessageDigest, pkey::PKey, sign::Signer};
+
+use nanocl_error::{
+  http::{HttpError, HttpResult},
+  io::{IoError, IoResult},
+};
+use nanocl_stubs::{
+  generic::{GenericClause, GenericFilter},
+  resource::ResourcePartial,
+  system::{Event, EventActorKind},
+  webhook::{EventWebhookStatus, ResourceEventWebhook},
+};
+
+use crate::{
+  models::{EventDeadLetterDb, ResourceDb, SecretDb, SystemState},
+  repositories::generic::*,
+  vars,
+};
+
+/// Number of attempts before a delivery is dead-lettered
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+
+/// Seconds before an attempt is abandoned
+const DELIVERY_TIMEOUT: u32 = 10;
+
+/// Longest delay between two attempts in seconds
+const MAX_BACKOFF: u64 = 300;
+
+/// Seconds before the cached webhooks are read again from the store,
+/// the resources changed from another node don't clear the cache
+const CACHE_TTL: u64 = 30;
+
+/// Interval to check the shutdown while waiting for the next attempt
+const SHUTDOWN_POLL: Duration = Duration::from_millis(500);
+
+/// Delay before the next attempt, doubled after each failed attempt
+fn gen_backoff(attempts: u32) -> Duration {
+  let delay = 2_u64
+    .checked_pow(attempts.saturating_sub(1))
+    .unwrap_or(MAX_BACKOFF);
+  Duration::from_secs(delay.min(MAX_BACKOFF))
+}
+
+/// Signature of the body with the secret of the webhook
+/// sent in the `X-Nanocl-Signature` header
+fn gen_signature(secret: &str, body: &[u8]) -> IoResult<String> {
+  let map_err = |err: openssl::error::ErrorStack| {
+    IoError::interrupted("Event webhook signature", &err.to_string())
+  };
+  let key = PKey::hmac(secret.as_bytes()).map_err(map_err)?;
+  let mut signer =
+    Signer::new(MessageDigest::sha256(), &key).map_err(map_err)?;
+  signer.update(body).map_err(map_err)?;
+  let hmac = signer.sign_to_vec().map_err(map_err)?;
+  Ok(
+    hmac
+      .iter()
+      .fold("sha256=".to_owned(), |acc, byte| format!("{acc}{byte:02x}")),
+  )
+}
+
+/// Update the deliveries of a webhook after an attempt
+fn update_status(
+  name: &str,
+  url: &str,
+  res: &IoResult<()>,
+  state: &SystemState,
+) {
+  let Ok(mut webhooks) = state.inner.webhooks.lock() else {
+    return;
+  };
+  let status = webhooks.entry(name.to_owned()).or_default();
+  name.clone_into(&mut status.name);
+  url.clone_into(&mut status.url);
+  let now = chrono::Utc::now().naive_utc();
+  match res {
+    Ok(_) => {
+      status.delivered += 1;
+      status.last_delivered_at = Some(now);
+    }
+    Err(err) => {
+      status.failed += 1;
+      status.last_failed_at = Some(now);
+      status.last_error = Some(err.to_string());
+    }
+  }
+}
+
+/// Wait for the delay before the next attempt,
+/// return false when the daemon start to shutdown in the meantime
+async fn wait_backoff(delay: Duration, state: &SystemState) -> bool {
+  let deadline = Instant::now() + delay;
+  loop {
+    if state.is_shutting_down() {
+      return false;
+    }
+    let now = Instant::now();
+    if now >= deadline {
+      return true;
+    }
+    time::sleep(SHUTDOWN_POLL.min(deadline - now)).await;
+  }
+}
+
+/// Read the key of a `nanocl.io/webhook-secret` secret
+async fn read_secret(name: &str, state: &SystemState) -> IoResult<String> {
+  let secret = SecretDb::read_by_pk(name, &state.inner.pool).await?;
+  if secret.kind != vars::WEBHOOK_SECRET_KIND {
+    return Err(IoError::invalid_data(
+      "Event webhook secret",
+      format!("{name} isn't a {} secret", vars::WEBHOOK_SECRET_KIND).as_str(),
+    ));
+  }
+  Ok(serde_json::from_value::<String>(secret.data)?)
+}
+
+/// Signature of the body with the secret of a webhook if it has one
+async fn sign(
+  webhook: &ResourceEventWebhook,
+  body: &[u8],
+  state: &SystemState,
+) -> IoResult<Option<String>> {
+  let Some(secret) = &webhook.secret else {
+    return Ok(None);
+  };
+  let key = read_secret(secret, state).await?;
+  Ok(Some(gen_signature(&key, body)?))
+}
+
+/// Check the secret of an event webhook resource before it's saved
+pub async fn validate(
+  resource: &ResourcePartial,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let webhook =
+    serde_json::from_value::<ResourceEventWebhook>(resource.data.clone())
+      .map_err(|err| {
+        HttpError::bad_request(format!("Invalid event webhook {err}"))
+      })?;
+  if let Some(secret) = &webhook.secret {
+    read_secret(secret, state).await.map_err(|err| {
+      HttpError::bad_request(format!("Invalid event webhook secret {err}"))
+    })?;
+  }
+  Ok(())
+}
+
+/// Send the event to the url of a webhook
+async fn send(
+  url: &str,
+  body: &[u8],
+  signature: Option<&str>,
+  event: &Event,
+) -> IoResult<()> {
+  let client = Client::build()
+    .timeout(time::Millis::from_secs(DELIVERY_TIMEOUT))
+    .finish();
+  let mut req = client
+    .post(url)
+    .content_type("application/json")
+    .header("X-Nanocl-Event", event.action.as_str())
+    .header("X-Nanocl-Delivery", event.key.to_string());
+  if let Some(signature) = signature {
+    req = req.header("X-Nanocl-Signature", signature);
+  }
+  let res = req
+    .send_body(body.to_vec())
+    .await
+    .map_err(|err| IoError::interrupted("Event webhook", &err.to_string()))?;
+  if !res.status().is_success() {
+    return Err(IoError::interrupted(
+      "Event webhook",
+      format!("{url} responded {}", res.status()).as_str(),
+    ));
+  }
+  Ok(())
+}
+
+/// Save an event not delivered as a dead letter
+async fn save_dead_letter(
+  name: &str,
+  url: &str,
+  attempts: u32,
+  err: &IoError,
+  event: &Event,
+  state: &SystemState,
+) -> IoResult<()> {
+  log::warn!("event_webhook::deliver: {name} dead-lettered: {err}");
+  if let Ok(mut webhooks) = state.inner.webhooks.lock() {
+    webhooks.entry(name.to_owned()).or_default().dead_lettered += 1;
+  }
+  let dead_letter = EventDeadLetterDb {
+    key: uuid::Uuid::new_v4(),
+    created_at: chrono::Utc::now().naive_utc(),
+    node_name: state.config().hostname.clone(),
+    webhook: name.to_owned(),
+    url: url.to_owned(),
+    attempts: attempts as i64,
+    error: err.to_string(),
+    event: serde_json::to_value(event)?,
+  };
+  EventDeadLetterDb::create_from(dead_letter, &state.inner.pool).await?;
+  Ok(())
+}
+
+/// Deliver an event to a webhook, retry with a backoff when it fails
+/// and save it as a dead letter after the last attempt
+/// or when the daemon start to shutdown before the next one
+async fn deliver(
+  name: String,
+  webhook: ResourceEventWebhook,
+  event: Event,
+  state: SystemState,
+) -> IoResult<()> {
+  let body = serde_json::to_vec(&event)?;
+  let signature = match sign(&webhook, &body, &state).await {
+    Ok(signature) => signature,
+    Err(err) => {
+      // Without its secret the event can't be signed, no attempt is made
+      let res =
+        save_dead_letter(&name, &webhook.url, 0, &err, &event, &state).await;
+      update_status(&name, &webhook.url, &Err(err), &state);
+      return res;
+    }
+  };
+  let max_attempts = webhook.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS);
+  let mut attempts = 0;
+  loop {
+    attempts += 1;
+    let res = send(&webhook.url, &body, signature.as_deref(), &event).await;
+    update_status(&name, &webhook.url, &res, &state);
+    let Err(err) = res else {
+      return Ok(());
+    };
+    if attempts < max_attempts && !state.is_shutting_down() {
+      log::debug!("event_webhook::deliver: {name} attempt {attempts}: {err}");
+      state
+        .inner
+        .pending_deliveries
+        .fetch_add(1, Ordering::SeqCst);
+      if wait_backoff(gen_backoff(attempts), &state).await {
+        state
+          .inner
+          .pending_deliveries
+          .fetch_sub(1, Ordering::SeqCst);
+        continue;
+      }
+      let res =
+        save_dead_letter(&name, &webhook.url, attempts, &err, &event, &state)
+          .await;
+      state
+        .inner
+        .pending_deliveries
+        .fetch_sub(1, Ordering::SeqCst);
+      return res;
+    }
+    return save_dead_letter(
+      &name,
+      &webhook.url,
+      attempts,
+      &err,
+      &event,
+      &state,
+    )
+    .await;
+  }
+}
+
+/// Wait for the deliveries waiting for a retry to be dead-lettered
+/// once the daemon is shutting down, return false on timeout
+pub async fn flush(state: &SystemState, timeout: Duration) -> bool {
+  let wait = async {
+    while state.inner.pending_deliveries.load(Ordering::SeqCst) > 0 {
+      time::sleep(Duration::from_millis(50)).await;
+    }
+  };
+  time::timeout(time::Millis::from(timeout), wait)
+    .await
+    .is_ok()
+}
+
+/// Event webhook resources from the cache or the store
+async fn list_webhooks(
+  state: &SystemState,
+) -> IoResult<Arc<Vec<(String, ResourceEventWebhook)>>> {
+  let version = {
+    let Ok(cache) = state.inner.webhook_cache.lock() else {
+      return Err(IoError::interrupted("Event webhook", "unable to lock"));
+    };
+    let is_valid = cache.loaded_at.is_some_and(|(loaded_at, version)| {
+      version == cache.version
+        && loaded_at.elapsed() < Duration::from_secs(CACHE_TTL)
+    });
+    if is_valid {
+      return Ok(cache.webhooks.clone());
+    }
+    cache.version
+  };
+  let loaded_at = Instant::now();
+  let filter = GenericFilter::new().r#where(
+    "kind",
+    GenericClause::Eq(vars::EVENT_WEBHOOK_KIND.to_owned()),
+  );
+  let resources =
+    ResourceDb::transform_read_by(&filter, &state.inner.pool).await?;
+  let webhooks = resources
+    .into_iter()
+    .filter_map(|resource| {
+      let name = resource.spec.resource_key;
+      match serde_json::from_value::<ResourceEventWebhook>(resource.spec.data) {
+        Ok(webhook) => Some((name, webhook)),
+        Err(err) => {
+          log::warn!("event_webhook::list_webhooks: {name}: {err}");
+          None
+        }
+      }
+    })
+    .collect::<Vec<_>>();
+  let webhooks = Arc::new(webhooks);
+  // The webhooks are stale if a resource changed during the read
+  if let Ok(mut cache) = state.inner.webhook_cache.lock() {
+    cache.loaded_at = Some((loaded_at, version));
+    cache.webhooks = webhooks.clone();
+  }
+  Ok(webhooks)
+}
+
+/// Send an event in the background to the webhooks with a matching condition
+pub fn dispatch(event: &Event, state: &SystemState) {
+  // A resource changed, the webhooks are read again on the next event
+  let is_resource = event
+    .actor
+    .as_ref()
+    .is_some_and(|actor| actor.kind == EventActorKind::Resource);
+  if is_resource {
+    if let Ok(mut cache) = state.inner.webhook_cache.lock() {
+      cache.version += 1;
+    }
+  }
+  let event = event.clone();
+  let state = state.clone();
+  rt::spawn(async move {
+    let webhooks = match list_webhooks(&state).await {
+      Ok(webhooks) => webhooks,
+      Err(err) => {
+        log::warn!("event_webhook::dispatch: {err}");
+        return;
+      }
+    };
+    for (name, webhook) in webhooks.iter() {
+      let is_matching = match &webhook.conditions {
+        None => true,
+        Some(conditions) => conditions.iter().any(|c| c.matches(&event)),
+      };
+      if !is_matching {
+        continue;
+      }
+      let name = name.clone();
+      let webhook = webhook.clone();
+      let event = event.clone();
+      let state = state.clone();
+      rt::spawn(async move {
+        if let Err(err) = deliver(name.clone(), webhook, event, state).await {
+          log::error!("event_webhook::dispatch: {name}: {err}");
+        }
+      });
+    }
+  });
+}
+
+/// Deliveries of the webhooks by the node
+pub fn list_status(state: &SystemState) -> IoResult<Vec<EventWebhookStatus>> {
+  let webhooks = state
+    .inner
+    .webhooks
+    .lock()
+    .map_err(|_| IoError::interrupted("Event webhook", "unable to lock"))?;
+  let mut webhooks = webhooks.values().cloned().collect::<Vec<_>>();
+  webhooks.sort_by(|a, b| a.name.cmp(&b.name));
+  Ok(webhooks)
+}
+
+#[cfg(test)]
+mod tests {
+  use nanocl_stubs::{
+    secret::SecretPartial,
+    system::{EventKind, EventPartial},
+  };
+
+  use super::*;
+
+  use crate::{models::EventDb, objects::generic::*, utils::tests::*};
+
+  #[test]
+  fn backoff() {
+    assert_eq!(gen_backoff(1), Duration::from_secs(1));
+    assert_eq!(gen_backoff(2), Duration::from_secs(2));
+    assert_eq!(gen_backoff(4), Duration::from_secs(8));
+    assert_eq!(gen_backoff(10), Duration::from_secs(MAX_BACKOFF));
+    assert_eq!(gen_backoff(100), Duration::from_secs(MAX_BACKOFF));
+  }
+
+  #[test]
+  fn signature() {
+    // RFC 4231 test case 2
+    let signature =
+      gen_signature("Jefe", b"what do ya want for nothing?").unwrap();
+    assert_eq!(
+      signature,
+      "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+  }
+
+  #[ntex::test]
+  async fn dead_letter() {
+    let system = gen_default_test_system().await;
+    let state = system.state;
+    let event: Event = EventDb::try_from(EventPartial {
+      reporting_controller: vars::CONTROLLER_NAME.to_owned(),
+      reporting_node: state.config().hostname.clone(),
+      action: "webhook_test".to_owned(),
+      reason: "test".to_owned(),
+      kind: EventKind::Normal,
+      actor: None,
+      related: None,
+      metadata: None,
+      note: None,
+    })
+    .unwrap()
+    .try_into()
+    .unwrap();
+    let secret = SecretPartial {
+      name: "test-webhook-secret".to_owned(),
+      kind: vars::WEBHOOK_SECRET_KIND.to_owned(),
+      immutable: false,
+      metadata: None,
+      data: serde_json::json!("secret"),
+    };
+    SecretDb::create_obj(&secret, &state).await.unwrap();
+    let webhook = ResourceEventWebhook {
+      url: "http://127.0.0.1:1/events".to_owned(),
+      conditions: None,
+      secret: Some("test-webhook-secret".to_owned()),
+      max_attempts: Some(1),
+    };
+    deliver(
+      "test-webhook".to_owned(),
+      webhook,
+      event.clone(),
+      state.clone(),
+    )
+    .await
+    .unwrap();
+    let status = list_status(&state).unwrap();
+    let status = status.iter().find(|s| s.name == "test-webhook").unwrap();
+    assert_eq!(status.failed, 1);
+    assert_eq!(status.dead_lettered, 1);
+    let filter = GenericFilter::new()
+      .r#where("webhook", GenericClause::Eq("test-webhook".to_owned()));
+    let dead_letters =
+      EventDeadLetterDb::transform_read_by(&filter, &state.inner.pool)
+        .await
+        .unwrap();
+    assert!(dead_letters.iter().any(|d| d.event.key == event.key));
+    SecretDb::del_obj_by_pk(&secret.name, &(), &state)
+      .await
+      .unwrap();
+  }
+
+  #[ntex::test]
+  async fn missing_secret() {
+    let system = gen_default_test_system().await;
+    let state = system.state;
+    let event: Event = EventDb::try_from(EventPartial {
+      reporting_controller: vars::CONTROLLER_NAME.to_owned(),
+      reporting_node: state.config().hostname.clone(),
+      action: "webhook_test".to_owned(),
+      reason: "test".to_owned(),
+      kind: EventKind::Normal,
+      actor: None,
+      related: None,
+      metadata: None,
+      note: None,
+    })
+    .unwrap()
+    .try_into()
+    .unwrap();
+    let webhook = ResourceEventWebhook {
+      url: "http://127.0.0.1:1/events".to_owned(),
+      conditions: None,
+      secret: Some("test-missing-webhook-secret".to_owned()),
+      max_attempts: Some(1),
+    };
+    let resource = ResourcePartial {
+      name: "test-missing-secret-webhook".to_owned(),
+      kind: vars::EVENT_WEBHOOK_KIND.to_owned(),
+      data: serde_json::to_value(&webhook).unwrap(),
+      metadata: None,
+    };
+    let err = ResourceDb::create_obj(&resource, &state).await.unwrap_err();
+    assert_eq!(err.status, ntex::http::StatusCode::BAD_REQUEST);
+    deliver(
+      "test-missing-secret-webhook".to_owned(),
+      webhook,
+      event.clone(),
+      state.clone(),
+    )
+    .await
+    .unwrap();
+    let status = list_status(&state).unwrap();
+    let status = status
+      .iter()
+      .find(|s| s.name == "test-missing-secret-webhook")
+      .unwrap();
+    assert_eq!(status.failed, 1);
+    assert_eq!(status.dead_lettered, 1);
+    let filter = GenericFilter::new().r#where(
+      "webhook",
+      GenericClause::Eq("test-missing-secret-webhook".to_owned()),
+    );
+    let dead_letters =
+      EventDeadLetterDb::transform_read_by(&filter, &state.inner.pool)
+        .await
+        .unwrap();
+    assert!(dead_letters
+      .iter()
+      .any(|d| d.event.key == event.key && d.attempts == 0));
+  }
+}
@@ -23,7 +23,7 @@ pub async fn create_exec_command(
   let name = format!("{name}.c");
   let result = state
     .inner
-    .docker_api
+    .runtime
     .create_exec(&name, args.to_owned())
     .await?;
   Ok(result)
@@ -37,7 +37,7 @@ pub async fn start_exec_command(
 ) -> HttpResult<web::HttpResponse> {
   let res = state
     .inner
-    .docker_api
+    .runtime
     .start_exec(exec_id, Some(args.to_owned()))
     .await?;
   match res {
@@ -58,6 +58,6 @@ pub async fn inspect_exec_command(
   exec_id: &str,
   state: &SystemState,
 ) -> HttpResult<ExecInspectResponse> {
-  let result = state.inner.docker_api.inspect_exec(exec_id).await?;
+  let result = state.inner.runtime.inspect_exec(exec_id).await?;
   Ok(result)
 }
@@ -0,0 +1,163 @@
+use std::{collections::HashSet, future::Future, sync::atomic::Ordering};
+
+use diesel::RunQueryDsl;
+use futures::future::join_all;
+use ntex::{time, web};
+
+use nanocl_error::io::{IoError, IoResult};
+use nanocl_stubs::{
+  generic::GenericFilter,
+  system::{HealthCheck, HealthReport, HealthStatus},
+};
+
+use crate::{
+  models::{ResourceKindDb, SystemState},
+  repositories::generic::*,
+  utils::ctrl_client::CtrlClient,
+};
+
+/// Maximum duration of a check in milliseconds
+const CHECK_TIMEOUT: u64 = 2_000;
+
+/// Number of events waiting in the event loop above which it's considered stuck
+const EVENT_BACKLOG_LIMIT: usize = 1_000;
+
+/// Run a check with a timeout and measure his duration
+async fn check<F>(name: String, critical: bool, fut: F) -> HealthCheck
+where
+  F: Future<Output = IoResult<()>>,
+{
+  let start = std::time::Instant::now();
+  let res = time::timeout(time::Millis(CHECK_TIMEOUT as u32), fut).await;
+  let duration = start.elapsed().as_millis() as u64;
+  let message = match res {
+    Ok(Ok(_)) => None,
+    Ok(Err(err)) => Some(err.to_string()),
+    Err(_) => Some(format!("Timeout after {CHECK_TIMEOUT}ms")),
+  };
+  HealthCheck {
+    name,
+    status: if message.is_none() {
+      HealthStatus::Up
+    } else {
+      HealthStatus::Down
+    },
+    critical,
+    duration,
+    message,
+  }
+}
+
+/// Check that a connection to the store can run a query
+async fn check_store(state: &SystemState) -> IoResult<()> {
+  let pool = state.inner.pool.clone();
+  web::block(move || {
+    let mut conn = pool
+      .get_timeout(std::time::Duration::from_millis(CHECK_TIMEOUT))
+      .map_err(|err| IoError::interrupted("Store", &err.to_string()))?;
+    diesel::sql_query("SELECT 1")
+      .execute(&mut conn)
+      .map_err(|err| IoError::interrupted("Store", &err.to_string()))?;
+    Ok::<_, IoError>(())
+  })
+  .await?;
+  Ok(())
+}
+
+/// Check that the docker api answer
+async fn check_docker(state: &SystemState) -> IoResult<()> {
+  state
+    .inner
+    .runtime
+    .ping()
+    .await
+    .map_err(|err| IoError::interrupted("Docker", &err.to_string()))?;
+  Ok(())
+}
+
+/// Check that the event loop isn't stuck
+async fn check_event_loop(state: &SystemState) -> IoResult<()> {
+  let backlog = state.inner.event_backlog.load(Ordering::SeqCst);
+  if backlog > EVENT_BACKLOG_LIMIT {
+    return Err(IoError::interrupted(
+      "Event loop",
+      &format!("{backlog} events waiting"),
+    ));
+  }
+  Ok(())
+}
+
+/// Check that the daemon is subscribed to the metrics daemon
+async fn check_metrics(state: &SystemState) -> IoResult<()> {
+  if !state.inner.metrics_subscribed.load(Ordering::SeqCst) {
+    return Err(IoError::interrupted("Metrics", "Not subscribed to metrsd"));
+  }
+  Ok(())
+}
+
+/// Check the controllers of the resource kinds like ncproxy and ncdns
+async fn check_controllers(state: &SystemState) -> Vec<HealthCheck> {
+  let kinds = match ResourceKindDb::transform_read_by(
+    &GenericFilter::new(),
+    &state.inner.pool,
+  )
+  .await
+  {
+    Ok(kinds) => kinds,
+    Err(err) => {
+      log::warn!("health::check_controllers: {err}");
+      return Vec::new();
+    }
+  };
+  let mut urls = HashSet::new();
+  let clients = kinds
+    .into_iter()
+    .filter_map(|kind| {
+      let url = kind.data.url?;
+      if !urls.insert(url.clone()) {
+        return None;
+      }
+      Some(CtrlClient::new(&kind.name, &url))
+    })
+    .collect::<Vec<_>>();
+  join_all(clients.iter().map(|client| {
+    check(format!("controller/{}", client.name), false, async move {
+      client
+        .ping()
+        .await
+        .map_err(|err| IoError::interrupted("Controller", &err.to_string()))
+    })
+  }))
+  .await
+}
+
+/// Check the dependencies of the daemon
+pub async fn report(state: &SystemState) -> HealthReport {
+  let (store, docker, event_loop, metrics, controllers) = futures::join!(
+    check("store".to_owned(), true, check_store(state)),
+    check("docker".to_owned(), true, check_docker(state)),
+    check("event_loop".to_owned(), true, check_event_loop(state)),
+    check("metrics".to_owned(), false, check_metrics(state)),
+    check_controllers(state),
+  );
+  let mut checks = vec![store, docker, event_loop, metrics];
+  checks.extend(controllers);
+  let status = if checks
+    .iter()
+    .any(|check| check.critical && check.status == HealthStatus::Down)
+  {
+    HealthStatus::Down
+  } else if checks
+    .iter()
+    .any(|check| check.status == HealthStatus::Down)
+  {
+    HealthStatus::Degraded
+  } else {
+    HealthStatus::Up
+  };
+  HealthReport {
+    status,
+    node_name: state.config().hostname.clone(),
+    checks,
+  }
+}
@@ -0,0 +1,488 @@
+use std::collections::BTreeMap;
+
+use nanocl_error::io::{IoError, IoResult};
+use nanocl_stubs::metric::{
+  HttpAnalytics, HttpAnalyticsPoint, HttpAnalyticsQuery, MetricPoint,
+  MetricQuery, MetricSeries, MetricSource,
+};
+
+use crate::models::{
+  HttpHistogram, HttpRollupDb, MetricDb, MetricField, MetricRange, SystemState,
+};
+
+/// Default duration of a query range in seconds
+const DEFAULT_RANGE: i64 = 3_600;
+
+/// Default duration of a bucket in seconds
+const DEFAULT_STEP: i64 = 60;
+
+/// Maximum number of buckets of a series
+const MAX_BUCKETS: i64 = 11_000;
+
+/// Maximum number of buckets saved by a rollup at once
+/// to catch up slowly after a long downtime
+const MAX_ROLLUP_BUCKETS: i64 = 288;
+
+/// Rollups with the number of days their buckets are kept
+pub const ROLLUPS: [(MetricSource, i64); 2] = [
+  (MetricSource::FiveMinutes, 90),
+  (MetricSource::OneHour, 400),
+];
+
+/// Fields rolled up in the background by kind of metric,
+/// other fields can only be queried from the raw metrics
+pub const ROLLUP_FIELDS: [(&str, &str); 4] = [
+  ("nanocl.io/metrs", "Cpus.*.Usage"),
+  ("nanocl.io/metrs", "Memory.Used"),
+  ("ncproxy.io/http", "request_time"),
+  ("ncproxy.io/http", "bytes_sent"),
+];
+
+/// Whether a field of the given kind of metrics, or of any kind, is rolled up
+fn is_rollup_field(field: &str, kind: Option<&str>) -> bool {
+  ROLLUP_FIELDS.iter().any(|(rollup_kind, rollup_field)| {
+    *rollup_field == field && kind.map_or(true, |kind| kind == *rollup_kind)
+  })
+}
+
+/// Convert a unix timestamp into a date
+fn from_timestamp(secs: i64) -> IoResult<chrono::NaiveDateTime> {
+  chrono::DateTime::from_timestamp(secs, 0)
+    .map(|date| date.naive_utc())
+    .ok_or_else(|| {
+      IoError::invalid_input(
+        "Metric query",
+        format!("invalid timestamp {secs}").as_str(),
+      )
+    })
+}
+
+/// Compute a time series of a field of the metrics
+/// aggregated by bucket and optionally split by node or kind
+pub async fn query(
+  qs: &MetricQuery,
+  state: &SystemState,
+) -> IoResult<Vec<MetricSeries>> {
+  let field = qs.field.parse::<MetricField>()?;
+  let now = chrono::Utc::now().timestamp();
+  let until = qs.until.unwrap_or(now);
+  let since = qs.since.unwrap_or(until - DEFAULT_RANGE);
+  let step = qs.step.unwrap_or(DEFAULT_STEP);
+  let source = qs.source.unwrap_or_default();
+  if step < 1 {
+    return Err(IoError::invalid_input("Metric query", "step must be >= 1"));
+  }
+  if until <= since {
+    return Err(IoError::invalid_input(
+      "Metric query",
+      "until must be after since",
+    ));
+  }
+  if (until - since) / step > MAX_BUCKETS {
+    return Err(IoError::invalid_input(
+      "Metric query",
+      format!("more than {MAX_BUCKETS} buckets, increase the step").as_str(),
+    ));
+  }
+  let range = MetricRange {
+    since: from_timestamp(since)?,
+    until: from_timestamp(until)?,
+    step,
+    kind: qs.kind.clone(),
+    node_name: qs.node_name.clone(),
+  };
+  let aggregate = qs.aggregate.unwrap_or_default();
+  let pool = &state.inner.pool;
+  let points = match source.resolution() {
+    None => {
+      MetricDb::query_raw(&field, &range, aggregate, qs.group_by, pool).await?
+    }
+    Some(resolution) => {
+      if !is_rollup_field(&qs.field, qs.kind.as_deref()) {
+        return Err(IoError::invalid_input(
+          "Metric query",
+          format!("{} isn't rolled up, query the raw metrics", qs.field)
+            .as_str(),
+        ));
+      }
+      if step % resolution != 0 {
+        return Err(IoError::invalid_input(
+          "Metric query",
+          format!("step must be a multiple of {resolution} for this source")
+            .as_str(),
+        ));
+      }
+      MetricDb::query_rollup(
+        resolution,
+        &field,
+        &range,
+        aggregate,
+        qs.group_by,
+        pool,
+      )
+      .await?
+    }
+  };
+  let mut series: Vec<MetricSeries> = Vec::new();
+  for point in points {
+    let Some(value) = point.value else {
+      continue;
+    };
+    let point_series = MetricPoint {
+      time: point.bucket,
+      value,
+      count: point.count,
+    };
+    match series.last_mut() {
+      Some(last) if last.name == point.series => last.points.push(point_series),
+      _ => series.push(MetricSeries {
+        name: point.series,
+        points: vec![point_series],
+      }),
+    }
+  }
+  Ok(series)
+}
+
+/// Roll up the complete buckets of the metrics of the node
+/// since the last bucket saved for each rollup
+pub async fn rollup(state: &SystemState) -> IoResult<usize> {
+  let node_name = state.config().hostname.clone();
+  let pool = &state.inner.pool;
+  let now = chrono::Utc::now().timestamp();
+  let mut count = 0;
+  for (source, ttl_days) in ROLLUPS {
+    let Some(resolution) = source.resolution() else {
+      continue;
+    };
+    let Some(watermark) =
+      MetricDb::rollup_watermark(resolution, &node_name, pool).await?
+    else {
+      continue;
+    };
+    // The last bucket saved is rolled up again for the metrics saved late
+    let since = watermark.and_utc().timestamp();
+    let since = since - since % resolution;
+    let until =
+      (now - now % resolution).min(since + MAX_ROLLUP_BUCKETS * resolution);
+    if until <= since {
+      continue;
+    }
+    for (kind, field) in ROLLUP_FIELDS {
+      let field = field.parse::<MetricField>()?;
+      let range = MetricRange {
+        since: from_timestamp(since)?,
+        until: from_timestamp(until)?,
+        step: resolution,
+        kind: Some(kind.to_owned()),
+        node_name: Some(node_name.clone()),
+      };
+      count +=
+        MetricDb::rollup(&field, &range, ttl_days * 86_400, pool).await?;
+    }
+    let range = MetricRange {
+      since: from_timestamp(since)?,
+      until: from_timestamp(until)?,
+      step: resolution,
+      kind: None,
+      node_name: Some(node_name.clone()),
+    };
+    count += MetricDb::rollup_http(&range, ttl_days * 86_400, pool).await?;
+  }
+  Ok(count)
+}
+
+/// Traffic of the http requests of a window from the buckets of a rollup
+#[derive(Default)]
+struct HttpTraffic {
+  requests: i64,
+  client_errors: i64,
+  server_errors: i64,
+  bytes_sent: i64,
+  latency: HttpHistogram,
+}
+
+impl HttpTraffic {
+  fn add(&mut self, row: &HttpRollupDb) {
+    self.requests += row.requests;
+    self.client_errors += row.client_errors;
+    self.server_errors += row.server_errors;
+    self.bytes_sent += row.bytes_sent;
+    let mut latency = HttpHistogram {
+      count: row.requests as u64,
+      sum: row.latency_sum,
+      ..Default::default()
+    };
+    for (bucket, count) in latency.buckets.iter_mut().zip(&row.latency_buckets)
+    {
+      *bucket = *count as u64;
+    }
+    self.latency.merge(&latency);
+  }
+
+  fn into_point(
+    self,
+    time: chrono::NaiveDateTime,
+    secs: i64,
+  ) -> HttpAnalyticsPoint {
+    let requests = self.requests.max(1) as f64;
+    HttpAnalyticsPoint {
+      time,
+      requests: self.requests,
+      rps: self.requests as f64 / secs as f64,
+      client_errors: self.client_errors,
+      server_errors: self.server_errors,
+      error_rate: self.server_errors as f64 / requests,
+      bytes_sent: self.bytes_sent,
+      latency_avg: self.latency.sum / requests,
+      latency_p50: self.latency.quantile(0.5),
+      latency_p95: self.latency.quantile(0.95),
+      latency_p99: self.latency.quantile(0.99),
+    }
+  }
+}
+
+/// Compute the requests per second, the error rates and the latency percentiles
+/// of the http requests of the proxy by domain, location or upstream
+/// from the rollups, the buckets not rolled up yet are missing
+pub async fn http_analytics(
+  qs: &HttpAnalyticsQuery,
+  state: &SystemState,
+) -> IoResult<Vec<HttpAnalytics>> {
+  let source = qs.source.unwrap_or(MetricSource::FiveMinutes);
+  let Some(resolution) = source.resolution() else {
+    return Err(IoError::invalid_input(
+      "Http analytics",
+      "source must be a rollup",
+    ));
+  };
+  let now = chrono::Utc::now().timestamp();
+  let until = qs.until.unwrap_or(now);
+  let since = qs.since.unwrap_or(until - DEFAULT_RANGE);
+  let step = qs.step.unwrap_or(resolution);
+  if step < 1 || step % resolution != 0 {
+    return Err(IoError::invalid_input(
+      "Http analytics",
+      format!("step must be a multiple of {resolution} for this source")
+        .as_str(),
+    ));
+  }
+  if until <= since {
+    return Err(IoError::invalid_input(
+      "Http analytics",
+      "until must be after since",
+    ));
+  }
+  if (until - since) / step > MAX_BUCKETS {
+    return Err(IoError::invalid_input(
+      "Http analytics",
+      format!("more than {MAX_BUCKETS} buckets, increase the step").as_str(),
+    ));
+  }
+  let range = MetricRange {
+    since: from_timestamp(since)?,
+    until: from_timestamp(until)?,
+    step,
+    kind: None,
+    node_name: qs.node_name.clone(),
+  };
+  let rows = MetricDb::query_http_rollup(
+    resolution,
+    &range,
+    qs.domain.clone(),
+    qs.group_by.unwrap_or_default(),
+    &state.inner.pool,
+  )
+  .await?;
+  let mut groups: BTreeMap<
+    String,
+    (HttpTraffic, BTreeMap<chrono::NaiveDateTime, HttpTraffic>),
+  > = BTreeMap::new();
+  for row in rows {
+    let (total, points) = groups.entry(row.series.clone()).or_default();
+    total.add(&row);
+    points.entry(row.bucket).or_default().add(&row);
+  }
+  let analytics = groups
+    .into_iter()
+    .map(|(name, (total, points))| HttpAnalytics {
+      name,
+      total: total.into_point(range.since, until - since),
+      points: points
+        .into_iter()
+        .map(|(time, traffic)| traffic.into_point(time, step))
+        .collect(),
+    })
+    .collect();
+  Ok(analytics)
+}
+
+#[cfg(test)]
+mod tests {
+  use nanocl_stubs::metric::{
+    HttpAnalyticsGroupBy, HttpAnalyticsQuery, MetricPartial, MetricQuery,
+    MetricSource,
+  };
+
+  use crate::{
+    models::{
+      HttpHistogram, MetricDb, MetricField, MetricNodePartial, MetricRange,
+    },
+    repositories::generic::*,
+    utils::tests::*,
+  };
+
+  #[test]
+  fn parse_field() {
+    let field = "Cpus.*.Usage".parse::<MetricField>().unwrap();
+    assert_eq!(field.prefix, vec!["Cpus"]);
+    assert_eq!(field.suffix, vec!["Usage"]);
+    assert!(field.is_array);
+    let field = "request_time".parse::<MetricField>().unwrap();
+    assert!(!field.is_array);
+    assert!(field.suffix.is_empty());
+    assert!("*.Usage".parse::<MetricField>().is_err());
+    assert!("Cpus.*.*".parse::<MetricField>().is_err());
+    assert!("Cpus..Usage".parse::<MetricField>().is_err());
+    assert!("Cpus.'Usage".parse::<MetricField>().is_err());
+  }
+
+  #[test]
+  fn rollup_field() {
+    assert!(super::is_rollup_field("request_time", None));
+    assert!(super::is_rollup_field(
+      "Memory.Used",
+      Some("nanocl.io/metrs")
+    ));
+    assert!(!super::is_rollup_field(
+      "Memory.Used",
+      Some("ncproxy.io/http")
+    ));
+    assert!(!super::is_rollup_field("Memory.Free", None));
+  }
+
+  #[ntex::test]
+  async fn query() {
+    let system = gen_default_test_system().await;
+    let state = &system.state;
+    let node = &state.config().hostname;
+    for value in ["1.5", "2.5", "not a number"] {
+      let metric = MetricPartial {
+        kind: "test.io/query".to_owned(),
+        data: serde_json::json!({ "Values": [{ "Value": value }] }),
+        note: None,
+      };
+      let metric = MetricNodePartial::try_new_node(
+        node,
+        &metric,
+        &state.config().retention,
+      )
+      .unwrap();
+      MetricDb::create_from(&metric, &state.inner.pool)
+        .await
+        .unwrap();
+    }
+    let qs = MetricQuery {
+      field: "Values.*.Value".to_owned(),
+      kind: Some("test.io/query".to_owned()),
+      since: Some(chrono::Utc::now().timestamp() - 60),
+      until: Some(chrono::Utc::now().timestamp() + 60),
+      step: Some(120),
+      ..Default::default()
+    };
+    let series = super::query(&qs, state).await.unwrap();
+    assert_eq!(series.len(), 1);
+    let count = series[0].points.iter().map(|p| p.count).sum::<i64>();
+    assert!(count >= 2);
+    let qs = MetricQuery {
+      source: Some(MetricSource::FiveMinutes),
+      ..qs
+    };
+    assert!(super::query(&qs, state).await.is_err());
+    super::rollup(state).await.unwrap();
+  }
+
+  #[test]
+  fn quantile() {
+    let mut histogram = HttpHistogram::default();
+    assert_eq!(histogram.quantile(0.95), 0.0);
+    // 90 fast requests and 10 slow ones
+    histogram.buckets[0] = 90;
+    histogram.buckets[7] = 10;
+    histogram.count = 100;
+    assert!(histogram.quantile(0.5) <= 0.005);
+    let p95 = histogram.quantile(0.95);
+    assert!(p95 > 0.5 && p95 <= 1.0);
+    // Requests above the last bound
+    let mut other = HttpHistogram {
+      count: 100,
+      ..Default::default()
+    };
+    other.merge(&histogram);
+    assert_eq!(other.count, 200);
+    assert_eq!(other.quantile(0.99), 10.0);
+  }
+
+  #[ntex::test]
+  async fn http_analytics() {
+    let system = gen_default_test_system().await;
+    let state = &system.state;
+    let node = &state.config().hostname;
+    let domain = "analytics.nanocl.internal";
+    for (status, request_time) in [("200", "0.004"), ("502", "0.300")] {
+      let metric = MetricPartial {
+        kind: "ncproxy.io/http".to_owned(),
+        data: serde_json::json!({
+          "host": domain,
+          "location": "/api",
+          "upstream_addr": "10.0.0.1:80",
+          "status": status,
+          "bytes_sent": "100",
+          "request_time": request_time,
+        }),
+        note: None,
+      };
+      let metric = MetricNodePartial::try_new_node(
+        node,
+        &metric,
+        &state.config().retention,
+      )
+      .unwrap();
+      MetricDb::create_from(&metric, &state.inner.pool)
+        .await
+        .unwrap();
+    }
+    // The current bucket isn't complete so we roll it up manually
+    let now = chrono::Utc::now().timestamp();
+    let since = now - now % 300;
+    let range = MetricRange {
+      since: super::from_timestamp(since).unwrap(),
+      until: super::from_timestamp(since + 300).unwrap(),
+      step: 300,
+      kind: None,
+      node_name: Some(node.clone()),
+    };
+    MetricDb::rollup_http(&range, 3_600, &state.inner.pool)
+      .await
+      .unwrap();
+    let qs = HttpAnalyticsQuery {
+      since: Some(since),
+      until: Some(since + 300),
+      group_by: Some(HttpAnalyticsGroupBy::Location),
+      domain: Some(domain.to_owned()),
+      ..Default::default()
+    };
+    let analytics = super::http_analytics(&qs, state).await.unwrap();
+    let api = analytics.iter().find(|item| item.name == "/api").unwrap();
+    assert!(api.total.requests >= 2);
+    assert!(api.total.server_errors >= 1);
+    assert!(api.total.error_rate > 0.0);
+    assert!(api.total.latency_p99 > 0.25);
+    assert_eq!(api.points.len(), 1);
+    let qs = HttpAnalyticsQuery {
+      step: Some(60),
+      ..qs
+    };
+    assert!(super::http_analytics(&qs, state).await.is_err());
+  }
+}
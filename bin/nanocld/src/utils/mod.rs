@@ -2,23 +2,44 @@ pub mod key;
 pub mod stream;
 pub mod ws;
 
+pub mod alert;
 pub mod container;
 pub mod cron;
 pub mod ctrl_client;
+pub mod event;
+pub mod event_webhook;
 pub mod exec;
+pub mod health;
+pub mod metric;
+pub mod namespace;
+pub mod process_stats;
+pub mod prometheus;
 pub mod query_string;
+pub mod reconcile;
+pub mod registry_policy;
+pub mod retention;
 pub mod server;
+pub mod shutdown;
 pub mod store;
 pub mod system;
+pub mod task;
 pub mod vm_image;
 
 #[cfg(test)]
 pub mod tests {
   use ntex::web::{self, *};
-  use std::env;
+  use std::{env, sync::Arc};
 
-  use crate::{models::SystemState, services, vars::VERSION};
-  use nanocl_stubs::config::DaemonConfig;
+  use crate::{
+    models::SystemState,
+    runtime::{ContainerRuntime, FakeRuntime},
+    services,
+    vars::VERSION,
+  };
+  use nanocl_stubs::{
+    cargo_spec::CargoSpecPartial, config::DaemonConfig,
+    generic::GenericNspQuery, namespace::NamespacePartial,
+  };
 
   pub use nanocl_utils::ntex::test_client::*;
 
@@ -42,6 +63,16 @@ pub mod tests {
   }
 
   pub async fn gen_test_system(routes: Config, version: &str) -> TestSystem {
+    gen_test_system_with_runtime(routes, version, None).await
+  }
+
+  /// Build a test system using the given container runtime
+  /// or the docker one when none is given
+  pub async fn gen_test_system_with_runtime(
+    routes: Config,
+    version: &str,
+    runtime: Option<Arc<dyn ContainerRuntime>>,
+  ) -> TestSystem {
     before();
     // Build a test daemon config
     let home = env::var("HOME").expect("Failed to get home dir");
@@ -57,12 +88,19 @@ pub mod tests {
       ),
       ..Default::default()
     };
-    let state = SystemState::new(&config).await.unwrap();
+    let state = match runtime {
+      None => SystemState::new(&config).await.unwrap(),
+      Some(runtime) => {
+        SystemState::with_runtime(&config, runtime).await.unwrap()
+      }
+    };
     let state_ptr = state.clone();
     // Create test server
     let srv = test::server(move || {
       App::new()
         .state(state_ptr.clone())
+        .wrap(super::shutdown::ShutdownGuard::new(&state_ptr))
+        .wrap(super::prometheus::HttpMetricsRecorder::new(&state_ptr))
         .configure(routes)
         .default_service(web::route().to(services::unhandled))
     });
@@ -73,4 +111,64 @@ pub mod tests {
   pub async fn gen_default_test_system() -> TestSystem {
     gen_test_system(services::ntex_config, VERSION).await
   }
+
+  /// Build a test system running the containers, images and networks in memory
+  /// so it doesn't need a docker daemon, the store is still required
+  pub async fn gen_fake_test_system() -> (TestSystem, FakeRuntime) {
+    let runtime = FakeRuntime::new();
+    let system = gen_test_system_with_runtime(
+      services::ntex_config,
+      VERSION,
+      Some(Arc::new(runtime.clone())),
+    )
+    .await;
+    (system, runtime)
+  }
+
+  /// Namespace without network, quota or limit range used by the tests
+  pub fn test_namespace(name: &str) -> NamespacePartial {
+    NamespacePartial {
+      name: name.to_owned(),
+      metadata: None,
+      network: None,
+      quota: None,
+      limit_range: None,
+    }
+  }
+
+  /// Cargo running the get started image used by the tests
+  pub fn test_cargo(name: &str) -> CargoSpecPartial {
+    CargoSpecPartial {
+      name: name.to_owned(),
+      container: bollard_next::container::Config {
+        image: Some("ghcr.io/next-hat/nanocl-get-started:latest".to_owned()),
+        ..Default::default()
+      },
+      ..Default::default()
+    }
+  }
+
+  /// Create the given namespace then the given cargo in it
+  /// and return the response of the cargo creation
+  pub async fn create_test_namespace_cargo(
+    client: &TestClient,
+    namespace: &NamespacePartial,
+    cargo: &CargoSpecPartial,
+  ) -> ntex::http::client::ClientResponse {
+    let res = client
+      .send_post("/namespaces", Some(namespace), None::<String>)
+      .await;
+    test_status_code!(
+      res.status(),
+      ntex::http::StatusCode::CREATED,
+      "create test namespace"
+    );
+    client
+      .send_post(
+        "/cargoes",
+        Some(cargo),
+        Some(&GenericNspQuery::new(Some(&namespace.name))),
+      )
+      .await
+  }
 }
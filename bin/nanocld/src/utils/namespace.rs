@@ -0,0 +1,1427 @@
+use std::{
+  collections::{HashMap, HashSet},
+  time::Duration,
+};
+
+use bollard_next::{
+  container::Config,
+  network::{
+    ConnectNetworkOptions, CreateNetworkOptions, DisconnectNetworkOptions,
+    InspectNetworkOptions,
+  },
+  service::{EndpointSettings, Ipam, IpamConfig, MountTypeEnum, Network},
+  volume::{ListVolumesOptions, RemoveVolumeOptions},
+};
+
+use futures::StreamExt;
+use ntex::{rt, time};
+
+use nanocl_error::{
+  http::{HttpError, HttpResult},
+  io::{FromIo, IoError, IoResult},
+};
+use nanocl_stubs::{
+  cargo::{Cargo, CargoDeleteQuery},
+  cargo_spec::{CargoSpecPartial, ReplicationMode},
+  dns::ResourceDnsRule,
+  generic::{GenericClause, GenericFilter},
+  job::JobPartial,
+  namespace::{
+    NamespaceCloneOpts, NamespaceCloneSkippedRule, NamespaceCloned,
+    NamespaceDeleteQuery, NamespaceLimitRange, NamespaceNetwork,
+    NamespacePartial, NamespaceQuota, ResourceNetworkPolicy,
+  },
+  process::{Process, ProcessKind},
+  proxy::{ProxyRule, ResourceProxyRule},
+  resource::{Resource, ResourcePartial},
+  system::{
+    Event, EventActor, EventActorKind, EventCondition, NativeEventAction,
+    ObjPsStatusKind,
+  },
+  vm::Vm,
+  vm_spec::{VmHostConfig, VmSpecPartial},
+};
+
+use crate::{
+  models::{
+    CargoDb, CargoObjCreateIn, JobDb, NamespaceDb, NodeDb, ProcessDb,
+    RawEventReceiver, ResourceDb, SystemState, TaskManager, VmDb, VmImageDb,
+    VmObjCreateIn,
+  },
+  objects::generic::*,
+  repositories::generic::*,
+  utils::{self, container::cargo::Replicas},
+  vars,
+};
+
+/// Generate the name of the network of an isolated namespace
+pub fn network_name(namespace: &str) -> String {
+  format!("nanocl.{namespace}")
+}
+
+/// Return true if the given network is the network of an isolated namespace
+fn is_namespace_network(network: &str) -> bool {
+  network.starts_with("nanocl.")
+}
+
+/// Get the network where the processes of a namespace must be attached
+/// It's the shared `nanoclbr0` bridge unless the namespace is isolated
+pub async fn get_network(
+  namespace: &str,
+  state: &SystemState,
+) -> IoResult<String> {
+  let namespace = NamespaceDb::read_by_pk(namespace, &state.inner.pool).await?;
+  if namespace.is_isolated() {
+    return Ok(network_name(&namespace.name));
+  }
+  Ok(vars::DEFAULT_NETWORK.to_owned())
+}
+
+/// Create the network of an isolated namespace
+pub async fn create_network(
+  namespace: &NamespaceDb,
+  state: &SystemState,
+) -> IoResult<()> {
+  let Some(network) = namespace.network() else {
+    return Ok(());
+  };
+  if !network.isolated {
+    return Ok(());
+  }
+  let name = network_name(&namespace.name);
+  if inspect_network(&namespace.name, state).await.is_some() {
+    log::debug!("namespace::create_network: {name} already exists");
+    return Ok(());
+  }
+  let ipam = match (&network.subnet, &network.gateway) {
+    (None, None) => Ipam::default(),
+    (subnet, gateway) => Ipam {
+      config: Some(vec![IpamConfig {
+        subnet: subnet.clone(),
+        gateway: gateway.clone(),
+        ..Default::default()
+      }]),
+      ..Default::default()
+    },
+  };
+  let labels = HashMap::from([
+    ("io.nanocl".to_owned(), "enabled".to_owned()),
+    ("io.nanocl.n".to_owned(), namespace.name.clone()),
+  ]);
+  state
+    .inner
+    .runtime
+    .create_network(CreateNetworkOptions {
+      name: name.clone(),
+      check_duplicate: true,
+      driver: "bridge".to_owned(),
+      internal: false,
+      attachable: true,
+      ingress: false,
+      enable_ipv6: false,
+      ipam,
+      labels,
+      ..Default::default()
+    })
+    .await
+    .map_err(|err| err.map_err_context(|| format!("Network {name}")))?;
+  log::debug!("namespace::create_network: {name} created");
+  Ok(())
+}
+
+/// Remove the network of an isolated namespace
+pub async fn remove_network(namespace: &str, state: &SystemState) {
+  let name = network_name(namespace);
+  if let Err(err) = state.inner.runtime.remove_network(&name).await {
+    log::warn!("namespace::remove_network: {name} {err}");
+  }
+}
+
+/// Inspect the network of an isolated namespace
+pub async fn inspect_network(
+  namespace: &str,
+  state: &SystemState,
+) -> Option<Network> {
+  state
+    .inner
+    .runtime
+    .inspect_network(
+      &network_name(namespace),
+      None::<InspectNetworkOptions<String>>,
+    )
+    .await
+    .ok()
+}
+
+/// List the networks of isolated namespaces
+/// that the processes of the given namespace are allowed to reach
+pub async fn allowed_networks(
+  namespace: &str,
+  state: &SystemState,
+) -> IoResult<Vec<String>> {
+  let filter = GenericFilter::new()
+    .r#where(
+      "kind",
+      GenericClause::Eq(vars::NETWORK_POLICY_KIND.to_owned()),
+    )
+    .r#where(
+      "data",
+      GenericClause::Contains(serde_json::json!({
+        "AllowFrom": [namespace]
+      })),
+    );
+  let policies = ResourceDb::transform_read_by(&filter, &state.inner.pool)
+    .await?
+    .into_iter()
+    .filter_map(|resource| {
+      serde_json::from_value::<ResourceNetworkPolicy>(resource.spec.data).ok()
+    })
+    .collect::<Vec<_>>();
+  let mut networks = Vec::new();
+  for policy in policies {
+    if policy.namespace == namespace {
+      continue;
+    }
+    match NamespaceDb::read_by_pk(&policy.namespace, &state.inner.pool).await {
+      Ok(target) if target.is_isolated() => {
+        networks.push(network_name(&target.name));
+      }
+      Ok(_) => {
+        log::warn!(
+          "namespace::allowed_networks: {} is not isolated",
+          policy.namespace
+        );
+      }
+      Err(err) => {
+        log::warn!("namespace::allowed_networks: {err}");
+      }
+    }
+  }
+  Ok(networks)
+}
+
+/// Connect and disconnect a process to the networks of other namespaces
+/// to match the given allowed networks
+async fn sync_process_networks(
+  process: &Process,
+  allowed: &[String],
+  state: &SystemState,
+) {
+  let own_network = process
+    .data
+    .host_config
+    .clone()
+    .unwrap_or_default()
+    .network_mode
+    .unwrap_or_default();
+  let current = process
+    .data
+    .network_settings
+    .clone()
+    .unwrap_or_default()
+    .networks
+    .unwrap_or_default();
+  for network in allowed {
+    if current.contains_key(network) || *network == own_network {
+      continue;
+    }
+    log::debug!(
+      "namespace::sync_process_networks: connect {} to {network}",
+      process.name
+    );
+    if let Err(err) = state
+      .inner
+      .runtime
+      .connect_network(
+        network,
+        ConnectNetworkOptions {
+          container: process.key.clone(),
+          endpoint_config: EndpointSettings::default(),
+        },
+      )
+      .await
+    {
+      log::warn!("namespace::sync_process_networks: {network} {err}");
+    }
+  }
+  for network in current.keys() {
+    if *network == own_network
+      || !is_namespace_network(network)
+      || allowed.contains(network)
+    {
+      continue;
+    }
+    log::debug!(
+      "namespace::sync_process_networks: disconnect {} from {network}",
+      process.name
+    );
+    if let Err(err) = state
+      .inner
+      .runtime
+      .disconnect_network(
+        network,
+        DisconnectNetworkOptions {
+          container: process.key.clone(),
+          force: true,
+        },
+      )
+      .await
+    {
+      log::warn!("namespace::sync_process_networks: {network} {err}");
+    }
+  }
+}
+
+/// Apply the network policies to the given processes of a namespace
+pub async fn connect_processes(
+  namespace: &str,
+  processes: &[Process],
+  state: &SystemState,
+) -> IoResult<()> {
+  let allowed = allowed_networks(namespace, state).await?;
+  if allowed.is_empty() {
+    return Ok(());
+  }
+  for process in processes {
+    sync_process_networks(process, &allowed, state).await;
+  }
+  Ok(())
+}
+
+/// Apply the network policies to every process of every namespace
+/// Called when a network policy is created, updated or deleted
+pub async fn sync_network_policies(state: &SystemState) -> IoResult<()> {
+  log::info!("namespace::sync_network_policies: start");
+  let namespaces =
+    NamespaceDb::read_by(&GenericFilter::new(), &state.inner.pool).await?;
+  for namespace in namespaces {
+    let allowed = allowed_networks(&namespace.name, state).await?;
+    let processes =
+      ProcessDb::list_by_namespace(&namespace.name, &state.inner.pool).await?;
+    for process in processes {
+      sync_process_networks(&process, &allowed, state).await;
+    }
+  }
+  log::info!("namespace::sync_network_policies: done");
+  Ok(())
+}
+
+/// Delay to wait for the quota of a namespace locked by another node
+const QUOTA_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+/// Time to live of the lease of a quota lock if the node stops before releasing it
+const QUOTA_LEASE_TTL: u64 = 30;
+
+/// Lock on the quota of a namespace held from the quota check to the save of an object,
+/// so concurrent requests can't all fit in the remaining quota.
+/// Requests of the daemon are serialized with a mutex and the other nodes with a lease,
+/// both are released when it's dropped.
+pub struct QuotaLock {
+  lease: String,
+  state: SystemState,
+  guard: Option<tokio::sync::OwnedMutexGuard<()>>,
+}
+
+impl Drop for QuotaLock {
+  fn drop(&mut self) {
+    let lease = std::mem::take(&mut self.lease);
+    let state = self.state.clone();
+    let guard = self.guard.take();
+    // The mutex is kept until the lease is released
+    // so the next request of the daemon doesn't lose the lease it renewed
+    rt::spawn(async move {
+      let hostname = state.config().hostname.clone();
+      if let Err(err) =
+        NodeDb::release_lease(&lease, &hostname, &state.inner.pool).await
+      {
+        log::warn!("namespace::quota_lock: {lease} {err}");
+      }
+      drop(guard);
+    });
+  }
+}
+
+/// Lock the quota of a namespace, see `QuotaLock`
+async fn lock_quota(
+  namespace: &str,
+  state: &SystemState,
+) -> HttpResult<QuotaLock> {
+  let mutex = {
+    let Ok(mut locks) = state.inner.quota_locks.lock() else {
+      return Err(HttpError::internal_server_error(format!(
+        "Namespace {namespace}: unable to lock the quota"
+      )));
+    };
+    locks.entry(namespace.to_owned()).or_default().clone()
+  };
+  let guard = mutex.lock_owned().await;
+  let lease = format!("quota.{namespace}");
+  let hostname = state.config().hostname.clone();
+  let started = std::time::Instant::now();
+  while !NodeDb::acquire_lease(
+    &lease,
+    &hostname,
+    QUOTA_LEASE_TTL,
+    &state.inner.pool,
+  )
+  .await?
+  {
+    if started.elapsed() > QUOTA_LOCK_TIMEOUT {
+      return Err(HttpError::conflict(format!(
+        "Namespace {namespace}: quota is locked by another node, retry later"
+      )));
+    }
+    time::sleep(Duration::from_millis(100)).await;
+  }
+  Ok(QuotaLock {
+    lease,
+    state: state.clone(),
+    guard: Some(guard),
+  })
+}
+
+/// Resources used by the objects of a namespace
+#[derive(Default)]
+struct NamespaceUsage {
+  cargoes: usize,
+  instances: usize,
+  vms: usize,
+  nano_cpus: i64,
+  memory: i64,
+}
+
+/// Number of instances wanted by a cargo in the cluster with the given nodes.
+/// Modes by node groups aren't scheduled so they don't have instances.
+fn cargo_instances(
+  replication: &Option<ReplicationMode>,
+  nodes: &[String],
+) -> usize {
+  match utils::container::cargo::replicas(replication, "") {
+    None => 0,
+    Some(Replicas::Single(number)) => number,
+    Some(Replicas::PerNode(_)) => nodes
+      .iter()
+      .filter_map(|node| utils::container::cargo::replicas(replication, node))
+      .map(|replicas| replicas.number())
+      .sum(),
+  }
+}
+
+/// Names of the nodes of the cluster
+async fn list_node_names(state: &SystemState) -> IoResult<Vec<String>> {
+  let nodes = NodeDb::read_by(&GenericFilter::new(), &state.inner.pool).await?;
+  Ok(nodes.into_iter().map(|node| node.name).collect())
+}
+
+/// Cpu and memory requested by a container
+fn container_resources(container: &Config) -> (i64, i64) {
+  let host_config = container.host_config.clone().unwrap_or_default();
+  (
+    host_config.nano_cpus.unwrap_or_default(),
+    host_config.memory.unwrap_or_default(),
+  )
+}
+
+/// Cpu and memory requested by a virtual machine
+fn vm_resources(host_config: &VmHostConfig) -> (i64, i64) {
+  (
+    host_config.cpu as i64 * 1_000_000_000,
+    host_config.memory as i64 * 1024 * 1024,
+  )
+}
+
+/// Compute the resources used by the objects of a namespace
+/// The object with the given key is ignored since it's the one being created or updated
+async fn get_usage(
+  namespace: &str,
+  key: &str,
+  nodes: &[String],
+  state: &SystemState,
+) -> IoResult<NamespaceUsage> {
+  let mut usage = NamespaceUsage::default();
+  let cargoes =
+    CargoDb::read_by_namespace(namespace, &state.inner.pool).await?;
+  for cargo in cargoes.iter().filter(|cargo| cargo.spec.cargo_key != key) {
+    let instances = cargo_instances(&cargo.spec.replication, nodes);
+    let (nano_cpus, memory) = container_resources(&cargo.spec.container);
+    usage.cargoes += 1;
+    usage.instances += instances;
+    usage.nano_cpus += nano_cpus * instances as i64;
+    usage.memory += memory * instances as i64;
+  }
+  let vms = VmDb::read_by_namespace(namespace, &state.inner.pool).await?;
+  for vm in vms.iter().filter(|vm| vm.spec.vm_key != key) {
+    let (nano_cpus, memory) = vm_resources(&vm.spec.host_config);
+    usage.vms += 1;
+    usage.nano_cpus += nano_cpus;
+    usage.memory += memory;
+  }
+  // Jobs don't have a namespace they are accounted in the global one
+  if namespace == vars::DEFAULT_NAMESPACE {
+    let jobs =
+      JobDb::transform_read_by(&GenericFilter::new(), &state.inner.pool)
+        .await?;
+    for job in jobs.iter().filter(|job| job.name != key) {
+      for container in &job.containers {
+        let (nano_cpus, memory) = container_resources(container);
+        usage.instances += 1;
+        usage.nano_cpus += nano_cpus;
+        usage.memory += memory;
+      }
+    }
+  }
+  Ok(usage)
+}
+
+/// Ensure the usage of a namespace fit in his quotas
+fn check_usage(
+  namespace: &str,
+  quota: &NamespaceQuota,
+  usage: &NamespaceUsage,
+) -> HttpResult<()> {
+  let exceeded = |what: &str, max: String, wanted: String| {
+    HttpError::forbidden(format!(
+      "Namespace {namespace}: quota exceeded for {what}, {wanted} requested but the maximum is {max}"
+    ))
+  };
+  if let Some(max) = quota.max_cargoes {
+    if usage.cargoes > max {
+      return Err(exceeded(
+        "cargoes",
+        max.to_string(),
+        usage.cargoes.to_string(),
+      ));
+    }
+  }
+  if let Some(max) = quota.max_instances {
+    if usage.instances > max {
+      return Err(exceeded(
+        "instances",
+        max.to_string(),
+        usage.instances.to_string(),
+      ));
+    }
+  }
+  if let Some(max) = quota.max_vms {
+    if usage.vms > max {
+      return Err(exceeded("vms", max.to_string(), usage.vms.to_string()));
+    }
+  }
+  if let Some(max) = quota.max_nano_cpus {
+    if usage.nano_cpus > max {
+      return Err(exceeded(
+        "cpu",
+        format!("{max} nano cpus"),
+        format!("{} nano cpus", usage.nano_cpus),
+      ));
+    }
+  }
+  if let Some(max) = quota.max_memory {
+    if usage.memory > max {
+      return Err(exceeded(
+        "memory",
+        format!("{max} bytes"),
+        format!("{} bytes", usage.memory),
+      ));
+    }
+  }
+  Ok(())
+}
+
+/// Ensure a single container or virtual machine fit in the limit range
+fn check_limits(
+  namespace: &str,
+  name: &str,
+  limit_range: &NamespaceLimitRange,
+  (nano_cpus, memory): (i64, i64),
+) -> HttpResult<()> {
+  if let Some(max) = limit_range.max_nano_cpus {
+    if nano_cpus > max {
+      return Err(HttpError::forbidden(format!(
+        "Namespace {namespace}: {name} requests {nano_cpus} nano cpus but the maximum is {max}"
+      )));
+    }
+  }
+  if let Some(max) = limit_range.max_memory {
+    if memory > max {
+      return Err(HttpError::forbidden(format!(
+        "Namespace {namespace}: {name} requests {memory} bytes of memory but the maximum is {max}"
+      )));
+    }
+  }
+  Ok(())
+}
+
+/// Inject the default limits of a namespace into a container when missing
+/// and ensure it doesn't exceed the max limits
+fn apply_limit_range(
+  namespace: &str,
+  name: &str,
+  limit_range: &NamespaceLimitRange,
+  container: &mut Config,
+) -> HttpResult<()> {
+  let mut host_config = container.host_config.clone().unwrap_or_default();
+  // Docker use 0 as unlimited, the max limit is used when there is no default
+  if host_config.nano_cpus.unwrap_or_default() == 0 {
+    host_config.nano_cpus = limit_range
+      .default_nano_cpus
+      .or(limit_range.max_nano_cpus)
+      .or(host_config.nano_cpus);
+  }
+  if host_config.memory.unwrap_or_default() == 0 {
+    host_config.memory = limit_range
+      .default_memory
+      .or(limit_range.max_memory)
+      .or(host_config.memory);
+  }
+  container.host_config = Some(host_config);
+  check_limits(namespace, name, limit_range, container_resources(container))
+}
+
+/// Apply the limit range of a namespace to a cargo
+/// and ensure the namespace quotas and registry policies are respected.
+/// The returned lock must be kept until the cargo is saved.
+pub async fn check_cargo(
+  namespace: &str,
+  key: &str,
+  spec: &mut CargoSpecPartial,
+  state: &SystemState,
+) -> HttpResult<Option<QuotaLock>> {
+  let namespace = NamespaceDb::read_by_pk(namespace, &state.inner.pool).await?;
+  if namespace.is_destroying() {
+    return Err(HttpError::conflict(format!(
+      "Namespace {}: is being deleted",
+      namespace.name
+    )));
+  }
+  let init_image = spec
+    .init_container
+    .as_ref()
+    .and_then(|init| init.image.clone());
+  let images = [spec.container.image.clone(), init_image]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+  let actor = EventActor {
+    key: Some(key.to_owned()),
+    kind: EventActorKind::Cargo,
+    attributes: None,
+  };
+  utils::registry_policy::check_images(&namespace.name, &images, &actor, state)
+    .await?;
+  if let Some(limit_range) = namespace.limit_range() {
+    apply_limit_range(
+      &namespace.name,
+      &spec.name,
+      &limit_range,
+      &mut spec.container,
+    )?;
+    if let Some(init_container) = spec.init_container.as_mut() {
+      apply_limit_range(
+        &namespace.name,
+        &spec.name,
+        &limit_range,
+        init_container,
+      )?;
+    }
+  }
+  let Some(quota) = namespace.quota() else {
+    return Ok(None);
+  };
+  let lock = lock_quota(&namespace.name, state).await?;
+  let nodes = list_node_names(state).await?;
+  let mut usage = get_usage(&namespace.name, key, &nodes, state).await?;
+  let instances = cargo_instances(&spec.replication, &nodes);
+  let (nano_cpus, memory) = container_resources(&spec.container);
+  usage.cargoes += 1;
+  usage.instances += instances;
+  usage.nano_cpus += nano_cpus * instances as i64;
+  usage.memory += memory * instances as i64;
+  check_usage(&namespace.name, &quota, &usage)?;
+  Ok(Some(lock))
+}
+
+/// Ensure a virtual machine respect the limit range, quotas and registry policies of a namespace.
+/// The returned lock must be kept until the virtual machine is saved.
+pub async fn check_vm(
+  namespace: &str,
+  key: &str,
+  spec: &VmSpecPartial,
+  state: &SystemState,
+) -> HttpResult<Option<QuotaLock>> {
+  let namespace = NamespaceDb::read_by_pk(namespace, &state.inner.pool).await?;
+  if namespace.is_destroying() {
+    return Err(HttpError::conflict(format!(
+      "Namespace {}: is being deleted",
+      namespace.name
+    )));
+  }
+  let host_config = spec.host_config.clone().unwrap_or_default();
+  let image = host_config
+    .runtime
+    .clone()
+    .unwrap_or(vars::VM_RUNTIME.to_owned());
+  let actor = EventActor {
+    key: Some(key.to_owned()),
+    kind: EventActorKind::Vm,
+    attributes: None,
+  };
+  utils::registry_policy::check_images(
+    &namespace.name,
+    &[image],
+    &actor,
+    state,
+  )
+  .await?;
+  let resources = vm_resources(&host_config);
+  if let Some(limit_range) = namespace.limit_range() {
+    check_limits(&namespace.name, &spec.name, &limit_range, resources)?;
+  }
+  let Some(quota) = namespace.quota() else {
+    return Ok(None);
+  };
+  let lock = lock_quota(&namespace.name, state).await?;
+  let nodes = list_node_names(state).await?;
+  let mut usage = get_usage(&namespace.name, key, &nodes, state).await?;
+  usage.vms += 1;
+  usage.nano_cpus += resources.0;
+  usage.memory += resources.1;
+  check_usage(&namespace.name, &quota, &usage)?;
+  Ok(Some(lock))
+}
+
+/// Apply the limit range of the global namespace to a job
+/// and ensure the global namespace quotas and registry policies are respected.
+/// The returned lock must be kept until the job is saved.
+pub async fn check_job(
+  job: &mut JobPartial,
+  state: &SystemState,
+) -> HttpResult<Option<QuotaLock>> {
+  let namespace =
+    NamespaceDb::read_by_pk(vars::DEFAULT_NAMESPACE, &state.inner.pool).await?;
+  let images = job
+    .containers
+    .iter()
+    .filter_map(|container| container.image.clone())
+    .collect::<Vec<_>>();
+  let actor = EventActor {
+    key: Some(job.name.clone()),
+    kind: EventActorKind::Job,
+    attributes: None,
+  };
+  utils::registry_policy::check_images(&namespace.name, &images, &actor, state)
+    .await?;
+  if let Some(limit_range) = namespace.limit_range() {
+    for container in job.containers.iter_mut() {
+      apply_limit_range(&namespace.name, &job.name, &limit_range, container)?;
+    }
+  }
+  let Some(quota) = namespace.quota() else {
+    return Ok(None);
+  };
+  let lock = lock_quota(&namespace.name, state).await?;
+  let nodes = list_node_names(state).await?;
+  let mut usage = get_usage(&namespace.name, &job.name, &nodes, state).await?;
+  for container in &job.containers {
+    let (nano_cpus, memory) = container_resources(container);
+    usage.instances += 1;
+    usage.nano_cpus += nano_cpus;
+    usage.memory += memory;
+  }
+  check_usage(&namespace.name, &quota, &usage)?;
+  Ok(Some(lock))
+}
+
+/// Return true if the upstream target key `<name>.<namespace>.<kind>` is in the namespace
+fn is_target_in_namespace(key: &str, namespace: &str) -> bool {
+  key.split('.').nth(1) == Some(namespace)
+}
+
+/// List the proxy and dns rules scoped to a namespace.
+/// A proxy rule is scoped when all his upstream targets are in the namespace,
+/// a dns rule is scoped when his network is the namespace network.
+pub async fn list_resources(
+  namespace: &str,
+  state: &SystemState,
+) -> IoResult<Vec<Resource>> {
+  let filter = GenericFilter::new().r#where(
+    "kind",
+    GenericClause::In(vec![
+      vars::PROXY_RULE_KIND.to_owned(),
+      vars::DNS_RULE_KIND.to_owned(),
+    ]),
+  );
+  let resources = ResourceDb::transform_read_by(&filter, &state.inner.pool)
+    .await?
+    .into_iter()
+    .filter(|resource| match resource.kind.as_str() {
+      vars::PROXY_RULE_KIND => {
+        let Ok(rule) = serde_json::from_value::<ResourceProxyRule>(
+          resource.spec.data.clone(),
+        ) else {
+          return false;
+        };
+        let targets = rule.upstream_targets();
+        !targets.is_empty()
+          && targets
+            .iter()
+            .all(|target| is_target_in_namespace(&target.key, namespace))
+      }
+      _ => {
+        let Ok(rule) =
+          serde_json::from_value::<ResourceDnsRule>(resource.spec.data.clone())
+        else {
+          return false;
+        };
+        rule.network == namespace
+      }
+    })
+    .collect();
+  Ok(resources)
+}
+
+/// List the named volumes used by the cargoes of a namespace
+/// and the volumes labeled with the namespace
+pub async fn list_volumes(
+  namespace: &str,
+  cargoes: &[Cargo],
+  state: &SystemState,
+) -> IoResult<Vec<String>> {
+  let mut volumes = Vec::new();
+  for cargo in cargoes {
+    let host_config =
+      cargo.spec.container.host_config.clone().unwrap_or_default();
+    for bind in host_config.binds.unwrap_or_default() {
+      let source = bind.split(':').next().unwrap_or_default();
+      // Host paths are never removed
+      if source.is_empty() || source.starts_with('/') {
+        continue;
+      }
+      volumes.push(source.to_owned());
+    }
+    for mount in host_config.mounts.unwrap_or_default() {
+      if mount.typ != Some(MountTypeEnum::VOLUME) {
+        continue;
+      }
+      if let Some(source) = mount.source {
+        volumes.push(source);
+      }
+    }
+  }
+  let filters = HashMap::from([(
+    "label".to_owned(),
+    vec![format!("io.nanocl.n={namespace}")],
+  )]);
+  let labeled = state
+    .inner
+    .runtime
+    .list_volumes(Some(ListVolumesOptions { filters }))
+    .await
+    .map_err(|err| err.map_err_context(|| "Volumes"))?
+    .volumes
+    .unwrap_or_default();
+  volumes.extend(labeled.into_iter().map(|volume| volume.name));
+  volumes.sort();
+  volumes.dedup();
+  Ok(volumes)
+}
+
+/// Remove a volume, a volume still in use by another container is kept
+pub async fn remove_volume(name: &str, state: &SystemState) {
+  if let Err(err) = state
+    .inner
+    .runtime
+    .remove_volume(name, None::<RemoveVolumeOptions>)
+    .await
+  {
+    log::warn!("namespace::remove_volume: {name} {err}");
+  }
+}
+
+/// Delay to wait for the event loop to queue the deletion tasks
+const QUEUE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Refuse to delete the objects with running instances without cascade option
+async fn ensure_not_running(
+  kind: ProcessKind,
+  key: &str,
+  name: &str,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let processes = ProcessDb::read_by_kind_key(key, &state.inner.pool)
+    .await?
+    .into_iter()
+    .filter(|process| process.kind == kind)
+    .collect::<Vec<_>>();
+  let (_, _, _, running) = utils::container::generic::count_status(&processes);
+  if running > 0 {
+    return Err(HttpError::bad_request(format!(
+      "Unable to delete {kind} {name} with running instances without cascade option"
+    )));
+  }
+  Ok(())
+}
+
+/// Wait for the `Destroying` events of the given objects to be handled by the event loop
+/// so their deletion tasks are queued in the task manager
+async fn wait_queued(
+  mut events: RawEventReceiver,
+  mut keys: HashSet<String>,
+) -> IoResult<()> {
+  let wait = async {
+    while !keys.is_empty() {
+      let Some(Ok(bytes)) = events.next().await else {
+        break;
+      };
+      // The keep alive messages aren't events
+      let Ok(event) = serde_json::from_slice::<Event>(&bytes) else {
+        continue;
+      };
+      if let Some(actor) = &event.actor {
+        keys.remove(&TaskManager::gen_key(actor));
+      }
+    }
+  };
+  if time::timeout(time::Millis::from(QUEUE_TIMEOUT), wait)
+    .await
+    .is_err()
+  {
+    return Err(IoError::interrupted(
+      "Namespace",
+      "Timeout waiting for the deletion tasks to be queued",
+    ));
+  }
+  Ok(())
+}
+
+/// Delete cargoes and virtual machines like the api does.
+/// Their `Destroying` event queue the deletion tasks behind the existing tasks of the objects,
+/// objects already being destroyed are only awaited.
+/// Cargoes and virtual machines with running instances are refused unless `force` is set.
+/// Return the key of the task queue and the actor of each object once they are queued
+pub async fn push_delete_objects(
+  cargoes: &[Cargo],
+  vms: &[Vm],
+  force: bool,
+  state: &SystemState,
+) -> HttpResult<Vec<(String, EventActor)>> {
+  if !force {
+    for cargo in cargoes {
+      ensure_not_running(
+        ProcessKind::Cargo,
+        &cargo.spec.cargo_key,
+        &cargo.spec.name,
+        state,
+      )
+      .await?;
+    }
+    for vm in vms {
+      ensure_not_running(
+        ProcessKind::Vm,
+        &vm.spec.vm_key,
+        &vm.spec.name,
+        state,
+      )
+      .await?;
+    }
+  }
+  let filter = EventCondition {
+    action: vec![NativeEventAction::Destroying],
+    ..Default::default()
+  };
+  let events = state.subscribe_raw(None, Some(filter)).await?;
+  let mut objects = Vec::new();
+  let mut queued = HashSet::new();
+  let opts = CargoDeleteQuery {
+    force: Some(force),
+    ..Default::default()
+  };
+  for cargo in cargoes {
+    let actor: EventActor = cargo.clone().into();
+    let task_key = TaskManager::gen_key(&actor);
+    if cargo.status.actual != ObjPsStatusKind::Destroying {
+      CargoDb::del_obj_by_pk(&cargo.spec.cargo_key, &opts, state).await?;
+      queued.insert(task_key.clone());
+    }
+    objects.push((task_key, actor));
+  }
+  for vm in vms {
+    let actor: EventActor = vm.clone().into();
+    let task_key = TaskManager::gen_key(&actor);
+    if vm.status.actual != ObjPsStatusKind::Destroying {
+      VmDb::del_obj_by_pk(&vm.spec.vm_key, &(), state).await?;
+      queued.insert(task_key.clone());
+    }
+    objects.push((task_key, actor));
+  }
+  wait_queued(events, queued).await?;
+  Ok(objects)
+}
+
+/// Ensure no cargo or virtual machine is left in a namespace before removing it
+pub async fn ensure_empty(
+  namespace: &str,
+  state: &SystemState,
+) -> IoResult<()> {
+  let cargoes =
+    CargoDb::read_by_namespace(namespace, &state.inner.pool).await?;
+  let vms = VmDb::read_by_namespace(namespace, &state.inner.pool).await?;
+  if cargoes.is_empty() && vms.is_empty() {
+    return Ok(());
+  }
+  Err(IoError::interrupted(
+    "Namespace",
+    &format!(
+      "{namespace}: {} cargoes and {} vms could not be deleted",
+      cargoes.len(),
+      vms.len()
+    ),
+  ))
+}
+
+/// Set or replace the `KEY=VALUE` environment variables of a container
+fn merge_env(
+  env: Option<Vec<String>>,
+  overrides: &[String],
+) -> Option<Vec<String>> {
+  if overrides.is_empty() {
+    return env;
+  }
+  let mut env = env.unwrap_or_default();
+  for value in overrides {
+    let key = value.split('=').next().unwrap_or_default();
+    env.retain(|current| current.split('=').next() != Some(key));
+    env.push(value.clone());
+  }
+  Some(env)
+}
+
+/// Replace the segments of a name equal to the source namespace,
+/// segments are delimited by `.` or `-`.
+/// Return none when the name doesn't contain the source namespace as a segment
+fn rewrite_namespace(name: &str, source: &str, target: &str) -> Option<String> {
+  let is_delimiter =
+    |c: Option<char>| matches!(c, None | Some('.') | Some('-'));
+  let mut rewritten = String::new();
+  let mut last = 0;
+  for (index, _) in name.match_indices(source) {
+    let end = index + source.len();
+    if index < last
+      || !is_delimiter(name[..index].chars().next_back())
+      || !is_delimiter(name[end..].chars().next())
+    {
+      continue;
+    }
+    rewritten.push_str(&name[last..index]);
+    rewritten.push_str(target);
+    last = end;
+  }
+  if last == 0 {
+    return None;
+  }
+  rewritten.push_str(&name[last..]);
+  Some(rewritten)
+}
+
+/// Name of a resource once cloned into another namespace
+/// The source namespace segment is replaced in the name or the target is appended
+fn clone_resource_name(name: &str, source: &str, target: &str) -> String {
+  rewrite_namespace(name, source, target)
+    .unwrap_or_else(|| format!("{name}-{target}"))
+}
+
+/// Copy a proxy or dns rule of a namespace pointing to the target namespace.
+/// The domains of the http rules are rewritten to the target namespace,
+/// the rules that would listen on the same domain or port as the source
+/// are skipped, none is returned when no rule is left
+fn clone_resource(
+  resource: &Resource,
+  source: &str,
+  target: &str,
+  skipped: &mut Vec<NamespaceCloneSkippedRule>,
+) -> IoResult<Option<ResourcePartial>> {
+  let name = &resource.spec.resource_key;
+  let mut skip = |reason: String| {
+    skipped.push(NamespaceCloneSkippedRule {
+      resource: name.clone(),
+      reason,
+    });
+  };
+  let data = match resource.kind.as_str() {
+    vars::PROXY_RULE_KIND => {
+      let mut rule =
+        serde_json::from_value::<ResourceProxyRule>(resource.spec.data.clone())
+          .map_err(|err| err.map_err_context(|| "Proxy rule"))?;
+      let mut rules = Vec::new();
+      for item in rule.rules {
+        match item {
+          ProxyRule::Http(mut http) => {
+            let domain = http
+              .domain
+              .as_deref()
+              .and_then(|domain| rewrite_namespace(domain, source, target));
+            if domain.is_none() {
+              skip(format!(
+                "http rule on domain {} doesn't contain the namespace {source}",
+                http.domain.unwrap_or_default()
+              ));
+              continue;
+            }
+            http.domain = domain;
+            rules.push(ProxyRule::Http(http));
+          }
+          ProxyRule::Stream(stream) => {
+            skip(format!(
+              "stream rule would listen on the same port {}",
+              stream.port
+            ));
+          }
+        }
+      }
+      rule.rules = rules;
+      if rule.rules.is_empty() {
+        return Ok(None);
+      }
+      for upstream in rule.upstream_targets_mut() {
+        let mut parts = upstream.key.split('.').collect::<Vec<_>>();
+        if parts.len() > 1 {
+          parts[1] = target;
+        }
+        upstream.key = parts.join(".");
+      }
+      serde_json::to_value(rule)
+        .map_err(|err| err.map_err_context(|| "Proxy rule"))?
+    }
+    _ => {
+      let mut rule =
+        serde_json::from_value::<ResourceDnsRule>(resource.spec.data.clone())
+          .map_err(|err| err.map_err_context(|| "Dns rule"))?;
+      target.clone_into(&mut rule.network);
+      serde_json::to_value(rule)
+        .map_err(|err| err.map_err_context(|| "Dns rule"))?
+    }
+  };
+  Ok(Some(ResourcePartial {
+    name: clone_resource_name(name, source, target),
+    kind: format!("{}/{}", resource.kind, resource.spec.version),
+    data,
+    metadata: resource.spec.metadata.clone(),
+  }))
+}
+
+/// Objects created by a namespace clone, removed when the clone fail
+#[derive(Default)]
+struct ClonedObjects {
+  cargoes: Vec<Cargo>,
+  vms: Vec<Vm>,
+  resources: Vec<String>,
+  /// Proxy rules of the source left out of the clone
+  skipped_rules: Vec<NamespaceCloneSkippedRule>,
+}
+
+/// Clone a virtual machine into another namespace
+/// The disk is created from the base image of the source vm
+/// or from a snapshot of his current disk when `snapshot` is true.
+/// The current disk is frozen into a base image shared by the source and the clone,
+/// it's kept when the clone fail since the source vm now use it.
+async fn clone_vm(
+  vm: Vm,
+  target: &str,
+  snapshot: bool,
+  state: &SystemState,
+) -> HttpResult<Vm> {
+  let version = vm.spec.version.clone();
+  let mut spec: VmSpecPartial = vm.spec.into();
+  let disk = VmImageDb::read_by_pk(&spec.disk.image, &state.inner.pool).await?;
+  spec.disk.image = if snapshot {
+    let base_name = format!("{}.base", utils::key::gen_key(target, &spec.name));
+    let size = spec.disk.size.unwrap_or(20);
+    let base = utils::vm_image::freeze(&base_name, size, &disk, state).await?;
+    base.name
+  } else {
+    disk.parent.unwrap_or(disk.name)
+  };
+  let obj = VmObjCreateIn {
+    namespace: target.to_owned(),
+    spec,
+    version,
+  };
+  VmDb::create_obj(&obj, state).await
+}
+
+/// Ensure the virtual machines are stopped so their disks can be copied
+async fn check_vms_stopped(vms: &[Vm], state: &SystemState) -> HttpResult<()> {
+  for vm in vms {
+    let processes =
+      ProcessDb::read_by_kind_key(&vm.spec.vm_key, &state.inner.pool).await?;
+    let (_, _, _, running) =
+      utils::container::generic::count_status(&processes);
+    if running > 0 {
+      return Err(HttpError::conflict(format!(
+        "Vm {}: must be stopped to snapshot his disk",
+        vm.spec.name
+      )));
+    }
+  }
+  Ok(())
+}
+
+/// Create the cargoes, virtual machines, proxy and dns rules of the clone.
+/// Return the objects to start once everything is created
+async fn clone_objects(
+  name: &str,
+  opts: &NamespaceCloneOpts,
+  vms: Vec<Vm>,
+  cloned: &mut ClonedObjects,
+  state: &SystemState,
+) -> HttpResult<Vec<(String, ProcessKind)>> {
+  let target = opts.name.as_str();
+  let env = opts.env.clone().unwrap_or_default();
+  let images = opts.images.clone().unwrap_or_default();
+  let mut to_start = Vec::new();
+  let cargoes = CargoDb::read_by_namespace(name, &state.inner.pool).await?;
+  for cargo in cargoes {
+    let wanted = cargo.status.wanted.clone();
+    let version = cargo.spec.version.clone();
+    let mut spec: CargoSpecPartial = cargo.spec.into();
+    if let Some(image) = images.get(&spec.name) {
+      spec.container.image = Some(image.clone());
+    }
+    spec.container.env = merge_env(spec.container.env.take(), &env);
+    let obj = CargoObjCreateIn {
+      namespace: target.to_owned(),
+      spec,
+      version,
+    };
+    let cargo = CargoDb::create_obj(&obj, state).await?;
+    if wanted == ObjPsStatusKind::Start {
+      to_start.push((cargo.spec.cargo_key.clone(), ProcessKind::Cargo));
+    }
+    cloned.cargoes.push(cargo);
+  }
+  let snapshot = opts.snapshot_vms.unwrap_or_default();
+  for vm in vms {
+    let wanted = vm.status.wanted.clone();
+    let vm = clone_vm(vm, target, snapshot, state).await?;
+    if wanted == ObjPsStatusKind::Start {
+      to_start.push((vm.spec.vm_key.clone(), ProcessKind::Vm));
+    }
+    cloned.vms.push(vm);
+  }
+  for resource in list_resources(name, state).await? {
+    let Some(partial) =
+      clone_resource(&resource, name, target, &mut cloned.skipped_rules)?
+    else {
+      continue;
+    };
+    let resource = ResourceDb::create_obj(&partial, state).await?;
+    cloned.resources.push(resource.spec.resource_key);
+  }
+  Ok(to_start)
+}
+
+/// Remove the objects created by a failed clone and the cloned namespace
+async fn rollback_clone(
+  target: &str,
+  cloned: ClonedObjects,
+  state: &SystemState,
+) {
+  for resource in &cloned.resources {
+    if let Err(err) = ResourceDb::del_obj_by_pk(resource, &(), state).await {
+      log::warn!("namespace::rollback_clone: {resource} {err}");
+    }
+  }
+  match push_delete_objects(&cloned.cargoes, &cloned.vms, true, state).await {
+    Ok(objects) => {
+      for (task_key, _) in objects {
+        state.inner.task_manager.wait_task(&task_key).await;
+      }
+    }
+    Err(err) => log::warn!("namespace::rollback_clone: {target} {err}"),
+  }
+  if let Err(err) =
+    NamespaceDb::del_obj_by_pk(target, &NamespaceDeleteQuery::default(), state)
+      .await
+  {
+    log::warn!("namespace::rollback_clone: {target} {err}");
+  }
+}
+
+/// Clone a namespace with his cargoes, virtual machines, proxy and dns rules.
+/// Processes that were wanted started in the source namespace are started
+/// once everything is cloned, the created objects are removed if a step fail.
+/// Return the cloned namespace with the proxy rules that couldn't be cloned
+pub async fn clone_namespace(
+  name: &str,
+  opts: &NamespaceCloneOpts,
+  state: &SystemState,
+) -> HttpResult<NamespaceCloned> {
+  let source = NamespaceDb::read_by_pk(name, &state.inner.pool).await?;
+  if source.is_destroying() {
+    return Err(HttpError::conflict(format!(
+      "Namespace {name}: is being deleted"
+    )));
+  }
+  let vms = VmDb::read_by_namespace(name, &state.inner.pool).await?;
+  if opts.snapshot_vms.unwrap_or_default() {
+    check_vms_stopped(&vms, state).await?;
+  }
+  let target = opts.name.as_str();
+  // The subnet of an isolated network can't be shared
+  let network = source.network().map(|network| NamespaceNetwork {
+    isolated: network.isolated,
+    ..Default::default()
+  });
+  let partial = NamespacePartial {
+    name: target.to_owned(),
+    metadata: source.metadata.clone(),
+    network,
+    quota: source.quota(),
+    limit_range: source.limit_range(),
+  };
+  let namespace = NamespaceDb::create_obj(&partial, state).await?;
+  let mut cloned = ClonedObjects::default();
+  let to_start = match clone_objects(name, opts, vms, &mut cloned, state).await
+  {
+    Ok(to_start) => to_start,
+    Err(err) => {
+      log::warn!("namespace::clone_namespace: {target} rollback: {err}");
+      rollback_clone(target, cloned, state).await;
+      return Err(err);
+    }
+  };
+  for (key, kind) in to_start {
+    utils::container::generic::emit_starting(&key, &kind, state).await?;
+  }
+  for rule in &cloned.skipped_rules {
+    log::warn!(
+      "namespace::clone_namespace: {target} skipped {}: {}",
+      rule.resource,
+      rule.reason
+    );
+  }
+  Ok(NamespaceCloned {
+    namespace,
+    skipped_rules: cloned.skipped_rules,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn resource_name() {
+    assert_eq!(
+      clone_resource_name("products-prod", "prod", "staging"),
+      "products-staging"
+    );
+    assert_eq!(
+      clone_resource_name("prod.products", "prod", "staging"),
+      "staging.products"
+    );
+    assert_eq!(
+      clone_resource_name("products", "prod", "staging"),
+      "products-staging"
+    );
+    assert_eq!(
+      clone_resource_name("my-prod-api", "my-prod", "dev"),
+      "dev-api"
+    );
+    assert_eq!(
+      rewrite_namespace("api.prod.products.io", "prod", "staging").as_deref(),
+      Some("api.staging.products.io")
+    );
+    assert_eq!(rewrite_namespace("reproduce.io", "prod", "staging"), None);
+  }
+  #[test]
+  fn instances_by_replication() {
+    use nanocl_stubs::cargo_spec::ReplicationStatic;
+
+    let nodes = vec!["node-a".to_owned(), "node-b".to_owned()];
+    assert_eq!(cargo_instances(&None, &nodes), 1);
+    assert_eq!(
+      cargo_instances(
+        &Some(ReplicationMode::Static(ReplicationStatic { number: 3 })),
+        &nodes
+      ),
+      3
+    );
+    assert_eq!(
+      cargo_instances(&Some(ReplicationMode::UniqueByNode), &nodes),
+      2
+    );
+    assert_eq!(
+      cargo_instances(
+        &Some(ReplicationMode::StaticByNodes(ReplicationStatic {
+          number: 3
+        })),
+        &nodes
+      ),
+      6
+    );
+    assert_eq!(
+      cargo_instances(
+        &Some(ReplicationMode::StaticByNodeNames {
+          names: vec!["node-a".to_owned(), "node-c".to_owned()],
+          number: 2,
+        }),
+        &nodes
+      ),
+      2
+    );
+    assert_eq!(
+      cargo_instances(
+        &Some(ReplicationMode::UniqueByNodeGroups {
+          groups: vec!["edge".to_owned()],
+        }),
+        &nodes
+      ),
+      0
+    );
+  }
+
+  #[test]
+  fn skipped_rules() {
+    let resource = Resource {
+      kind: vars::PROXY_RULE_KIND.to_owned(),
+      created_at: chrono::Utc::now().naive_utc(),
+      spec: nanocl_stubs::resource::ResourceSpec {
+        key: uuid::Uuid::new_v4(),
+        version: "v0.16".to_owned(),
+        created_at: chrono::Utc::now().naive_utc(),
+        resource_key: "prod-rules".to_owned(),
+        data: serde_json::json!({
+          "Rules": [
+            {
+              "Domain": "api.prod.io",
+              "Network": "Public",
+              "Locations": [],
+            },
+            {
+              "Domain": "api.io",
+              "Network": "Public",
+              "Locations": [],
+            },
+            {
+              "Network": "Public",
+              "Protocol": "Tcp",
+              "Port": 9000,
+              "Target": { "Uri": "tcp://127.0.0.1:9001" },
+            },
+          ],
+        }),
+        metadata: None,
+      },
+    };
+    let mut skipped = Vec::new();
+    let partial = clone_resource(&resource, "prod", "staging", &mut skipped)
+      .unwrap()
+      .expect("Expect the http rule in the namespace to be cloned");
+    assert_eq!(partial.name, "staging-rules");
+    let rule =
+      serde_json::from_value::<ResourceProxyRule>(partial.data).unwrap();
+    assert_eq!(rule.rules.len(), 1);
+    assert_eq!(skipped.len(), 2, "Expect the other rules to be reported");
+    assert!(skipped.iter().all(|rule| rule.resource == "prod-rules"));
+  }
+}
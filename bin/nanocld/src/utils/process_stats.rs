@@ -0,0 +1,177 @@
+use bollard_next::container::{MemoryStatsStats, Stats, StatsOptions};
+use futures::{future::join_all, StreamExt};
+
+use nanocl_error::io::{IoError, IoResult};
+use nanocl_stubs::{
+  generic::{GenericClause, GenericFilter},
+  metric::MetricPartial,
+  process::{Process, ProcessKind, ProcessStatsSample, ProcessStatsSummary},
+};
+
+use crate::{
+  models::{MetricDb, MetricNodePartial, ProcessDb, SystemState},
+  repositories::generic::*,
+  vars,
+};
+
+/// Processes running on the node
+pub async fn read_running(state: &SystemState) -> IoResult<Vec<Process>> {
+  let filter = GenericFilter::new().r#where(
+    "node_name",
+    GenericClause::Eq(state.config().hostname.clone()),
+  );
+  let processes = ProcessDb::transform_read_by(&filter, &state.inner.pool)
+    .await?
+    .into_iter()
+    .filter(|process| {
+      process
+        .data
+        .state
+        .as_ref()
+        .and_then(|state| state.running)
+        .unwrap_or_default()
+    })
+    .collect();
+  Ok(processes)
+}
+
+/// Get the stats of a running process once.
+/// Without `one_shot` the runtime wait for a second sample
+/// to be able to compute the usage of the cpus
+pub async fn get_stats(
+  process: &Process,
+  one_shot: bool,
+  state: &SystemState,
+) -> Option<Stats> {
+  let opts = StatsOptions {
+    stream: false,
+    one_shot,
+  };
+  state
+    .inner
+    .runtime
+    .stats(&process.key, Some(opts))
+    .next()
+    .await?
+    .ok()
+}
+
+/// Compute the resource usage of a process from his stats
+pub fn gen_sample(process: &Process, stats: &Stats) -> ProcessStatsSample {
+  let cpu_delta = stats.cpu_stats.cpu_usage.total_usage as f64
+    - stats.precpu_stats.cpu_usage.total_usage as f64;
+  let system_cpu_delta = stats.cpu_stats.system_cpu_usage.unwrap_or_default()
+    as f64
+    - stats.precpu_stats.system_cpu_usage.unwrap_or_default() as f64;
+  let number_cpus = stats.cpu_stats.online_cpus.unwrap_or_default() as f64;
+  let cpu_percent = if system_cpu_delta > 0.0 && cpu_delta > 0.0 {
+    (cpu_delta / system_cpu_delta) * number_cpus * 100.0
+  } else {
+    0.0
+  };
+  let usage = stats.memory_stats.usage.unwrap_or_default();
+  // The page cache can be reclaimed so it isn't counted as used
+  let cache = match stats.memory_stats.stats {
+    Some(MemoryStatsStats::V1(stats)) => stats.cache,
+    Some(MemoryStatsStats::V2(stats)) => stats.inactive_file,
+    None => 0,
+  };
+  let networks = stats.networks.clone().unwrap_or_default();
+  let namespace = process
+    .data
+    .config
+    .as_ref()
+    .and_then(|config| config.labels.as_ref())
+    .and_then(|labels| labels.get("io.nanocl.n"))
+    .cloned();
+  ProcessStatsSample {
+    key: process.key.clone(),
+    name: process.name.clone(),
+    kind: process.kind.clone(),
+    kind_key: process.kind_key.clone(),
+    namespace,
+    cpu_percent,
+    memory_usage: usage.saturating_sub(cache),
+    memory_limit: stats.memory_stats.limit.unwrap_or_default(),
+    network_rx: networks.values().map(|net| net.rx_bytes).sum(),
+    network_tx: networks.values().map(|net| net.tx_bytes).sum(),
+    pids: stats.pids_stats.current.unwrap_or_default(),
+  }
+}
+
+/// Last samples of the processes running on the node
+/// they are served by the prometheus metrics without asking the runtime again
+pub fn last_samples(state: &SystemState) -> Vec<ProcessStatsSample> {
+  state
+    .inner
+    .process_samples
+    .lock()
+    .map(|samples| samples.clone())
+    .unwrap_or_default()
+}
+
+/// Sample the resource usage of the processes running on the node
+/// and save them as metrics, return the number of samples saved
+pub async fn sample(state: &SystemState) -> IoResult<usize> {
+  let processes = read_running(state).await?;
+  let stats = join_all(
+    processes
+      .iter()
+      .map(|process| get_stats(process, false, state)),
+  )
+  .await;
+  let samples = processes
+    .iter()
+    .zip(stats)
+    .filter_map(|(process, stats)| Some(gen_sample(process, &stats?)))
+    .collect::<Vec<_>>();
+  if let Ok(mut last_samples) = state.inner.process_samples.lock() {
+    last_samples.clone_from(&samples);
+  }
+  let retention = state.config().retention.clone();
+  let mut count = 0;
+  for sample in samples {
+    let metric = MetricPartial {
+      kind: vars::PROCESS_STATS_KIND.to_owned(),
+      data: serde_json::to_value(&sample)?,
+      note: Some(format!(
+        "CPU {:.2}% | MEMORY {}MiB",
+        sample.cpu_percent,
+        sample.memory_usage / 1024 / 1024
+      )),
+    };
+    let metric = MetricNodePartial::try_new_node(
+      &state.config().hostname,
+      &metric,
+      &retention,
+    )?;
+    MetricDb::create_from(&metric, &state.inner.pool).await?;
+    count += 1;
+  }
+  Ok(count)
+}
+
+/// Summarize the samples of the processes of an object since a date,
+/// default to one hour ago
+pub async fn summary(
+  kind: &ProcessKind,
+  kind_key: &str,
+  since: Option<i64>,
+  state: &SystemState,
+) -> IoResult<Vec<ProcessStatsSummary>> {
+  let since = match since {
+    Some(since) => chrono::DateTime::from_timestamp(since, 0)
+      .ok_or_else(|| {
+        IoError::invalid_input("Since", format!("{since}").as_str())
+      })?
+      .naive_utc(),
+    None => chrono::Utc::now().naive_utc() - chrono::Duration::hours(1),
+  };
+  let summaries =
+    MetricDb::summarize_process_stats(kind, kind_key, since, &state.inner.pool)
+      .await?
+      .into_iter()
+      .map(ProcessStatsSummary::from)
+      .collect();
+  Ok(summaries)
+}
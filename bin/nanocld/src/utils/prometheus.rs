@@ -0,0 +1,449 @@
+use std::{collections::HashMap, fmt::Display, time::Instant};
+
+use ntex::http::StatusCode;
+use ntex::web::{Error, ErrorRenderer, WebRequest, WebResponse};
+use ntex::{Middleware, Service, ServiceCtx};
+
+use nanocl_error::io::IoResult;
+use nanocl_stubs::{generic::GenericFilter, system::ObjPsStatus};
+
+use crate::{
+  models::{
+    CargoDb, HttpMetrics, JobDb, SystemState, VmDb, HTTP_DURATION_BUCKETS,
+  },
+  repositories::generic::*,
+  utils,
+};
+
+/// Content type of the OpenMetrics text format
+pub const CONTENT_TYPE: &str =
+  "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Middleware recording the latency of the http handlers
+pub struct HttpMetricsRecorder {
+  metrics: HttpMetrics,
+}
+
+impl HttpMetricsRecorder {
+  pub fn new(state: &SystemState) -> Self {
+    Self {
+      metrics: state.inner.http_metrics.clone(),
+    }
+  }
+}
+
+impl<S> Middleware<S> for HttpMetricsRecorder {
+  type Service = HttpMetricsRecorderMiddleware<S>;
+
+  fn create(&self, service: S) -> Self::Service {
+    HttpMetricsRecorderMiddleware {
+      service,
+      metrics: self.metrics.clone(),
+    }
+  }
+}
+
+pub struct HttpMetricsRecorderMiddleware<S> {
+  service: S,
+  metrics: HttpMetrics,
+}
+
+/// Generate the route of a request from his path and the matched parameters
+/// to keep the number of routes bounded, eg: /{version}/cargoes/{name}/inspect
+fn gen_route(res: &WebResponse) -> String {
+  if res.status() == StatusCode::NOT_FOUND {
+    return "not_found".to_owned();
+  }
+  let req = res.request();
+  let path = req.path();
+  let params = req.match_info();
+  if params.is_empty() {
+    return path.to_owned();
+  }
+  path
+    .split('/')
+    .map(|segment| {
+      match params
+        .iter()
+        .find(|(_, value)| !value.is_empty() && *value == segment)
+      {
+        Some((name, _)) => format!("{{{name}}}"),
+        None => segment.to_owned(),
+      }
+    })
+    .collect::<Vec<_>>()
+    .join("/")
+}
+
+impl<S, Err> Service<WebRequest<Err>> for HttpMetricsRecorderMiddleware<S>
+where
+  S: Service<WebRequest<Err>, Response = WebResponse, Error = Error>,
+  Err: ErrorRenderer,
+{
+  type Response = WebResponse;
+  type Error = Error;
+
+  ntex::forward_ready!(service);
+
+  async fn call(
+    &self,
+    req: WebRequest<Err>,
+    ctx: ServiceCtx<'_, Self>,
+  ) -> Result<Self::Response, Self::Error> {
+    let start = Instant::now();
+    let method = req.method().to_string();
+    let res = ctx.call(&self.service, req).await?;
+    let key = (method, gen_route(&res), res.status().as_u16());
+    self.metrics.observe(key, start.elapsed().as_secs_f64());
+    Ok(res)
+  }
+}
+
+/// Escape the value of a label
+fn escape(value: &str) -> String {
+  value
+    .replace('\\', "\\\\")
+    .replace('"', "\\\"")
+    .replace('\n', "\\n")
+}
+
+/// Writer of metrics in the OpenMetrics text format
+#[derive(Default)]
+struct MetricsWriter {
+  out: String,
+}
+
+impl MetricsWriter {
+  /// Write the type and the help of a metric family
+  fn family(&mut self, name: &str, kind: &str, help: &str) {
+    self.out += &format!("# TYPE {name} {kind}\n# HELP {name} {help}\n");
+  }
+
+  /// Write a sample of a metric family
+  fn sample<V: Display>(
+    &mut self,
+    name: &str,
+    labels: &[(&str, &str)],
+    value: V,
+  ) {
+    let labels = labels
+      .iter()
+      .map(|(key, value)| format!("{key}=\"{}\"", escape(value)))
+      .collect::<Vec<_>>()
+      .join(",");
+    self.out += &format!("{name}{{{labels}}} {value}\n");
+  }
+
+  fn finish(mut self) -> String {
+    self.out += "# EOF\n";
+    self.out
+  }
+}
+
+/// Metrics of the node received from metrsd
+fn write_node(node: &str, state: &SystemState, w: &mut MetricsWriter) {
+  let metrics = state
+    .inner
+    .node_metrics
+    .lock()
+    .ok()
+    .and_then(|metrics| metrics.clone());
+  let Some(metrics) = metrics else {
+    return;
+  };
+  let labels = [("node", node)];
+  w.family(
+    "nanocl_node_cpu_usage_percent",
+    "gauge",
+    "Average usage of the cpus of the node",
+  );
+  let cpu = metrics.cpus.iter().map(|cpu| cpu.usage).sum::<f32>()
+    / metrics.cpus.len().max(1) as f32;
+  w.sample("nanocl_node_cpu_usage_percent", &labels, cpu);
+  w.family(
+    "nanocl_node_memory_used_bytes",
+    "gauge",
+    "Memory used on the node",
+  );
+  w.sample(
+    "nanocl_node_memory_used_bytes",
+    &labels,
+    metrics.memory.used,
+  );
+  w.family(
+    "nanocl_node_memory_total_bytes",
+    "gauge",
+    "Memory of the node",
+  );
+  w.sample(
+    "nanocl_node_memory_total_bytes",
+    &labels,
+    metrics.memory.total,
+  );
+  w.family(
+    "nanocl_node_disk_available_bytes",
+    "gauge",
+    "Space available on the disks of the node",
+  );
+  for disk in &metrics.disks {
+    w.sample(
+      "nanocl_node_disk_available_bytes",
+      &[
+        ("node", node),
+        ("device", &disk.device_name),
+        ("mount_point", &disk.mount_point),
+      ],
+      disk.available_space,
+    );
+  }
+  w.family(
+    "nanocl_node_disk_total_bytes",
+    "gauge",
+    "Space of the disks of the node",
+  );
+  for disk in &metrics.disks {
+    w.sample(
+      "nanocl_node_disk_total_bytes",
+      &[
+        ("node", node),
+        ("device", &disk.device_name),
+        ("mount_point", &disk.mount_point),
+      ],
+      disk.total_space,
+    );
+  }
+}
+
+/// Resource usage of the processes running on the node
+/// from the last samples of the process stats sampling
+fn write_processes(node: &str, state: &SystemState, w: &mut MetricsWriter) {
+  let samples = utils::process_stats::last_samples(state);
+  let families = [
+    (
+      "nanocl_process_cpu_usage_percent",
+      "gauge",
+      "Usage of the cpus by the process in percent of one cpu",
+    ),
+    (
+      "nanocl_process_memory_usage_bytes",
+      "gauge",
+      "Memory used by the process without the page cache",
+    ),
+    (
+      "nanocl_process_memory_limit_bytes",
+      "gauge",
+      "Memory limit of the process",
+    ),
+    (
+      "nanocl_process_network_receive_bytes_total",
+      "counter",
+      "Bytes received by the process",
+    ),
+    (
+      "nanocl_process_network_transmit_bytes_total",
+      "counter",
+      "Bytes transmitted by the process",
+    ),
+    (
+      "nanocl_process_pids",
+      "gauge",
+      "Number of pids of the process",
+    ),
+  ];
+  for (name, kind, help) in families {
+    // Counters are named without the _total suffix in the family
+    let family = name.trim_end_matches("_total");
+    w.family(family, kind, help);
+    for sample in &samples {
+      let process_kind = sample.kind.to_string();
+      let labels = [
+        ("node", node),
+        ("kind", process_kind.as_str()),
+        ("kind_key", sample.kind_key.as_str()),
+        ("process", sample.name.as_str()),
+      ];
+      let value = match name {
+        "nanocl_process_cpu_usage_percent" => sample.cpu_percent,
+        "nanocl_process_memory_usage_bytes" => sample.memory_usage as f64,
+        "nanocl_process_memory_limit_bytes" => sample.memory_limit as f64,
+        "nanocl_process_network_receive_bytes_total" => {
+          sample.network_rx as f64
+        }
+        "nanocl_process_network_transmit_bytes_total" => {
+          sample.network_tx as f64
+        }
+        _ => sample.pids as f64,
+      };
+      w.sample(name, &labels, value);
+    }
+  }
+}
+
+/// Count the objects by status
+fn count_status<'a, I>(kind: &str, statuses: I, w: &mut MetricsWriter)
+where
+  I: Iterator<Item = &'a ObjPsStatus>,
+{
+  let mut counts = HashMap::new();
+  for status in statuses {
+    *counts.entry(status.actual.to_string()).or_insert(0) += 1;
+  }
+  for (status, count) in counts {
+    w.sample(
+      "nanocl_objects",
+      &[("kind", kind), ("status", &status)],
+      count,
+    );
+  }
+}
+
+/// Number of cargoes, vms and jobs by status
+async fn write_objects(
+  state: &SystemState,
+  w: &mut MetricsWriter,
+) -> IoResult<()> {
+  let filter = GenericFilter::new();
+  let cargoes = CargoDb::transform_read_by(&filter, &state.inner.pool).await?;
+  let vms = VmDb::transform_read_by(&filter, &state.inner.pool).await?;
+  let jobs = JobDb::transform_read_by(&filter, &state.inner.pool).await?;
+  w.family("nanocl_objects", "gauge", "Number of objects by status");
+  count_status("cargo", cargoes.iter().map(|cargo| &cargo.status), w);
+  count_status("vm", vms.iter().map(|vm| &vm.status), w);
+  count_status("job", jobs.iter().map(|job| &job.status), w);
+  Ok(())
+}
+
+/// Depth of the task queues and state of the event loop
+async fn write_system(node: &str, state: &SystemState, w: &mut MetricsWriter) {
+  let labels = [("node", node)];
+  let stats = state.inner.task_manager.stats().await;
+  w.family(
+    "nanocl_task_workers",
+    "gauge",
+    "Maximum number of running tasks",
+  );
+  w.sample("nanocl_task_workers", &labels, stats.max_workers);
+  w.family("nanocl_tasks_running", "gauge", "Number of running tasks");
+  w.sample("nanocl_tasks_running", &labels, stats.running);
+  w.family("nanocl_tasks_pending", "gauge", "Number of waiting tasks");
+  w.sample("nanocl_tasks_pending", &labels, stats.pending);
+  w.family(
+    "nanocl_task_queue_depth",
+    "gauge",
+    "Number of tasks running and waiting for an object",
+  );
+  for (queue, depth) in &stats.queues {
+    w.sample(
+      "nanocl_task_queue_depth",
+      &[("node", node), ("queue", queue)],
+      depth,
+    );
+  }
+  w.family(
+    "nanocl_event_subscribers",
+    "gauge",
+    "Number of clients subscribed to the events",
+  );
+  w.sample(
+    "nanocl_event_subscribers",
+    &labels,
+    state.inner.event_emitter_raw.count(),
+  );
+  w.family(
+    "nanocl_event_backlog",
+    "gauge",
+    "Number of events waiting to be handled",
+  );
+  w.sample(
+    "nanocl_event_backlog",
+    &labels,
+    state
+      .inner
+      .event_backlog
+      .load(std::sync::atomic::Ordering::SeqCst),
+  );
+}
+
+/// Rows deleted by the pruning of the events and the metrics
+fn write_prune(node: &str, state: &SystemState, w: &mut MetricsWriter) {
+  w.family(
+    "nanocl_pruned_rows",
+    "counter",
+    "Rows deleted past their retention",
+  );
+  let stats = utils::retention::stats(state);
+  let mut tables = stats.total_deleted.into_iter().collect::<Vec<_>>();
+  tables.sort();
+  for (table, count) in tables {
+    w.sample(
+      "nanocl_pruned_rows_total",
+      &[("node", node), ("table", &table)],
+      count,
+    );
+  }
+}
+
+/// Latency histograms of the http handlers
+fn write_http(node: &str, state: &SystemState, w: &mut MetricsWriter) {
+  let name = "nanocl_http_request_duration_seconds";
+  w.family(name, "histogram", "Latency of the http handlers");
+  let mut histograms = state
+    .inner
+    .http_metrics
+    .snapshot()
+    .into_iter()
+    .collect::<Vec<_>>();
+  histograms.sort_by(|(a, _), (b, _)| a.cmp(b));
+  for ((method, route, status), histogram) in histograms {
+    let status = status.to_string();
+    let labels = [
+      ("node", node),
+      ("method", method.as_str()),
+      ("route", route.as_str()),
+      ("status", status.as_str()),
+    ];
+    let mut cumulative = 0;
+    for (bound, count) in HTTP_DURATION_BUCKETS.iter().zip(histogram.buckets) {
+      cumulative += count;
+      let bound = bound.to_string();
+      let mut bucket_labels = labels.to_vec();
+      bucket_labels.push(("le", &bound));
+      w.sample(&format!("{name}_bucket"), &bucket_labels, cumulative);
+    }
+    let mut bucket_labels = labels.to_vec();
+    bucket_labels.push(("le", "+Inf"));
+    w.sample(&format!("{name}_bucket"), &bucket_labels, histogram.count);
+    w.sample(&format!("{name}_count"), &labels, histogram.count);
+    w.sample(&format!("{name}_sum"), &labels, histogram.sum);
+  }
+}
+
+/// Render the metrics of the daemon in the OpenMetrics text format
+pub async fn render(state: &SystemState) -> IoResult<String> {
+  let node = state.config().hostname.clone();
+  let mut w = MetricsWriter::default();
+  write_node(&node, state, &mut w);
+  write_processes(&node, state, &mut w);
+  write_objects(state, &mut w).await?;
+  write_system(&node, state, &mut w).await;
+  write_prune(&node, state, &mut w);
+  write_http(&node, state, &mut w);
+  Ok(w.finish())
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::utils::tests::*;
+
+  #[ntex::test]
+  async fn render() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    client.send_get("/version", None::<String>).await;
+    let metrics = super::render(&system.state).await.unwrap();
+    assert!(metrics.ends_with("# EOF\n"));
+    assert!(metrics.contains("nanocl_tasks_pending{"));
+    assert!(metrics.contains("nanocl_event_subscribers{"));
+    assert!(metrics.contains("route=\"/{version}/version\""));
+  }
+}
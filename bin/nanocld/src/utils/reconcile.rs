@@ -0,0 +1,760 @@
+use std::collections::{HashMap, HashSet};
+
+use bollard_next::{
+  container::{
+    InspectContainerOptions, ListContainersOptions, RemoveContainerOptions,
+    StartContainerOptions, StopContainerOptions,
+  },
+  service::ContainerSummary,
+};
+use nanocl_error::io::{FromIo, IoResult};
+use nanocl_stubs::{
+  cargo::Cargo,
+  generic::{GenericClause, GenericFilter},
+  job::Job,
+  process::ProcessKind,
+  system::{
+    EventActor, EventActorKind, EventKind, NativeEventAction, ObjPsStatus,
+    ObjPsStatusKind,
+  },
+  vm::Vm,
+};
+
+use crate::{
+  models::{
+    CargoDb, JobDb, NodeDb, ObjPsStatusDb, ProcessDb, SystemState, VmDb,
+    VmImageDb,
+  },
+  repositories::generic::*,
+  utils::{self, container::cargo::Replicas},
+};
+
+/// Name of the lease electing the node recreating the objects without process
+const LEASE: &str = "reconcile";
+
+/// Containers of the runtime grouped by their kind and the key of their object
+type Instances = HashMap<(String, String), Vec<ContainerSummary>>;
+
+/// Nodes running the processes of the store grouped by their kind
+/// and the key of their object
+type Owners = HashMap<(String, String), HashSet<String>>;
+
+/// Name of a container without the leading slash
+fn instance_name(instance: &ContainerSummary) -> String {
+  instance
+    .names
+    .clone()
+    .unwrap_or_default()
+    .first()
+    .cloned()
+    .unwrap_or_default()
+    .trim_start_matches('/')
+    .to_owned()
+}
+
+/// Return true if the container is running or being restarted by the runtime
+fn is_running(instance: &ContainerSummary) -> bool {
+  matches!(instance.state.as_deref(), Some("running" | "restarting"))
+}
+
+/// Return true if the object has a task in progress
+/// or is in a transitional state that will be resolved by a task
+async fn is_busy(
+  kind: &EventActorKind,
+  key: &str,
+  status: &ObjPsStatus,
+  state: &SystemState,
+) -> bool {
+  let task_key = format!("{kind}@{key}");
+  if state.inner.task_manager.has_task(&task_key).await {
+    return true;
+  }
+  matches!(
+    status.actual,
+    ObjPsStatusKind::Starting
+      | ObjPsStatusKind::Stopping
+      | ObjPsStatusKind::Updating
+      | ObjPsStatusKind::Destroying
+  )
+}
+
+/// Emit a warning event describing a corrected drift
+fn emit_drift<A>(actor: &A, note: String, state: &SystemState)
+where
+  A: Into<EventActor> + Clone,
+{
+  log::warn!("reconcile: {note}");
+  let actor = actor.clone().into();
+  state.emit_action(
+    &actor,
+    NativeEventAction::Other("drift".to_owned()),
+    EventKind::Warning,
+    "reconcile",
+    Some(note),
+    None,
+  );
+}
+
+/// List the containers managed by nanocl
+/// Init containers and containers being replaced by an update are ignored
+async fn list_instances(state: &SystemState) -> IoResult<Instances> {
+  let options = Some(ListContainersOptions::<String> {
+    all: true,
+    filters: HashMap::from([(
+      "label".to_owned(),
+      vec!["io.nanocl".to_owned()],
+    )]),
+    ..Default::default()
+  });
+  let containers = state
+    .inner
+    .runtime
+    .list_containers(options)
+    .await
+    .map_err(|err| err.map_err_context(|| "Reconcile"))?;
+  let mut instances = Instances::new();
+  for container in containers {
+    let labels = container.labels.clone().unwrap_or_default();
+    if labels.contains_key("io.nanocl.init-c")
+      || instance_name(&container).starts_with("tmp-")
+    {
+      continue;
+    }
+    let Some(kind) = labels.get("io.nanocl.kind") else {
+      continue;
+    };
+    let key = match kind.as_str() {
+      "cargo" => labels.get("io.nanocl.c"),
+      "vm" => labels.get("io.nanocl.v"),
+      "job" => labels.get("io.nanocl.j"),
+      _ => None,
+    };
+    let Some(key) = key else {
+      continue;
+    };
+    instances
+      .entry((kind.to_owned(), key.to_owned()))
+      .or_default()
+      .push(container);
+  }
+  Ok(instances)
+}
+
+/// Remove the processes of this node from the store
+/// when their container doesn't exist anymore
+async fn reconcile_store(
+  instances: &Instances,
+  state: &SystemState,
+) -> IoResult<usize> {
+  let ids = instances
+    .values()
+    .flatten()
+    .filter_map(|instance| instance.id.clone())
+    .collect::<Vec<_>>();
+  let filter = GenericFilter::new()
+    .r#where(
+      "node_name",
+      GenericClause::Eq(state.config().hostname.clone()),
+    )
+    .r#where("key", GenericClause::NotIn(ids));
+  let processes = ProcessDb::read_by(&filter, &state.inner.pool).await?;
+  let mut count = 0;
+  for process in processes {
+    // Init and replaced containers aren't listed but still exist
+    if process.name.starts_with("init-") || process.name.starts_with("tmp-") {
+      continue;
+    }
+    let exists = state
+      .inner
+      .runtime
+      .inspect_container(&process.key, None::<InspectContainerOptions>)
+      .await
+      .is_ok();
+    if exists {
+      continue;
+    }
+    log::warn!(
+      "reconcile: process {} of {} is gone removing it from the store",
+      process.name,
+      process.kind_key
+    );
+    ProcessDb::del_by_pk(&process.key, &state.inner.pool).await?;
+    count += 1;
+  }
+  Ok(count)
+}
+
+/// List the nodes running the processes of each object
+async fn list_owners(state: &SystemState) -> IoResult<Owners> {
+  let processes =
+    ProcessDb::read_by(&GenericFilter::new(), &state.inner.pool).await?;
+  let mut owners = Owners::new();
+  for process in processes {
+    owners
+      .entry((process.kind, process.kind_key))
+      .or_default()
+      .insert(process.node_name);
+  }
+  Ok(owners)
+}
+
+/// Return true if this node manages the instances of an object running on a single node.
+/// The node running its processes owns it, when none exist the lease holder does.
+fn is_owner(
+  owners: &Owners,
+  kind: &ProcessKind,
+  key: &str,
+  is_leader: bool,
+  state: &SystemState,
+) -> bool {
+  match owners.get(&(kind.to_string(), key.to_owned())) {
+    Some(nodes) if !nodes.is_empty() => {
+      nodes.iter().all(|node| node == &state.config().hostname)
+    }
+    _ => is_leader,
+  }
+}
+
+/// Start the stopped instances of an object that should be running
+async fn start_stopped(
+  instances: &[ContainerSummary],
+  state: &SystemState,
+) -> IoResult<Vec<String>> {
+  let mut started = Vec::new();
+  for instance in instances.iter().filter(|instance| !is_running(instance)) {
+    let id = instance.id.clone().unwrap_or_default();
+    state
+      .inner
+      .runtime
+      .start_container(&id, None::<StartContainerOptions<String>>)
+      .await
+      .map_err(|err| err.map_err_context(|| "Reconcile"))?;
+    started.push(instance_name(instance));
+  }
+  Ok(started)
+}
+
+/// Stop the running instances of an object that should be stopped
+async fn stop_running(
+  instances: &[ContainerSummary],
+  state: &SystemState,
+) -> IoResult<Vec<String>> {
+  let mut stopped = Vec::new();
+  for instance in instances.iter().filter(|instance| is_running(instance)) {
+    let id = instance.id.clone().unwrap_or_default();
+    state
+      .inner
+      .runtime
+      .stop_container(&id, None::<StopContainerOptions>)
+      .await
+      .map_err(|err| err.map_err_context(|| "Reconcile"))?;
+    stopped.push(instance_name(instance));
+  }
+  Ok(stopped)
+}
+
+/// Ensure a cargo has the wanted number of instances in the wanted state
+async fn reconcile_cargo(
+  cargo: &Cargo,
+  wanted: usize,
+  instances: &[ContainerSummary],
+  state: &SystemState,
+) -> IoResult<usize> {
+  let key = &cargo.spec.cargo_key;
+  match cargo.status.wanted {
+    ObjPsStatusKind::Start => {}
+    ObjPsStatusKind::Stop => {
+      let stopped = stop_running(instances, state).await?;
+      if !stopped.is_empty() {
+        emit_drift(
+          cargo,
+          format!(
+            "Cargo {key} should be stopped, stopped {}",
+            stopped.join(", ")
+          ),
+          state,
+        );
+      }
+      return Ok(stopped.len());
+    }
+    _ => return Ok(0),
+  }
+  let mut count = 0;
+  if instances.len() > wanted {
+    // Stopped instances are removed first
+    let mut extra = instances.to_vec();
+    extra.sort_by_key(is_running);
+    let extra = extra
+      .into_iter()
+      .take(instances.len() - wanted)
+      .collect::<Vec<_>>();
+    for instance in &extra {
+      utils::container::process::delete_instance(
+        &instance.id.clone().unwrap_or_default(),
+        Some(RemoveContainerOptions {
+          force: true,
+          ..Default::default()
+        }),
+        state,
+      )
+      .await?;
+    }
+    emit_drift(
+      cargo,
+      format!(
+        "Cargo {key} has {} instances instead of {wanted}, removed {}",
+        instances.len(),
+        extra
+          .iter()
+          .map(instance_name)
+          .collect::<Vec<_>>()
+          .join(", ")
+      ),
+      state,
+    );
+    count += extra.len();
+    let ids = extra
+      .iter()
+      .filter_map(|instance| instance.id.clone())
+      .collect::<Vec<_>>();
+    let kept = instances
+      .iter()
+      .filter(|instance| {
+        !ids.contains(&instance.id.clone().unwrap_or_default())
+      })
+      .cloned()
+      .collect::<Vec<_>>();
+    count += start_cargo_stopped(cargo, &kept, state).await?;
+    return Ok(count);
+  }
+  if instances.len() < wanted {
+    let missing = wanted - instances.len();
+    let processes =
+      utils::container::cargo::create(cargo, missing, state).await?;
+    for process in &processes {
+      state
+        .inner
+        .runtime
+        .start_container(&process.key, None::<StartContainerOptions<String>>)
+        .await
+        .map_err(|err| err.map_err_context(|| "Reconcile"))?;
+    }
+    emit_drift(
+      cargo,
+      format!(
+        "Cargo {key} has {} instances instead of {wanted}, created {}",
+        instances.len(),
+        processes
+          .iter()
+          .map(|process| process.name.clone())
+          .collect::<Vec<_>>()
+          .join(", ")
+      ),
+      state,
+    );
+    count += processes.len();
+  }
+  count += start_cargo_stopped(cargo, instances, state).await?;
+  Ok(count)
+}
+
+/// Start the stopped instances of a cargo and report them
+async fn start_cargo_stopped(
+  cargo: &Cargo,
+  instances: &[ContainerSummary],
+  state: &SystemState,
+) -> IoResult<usize> {
+  let started = start_stopped(instances, state).await?;
+  if !started.is_empty() {
+    emit_drift(
+      cargo,
+      format!(
+        "Cargo {} should be running, started {}",
+        cargo.spec.cargo_key,
+        started.join(", ")
+      ),
+      state,
+    );
+  }
+  Ok(started.len())
+}
+
+/// Ensure a vm has his instance in the wanted state
+async fn reconcile_vm(
+  vm: &Vm,
+  instances: &[ContainerSummary],
+  state: &SystemState,
+) -> IoResult<usize> {
+  let key = &vm.spec.vm_key;
+  match vm.status.wanted {
+    ObjPsStatusKind::Start if instances.is_empty() => {
+      let image =
+        VmImageDb::read_by_pk(&vm.spec.disk.image, &state.inner.pool).await?;
+      let process =
+        utils::container::vm::create_vm_instance(vm, &image, true, state)
+          .await?;
+      state
+        .inner
+        .runtime
+        .start_container(&process.key, None::<StartContainerOptions<String>>)
+        .await
+        .map_err(|err| err.map_err_context(|| "Reconcile"))?;
+      emit_drift(
+        vm,
+        format!("Vm {key} has no instance, created {}", process.name),
+        state,
+      );
+      Ok(1)
+    }
+    ObjPsStatusKind::Start => {
+      let started = start_stopped(instances, state).await?;
+      if !started.is_empty() {
+        emit_drift(
+          vm,
+          format!("Vm {key} should be running, started {}", started.join(", ")),
+          state,
+        );
+      }
+      Ok(started.len())
+    }
+    ObjPsStatusKind::Stop => {
+      let stopped = stop_running(instances, state).await?;
+      if !stopped.is_empty() {
+        emit_drift(
+          vm,
+          format!("Vm {key} should be stopped, stopped {}", stopped.join(", ")),
+          state,
+        );
+      }
+      Ok(stopped.len())
+    }
+    _ => Ok(0),
+  }
+}
+
+/// Ensure a job isn't running when stopped
+/// and isn't left running when his instances are done or gone
+async fn reconcile_job(
+  job: &Job,
+  instances: &[ContainerSummary],
+  state: &SystemState,
+) -> IoResult<usize> {
+  if job.status.wanted == ObjPsStatusKind::Stop {
+    let stopped = stop_running(instances, state).await?;
+    if !stopped.is_empty() {
+      emit_drift(
+        job,
+        format!(
+          "Job {} should be stopped, stopped {}",
+          job.name,
+          stopped.join(", ")
+        ),
+        state,
+      );
+    }
+    return Ok(stopped.len());
+  }
+  if job.status.actual != ObjPsStatusKind::Start
+    || instances.iter().any(is_running)
+  {
+    return Ok(0);
+  }
+  // The job is marked as running but none of his instances are
+  // the die event was missed or the instances were removed
+  let mut failed = instances.len() < job.containers.len();
+  for instance in instances {
+    let id = instance.id.clone().unwrap_or_default();
+    let inspect = state
+      .inner
+      .runtime
+      .inspect_container(&id, None::<InspectContainerOptions>)
+      .await
+      .map_err(|err| err.map_err_context(|| "Reconcile"))?;
+    let exit_code = inspect.state.and_then(|state| state.exit_code);
+    if exit_code != Some(0) {
+      failed = true;
+    }
+  }
+  let (status, action) = if failed {
+    (ObjPsStatusKind::Fail, NativeEventAction::Fail)
+  } else {
+    (ObjPsStatusKind::Finish, NativeEventAction::Finish)
+  };
+  ObjPsStatusDb::update_actual_status(&job.name, &status, &state.inner.pool)
+    .await?;
+  emit_drift(
+    job,
+    format!(
+      "Job {} is running without running instances, marked as {status}",
+      job.name
+    ),
+    state,
+  );
+  state.emit_normal_native_action(job, action);
+  Ok(1)
+}
+
+/// Compare the wanted state of cargoes, vms and jobs with their processes
+/// and repair the drifts by creating, starting, stopping or removing instances.
+/// A warning event is emitted for each corrected drift.
+/// The store is shared so a node only repairs the objects it owns:
+/// the objects with processes on this node, its share of the cargoes replicated by node,
+/// and the objects without processes when it holds the lease.
+/// Objects with a task in progress are left untouched.
+/// Return the number of corrections.
+pub async fn reconcile(state: &SystemState) -> IoResult<usize> {
+  let hostname = state.config().hostname.clone();
+  let mut instances = list_instances(state).await?;
+  let mut count = reconcile_store(&instances, state).await?;
+  let owners = list_owners(state).await?;
+  // The lease outlives a few intervals so the leader keeps it
+  let ttl = state.config().reconcile.interval.max(1).saturating_mul(3);
+  let is_leader =
+    NodeDb::acquire_lease(LEASE, &hostname, ttl, &state.inner.pool).await?;
+  let filter = GenericFilter::new();
+  let cargoes = CargoDb::transform_read_by(&filter, &state.inner.pool).await?;
+  for cargo in cargoes {
+    let key = cargo.spec.cargo_key.clone();
+    let current = instances
+      .remove(&(ProcessKind::Cargo.to_string(), key.clone()))
+      .unwrap_or_default();
+    let wanted = match utils::container::cargo::replicas(
+      &cargo.spec.replication,
+      &hostname,
+    ) {
+      Some(Replicas::PerNode(number)) => number,
+      Some(Replicas::Single(number))
+        if is_owner(&owners, &ProcessKind::Cargo, &key, is_leader, state) =>
+      {
+        number
+      }
+      _ => continue,
+    };
+    if is_busy(&EventActorKind::Cargo, &key, &cargo.status, state).await {
+      continue;
+    }
+    match reconcile_cargo(&cargo, wanted, &current, state).await {
+      Ok(corrected) => count += corrected,
+      Err(err) => log::warn!("reconcile: cargo {key} {err}"),
+    }
+  }
+  let vms = VmDb::transform_read_by(&filter, &state.inner.pool).await?;
+  for vm in vms {
+    let key = vm.spec.vm_key.clone();
+    let current = instances
+      .remove(&(ProcessKind::Vm.to_string(), key.clone()))
+      .unwrap_or_default();
+    if !is_owner(&owners, &ProcessKind::Vm, &key, is_leader, state)
+      || is_busy(&EventActorKind::Vm, &key, &vm.status, state).await
+    {
+      continue;
+    }
+    match reconcile_vm(&vm, &current, state).await {
+      Ok(corrected) => count += corrected,
+      Err(err) => log::warn!("reconcile: vm {key} {err}"),
+    }
+  }
+  let jobs = JobDb::transform_read_by(&filter, &state.inner.pool).await?;
+  for job in jobs {
+    let current = instances
+      .remove(&(ProcessKind::Job.to_string(), job.name.clone()))
+      .unwrap_or_default();
+    if !is_owner(&owners, &ProcessKind::Job, &job.name, is_leader, state)
+      || is_busy(&EventActorKind::Job, &job.name, &job.status, state).await
+    {
+      continue;
+    }
+    match reconcile_job(&job, &current, state).await {
+      Ok(corrected) => count += corrected,
+      Err(err) => log::warn!("reconcile: job {} {err}", job.name),
+    }
+  }
+  Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+  use ntex::http;
+
+  use nanocl_stubs::{
+    cargo::CargoDeleteQuery, cargo_spec::CargoSpecPartial,
+    generic::ImagePullPolicy, process::ProcessPartial,
+  };
+
+  use super::*;
+  use crate::{runtime::ContainerRuntime, utils::tests::*};
+
+  const CARGO_KEY: &str = "reconcile-cargo.global";
+
+  async fn list_cargo_ids(
+    key: &str,
+    runtime: &dyn ContainerRuntime,
+  ) -> Vec<String> {
+    let options = Some(ListContainersOptions {
+      filters: HashMap::from([(
+        "label".to_owned(),
+        vec![format!("io.nanocl.c={key}")],
+      )]),
+      ..Default::default()
+    });
+    runtime
+      .list_containers(options)
+      .await
+      .unwrap()
+      .into_iter()
+      .filter_map(|container| container.id)
+      .collect()
+  }
+
+  #[ntex::test]
+  async fn recreate_removed_instance() {
+    let (system, runtime) = gen_fake_test_system().await;
+    let client = system.client;
+    let res = client
+      .send_post(
+        "/cargoes",
+        Some(&CargoSpecPartial {
+          name: "reconcile-cargo".to_owned(),
+          container: bollard_next::container::Config {
+            image: Some("fake:1.0".to_owned()),
+            ..Default::default()
+          },
+          image_pull_policy: Some(ImagePullPolicy::Never),
+          ..Default::default()
+        }),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "create");
+    let res = client
+      .send_post(
+        "/processes/cargo/reconcile-cargo/start",
+        None::<String>,
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "start");
+    let mut ids = Vec::new();
+    for _ in 0..10 {
+      ids = list_cargo_ids(CARGO_KEY, &runtime).await;
+      if !ids.is_empty() {
+        break;
+      }
+      ntex::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+    assert_eq!(ids.len(), 1, "Expect the cargo to be running");
+    let task_key = format!("{}@{CARGO_KEY}", EventActorKind::Cargo);
+    system.state.inner.task_manager.wait_task(&task_key).await;
+    // Remove the instance behind the back of the daemon
+    runtime
+      .remove_container(
+        &ids[0],
+        Some(RemoveContainerOptions {
+          force: true,
+          ..Default::default()
+        }),
+      )
+      .await
+      .unwrap();
+    let count = reconcile(&system.state).await.unwrap();
+    assert!(count >= 1, "Expect the drift to be corrected");
+    let new_ids = list_cargo_ids(CARGO_KEY, &runtime).await;
+    assert_eq!(new_ids.len(), 1, "Expect the instance to be recreated");
+    assert_ne!(new_ids[0], ids[0]);
+    let res = client
+      .send_delete(
+        "/cargoes/reconcile-cargo",
+        Some(CargoDeleteQuery {
+          force: Some(true),
+          ..Default::default()
+        }),
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "delete");
+    system.state.wait_event_loop().await;
+  }
+  #[ntex::test]
+  async fn skip_remote_instance() {
+    const KEY: &str = "reconcile-remote.global";
+    let (system, runtime) = gen_fake_test_system().await;
+    let client = system.client;
+    let res = client
+      .send_post(
+        "/cargoes",
+        Some(&CargoSpecPartial {
+          name: "reconcile-remote".to_owned(),
+          container: bollard_next::container::Config {
+            image: Some("fake:1.0".to_owned()),
+            ..Default::default()
+          },
+          image_pull_policy: Some(ImagePullPolicy::Never),
+          ..Default::default()
+        }),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "create");
+    let res = client
+      .send_post(
+        "/processes/cargo/reconcile-remote/start",
+        None::<String>,
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "start");
+    let task_key = format!("{}@{KEY}", EventActorKind::Cargo);
+    system.state.inner.task_manager.wait_task(&task_key).await;
+    let ids = list_cargo_ids(KEY, &runtime).await;
+    assert_eq!(ids.len(), 1, "Expect the cargo to be running");
+    // The instance moves to another node sharing the store
+    runtime
+      .remove_container(
+        &ids[0],
+        Some(RemoveContainerOptions {
+          force: true,
+          ..Default::default()
+        }),
+      )
+      .await
+      .unwrap();
+    let remote = ProcessDb::create_from(
+      &ProcessPartial {
+        key: "reconcile-remote-instance".to_owned(),
+        name: "reconcile-remote-instance".to_owned(),
+        kind: ProcessKind::Cargo,
+        data: serde_json::json!({}),
+        node_name: "reconcile-other-node".to_owned(),
+        kind_key: KEY.to_owned(),
+        created_at: None,
+      },
+      &system.state.inner.pool,
+    )
+    .await
+    .unwrap();
+    reconcile(&system.state).await.unwrap();
+    let new_ids = list_cargo_ids(KEY, &runtime).await;
+    assert!(
+      new_ids.is_empty(),
+      "Expect the instance of the other node to be left alone"
+    );
+    let processes = ProcessDb::read_by_kind_key(KEY, &system.state.inner.pool)
+      .await
+      .unwrap();
+    assert_eq!(processes.len(), 1, "Expect only the remote process");
+    assert_eq!(processes[0].node_name, "reconcile-other-node");
+    ProcessDb::del_by_pk(&remote.key, &system.state.inner.pool)
+      .await
+      .unwrap();
+    let res = client
+      .send_delete(
+        "/cargoes/reconcile-remote",
+        Some(CargoDeleteQuery {
+          force: Some(true),
+          ..Default::default()
+        }),
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "delete");
+    system.state.wait_event_loop().await;
+  }
+}
@@ -0,0 +1,202 @@
+use nanocl_error::http::{HttpError, HttpResult};
+use nanocl_stubs::{
+  container_image::ResourceRegistryPolicy,
+  generic::{GenericClause, GenericFilter},
+  system::{EventActor, EventKind, EventPartial, NativeEventAction},
+};
+
+use crate::{
+  models::{ResourceDb, SystemState},
+  repositories::generic::*,
+  vars,
+};
+
+/// Match a value against a pattern where `*` matches any sequence of characters
+fn match_pattern(pattern: &str, value: &str) -> bool {
+  let parts = pattern.split('*').collect::<Vec<_>>();
+  if parts.len() == 1 {
+    return pattern == value;
+  }
+  let last = parts.len() - 1;
+  let mut rest = value;
+  for (index, part) in parts.iter().enumerate() {
+    if index == 0 {
+      let Some(stripped) = rest.strip_prefix(part) else {
+        return false;
+      };
+      rest = stripped;
+    } else if index == last {
+      return rest.ends_with(part);
+    } else {
+      let Some(pos) = rest.find(part) else {
+        return false;
+      };
+      rest = &rest[pos + part.len()..];
+    }
+  }
+  true
+}
+
+/// Expand an image reference with his registry and tag
+/// `nginx` become `docker.io/library/nginx:latest`
+fn full_reference(image: &str) -> String {
+  let has_registry = match image.split_once('/') {
+    Some((domain, _)) => {
+      domain.contains('.') || domain.contains(':') || domain == "localhost"
+    }
+    None => false,
+  };
+  let mut reference = if has_registry {
+    image.to_owned()
+  } else if image.contains('/') {
+    format!("docker.io/{image}")
+  } else {
+    format!("docker.io/library/{image}")
+  };
+  let name = reference.rsplit('/').next().unwrap_or_default();
+  if !name.contains(':') && !name.contains('@') {
+    reference.push_str(":latest");
+  }
+  reference
+}
+
+/// Return true if the image has no tag or the `latest` tag
+fn is_latest(image: &str) -> bool {
+  let name = image.rsplit('/').next().unwrap_or_default();
+  if name.contains('@') {
+    return false;
+  }
+  match name.split_once(':') {
+    None => true,
+    Some((_, tag)) => tag == "latest",
+  }
+}
+
+/// Return true if one of the patterns match the image as written or his full reference
+fn match_any(patterns: &[String], image: &str) -> bool {
+  let reference = full_reference(image);
+  patterns.iter().any(|pattern| {
+    match_pattern(pattern, image) || match_pattern(pattern, &reference)
+  })
+}
+
+/// Get the reason why an image is rejected by a policy if any
+fn get_violation(
+  policy: &ResourceRegistryPolicy,
+  image: &str,
+) -> Option<String> {
+  if policy.reject_latest.unwrap_or_default() && is_latest(image) {
+    return Some("the latest tag is not allowed".to_owned());
+  }
+  if let Some(deny) = &policy.deny {
+    if match_any(deny, image) {
+      return Some("the image is denied".to_owned());
+    }
+  }
+  if let Some(allow) = &policy.allow {
+    if !match_any(allow, image) {
+      return Some(format!("the image must match one of {}", allow.join(", ")));
+    }
+  }
+  None
+}
+
+/// Ensure the images respect the registry policies of the namespace.
+/// A rejection is a bad request unless the policy is in audit mode
+/// where a warning event is emitted instead.
+pub async fn check_images(
+  namespace: &str,
+  images: &[String],
+  actor: &EventActor,
+  state: &SystemState,
+) -> HttpResult<()> {
+  if images.is_empty() {
+    return Ok(());
+  }
+  let filter = GenericFilter::new()
+    .r#where(
+      "kind",
+      GenericClause::Eq(vars::REGISTRY_POLICY_KIND.to_owned()),
+    )
+    .r#where(
+      "data",
+      GenericClause::Contains(serde_json::json!({
+        "Namespace": namespace
+      })),
+    );
+  let resources =
+    ResourceDb::transform_read_by(&filter, &state.inner.pool).await?;
+  for resource in resources {
+    let Ok(policy) = serde_json::from_value::<ResourceRegistryPolicy>(
+      resource.spec.data.clone(),
+    ) else {
+      continue;
+    };
+    for image in images {
+      let Some(reason) = get_violation(&policy, image) else {
+        continue;
+      };
+      let note = format!(
+        "Image {image} is rejected by registry policy {}: {reason}",
+        resource.spec.resource_key
+      );
+      if !policy.audit.unwrap_or_default() {
+        return Err(HttpError::bad_request(note));
+      }
+      state.spawn_emit_event(EventPartial {
+        reporting_controller: vars::CONTROLLER_NAME.to_owned(),
+        reporting_node: state.config().hostname.clone(),
+        action: NativeEventAction::Other("policy_violation".to_owned())
+          .to_string(),
+        reason: "registry_policy".to_owned(),
+        kind: EventKind::Warning,
+        actor: Some(actor.clone()),
+        related: Some(resource.clone().into()),
+        metadata: Some(serde_json::json!({
+          "Image": image,
+        })),
+        note: Some(note),
+      });
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn pattern() {
+    assert!(match_pattern(
+      "registry.internal/*",
+      "registry.internal/app:1"
+    ));
+    assert!(!match_pattern("registry.internal/*", "docker.io/app:1"));
+    assert!(match_pattern("*/app:*", "registry.internal/app:1"));
+    assert!(match_pattern("nginx:1.25", "nginx:1.25"));
+    assert!(!match_pattern("nginx:*-alpine", "nginx:1.25"));
+  }
+
+  #[test]
+  fn violation() {
+    let policy = ResourceRegistryPolicy {
+      namespace: "prod".to_owned(),
+      allow: Some(vec!["registry.internal/*".to_owned()]),
+      deny: Some(vec!["registry.internal/legacy/*".to_owned()]),
+      reject_latest: Some(true),
+      audit: None,
+    };
+    assert!(get_violation(&policy, "registry.internal/app:1.0").is_none());
+    assert!(get_violation(&policy, "registry.internal/app").is_some());
+    assert!(get_violation(&policy, "registry.internal/app:latest").is_some());
+    assert!(get_violation(&policy, "registry.internal/legacy/app:1").is_some());
+    assert!(get_violation(&policy, "nginx:1.25").is_some());
+    let policy = ResourceRegistryPolicy {
+      allow: Some(vec!["docker.io/library/*".to_owned()]),
+      ..Default::default()
+    };
+    assert!(get_violation(&policy, "nginx").is_none());
+    assert!(get_violation(&policy, "ghcr.io/next-hat/nanocl:1").is_some());
+  }
+}
@@ -0,0 +1,130 @@
+use std::{collections::HashMap, future::Future, time::Instant};
+
+use nanocl_error::io::IoResult;
+use nanocl_stubs::system::PruneStats;
+
+use crate::models::{EventDb, EventDeadLetterDb, MetricDb, SystemState};
+
+/// Date before which the rows with a retention in seconds are pruned
+fn gen_created_before(retention: u64) -> chrono::NaiveDateTime {
+  let now = chrono::Utc::now().naive_utc();
+  i64::try_from(retention)
+    .ok()
+    .and_then(chrono::Duration::try_seconds)
+    .and_then(|retention| now.checked_sub_signed(retention))
+    .unwrap_or(chrono::NaiveDateTime::MIN)
+}
+
+/// Delete batches of rows until a batch isn't full
+/// to avoid long transactions on the store
+async fn prune_batches<F, Fut>(batch_size: u64, prune: F) -> IoResult<u64>
+where
+  F: Fn(i64) -> Fut,
+  Fut: Future<Output = IoResult<usize>>,
+{
+  let batch_size = batch_size.clamp(1, i64::MAX as u64);
+  let mut total = 0;
+  loop {
+    let deleted = prune(batch_size as i64).await? as u64;
+    total += deleted;
+    if deleted < batch_size {
+      break;
+    }
+  }
+  Ok(total)
+}
+
+/// Delete the events and the metrics past their retention
+/// Return the number of rows deleted by table
+async fn prune_tables(state: &SystemState) -> IoResult<HashMap<String, u64>> {
+  let config = state.config().retention.clone();
+  let pool = &state.inner.pool;
+  let mut deleted = HashMap::new();
+  let created_before = gen_created_before(config.events);
+  let count = prune_batches(config.batch_size, |batch_size| {
+    EventDb::prune(created_before, batch_size, pool)
+  })
+  .await?;
+  deleted.insert("events".to_owned(), count);
+  // Dead letters are kept as long as the events they failed to deliver
+  let count = prune_batches(config.batch_size, |batch_size| {
+    EventDeadLetterDb::prune(created_before, batch_size, pool)
+  })
+  .await?;
+  deleted.insert("event_dead_letters".to_owned(), count);
+  let mut metrics = 0;
+  for (kind, retention) in &config.metric_kinds {
+    let created_before = gen_created_before(*retention);
+    metrics += prune_batches(config.batch_size, |batch_size| {
+      MetricDb::prune(
+        Some(kind.clone()),
+        Vec::new(),
+        created_before,
+        batch_size,
+        pool,
+      )
+    })
+    .await?;
+  }
+  let excluded_kinds = config.metric_kinds.keys().cloned().collect::<Vec<_>>();
+  let created_before = gen_created_before(config.metrics);
+  metrics += prune_batches(config.batch_size, |batch_size| {
+    MetricDb::prune(
+      None,
+      excluded_kinds.clone(),
+      created_before,
+      batch_size,
+      pool,
+    )
+  })
+  .await?;
+  deleted.insert("metrics".to_owned(), metrics);
+  let count = prune_batches(config.batch_size, |batch_size| {
+    MetricDb::prune_rollups(batch_size, pool)
+  })
+  .await?;
+  deleted.insert("metric_rollups".to_owned(), count);
+  let count = prune_batches(config.batch_size, |batch_size| {
+    MetricDb::prune_http_rollups(batch_size, pool)
+  })
+  .await?;
+  deleted.insert("http_rollups".to_owned(), count);
+  Ok(deleted)
+}
+
+/// Prune the events and the metrics past their retention
+/// and update the prune statistics of the daemon
+pub async fn prune(state: &SystemState) -> IoResult<PruneStats> {
+  let start = Instant::now();
+  let res = prune_tables(state).await;
+  let Ok(mut stats) = state.inner.prune_stats.lock() else {
+    return res.map(|_| PruneStats::default());
+  };
+  stats.last_prune = Some(chrono::Utc::now().naive_utc());
+  stats.duration = start.elapsed().as_millis() as u64;
+  match &res {
+    Ok(deleted) => {
+      for (table, count) in deleted {
+        *stats.total_deleted.entry(table.clone()).or_default() += count;
+      }
+      stats.deleted.clone_from(deleted);
+      stats.error = None;
+    }
+    Err(err) => {
+      stats.deleted.clear();
+      stats.error = Some(err.to_string());
+    }
+  }
+  let stats = stats.clone();
+  res.map(|_| stats)
+}
+
+/// Statistics of the last prune
+pub fn stats(state: &SystemState) -> PruneStats {
+  state
+    .inner
+    .prune_stats
+    .lock()
+    .map(|stats| stats.clone())
+    .unwrap_or_default()
+}
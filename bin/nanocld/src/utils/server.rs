@@ -1,11 +1,29 @@
+use std::os::unix::fs::PermissionsExt;
+
 use ntex::web;
 use ntex_cors::Cors;
 use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod, SslVerifyMode};
 
+use nanocl_stubs::system::SslConfig;
 use nanocl_utils::ntex::middlewares;
 
 use crate::{models::SystemState, services, vars};
 
+/// Hash of the certificate files of the ssl config
+/// Used on reload to restart the listeners only when the certificates are renewed
+pub fn ssl_fingerprint(ssl: Option<&SslConfig>) -> Option<[u8; 32]> {
+  let ssl = ssl?;
+  let mut hasher = openssl::sha::Sha256::new();
+  for path in [&ssl.cert, &ssl.cert_key, &ssl.cert_ca]
+    .into_iter()
+    .flatten()
+  {
+    hasher.update(&std::fs::read(path).unwrap_or_default());
+    hasher.update(&[0]);
+  }
+  Some(hasher.finish())
+}
+
 /// This function will generate the HTTP server with the given configuration.
 /// It will also bind the server to the given address.
 /// The server will be returned.
@@ -24,6 +42,10 @@ pub async fn gen(
         web::types::PayloadConfig::new(20_000_000_000), // <- limit size of the payload
       )
       .wrap(Cors::new().finish())
+      .wrap(super::shutdown::ShutdownGuard::new(&daemon_state_ptr))
+      .wrap(super::prometheus::HttpMetricsRecorder::new(
+        &daemon_state_ptr,
+      ))
       .wrap(middlewares::Versioning::new(vars::VERSION).finish())
       .wrap(middlewares::SerializeError)
       // Default logger middleware
@@ -33,7 +55,7 @@ pub async fn gen(
       .configure(services::ntex_config)
       .default_service(web::route().to(services::unhandled))
   });
-  let config = daemon_state.inner.config.clone();
+  let config = daemon_state.config();
   let mut count = 0;
   let hosts = config.hosts.clone();
   let len = hosts.len();
@@ -48,6 +70,12 @@ pub async fn gen(
         }
         Ok(server) => server,
       };
+      // The socket is created again when the listeners are restarted
+      if let Err(err) =
+        std::fs::set_permissions(&addr, std::fs::Permissions::from_mode(0o770))
+      {
+        log::warn!("server::gen: {addr}: {err}");
+      }
     } else if host.starts_with("tcp://") {
       let addr = host.replace("tcp://", "");
       if let Some(ssl) = config.ssl.clone() {
@@ -104,7 +132,9 @@ pub async fn gen(
       "server::gen: swagger available at http://0.0.0.0:8585/explorer/"
     );
   }
-  server = server.workers(num_cpus::get());
+  daemon_state.set_ssl_fingerprint(ssl_fingerprint(config.ssl.as_ref()));
+  // Signals are handled by the daemon to shutdown gracefully
+  server = server.workers(num_cpus::get()).disable_signals();
   log::info!("server::gen: ready");
   Ok(server.run())
 }
@@ -193,6 +223,26 @@ mod tests {
     assert_config_err(args).await;
   }
 
+  /// Test that the listeners are restarted on reload only for new certificates
+  #[test]
+  fn ssl_fingerprint_change() {
+    assert_eq!(ssl_fingerprint(None), None);
+    let ssl = SslConfig {
+      cert: Some("../../tests/server.crt".to_owned()),
+      cert_key: Some("../../tests/server.key".to_owned()),
+      cert_ca: Some("../../tests/ca.crt".to_owned()),
+    };
+    let fingerprint = ssl_fingerprint(Some(&ssl));
+    assert!(fingerprint.is_some());
+    assert_eq!(fingerprint, ssl_fingerprint(Some(&ssl.clone())));
+    let renewed = SslConfig {
+      cert: Some("../../tests/client.crt".to_owned()),
+      cert_key: Some("../../tests/client.key".to_owned()),
+      ..ssl
+    };
+    assert_ne!(fingerprint, ssl_fingerprint(Some(&renewed)));
+  }
+
   #[ntex::test]
   async fn ssl_valid_client() {
     let args = init_test_config(vec![
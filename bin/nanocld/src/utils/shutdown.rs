@@ -0,0 +1,152 @@
+use std::time::Duration;
+
+use ntex::http::Method;
+use ntex::web::{Error, ErrorRenderer, HttpResponse, WebRequest, WebResponse};
+use ntex::{Middleware, Service, ServiceCtx};
+
+use nanocl_error::io::IoResult;
+use nanocl_stubs::system::{Event, EventKind, EventPartial};
+
+use crate::{
+  models::{EventDb, SystemState},
+  repositories::generic::*,
+  utils, vars,
+};
+
+/// Middleware refusing the requests mutating the state
+/// once the daemon started to shutdown
+pub struct ShutdownGuard {
+  state: SystemState,
+}
+
+impl ShutdownGuard {
+  pub fn new(state: &SystemState) -> Self {
+    Self {
+      state: state.clone(),
+    }
+  }
+}
+
+impl<S> Middleware<S> for ShutdownGuard {
+  type Service = ShutdownGuardMiddleware<S>;
+
+  fn create(&self, service: S) -> Self::Service {
+    ShutdownGuardMiddleware {
+      service,
+      state: self.state.clone(),
+    }
+  }
+}
+
+pub struct ShutdownGuardMiddleware<S> {
+  service: S,
+  state: SystemState,
+}
+
+impl<S, Err> Service<WebRequest<Err>> for ShutdownGuardMiddleware<S>
+where
+  S: Service<WebRequest<Err>, Response = WebResponse, Error = Error>,
+  Err: ErrorRenderer,
+{
+  type Response = WebResponse;
+  type Error = Error;
+
+  ntex::forward_ready!(service);
+
+  async fn call(
+    &self,
+    req: WebRequest<Err>,
+    ctx: ServiceCtx<'_, Self>,
+  ) -> Result<Self::Response, Self::Error> {
+    let is_read =
+      matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    if !is_read && self.state.is_shutting_down() {
+      let res = HttpResponse::ServiceUnavailable().json(&serde_json::json!({
+        "msg": "Daemon is shutting down",
+      }));
+      return Ok(req.into_response(res));
+    }
+    ctx.call(&self.service, req).await
+  }
+}
+
+/// Save the last event of the daemon and send it to the subscribers
+/// before closing their stream
+async fn close_subscribers(state: &SystemState) -> IoResult<()> {
+  let event = EventPartial {
+    reporting_controller: vars::CONTROLLER_NAME.to_owned(),
+    reporting_node: state.config().hostname.clone(),
+    kind: EventKind::Normal,
+    action: "shutdown".to_owned(),
+    reason: "shutdown".to_owned(),
+    note: Some(format!("Node {} is shutting down", state.config().hostname)),
+    metadata: None,
+    actor: None,
+    related: None,
+  };
+  let retention = state.config().retention.events;
+  let event: Event =
+    EventDb::create_try_from((event, retention), &state.inner.pool)
+      .await?
+      .try_into()?;
+  state.inner.event_emitter_raw.close(&event)?;
+  Ok(())
+}
+
+/// Shutdown the daemon gracefully:
+/// - Mutations are refused
+/// - Running tasks can finish until the shutdown timeout
+///   the others are kept in the store to be resumed at boot
+/// - Pending events are saved in the store
+/// - Webhook deliveries waiting for a retry are dead-lettered
+/// - Event subscribers receive a last event and are disconnected
+pub async fn graceful(state: &SystemState) {
+  if state
+    .inner
+    .shutting_down
+    .swap(true, std::sync::atomic::Ordering::SeqCst)
+  {
+    return;
+  }
+  let timeout = Duration::from_secs(state.config().shutdown_timeout);
+  log::info!("shutdown: draining tasks for {}s", timeout.as_secs());
+  let aborted = state.inner.task_manager.drain(timeout).await;
+  if aborted > 0 {
+    log::warn!("shutdown: {aborted} tasks will be resumed at next boot");
+  }
+  if !state.flush_events(Duration::from_secs(5)).await {
+    log::warn!("shutdown: some events were not saved");
+  }
+  if !utils::event_webhook::flush(state, Duration::from_secs(5)).await {
+    log::warn!("shutdown: some webhook deliveries were not dead-lettered");
+  }
+  if let Err(err) = close_subscribers(state).await {
+    log::warn!("shutdown: close subscribers {err}");
+  }
+  log::info!("shutdown: done");
+}
+
+#[cfg(test)]
+mod tests {
+  use ntex::http;
+
+  use crate::utils::tests::*;
+
+  #[ntex::test]
+  async fn refuse_mutations() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    super::graceful(&system.state).await;
+    assert!(system.state.is_shutting_down());
+    let res = client
+      .send_post("/cargoes", None::<String>, None::<String>)
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::SERVICE_UNAVAILABLE,
+      "create cargo while shutting down"
+    );
+    let res = client.send_get("/version", None::<String>).await;
+    test_status_code!(res.status(), http::StatusCode::OK, "get version");
+  }
+}
@@ -32,6 +32,18 @@ pub async fn create_pool(store_addr: &str) -> IoResult<Pool> {
   Ok(pool)
 }
 
+/// Date after which a row created at a date is pruned given his retention in seconds
+pub fn gen_expires_at(
+  created_at: chrono::NaiveDateTime,
+  retention: u64,
+) -> chrono::NaiveDateTime {
+  i64::try_from(retention)
+    .ok()
+    .and_then(chrono::Duration::try_seconds)
+    .and_then(|retention| created_at.checked_add_signed(retention))
+    .unwrap_or(chrono::NaiveDateTime::MAX)
+}
+
 /// Get connection from the connection pool for the store `cockroachdb`
 pub fn get_pool_conn(pool: &Pool) -> IoResult<DBConn> {
   let conn = match pool.get() {
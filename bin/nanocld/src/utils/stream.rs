@@ -1,9 +1,32 @@
-use futures::StreamExt;
-use ntex::util::Bytes;
+use std::{cell::RefCell, io, rc::Rc, time::Instant};
+
+use futures::{
+  future::ready,
+  stream::{self, LocalBoxStream},
+  StreamExt,
+};
+use ntex::{
+  chain,
+  channel::oneshot,
+  fn_service,
+  http::header,
+  rt,
+  service::{fn_factory_with_config, fn_shutdown, map_config},
+  util::{self, Bytes},
+  web, ws, Service,
+};
 use serde::Serialize;
 
 use nanocl_error::http::{HttpError, HttpResult};
 
+use crate::{models::WsConState, utils};
+
+/// Stream of json lines sent to the http clients
+pub type ByteStream = LocalBoxStream<'static, Result<Bytes, web::Error>>;
+
+/// Generate the id of a server-sent event from its json line
+pub type SseIdFn = fn(&[u8]) -> Option<String>;
+
 /// Transform a stream of items serializable in json into a stream of bytes
 pub fn transform_stream<I, T>(
   stream: impl StreamExt<Item = Result<I, impl std::error::Error>>,
@@ -27,3 +50,231 @@ where
     Ok(Bytes::from(item + "\r\n"))
   })
 }
+
+/// Transport of a stream asked by the headers of the request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamTransport {
+  /// Json lines in a chunked response
+  Raw,
+  /// Server-sent events when `Accept: text/event-stream` is set
+  Sse,
+  /// A text message by json line when the connection is upgraded to a websocket
+  Ws,
+}
+
+impl From<&web::HttpRequest> for StreamTransport {
+  fn from(req: &web::HttpRequest) -> Self {
+    let has_header = |name: header::HeaderName, value: &str| {
+      req
+        .headers()
+        .get(name)
+        .and_then(|header| header.to_str().ok())
+        .map(|header| header.to_ascii_lowercase().contains(value))
+        .unwrap_or_default()
+    };
+    if has_header(header::UPGRADE, "websocket") {
+      return Self::Ws;
+    }
+    if has_header(header::ACCEPT, "text/event-stream") {
+      return Self::Sse;
+    }
+    Self::Raw
+  }
+}
+
+/// Json lines of a chunk, a chunk without lines is a keep alive message
+fn split_lines(bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
+  bytes
+    .split(|byte| *byte == b'\n')
+    .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+    .filter(|line| !line.is_empty())
+}
+
+/// Convert a chunk of json lines to server-sent events,
+/// a keep alive message is sent as a comment
+fn to_sse(bytes: &[u8], gen_id: Option<SseIdFn>) -> Bytes {
+  let mut data = Vec::new();
+  for line in split_lines(bytes) {
+    if let Some(id) = gen_id.and_then(|gen_id| gen_id(line)) {
+      data.extend(format!("id: {id}\n").as_bytes());
+    }
+    data.extend(b"data: ");
+    data.extend(line);
+    data.extend(b"\n\n");
+  }
+  if data.is_empty() {
+    return Bytes::from_static(b": keep-alive\n\n");
+  }
+  Bytes::from(data)
+}
+
+/// Send the json lines of the stream as text messages until the stream ends
+/// or the connection is closed
+async fn forward_ws(
+  mut stream: ByteStream,
+  sink: ws::WsSink,
+  mut rx: oneshot::Receiver<()>,
+) {
+  loop {
+    let item = match util::select(stream.next(), &mut rx).await {
+      util::Either::Left(Some(item)) => item,
+      util::Either::Left(None) => break,
+      util::Either::Right(_) => return,
+    };
+    let bytes = match item {
+      Ok(bytes) => bytes,
+      Err(err) => {
+        let reason = (ws::CloseCode::Error, err.to_string()).into();
+        let _ = sink.send(ws::Message::Close(Some(reason))).await;
+        return;
+      }
+    };
+    for line in split_lines(&bytes) {
+      let msg =
+        ws::Message::Text(String::from_utf8_lossy(line).as_ref().into());
+      if sink.send(msg).await.is_err() {
+        return;
+      }
+    }
+  }
+  let _ = sink.send(ws::Message::Close(None)).await;
+}
+
+async fn ws_stream_service(
+  (stream, sink): (Option<ByteStream>, ws::WsSink),
+) -> Result<
+  impl Service<ws::Frame, Response = Option<ws::Message>, Error = io::Error>,
+  web::Error,
+> {
+  let stream = stream.unwrap_or_else(|| stream::empty().boxed_local());
+  // start heartbeat task
+  let con_state = Rc::new(RefCell::new(WsConState::new()));
+  let (hb_tx, hb_rx) = oneshot::channel();
+  rt::spawn(utils::ws::heartbeat(con_state.clone(), sink.clone(), hb_rx));
+  let (tx, rx) = oneshot::channel();
+  rt::spawn(forward_ws(stream, sink, rx));
+  // handler service for incoming websockets frames
+  let service = fn_service(move |frame| {
+    let item = match frame {
+      ws::Frame::Ping(msg) => {
+        con_state.borrow_mut().hb = Instant::now();
+        Some(ws::Message::Pong(msg))
+      }
+      // update heartbeat
+      ws::Frame::Pong(_) => {
+        con_state.borrow_mut().hb = Instant::now();
+        None
+      }
+      ws::Frame::Text(_) | ws::Frame::Binary(_) => None,
+      ws::Frame::Close(reason) => Some(ws::Message::Close(reason)),
+      _ => Some(ws::Message::Close(None)),
+    };
+    ready(Ok(item))
+  });
+  // handler service for shutdown notification that stop the tasks
+  let on_shutdown = fn_shutdown(move || {
+    let _ = hb_tx.send(());
+    let _ = tx.send(());
+  });
+  Ok(chain(service).and_then(on_shutdown))
+}
+
+/// Respond with the stream of json lines in the transport asked by the request,
+/// the `content_type` is used for the raw stream and
+/// `gen_id` gives the ids of the server-sent events
+pub async fn respond(
+  req: web::HttpRequest,
+  content_type: &str,
+  stream: ByteStream,
+  gen_id: Option<SseIdFn>,
+) -> Result<web::HttpResponse, web::Error> {
+  match StreamTransport::from(&req) {
+    StreamTransport::Raw => Ok(
+      web::HttpResponse::Ok()
+        .content_type(content_type)
+        .streaming(stream),
+    ),
+    StreamTransport::Sse => {
+      let stream = stream.map(move |item| match item {
+        Ok(bytes) => Ok::<_, web::Error>(to_sse(&bytes, gen_id)),
+        Err(err) => Ok(Bytes::from(format!("event: error\ndata: {err}\n\n"))),
+      });
+      Ok(
+        web::HttpResponse::Ok()
+          .content_type("text/event-stream")
+          .set_header(header::CACHE_CONTROL, "no-cache")
+          .streaming(stream),
+      )
+    }
+    StreamTransport::Ws => {
+      let stream = RefCell::new(Some(stream));
+      web::ws::start(
+        req,
+        // inject the stream to ws_stream_service factory
+        map_config(fn_factory_with_config(ws_stream_service), move |sink| {
+          (stream.borrow_mut().take(), sink)
+        }),
+      )
+      .await
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ntex::http::StatusCode;
+
+  use super::*;
+
+  #[ntex::test]
+  async fn ws_upgrade() {
+    let srv = web::test::server(|| {
+      web::App::new().route(
+        "/stream",
+        web::get().to(|req: web::HttpRequest| async move {
+          let stream = stream::iter([Ok(Bytes::from_static(
+            b"{\"Seq\":1}\n{\"Seq\":2}\r\n",
+          ))]);
+          respond(req, "application/json", stream.boxed_local(), None).await
+        }),
+      )
+    });
+    let con = srv.ws_at("/stream").await.unwrap();
+    assert_eq!(con.response().status(), StatusCode::SWITCHING_PROTOCOLS);
+    let rx = con.receiver();
+    let mut frames = Vec::new();
+    while let Some(frame) = rx.recv().await {
+      match frame.unwrap() {
+        ws::Frame::Ping(_) => continue,
+        ws::Frame::Close(_) => break,
+        frame => frames.push(frame),
+      }
+    }
+    assert_eq!(
+      frames,
+      vec![
+        ws::Frame::Text(Bytes::from_static(b"{\"Seq\":1}")),
+        ws::Frame::Text(Bytes::from_static(b"{\"Seq\":2}")),
+      ]
+    );
+  }
+
+  #[test]
+  fn sse() {
+    let gen_id: SseIdFn = |line| {
+      let value = serde_json::from_slice::<serde_json::Value>(line).ok()?;
+      value["Seq"].as_i64().map(|seq| seq.to_string())
+    };
+    let bytes = to_sse(b"{\"Seq\":1}\n{\"Seq\":2}\n", Some(gen_id));
+    assert_eq!(
+      bytes,
+      Bytes::from_static(
+        b"id: 1\ndata: {\"Seq\":1}\n\nid: 2\ndata: {\"Seq\":2}\n\n"
+      )
+    );
+    let bytes = to_sse(b"{\"Name\":\"a\"}\r\n", None);
+    assert_eq!(bytes, Bytes::from_static(b"data: {\"Name\":\"a\"}\n\n"));
+    let bytes = to_sse(b"", None);
+    assert_eq!(bytes, Bytes::from_static(b": keep-alive\n\n"));
+  }
+}
@@ -10,16 +10,19 @@ use bollard_next::{
 use nanocl_stubs::{
   cargo::Cargo,
   cargo_spec::CargoSpecPartial,
+  config::DaemonConfigReload,
   generic::{GenericClause, GenericFilter},
-  namespace::NamespacePartial,
+  namespace::{Namespace, NamespacePartial},
   process::ProcessPartial,
-  system::ObjPsStatusKind,
+  resource_kind::{ResourceKindPartial, ResourceKindSpec},
+  system::{NativeEventAction, ObjPsStatusKind},
 };
 
 use crate::{
+  config,
   models::{
     CargoDb, CargoObjCreateIn, NamespaceDb, ObjPsStatusDb, ObjPsStatusUpdate,
-    ProcessDb, ProcessUpdateDb, SystemState, VmImageDb,
+    ProcessDb, ProcessUpdateDb, ResourceKindDb, SpecDb, SystemState, VmImageDb,
   },
   objects::generic::ObjCreate,
   repositories::generic::*,
@@ -68,7 +71,7 @@ pub async fn sync_process(
         name: name.clone(),
         kind: kind.to_owned().try_into()?,
         data: container_instance_data.clone(),
-        node_name: state.inner.config.hostname.clone(),
+        node_name: state.config().hostname.clone(),
         kind_key: key.to_owned(),
         created_at: Some(
           chrono::NaiveDateTime::parse_from_str(
@@ -105,11 +108,167 @@ pub async fn register_namespace(
   let new_nsp = NamespacePartial {
     name: name.to_owned(),
     metadata: None,
+    network: None,
+    quota: None,
+    limit_range: None,
   };
   NamespaceDb::create_from(&new_nsp, &state.inner.pool).await?;
   Ok(())
 }
 
+/// Resume the cascading deletion of namespaces
+/// that were still in a destroying state when the daemon stopped.
+pub async fn resume_namespaces_deletion(state: &SystemState) -> IoResult<()> {
+  let filter = GenericFilter::new().r#where(
+    "status",
+    GenericClause::Eq(ObjPsStatusKind::Destroying.to_string()),
+  );
+  let namespaces = NamespaceDb::read_by(&filter, &state.inner.pool).await?;
+  for namespace in namespaces {
+    log::info!("system::resume_namespaces_deletion: {}", namespace.name);
+    let namespace: Namespace = namespace.into();
+    state
+      .emit_normal_native_action_sync(&namespace, NativeEventAction::Destroying)
+      .await;
+  }
+  Ok(())
+}
+
+/// Ensure existence of the resource kinds provided by nanocld itself.
+/// The `nanocl.io/network-policy` kind is used to allow traffic between isolated namespaces
+/// the `nanocl.io/registry-policy` kind to restrict the images of a namespace,
+/// the `nanocl.io/alert-rule` kind to fire alerts evaluated by the nodes
+/// and the `nanocl.io/event-webhook` kind to send the events to an url.
+pub async fn register_resource_kinds(state: &SystemState) -> IoResult<()> {
+  let version = format!("v{}", vars::VERSION);
+  let kinds = [
+    (
+      vars::NETWORK_POLICY_KIND,
+      serde_json::json!({
+        "type": "object",
+        "required": ["Namespace", "AllowFrom"],
+        "additionalProperties": false,
+        "properties": {
+          "Namespace": { "type": "string" },
+          "AllowFrom": {
+            "type": "array",
+            "items": { "type": "string" }
+          }
+        }
+      }),
+    ),
+    (
+      vars::REGISTRY_POLICY_KIND,
+      serde_json::json!({
+        "type": "object",
+        "required": ["Namespace"],
+        "additionalProperties": false,
+        "properties": {
+          "Namespace": { "type": "string" },
+          "Allow": {
+            "type": "array",
+            "items": { "type": "string" }
+          },
+          "Deny": {
+            "type": "array",
+            "items": { "type": "string" }
+          },
+          "RejectLatest": { "type": "boolean" },
+          "Audit": { "type": "boolean" }
+        }
+      }),
+    ),
+    (
+      vars::ALERT_RULE_KIND,
+      serde_json::json!({
+        "type": "object",
+        "required": ["Condition"],
+        "additionalProperties": false,
+        "properties": {
+          "Condition": {
+            "type": "object",
+            "minProperties": 1,
+            "maxProperties": 1,
+            "additionalProperties": false,
+            "properties": {
+              "Metric": {
+                "type": "object",
+                "required": ["Kind", "Field", "Operator", "Threshold"]
+              },
+              "Event": { "type": "object" },
+              "Process": {
+                "type": "object",
+                "required": ["Kind", "Name", "Status"]
+              }
+            }
+          },
+          "For": { "type": "integer", "minimum": 0 },
+          "Severity": {
+            "type": "string",
+            "enum": ["info", "warning", "critical"]
+          },
+          "Summary": { "type": "string" },
+          "Node": { "type": "string" },
+          "Sinks": {
+            "type": "array",
+            "items": {
+              "type": "object",
+              "minProperties": 1,
+              "maxProperties": 1,
+              "additionalProperties": false,
+              "properties": {
+                "Webhook": { "type": "string" },
+                "Unix": { "type": "string" },
+                "Exec": {
+                  "type": "array",
+                  "minItems": 1,
+                  "items": { "type": "string" }
+                }
+              }
+            }
+          }
+        }
+      }),
+    ),
+    (
+      vars::EVENT_WEBHOOK_KIND,
+      serde_json::json!({
+        "type": "object",
+        "required": ["Url"],
+        "additionalProperties": false,
+        "properties": {
+          "Url": { "type": "string", "pattern": "^https?://" },
+          "Conditions": {
+            "type": "array",
+            "items": { "type": "object" }
+          },
+          "Secret": { "type": "string" },
+          "MaxAttempts": { "type": "integer", "minimum": 1 }
+        }
+      }),
+    ),
+  ];
+  for (name, schema) in kinds {
+    if SpecDb::get_version(name, &version, &state.inner.pool)
+      .await
+      .is_ok()
+    {
+      continue;
+    }
+    let kind = ResourceKindPartial {
+      name: name.to_owned(),
+      version: version.clone(),
+      metadata: None,
+      data: ResourceKindSpec {
+        schema: Some(schema),
+        url: None,
+      },
+    };
+    ResourceKindDb::create_from_spec(&kind, &state.inner.pool).await?;
+  }
+  Ok(())
+}
+
 async fn sync_cargo_status(
   cargo: &Cargo,
   container: &ContainerInspectResponse,
@@ -143,13 +302,13 @@ async fn sync_cargo_status(
 /// We use it to be sure that all existing containers are registered as cargo.
 pub async fn sync_processes(state: &SystemState) -> IoResult<()> {
   log::info!("system::sync_processes: starting");
-  let options = Some(ListContainersOptions::<&str> {
+  let options = Some(ListContainersOptions::<String> {
     all: true,
     ..Default::default()
   });
   let containers = state
     .inner
-    .docker_api
+    .runtime
     .list_containers(options)
     .await
     .map_err(|err| err.map_err_context(|| "SyncInstance"))?;
@@ -172,7 +331,7 @@ pub async fn sync_processes(state: &SystemState) -> IoResult<()> {
     let id = container_summary.id.unwrap_or_default();
     let container = state
       .inner
-      .docker_api
+      .runtime
       .inspect_container(&id, None::<InspectContainerOptions>)
       .await
       .map_err(|err| err.map_err_context(|| "SyncInstance"))?;
@@ -243,7 +402,7 @@ pub async fn sync_processes(state: &SystemState) -> IoResult<()> {
     )
     .r#where(
       "node_name",
-      GenericClause::Eq(state.inner.config.hostname.clone()),
+      GenericClause::Eq(state.config().hostname.clone()),
     );
   ProcessDb::del_by(&filter, &state.inner.pool).await?;
   log::info!("system::sync_processes: done");
@@ -255,7 +414,7 @@ pub async fn sync_processes(state: &SystemState) -> IoResult<()> {
 pub async fn sync_vm_images(state: &SystemState) -> IoResult<()> {
   log::info!("system::sync_vm_images: start");
   let files =
-    std::fs::read_dir(format!("{}/vms/images", &state.inner.config.state_dir))?;
+    std::fs::read_dir(format!("{}/vms/images", &state.config().state_dir))?;
   for file in files {
     let file = file?;
     let file_name = file.file_name();
@@ -281,3 +440,39 @@ pub async fn sync_vm_images(state: &SystemState) -> IoResult<()> {
   log::info!("system::sync_vm_images: done");
   Ok(())
 }
+
+/// Read the config of the daemon again and apply the settings that can change live.
+/// The http listeners are restarted only when the hosts, the ssl config
+/// or the content of the certificates change.
+pub async fn reload_config(
+  state: &SystemState,
+) -> IoResult<DaemonConfigReload> {
+  let current = state.config();
+  let (new, mut reload) = config::reload(&current)?;
+  config::set_log_level(&new)?;
+  let fingerprint = utils::server::ssl_fingerprint(new.ssl.as_ref());
+  if fingerprint != state.ssl_fingerprint()
+    && !reload.applied.iter().any(|field| field == "ssl")
+  {
+    reload.applied.push("ssl".to_owned());
+  }
+  let restart_listeners = reload
+    .applied
+    .iter()
+    .any(|field| field == "hosts" || field == "ssl");
+  state.set_config(new);
+  log::info!(
+    "system::reload_config: applied {:?} restart required {:?}",
+    reload.applied,
+    reload.restart_required
+  );
+  if restart_listeners {
+    if let Some(server) = state.server() {
+      log::info!("system::reload_config: restarting http listeners");
+      ntex::rt::spawn(async move {
+        server.stop(true).await;
+      });
+    }
+  }
+  Ok(reload)
+}
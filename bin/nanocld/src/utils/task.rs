@@ -0,0 +1,116 @@
+use std::str::FromStr;
+
+use nanocl_error::{
+  http::{HttpError, HttpResult},
+  io::IoResult,
+};
+use nanocl_stubs::{
+  generic::{GenericClause, GenericFilter},
+  system::{EventActorKind, NativeEventAction, ObjPsStatusKind},
+  task::{Task, TaskStatus},
+};
+
+use crate::{
+  models::{ObjPsStatusDb, SystemState, TaskDb},
+  repositories::generic::*,
+  utils,
+};
+
+/// Undo the partial work of an interrupted task.
+/// An interrupted cargo update leaves his old instances renamed with a `tmp-` prefix,
+/// they are the instances still serving so they are restored
+/// and the instances created by the update are removed.
+async fn compensate(task: &Task, state: &SystemState) -> IoResult<()> {
+  let action = NativeEventAction::from_str(&task.action)?;
+  if task.actor.kind != EventActorKind::Cargo
+    || action != NativeEventAction::Updating
+  {
+    return Ok(());
+  }
+  let key = task.actor.key.clone().unwrap_or_default();
+  let restored = utils::container::cargo::rollback_update(&key, state).await?;
+  if restored > 0 {
+    log::info!("task::compensate: restored {restored} instances of {key}");
+  }
+  Ok(())
+}
+
+/// Resume the tasks of this node that were interrupted by a restart of the daemon.
+/// Running tasks are compensated if needed then every task is queued again.
+/// Namespace deletions are resumed from their status by `resume_namespaces_deletion`.
+pub async fn resume(state: &SystemState) -> IoResult<()> {
+  let filter = GenericFilter::new().r#where(
+    "node_name",
+    GenericClause::Eq(state.config().hostname.clone()),
+  );
+  let tasks = TaskDb::transform_read_by(&filter, &state.inner.pool).await?;
+  for task in tasks {
+    TaskDb::del_by_pk(&task.key, &state.inner.pool).await?;
+    if task.actor.kind == EventActorKind::Namespace {
+      continue;
+    }
+    let key = task.actor.key.clone().unwrap_or_default();
+    // The object was removed before the task was cleared
+    if ObjPsStatusDb::read_by_pk(&key, &state.inner.pool)
+      .await
+      .is_err()
+    {
+      continue;
+    }
+    log::info!("task::resume: {} {}", task.action, task.key);
+    if task.status == TaskStatus::Running {
+      if let Err(err) = compensate(&task, state).await {
+        log::warn!("task::resume: {} {err}", task.key);
+      }
+    }
+    let action = NativeEventAction::from_str(&task.action)?;
+    state
+      .inner
+      .task_manager
+      .push(&task.actor, action, state)
+      .await;
+  }
+  Ok(())
+}
+
+/// Cancel a task running or waiting on this node.
+/// The task is aborted, compensated and the object is marked as failed.
+pub async fn cancel(key: &str, state: &SystemState) -> HttpResult<()> {
+  let task = TaskDb::transform_read_by_pk(key, &state.inner.pool).await?;
+  if task.node_name != state.config().hostname {
+    return Err(HttpError::bad_request(format!(
+      "Task {key} is running on node {}",
+      task.node_name
+    )));
+  }
+  if !state.inner.task_manager.cancel_task(key, state).await {
+    return Err(HttpError::not_found(format!("Task {key} not found")));
+  }
+  if task.status == TaskStatus::Running {
+    compensate(&task, state).await?;
+  }
+  let action = NativeEventAction::from_str(&task.action)
+    .map_err(|err| HttpError::internal_server_error(err.to_string()))?;
+  let action = match action {
+    NativeEventAction::Starting => NativeEventAction::Start,
+    NativeEventAction::Stopping => NativeEventAction::Stop,
+    NativeEventAction::Updating => NativeEventAction::Update,
+    NativeEventAction::Destroying => NativeEventAction::Destroy,
+    action => action,
+  };
+  let object_key = task.actor.key.clone().unwrap_or_default();
+  if task.actor.kind != EventActorKind::Namespace {
+    ObjPsStatusDb::update_actual_status(
+      &object_key,
+      &ObjPsStatusKind::Fail,
+      &state.inner.pool,
+    )
+    .await?;
+  }
+  state.emit_error_native_action(
+    &task.actor,
+    action,
+    Some(format!("Task {key} cancelled")),
+  );
+  Ok(())
+}
@@ -71,7 +71,7 @@ pub async fn create_snap(
   }
   let img_path = image.path.clone();
   let snapshot_path =
-    format!("{}/vms/images/{}.img", state.inner.config.state_dir, name);
+    format!("{}/vms/images/{}.img", state.config().state_dir, name);
   let output = Command::new("qemu-img")
     .args([
       "create",
@@ -113,7 +113,7 @@ pub async fn create_snap(
   let img_info = get_info(&snapshot_path).await?;
   let snap_image = VmImageDb {
     name: name.to_owned(),
-    node_name: state.inner.config.hostname.clone(),
+    node_name: state.config().hostname.clone(),
     created_at: chrono::Utc::now().naive_utc(),
     kind: "Snapshot".into(),
     path: snapshot_path.clone(),
@@ -127,6 +127,62 @@ pub async fn create_snap(
   Ok(snap_image)
 }
 
+/// Freeze the current content of a vm disk into a new `Base` image.
+/// The disk file is moved to the base image and the disk is recreated
+/// as a snapshot of it with `create_snap`, so the vm using the disk is unchanged
+/// and new snapshots of the base image don't see the next writes of the vm.
+pub async fn freeze(
+  name: &str,
+  size: u64,
+  disk: &VmImageDb,
+  state: &SystemState,
+) -> HttpResult<VmImageDb> {
+  if VmImageDb::read_by_pk(name, &state.inner.pool).await.is_ok() {
+    return Err(HttpError::conflict(format!("Vm image {name} already used")));
+  }
+  let children =
+    VmImageDb::read_by_parent(&disk.name, &state.inner.pool).await?;
+  if !children.is_empty() {
+    return Err(HttpError::conflict(format!(
+      "Vm image {} has children images and can't be frozen",
+      disk.name
+    )));
+  }
+  let base_path =
+    format!("{}/vms/images/{}.img", state.config().state_dir, name);
+  let base = VmImageDb {
+    name: name.to_owned(),
+    created_at: chrono::Utc::now().naive_utc(),
+    kind: "Base".into(),
+    path: base_path.clone(),
+    ..disk.clone()
+  };
+  let base = VmImageDb::create_from(base, &state.inner.pool).await?;
+  let pool = &state.inner.pool;
+  if let Err(err) = VmImageDb::del_by_pk(&disk.name, pool).await {
+    VmImageDb::del_by_pk(name, pool).await?;
+    return Err(err.into());
+  }
+  let res = match fs::rename(&disk.path, &base_path).await {
+    Err(err) => Err(HttpError::internal_server_error(format!(
+      "Failed to freeze {}: {err}",
+      disk.name
+    ))),
+    Ok(_) => match create_snap(&disk.name, size, &base, state).await {
+      Err(err) => {
+        let _ = fs::rename(&base_path, &disk.path).await;
+        Err(err)
+      }
+      Ok(_) => Ok(base),
+    },
+  };
+  if res.is_err() {
+    VmImageDb::create_from(disk.clone(), pool).await?;
+    VmImageDb::del_by_pk(name, pool).await?;
+  }
+  res
+}
+
 /// Clone a vm image snapshot from a `Snapshot` vm image.
 /// The snapshot is created using qemu-img create command using the `Snapshot` image.
 /// The created clone is a qcow2 image. Stored in the state directory and added to the database.
@@ -147,7 +203,7 @@ pub async fn clone(
   let (tx, rx) = ntex::channel::mpsc::channel::<HttpResult<Bytes>>();
   let name = name.to_owned();
   let image = image.clone();
-  let daemon_conf = state.inner.config.clone();
+  let daemon_conf = state.config().clone();
   let pool = state.inner.pool.clone();
   rt::spawn(async move {
     let img_path = image.path.clone();
@@ -327,7 +383,7 @@ pub async fn create(
   };
   let vm_image = VmImageDb {
     name: name.to_owned(),
-    node_name: state.inner.config.hostname.clone(),
+    node_name: state.config().hostname.clone(),
     created_at: chrono::Utc::now().naive_utc(),
     kind: "Base".into(),
     format: img_info.format,
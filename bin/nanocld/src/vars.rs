@@ -17,3 +17,23 @@ pub const CHANNEL: &str = env!("CHANNEL");
 pub const CONTROLLER_NAME: &str = "nanocl.io/core";
 /// Default Virtual Machine runtime
 pub const VM_RUNTIME: &str = "ghcr.io/next-hat/nanocl-qemu:8.0.2.0";
+/// Default network where processes are attached
+pub const DEFAULT_NETWORK: &str = "nanoclbr0";
+/// Resource kind used to allow traffic between isolated namespaces
+pub const NETWORK_POLICY_KIND: &str = "nanocl.io/network-policy";
+/// Resource kind used to restrict the container images of a namespace
+pub const REGISTRY_POLICY_KIND: &str = "nanocl.io/registry-policy";
+/// Resource kind of the alerting rules evaluated by the daemon
+pub const ALERT_RULE_KIND: &str = "nanocl.io/alert-rule";
+/// Resource kind of the webhooks receiving the events of the daemon
+pub const EVENT_WEBHOOK_KIND: &str = "nanocl.io/event-webhook";
+/// Secret kind of the keys signing the events sent to the webhooks
+pub const WEBHOOK_SECRET_KIND: &str = "nanocl.io/webhook-secret";
+/// Namespace used when none is specified
+pub const DEFAULT_NAMESPACE: &str = "global";
+/// Resource kind of the proxy rules managed by ncproxy
+pub const PROXY_RULE_KIND: &str = "ncproxy.io/rule";
+/// Resource kind of the dns rules managed by ncdns
+pub const DNS_RULE_KIND: &str = "ncdns.io/rule";
+/// Metric kind of the resource usage of the processes sampled by the daemon
+pub const PROCESS_STATS_KIND: &str = "nanocl.io/process-stats";
@@ -32,7 +32,34 @@ async fn get_bridge_addr(client: &NanocldClient) -> IoResult<String> {
   Ok(network.gateway.clone().unwrap_or_default())
 }
 
+/// Get address of the network of an isolated namespace
+async fn get_namespace_addr(
+  namespace: &str,
+  client: &NanocldClient,
+) -> IoResult<String> {
+  let namespace = client.inspect_namespace(namespace).await.map_err(|err| {
+    err.map_err_context(|| format!("Unable to inspect namespace {namespace}"))
+  })?;
+  let Some(network) = namespace.network else {
+    return Err(IoError::invalid_input(
+      "Network",
+      &format!("{} is not supported", namespace.name),
+    ));
+  };
+  let ipam = network.ipam.unwrap_or_default();
+  let ipam_config = ipam.config.unwrap_or_default();
+  let Some(config) = ipam_config.first() else {
+    return Err(IoError::invalid_data(
+      "Network",
+      &format!("No network found for {}", namespace.name),
+    ));
+  };
+  Ok(config.gateway.clone().unwrap_or_default())
+}
+
 /// Get network address of given network
+/// Any other value than `Local`, `Public` or `Internal`
+/// is the name of an isolated namespace
 async fn get_network_addr(
   network: &str,
   client: &NanocldClient,
@@ -41,12 +68,7 @@ async fn get_network_addr(
     "Local" => "127.0.0.1".to_owned(),
     "Public" => get_host_addr(client).await?,
     "Internal" => get_bridge_addr(client).await?,
-    _ => {
-      return Err(IoError::invalid_input(
-        "Network",
-        &format!("{network} is not supported"),
-      ))
-    }
+    _ => get_namespace_addr(network, client).await?,
   };
   Ok(addr)
 }
@@ -94,7 +116,11 @@ pub(crate) async fn update_entries(
     let ip_address = match entry.ip_address.as_str() {
       "Public" => get_host_addr(client).await?,
       "Private" => "127.0.0.1".into(),
-      "Internal" => get_bridge_addr(client).await?,
+      "Internal" => match dns_rule.network.as_str() {
+        "Local" | "Public" | "Internal" => get_bridge_addr(client).await?,
+        // Entries of an isolated namespace resolve to its own network
+        namespace => get_namespace_addr(namespace, client).await?,
+      },
       _ => entry.ip_address.clone(),
     };
     let entry = &format!("address=/{}/{}", entry.name, ip_address);
@@ -6,6 +6,8 @@ use nanocl_error::io::{IoError, IoResult};
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LocationTemplate {
   pub path: String,
+  /// Path of the location written in the access logs
+  pub label: String,
   pub upstream_key: String,
   pub upstream_path: String,
   pub redirect: Option<String>,
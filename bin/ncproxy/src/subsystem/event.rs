@@ -157,6 +157,8 @@ async fn r#loop(state: &SystemStateRef) {
           log::warn!("event::loop: {err}");
           continue;
         }
+        // The watch resume after the last event received when the connection is lost
+        // so the rules are only synced when subscribing
         let _ = utils::nginx::ensure_conf(state).await;
         log::info!("event::loop: subscribed to nanocld events");
         while let Some(event) = stream.next().await {
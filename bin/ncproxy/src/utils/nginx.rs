@@ -95,6 +95,12 @@ pub async fn reload(client: &NanocldClient) -> IoResult<()> {
   Ok(())
 }
 
+/// Path of a location usable in a quoted nginx string,
+/// the characters interpolated or ending the string are removed
+fn gen_location_label(path: &str) -> String {
+  path.replace(['"', '$', '\\'], "")
+}
+
 pub async fn add_rule(
   name: &str,
   rule: &ResourceProxyRule,
@@ -198,6 +204,7 @@ pub async fn add_rule(
               };
               let location = LocationTemplate {
                 path: location.path.clone(),
+                label: gen_location_label(&location.path),
                 limit_req: location.limit_req.clone(),
                 upstream_key: format!("http://{upstream_key}"),
                 redirect: None,
@@ -218,6 +225,7 @@ pub async fn add_rule(
               .await?;
               let location = LocationTemplate {
                 path: location.path.clone(),
+                label: gen_location_label(&location.path),
                 upstream_key: format!("http://{upstream_key}"),
                 redirect: None,
                 limit_req: location.limit_req.clone(),
@@ -232,6 +240,7 @@ pub async fn add_rule(
             LocationTarget::Http(http) => {
               let location = LocationTemplate {
                 path: location.path.clone(),
+                label: gen_location_label(&location.path),
                 upstream_key: http.url.clone(),
                 limit_req: location.limit_req.clone(),
                 version: location.version,
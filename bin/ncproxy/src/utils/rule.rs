@@ -41,6 +41,22 @@ async fn get_bridge_addr(client: &NanocldClient) -> IoResult<String> {
   Ok(network.gateway.clone().unwrap_or_default())
 }
 
+/// Get the network where the processes of a namespace are attached
+/// It's the namespace network when isolated or nanoclbr0
+async fn get_namespace_network(
+  client: &NanocldClient,
+  namespace: &str,
+) -> IoResult<String> {
+  let namespace = client.inspect_namespace(namespace).await.map_err(|err| {
+    err.map_err_context(|| format!("Unable to inspect namespace {namespace}"))
+  })?;
+  let network = namespace
+    .network
+    .and_then(|network| network.name)
+    .unwrap_or("nanoclbr0".to_owned());
+  Ok(network)
+}
+
 fn parse_upstream_target(key: &str) -> IoResult<(String, String, String)> {
   let info = key.split('.').collect::<Vec<&str>>();
   if info.len() < 3 {
@@ -155,6 +171,7 @@ pub async fn gen_upstream(
   let (target_name, target_namespace, target_kind) =
     parse_upstream_target(&target.key)?;
   let port = target.port;
+  let network = get_namespace_network(&state.client, &target_namespace).await?;
   let (key, content) = match target_kind.as_str() {
     "c" => {
       let cargo = state
@@ -166,7 +183,7 @@ pub async fn gen_upstream(
             format!("Unable to inspect cargo {target_name}")
           })
         })?;
-      let addresses = get_addresses(&cargo.instances, "nanoclbr0").await?;
+      let addresses = get_addresses(&cargo.instances, &network).await?;
       let key = format!("{}-{}-cargo", cargo.spec.cargo_key, port);
       let data = UPSTREAM_TEMPLATE.compile(&liquid::object!({
         "key": key,
@@ -183,7 +200,7 @@ pub async fn gen_upstream(
         .map_err(|err| {
           err.map_err_context(|| format!("Unable to inspect vm {target_name}"))
         })?;
-      let addresses = get_addresses(&vm.instances, "nanoclbr0").await?;
+      let addresses = get_addresses(&vm.instances, &network).await?;
       let key = format!("{}-{}-vm", vm.spec.vm_key, port);
       let data = UPSTREAM_TEMPLATE.compile(&liquid::object!({
         "key": key,
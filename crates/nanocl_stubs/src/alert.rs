@@ -0,0 +1,285 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  metric::MetricAggregate, process::ProcessKind, system::ObjPsStatusKind,
+};
+
+/// Severity of an alert
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum AlertSeverity {
+  Info,
+  #[default]
+  Warning,
+  Critical,
+}
+
+impl std::fmt::Display for AlertSeverity {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Info => write!(f, "info"),
+      Self::Warning => write!(f, "warning"),
+      Self::Critical => write!(f, "critical"),
+    }
+  }
+}
+
+/// Comparison of the value of a metric with the threshold
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum AlertOperator {
+  Gt,
+  Ge,
+  Lt,
+  Le,
+}
+
+impl AlertOperator {
+  /// Compare a value with a threshold
+  pub fn compare(&self, value: f64, threshold: f64) -> bool {
+    match self {
+      Self::Gt => value > threshold,
+      Self::Ge => value >= threshold,
+      Self::Lt => value < threshold,
+      Self::Le => value <= threshold,
+    }
+  }
+}
+
+/// Condition on a field of the metrics aggregated over a window
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct AlertMetricCondition {
+  /// Kind of the metrics eg: `nanocl.io/metrs`
+  pub kind: String,
+  /// Path of the value in the data of the metrics eg: `Memory.Used`
+  pub field: String,
+  /// Aggregation of the values of the window, default to avg
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub aggregate: Option<MetricAggregate>,
+  /// Duration of the window in seconds, default to 300
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub window: Option<u64>,
+  /// Only the metrics of this node
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub node_name: Option<String>,
+  /// Comparison of the aggregated value with the threshold
+  pub operator: AlertOperator,
+  /// Threshold of the aggregated value
+  pub threshold: f64,
+}
+
+/// Condition on the number of events emitted during a window
+/// eg: 3 `die` events of a cargo in 5 minutes for a crash loop
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct AlertEventCondition {
+  /// Kind of the events (normal, warning, error)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub kind: Option<String>,
+  /// Action of the events eg: `fail`
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub action: Option<String>,
+  /// Kind of the actor of the events eg: `Cargo`
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub actor_kind: Option<String>,
+  /// Key of the actor of the events eg: `my-cargo.global`
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub actor_key: Option<String>,
+  /// Duration of the window in seconds, default to 300
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub window: Option<u64>,
+  /// Minimum number of events in the window, default to 1
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub count: Option<u64>,
+}
+
+/// Condition on the status of a cargo, a vm or a job
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct AlertProcessCondition {
+  /// Kind of the object
+  pub kind: ProcessKind,
+  /// Name of the object
+  pub name: String,
+  /// Namespace of the cargo or the vm, default to global
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub namespace: Option<String>,
+  /// Actual status of the object making the condition true eg: `fail`
+  pub status: ObjPsStatusKind,
+}
+
+/// Condition evaluated by an alert rule
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub enum AlertCondition {
+  Metric(AlertMetricCondition),
+  Event(AlertEventCondition),
+  Process(AlertProcessCondition),
+}
+
+/// Destination of the notifications of an alert,
+/// the alert is sent as json
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub enum AlertSink {
+  /// Url receiving a POST request
+  Webhook(String),
+  /// Path of a unix socket receiving a line
+  Unix(String),
+  /// Command executed with the alert on the standard input
+  Exec(Vec<String>),
+}
+
+/// Data of a `nanocl.io/alert-rule` resource
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct ResourceAlertRule {
+  /// Condition firing the alert
+  pub condition: AlertCondition,
+  /// Seconds the condition must stay true before firing, default to 0
+  #[cfg_attr(
+    feature = "serde",
+    serde(rename = "For", skip_serializing_if = "Option::is_none")
+  )]
+  pub r#for: Option<u64>,
+  /// Severity of the alert, default to warning
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub severity: Option<AlertSeverity>,
+  /// Description of the alert sent in the notifications
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub summary: Option<String>,
+  /// Node evaluating the rule, a single elected node evaluate it if not set
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub node: Option<String>,
+  /// Where the alert is sent when it fire and resolve
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub sinks: Option<Vec<AlertSink>>,
+}
+
+/// Status of an alert
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum AlertStatus {
+  /// The condition is true for less than the `For` duration
+  Pending,
+  Firing,
+  Resolved,
+}
+
+impl std::fmt::Display for AlertStatus {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Pending => write!(f, "pending"),
+      Self::Firing => write!(f, "firing"),
+      Self::Resolved => write!(f, "resolved"),
+    }
+  }
+}
+
+/// Alert of a rule evaluated by a node
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct Alert {
+  /// Name of the alert rule
+  pub rule: String,
+  /// Node evaluating the rule
+  pub node_name: String,
+  pub status: AlertStatus,
+  pub severity: AlertSeverity,
+  /// Summary of the rule or a description of the condition
+  pub summary: String,
+  /// Value of the metric or number of events of the last evaluation
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub value: Option<f64>,
+  /// Since when the condition is true
+  pub active_since: chrono::NaiveDateTime,
+  /// When the alert fired
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub fired_at: Option<chrono::NaiveDateTime>,
+  /// When the alert resolved
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub resolved_at: Option<chrono::NaiveDateTime>,
+}
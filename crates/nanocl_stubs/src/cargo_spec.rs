@@ -5,7 +5,7 @@ pub use bollard_next::container::Config;
 pub use bollard_next::models::HealthConfig;
 pub use bollard_next::models::HostConfig;
 
-use crate::generic::ImagePullPolicy;
+use crate::{container_image::ContainerBuild, generic::ImagePullPolicy};
 
 /// Auto is used to automatically define that the number of replicas in the cluster
 /// Number is used to manually set the number of replicas
@@ -96,6 +96,12 @@ pub struct CargoSpecPartial {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub image_pull_policy: Option<ImagePullPolicy>,
+  /// Build the image from a Dockerfile context when applied from a Statefile
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub build: Option<ContainerBuild>,
   /// Container specification of the cargo
   pub container: Config,
   /// Replication specification of the cargo
@@ -155,6 +161,12 @@ pub struct CargoSpecUpdate {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub image_pull_policy: Option<ImagePullPolicy>,
+  /// New build of the image
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub build: Option<ContainerBuild>,
   /// New container specification of the cargo
   #[cfg_attr(
     feature = "serde",
@@ -180,6 +192,7 @@ impl From<CargoSpecPartial> for CargoSpecUpdate {
       secrets: spec.secrets,
       image_pull_secret: spec.image_pull_secret,
       image_pull_policy: spec.image_pull_policy,
+      build: spec.build,
     }
   }
 }
@@ -234,6 +247,12 @@ pub struct CargoSpec {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub image_pull_policy: Option<ImagePullPolicy>,
+  /// Build the image from a Dockerfile context when applied from a Statefile
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub build: Option<ContainerBuild>,
   /// Container specification of the cargo
   pub container: Config,
   /// Replication specification of the cargo
@@ -255,6 +274,7 @@ impl From<CargoSpec> for CargoSpecPartial {
       secrets: spec.secrets,
       image_pull_secret: spec.image_pull_secret,
       image_pull_policy: spec.image_pull_policy,
+      build: spec.build,
     }
   }
 }
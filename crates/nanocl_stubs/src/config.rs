@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
@@ -33,6 +35,24 @@ pub struct DaemonConfig {
   pub gid: u32,
   /// Optional ssl configuration
   pub ssl: Option<SslConfig>,
+  /// Optional garbage collection of the container images
+  pub image_gc: Option<ImageGcConfig>,
+  /// Periodic reconciliation of the processes with the wanted state
+  pub reconcile: ReconcileConfig,
+  /// Maximum number of tasks (start, update, delete...) running at the same time
+  pub task_workers: usize,
+  /// Seconds to wait for the running tasks when the daemon shutdown
+  #[cfg_attr(feature = "serde", serde(default = "default_shutdown_timeout"))]
+  pub shutdown_timeout: u64,
+  /// Level of the logs of the daemon (error, warn, info, debug, trace)
+  /// replacing the one of the `LOG_LEVEL` environment variable
+  pub log_level: Option<String>,
+  /// Retention of the events and the metrics
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub retention: RetentionConfig,
+  /// Sampling of the resource usage of the processes
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub process_stats: ProcessStatsConfig,
 }
 
 /// Configuration File of the daemon
@@ -52,6 +72,151 @@ pub struct DaemonConfigFile {
   pub gateway: Option<String>,
   /// Hostname to use for the node automatically detected if not set
   pub hostname: Option<String>,
+  /// Garbage collection of the container images disabled if not set
+  pub image_gc: Option<ImageGcConfig>,
+  /// Reconciliation of the processes enabled every 60 seconds if not set
+  pub reconcile: Option<ReconcileConfig>,
+  /// Maximum number of tasks running at the same time default to 8
+  pub task_workers: Option<usize>,
+  /// Seconds to wait for the running tasks on shutdown default to 30
+  pub shutdown_timeout: Option<u64>,
+  /// Level of the logs of the daemon default to the `LOG_LEVEL` environment variable
+  pub log_level: Option<String>,
+  /// Retention of the events and the metrics default to 30 days
+  pub retention: Option<RetentionConfig>,
+  /// Sampling of the processes enabled every 60 seconds if not set
+  pub process_stats: Option<ProcessStatsConfig>,
+}
+
+/// Result of a reload of the daemon config
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct DaemonConfigReload {
+  /// Settings changed and applied without restart
+  pub applied: Vec<String>,
+  /// Settings changed that need a restart of the daemon to be applied
+  pub restart_required: Vec<String>,
+}
+
+/// Garbage collection policy of the container images
+/// Images used by a cargo or a job, or by a cargo history are never removed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ImageGcConfig {
+  /// Interval in seconds between two collections
+  #[cfg_attr(feature = "serde", serde(default = "default_gc_interval"))]
+  pub interval: u64,
+  /// Number of images to keep per repository, the most recent are kept
+  /// Untagged images count in the repository of their digest
+  pub keep_last: Option<usize>,
+  /// Disk usage in percent above which the oldest images are removed
+  pub high_water_mark: Option<u8>,
+  /// Disk usage in percent to reach once the high water mark is exceeded
+  /// Default to 10 percent under the high water mark
+  pub low_water_mark: Option<u8>,
+}
+
+impl Default for ImageGcConfig {
+  fn default() -> Self {
+    Self {
+      interval: default_gc_interval(),
+      keep_last: None,
+      high_water_mark: None,
+      low_water_mark: None,
+    }
+  }
+}
+
+/// Reconciliation of the processes of cargoes, vms and jobs
+/// Drifts between the wanted state and the running processes are repaired
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ReconcileConfig {
+  /// Interval in seconds between two reconciliations, 0 to disable it
+  #[cfg_attr(feature = "serde", serde(default = "default_reconcile_interval"))]
+  pub interval: u64,
+}
+
+impl Default for ReconcileConfig {
+  fn default() -> Self {
+    Self {
+      interval: default_reconcile_interval(),
+    }
+  }
+}
+
+/// Sampling of the resource usage of the processes running on the node
+/// saved as metrics of kind `nanocl.io/process-stats`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ProcessStatsConfig {
+  /// Interval in seconds between two samples, 0 to disable it
+  /// The prometheus metrics of the processes are the last samples
+  #[cfg_attr(
+    feature = "serde",
+    serde(default = "default_process_stats_interval")
+  )]
+  pub interval: u64,
+}
+
+impl Default for ProcessStatsConfig {
+  fn default() -> Self {
+    Self {
+      interval: default_process_stats_interval(),
+    }
+  }
+}
+
+/// Retention of the events and the metrics in the store
+/// Durations are in seconds, rows older than their retention are pruned
+/// by a single node of the cluster holding the retention lease
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RetentionConfig {
+  /// Interval in seconds between two prunes
+  #[cfg_attr(feature = "serde", serde(default = "default_prune_interval"))]
+  pub interval: u64,
+  /// Maximum number of rows deleted by query
+  #[cfg_attr(feature = "serde", serde(default = "default_prune_batch_size"))]
+  pub batch_size: u64,
+  /// Retention of the events
+  #[cfg_attr(feature = "serde", serde(default = "default_retention"))]
+  pub events: u64,
+  /// Retention of the metrics of a kind not in `metric_kinds`
+  #[cfg_attr(feature = "serde", serde(default = "default_retention"))]
+  pub metrics: u64,
+  /// Retention of the metrics by kind eg: `ncproxy.io/http: 86400`
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub metric_kinds: HashMap<String, u64>,
+}
+
+impl RetentionConfig {
+  /// Retention of the metrics of a kind
+  pub fn metric(&self, kind: &str) -> u64 {
+    self.metric_kinds.get(kind).copied().unwrap_or(self.metrics)
+  }
+}
+
+impl Default for RetentionConfig {
+  fn default() -> Self {
+    Self {
+      interval: default_prune_interval(),
+      batch_size: default_prune_batch_size(),
+      events: default_retention(),
+      metrics: default_retention(),
+      metric_kinds: HashMap::new(),
+    }
+  }
 }
 
 impl Default for DaemonConfig {
@@ -68,6 +233,13 @@ impl Default for DaemonConfig {
       nodes: Vec::default(),
       advertise_addr: String::default(),
       ssl: None,
+      image_gc: None,
+      reconcile: ReconcileConfig::default(),
+      task_workers: default_task_workers(),
+      shutdown_timeout: default_shutdown_timeout(),
+      log_level: None,
+      retention: RetentionConfig::default(),
+      process_stats: ProcessStatsConfig::default(),
     }
   }
 }
@@ -75,3 +247,35 @@ impl Default for DaemonConfig {
 fn default_host() -> String {
   "/var/run/docker.sock".to_owned()
 }
+
+fn default_gc_interval() -> u64 {
+  300
+}
+
+fn default_reconcile_interval() -> u64 {
+  60
+}
+
+fn default_task_workers() -> usize {
+  8
+}
+
+fn default_shutdown_timeout() -> u64 {
+  30
+}
+
+fn default_process_stats_interval() -> u64 {
+  60
+}
+
+fn default_prune_interval() -> u64 {
+  600
+}
+
+fn default_prune_batch_size() -> u64 {
+  1_000
+}
+
+fn default_retention() -> u64 {
+  30 * 24 * 3_600
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A container image with the cargoes and jobs using it
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct ContainerImageSummary {
+  /// Id of the image
+  pub id: String,
+  /// Names of the image with their tag
+  pub repo_tags: Vec<String>,
+  /// Size of the image in bytes
+  pub size: i64,
+  /// Creation date of the image as a unix timestamp
+  pub created: i64,
+  /// Keys of the cargoes using the image
+  pub cargoes: Vec<String>,
+  /// Names of the jobs using the image
+  pub jobs: Vec<String>,
+}
+
+/// Payload to pull a container image ahead of time
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ContainerImagePullOpts {
+  /// Name of the image with his tag (eg: nginx:1.27)
+  pub name: String,
+  /// Secret with the credentials of the registry
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub secret: Option<String>,
+}
+
+/// Remove container image query
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ContainerImageDeleteQuery {
+  /// Remove the image even if it's tagged multiple times or used by stopped containers
+  /// An image used by a cargo or a job is never removed
+  pub force: Option<bool>,
+}
+
+/// Result of the removal of the unused container images
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct ContainerImagePruneResult {
+  /// Ids of the removed images
+  pub images_deleted: Vec<String>,
+  /// Disk space reclaimed in bytes
+  pub space_reclaimed: i64,
+}
+
+/// Build of a container image from a Dockerfile context
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ContainerBuild {
+  /// Path of the build context relative to the Statefile
+  pub context: String,
+  /// Path of the Dockerfile inside the context default to `Dockerfile`
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub dockerfile: Option<String>,
+  /// Build arguments
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub args: Option<HashMap<String, String>>,
+  /// Stage to build in a multi-stage Dockerfile
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub target: Option<String>,
+}
+
+/// Build container image query
+/// The body of the request is a tar archive of the build context
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ContainerImageBuildQuery {
+  /// Repository of the image, the tag is a hash of the context and the options
+  pub name: String,
+  /// Path of the Dockerfile inside the context
+  pub dockerfile: Option<String>,
+  /// Build arguments as a json object
+  pub args: Option<String>,
+  /// Stage of a multi-stage Dockerfile to build
+  pub target: Option<String>,
+}
+
+/// Stream of a container image build
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub enum ContainerImageBuildStream {
+  /// A line of the build output
+  Progress(String),
+  /// The name of the built image with his tag
+  Done(String),
+}
+
+/// Policy restricting the container images deployed in a namespace
+/// Stored as a resource of kind `nanocl.io/registry-policy`
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ResourceRegistryPolicy {
+  /// Namespace where the policy is enforced
+  pub namespace: String,
+  /// Image patterns allowed like `registry.internal/*`, all images are allowed if not set
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub allow: Option<Vec<String>>,
+  /// Image patterns denied even if they are allowed
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub deny: Option<Vec<String>>,
+  /// Reject the images without tag or with the `latest` tag
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub reject_latest: Option<bool>,
+  /// Only emit a warning event instead of rejecting the images
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub audit: Option<bool>,
+}
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use bollard_next::container::Config;
 
+use crate::container_image::ContainerBuild;
 use crate::generic::ImagePullPolicy;
 use crate::process::Process;
 use crate::system::{EventActor, EventActorKind, ObjPsStatus};
@@ -54,6 +55,13 @@ pub struct JobPartial {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub image_pull_policy: Option<ImagePullPolicy>,
+  /// Build the image of the containers without image
+  /// when applied from a Statefile
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub build: Option<ContainerBuild>,
   /// List of container to run
   pub containers: Vec<Config>,
 }
@@ -70,6 +78,7 @@ impl From<Job> for JobPartial {
       containers: job.containers,
       image_pull_secret: job.image_pull_secret,
       image_pull_policy: job.image_pull_policy,
+      build: job.build,
     }
   }
 }
@@ -126,6 +135,13 @@ pub struct Job {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub image_pull_policy: Option<ImagePullPolicy>,
+  /// Build the image of the containers without image
+  /// when applied from a Statefile
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub build: Option<ContainerBuild>,
   /// Containers to run
   pub containers: Vec<Config>,
 }
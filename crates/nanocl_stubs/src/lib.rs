@@ -1,9 +1,11 @@
 pub mod generic;
 pub mod system;
 
+pub mod alert;
 pub mod cargo;
 pub mod cargo_spec;
 pub mod config;
+pub mod container_image;
 pub mod dns;
 pub mod job;
 pub mod metric;
@@ -15,6 +17,8 @@ pub mod resource;
 pub mod resource_kind;
 pub mod secret;
 pub mod statefile;
+pub mod task;
 pub mod vm;
 pub mod vm_image;
 pub mod vm_spec;
+pub mod webhook;
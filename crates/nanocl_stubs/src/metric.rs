@@ -40,6 +40,198 @@ pub struct MetricPartial {
   pub note: Option<String>,
 }
 
+/// Aggregation applied on the values of a time bucket
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum MetricAggregate {
+  #[default]
+  Avg,
+  Min,
+  Max,
+  Sum,
+  /// 95th percentile, only available on the raw metrics
+  P95,
+}
+
+/// Column used to split a query in multiple series
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum MetricGroupBy {
+  NodeName,
+  Kind,
+}
+
+/// Where the values of a query are read from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MetricSource {
+  /// The metrics as they were saved
+  #[default]
+  #[cfg_attr(feature = "serde", serde(rename = "raw"))]
+  Raw,
+  /// The metrics rolled up by 5 minutes
+  #[cfg_attr(feature = "serde", serde(rename = "5m"))]
+  FiveMinutes,
+  /// The metrics rolled up by hour
+  #[cfg_attr(feature = "serde", serde(rename = "1h"))]
+  OneHour,
+}
+
+impl MetricSource {
+  /// Duration in seconds of the buckets of a rollup
+  pub fn resolution(&self) -> Option<i64> {
+    match self {
+      Self::Raw => None,
+      Self::FiveMinutes => Some(300),
+      Self::OneHour => Some(3600),
+    }
+  }
+}
+
+/// Query string to compute a time series from the metrics
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MetricQuery {
+  /// Path of the value in the data of the metrics separated by dots,
+  /// a `*` expand an array eg: `Cpus.*.Usage` or `request_time`
+  pub field: String,
+  /// Kind of the metrics
+  pub kind: Option<String>,
+  /// Only the metrics of this node
+  pub node_name: Option<String>,
+  /// Start of the range as a unix timestamp, default to one hour ago
+  pub since: Option<i64>,
+  /// End of the range as a unix timestamp, default to now
+  pub until: Option<i64>,
+  /// Duration of a bucket in seconds, default to 60
+  pub step: Option<i64>,
+  /// Aggregation of the values of a bucket, default to avg
+  pub aggregate: Option<MetricAggregate>,
+  /// Split the result in a series by node or kind
+  pub group_by: Option<MetricGroupBy>,
+  /// Read the raw metrics or a rollup, default to raw.
+  /// Rollups only contain the rolled up fields and can't give a p95
+  pub source: Option<MetricSource>,
+}
+
+/// Aggregated value of a time bucket
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct MetricPoint {
+  /// Start of the bucket
+  pub time: chrono::NaiveDateTime,
+  /// Aggregated value
+  pub value: f64,
+  /// Number of values in the bucket
+  pub count: i64,
+}
+
+/// Time series of a group of metrics
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct MetricSeries {
+  /// Value of the group by column, empty without group by
+  pub name: String,
+  /// Buckets with at least one value ordered by time
+  pub points: Vec<MetricPoint>,
+}
+
+/// Dimension of the http requests used to split the analytics
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum HttpAnalyticsGroupBy {
+  /// The host of the requests
+  #[default]
+  Domain,
+  /// The path of the location of the proxy rule
+  Location,
+  /// The address of the upstream that served the requests
+  Upstream,
+}
+
+/// Query string to compute the analytics of the http requests of the proxy
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HttpAnalyticsQuery {
+  /// Start of the window as a unix timestamp, default to one hour ago
+  pub since: Option<i64>,
+  /// End of the window as a unix timestamp, default to now
+  pub until: Option<i64>,
+  /// Duration of a point in seconds, default to the resolution of the source
+  pub step: Option<i64>,
+  /// Split the analytics by domain, location or upstream, default to domain
+  pub group_by: Option<HttpAnalyticsGroupBy>,
+  /// Only the requests of this domain
+  pub domain: Option<String>,
+  /// Only the requests proxied by this node
+  pub node_name: Option<String>,
+  /// Rollup to read, default to 5m
+  pub source: Option<MetricSource>,
+}
+
+/// Traffic of a group of http requests during a window or a step
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct HttpAnalyticsPoint {
+  /// Start of the window
+  pub time: chrono::NaiveDateTime,
+  /// Number of requests
+  pub requests: i64,
+  /// Average number of requests per second
+  pub rps: f64,
+  /// Number of requests answered with a 4xx status
+  pub client_errors: i64,
+  /// Number of requests answered with a 5xx status
+  pub server_errors: i64,
+  /// Ratio of the requests answered with a 5xx status
+  pub error_rate: f64,
+  /// Number of bytes sent to the clients
+  pub bytes_sent: i64,
+  /// Average of the `request_time` in seconds
+  pub latency_avg: f64,
+  /// Median of the `request_time` in seconds
+  pub latency_p50: f64,
+  /// 95th percentile of the `request_time` in seconds
+  pub latency_p95: f64,
+  /// 99th percentile of the `request_time` in seconds
+  pub latency_p99: f64,
+}
+
+/// Analytics of the http requests of a domain, location or upstream
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct HttpAnalytics {
+  /// Value of the group by dimension, empty when unknown
+  pub name: String,
+  /// Traffic of the whole window
+  pub total: HttpAnalyticsPoint,
+  /// Traffic by step with at least one request ordered by time
+  pub points: Vec<HttpAnalyticsPoint>,
+}
+
 /// ## deserialize empty string
 ///
 /// Serde helper to deserialize string that can be empty to `Option<String>`.
@@ -99,6 +291,12 @@ pub struct HttpMetric {
   pub uri: String,
   /// The target host of the request
   pub host: String,
+  /// The path of the location of the proxy rule matching the request
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, deserialize_with = "deserialize_empty_string")
+  )]
+  pub location: Option<String>,
   /// The remote address of the request
   pub remote_addr: String,
   /// The real ip remote address of the request
@@ -1,11 +1,127 @@
+use std::collections::HashMap;
+
+use bollard_next::secret::Network;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::{
   cargo::CargoInspect,
-  system::{EventActor, EventActorKind},
+  system::{EventActor, EventActorKind, ObjPsStatusKind},
 };
 
+/// Network settings of a namespace
+/// By default every cargo and virtual machine is attached to the shared `nanoclbr0` bridge
+/// An isolated namespace get his own network created and removed with the namespace
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct NamespaceNetwork {
+  /// Create a dedicated network for the namespace
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub isolated: bool,
+  /// Subnet of the network in CIDR format eg: 10.10.0.0/16
+  /// automatically allocated if not set
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub subnet: Option<String>,
+  /// Gateway of the network eg: 10.10.0.1
+  /// automatically allocated if not set
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub gateway: Option<String>,
+}
+
+/// Quotas of a namespace
+/// Limit the number of objects and the total amount of resources
+/// the cargoes and virtual machines of a namespace can use
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct NamespaceQuota {
+  /// Maximum number of cargoes
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_cargoes: Option<usize>,
+  /// Maximum number of cargo instances
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_instances: Option<usize>,
+  /// Maximum number of virtual machines
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_vms: Option<usize>,
+  /// Maximum total of cpu in units of 10<sup>-9</sup> CPUs
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_nano_cpus: Option<i64>,
+  /// Maximum total of memory in bytes
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_memory: Option<i64>,
+}
+
+/// Limit range of a namespace
+/// Default limits are injected into the `HostConfig` of containers when missing
+/// Max limits are the maximum a single container or virtual machine can request
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct NamespaceLimitRange {
+  /// Default cpu of a container in units of 10<sup>-9</sup> CPUs
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub default_nano_cpus: Option<i64>,
+  /// Default memory of a container in bytes
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub default_memory: Option<i64>,
+  /// Maximum cpu of a container in units of 10<sup>-9</sup> CPUs
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_nano_cpus: Option<i64>,
+  /// Maximum memory of a container in bytes
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_memory: Option<i64>,
+}
+
 /// Namespace is a identifier for a set of cargoes
 /// It is used to group cargoes together
 #[derive(Clone, Debug)]
@@ -24,6 +140,30 @@ pub struct Namespace {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub metadata: Option<serde_json::Value>,
+  /// Network settings
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub network: Option<NamespaceNetwork>,
+  /// Quotas
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub quota: Option<NamespaceQuota>,
+  /// Limit range of containers
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub limit_range: Option<NamespaceLimitRange>,
+  /// Status of the namespace, set to `Destroying` during a cascading deletion
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub status: Option<ObjPsStatusKind>,
 }
 
 /// A Namespace partial is a payload used to create a new namespace
@@ -44,6 +184,24 @@ pub struct NamespacePartial {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub metadata: Option<serde_json::Value>,
+  /// Network settings
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub network: Option<NamespaceNetwork>,
+  /// Quotas
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub quota: Option<NamespaceQuota>,
+  /// Limit range of containers
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub limit_range: Option<NamespaceLimitRange>,
 }
 
 /// A Namespace Summary is a summary of a namespace
@@ -79,6 +237,101 @@ pub struct NamespaceInspect {
   pub name: String,
   /// Number of cargoes
   pub cargoes: Vec<CargoInspect>,
+  /// Network of the namespace when isolated
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub network: Option<Network>,
+}
+
+/// Delete namespace query
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NamespaceDeleteQuery {
+  /// Delete the cargoes, virtual machines, proxy and dns rules and volumes of the namespace
+  pub cascade: Option<bool>,
+}
+
+/// Network policy allowing the processes of other namespaces
+/// to reach the isolated network of a namespace
+/// Stored as a resource of kind `nanocl.io/network-policy`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ResourceNetworkPolicy {
+  /// Namespace owning the isolated network
+  pub namespace: String,
+  /// Namespaces allowed to reach the network
+  pub allow_from: Vec<String>,
+}
+
+/// Payload to clone a namespace with his cargoes, virtual machines and resources
+/// The domains of the http proxy rules are rewritten to the new namespace,
+/// stream rules and http rules without the namespace in their domain are not cloned
+/// and returned in the `SkippedRules` of the response
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct NamespaceCloneOpts {
+  /// Name of the new namespace
+  pub name: String,
+  /// Environment variables `KEY=VALUE` set or replaced in every cargo
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub env: Option<Vec<String>>,
+  /// Image to use by cargo name
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub images: Option<HashMap<String, String>>,
+  /// Snapshot the disks of the virtual machines instead of starting from their base image
+  /// The virtual machines of the namespace must be stopped, their current disk
+  /// is frozen into a base image shared with the clone
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub snapshot_vms: Option<bool>,
+}
+
+/// Proxy rule of a namespace that wasn't cloned
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct NamespaceCloneSkippedRule {
+  /// Name of the proxy rule resource in the source namespace
+  pub resource: String,
+  /// Why the rule wasn't cloned
+  pub reason: String,
+}
+
+/// Namespace created by a clone with the proxy rules left out of it
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct NamespaceCloned {
+  /// The created namespace
+  pub namespace: Namespace,
+  /// Rules of the source namespace that would listen on the same domain or port
+  pub skipped_rules: Vec<NamespaceCloneSkippedRule>,
 }
 
 /// Convert a Namespace into an EventActor
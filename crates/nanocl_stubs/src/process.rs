@@ -360,3 +360,76 @@ impl From<ProcessStatsQuery> for StatsOptions {
     }
   }
 }
+
+/// Resource usage of a process sampled by the daemon,
+/// saved as a metric of kind `nanocl.io/process-stats`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct ProcessStatsSample {
+  /// Key of the process
+  pub key: String,
+  /// Name of the process
+  pub name: String,
+  /// Kind of the object of the process
+  pub kind: ProcessKind,
+  /// Key of the cargo, vm or job of the process
+  pub kind_key: String,
+  /// Namespace of the cargo or the vm
+  pub namespace: Option<String>,
+  /// Usage of the cpus in percent of one cpu
+  pub cpu_percent: f64,
+  /// Memory used without the page cache in bytes
+  pub memory_usage: u64,
+  /// Memory limit in bytes
+  pub memory_limit: u64,
+  /// Bytes received on all networks
+  pub network_rx: u64,
+  /// Bytes transmitted on all networks
+  pub network_tx: u64,
+  /// Number of pids
+  pub pids: u64,
+}
+
+/// Query string of the summary of the resource usage of the processes
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProcessStatsSummaryQuery {
+  /// Name of the namespace
+  pub namespace: Option<String>,
+  /// Start of the summary as a unix timestamp, default to one hour ago
+  pub since: Option<i64>,
+}
+
+/// Resource usage of a process summarized from his samples
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct ProcessStatsSummary {
+  /// Name of the process
+  pub name: String,
+  /// Number of samples
+  pub samples: i64,
+  /// Date of the first sample
+  pub first_sample: chrono::NaiveDateTime,
+  /// Date of the last sample
+  pub last_sample: chrono::NaiveDateTime,
+  /// Average usage of the cpus in percent
+  pub cpu_avg: f64,
+  /// 95th percentile of the usage of the cpus in percent
+  pub cpu_p95: f64,
+  /// Highest usage of the cpus in percent
+  pub cpu_max: f64,
+  /// Average memory used in bytes
+  pub memory_avg: f64,
+  /// 95th percentile of the memory used in bytes
+  pub memory_p95: f64,
+  /// Highest memory used in bytes
+  pub memory_max: f64,
+  /// Memory limit of the last sample in bytes
+  pub memory_limit: f64,
+}
@@ -383,3 +383,49 @@ pub struct ResourceProxyRule {
   /// The rules to apply
   pub rules: Vec<ProxyRule>,
 }
+
+impl ResourceProxyRule {
+  /// List the upstream targets (cargoes and virtual machines) of the rules
+  pub fn upstream_targets(&self) -> Vec<&UpstreamTarget> {
+    let mut targets = Vec::new();
+    for rule in &self.rules {
+      match rule {
+        ProxyRule::Http(rule) => {
+          for location in &rule.locations {
+            if let LocationTarget::Upstream(target) = &location.target {
+              targets.push(target);
+            }
+          }
+        }
+        ProxyRule::Stream(rule) => {
+          if let StreamTarget::Upstream(target) = &rule.target {
+            targets.push(target);
+          }
+        }
+      }
+    }
+    targets
+  }
+
+  /// List the upstream targets of the rules as mutable references
+  pub fn upstream_targets_mut(&mut self) -> Vec<&mut UpstreamTarget> {
+    let mut targets = Vec::new();
+    for rule in &mut self.rules {
+      match rule {
+        ProxyRule::Http(rule) => {
+          for location in &mut rule.locations {
+            if let LocationTarget::Upstream(target) = &mut location.target {
+              targets.push(target);
+            }
+          }
+        }
+        ProxyRule::Stream(rule) => {
+          if let StreamTarget::Upstream(target) = &mut rule.target {
+            targets.push(target);
+          }
+        }
+      }
+    }
+    targets
+  }
+}
@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{collections::HashMap, str::FromStr};
 
 use bollard_next::{secret::Network, service::SystemInfo};
 
@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::config::DaemonConfig;
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -140,6 +140,84 @@ pub struct HostInfo {
   pub config: DaemonConfig,
 }
 
+/// Status of the daemon or of one of its dependencies
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub enum HealthStatus {
+  /// Everything works
+  #[default]
+  Up,
+  /// A dependency that isn't required to serve requests is down
+  Degraded,
+  /// A dependency required to serve requests is down
+  Down,
+}
+
+/// Result of the check of a dependency of the daemon
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct HealthCheck {
+  /// Name of the dependency eg: store, docker, controller/ncproxy.io/rule
+  pub name: String,
+  /// Status of the dependency, only `Up` or `Down`
+  pub status: HealthStatus,
+  /// The daemon can't serve requests when a critical dependency is down
+  pub critical: bool,
+  /// Duration of the check in milliseconds
+  pub duration: u64,
+  /// Reason of the failure
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub message: Option<String>,
+}
+
+/// Health of the daemon and of each of its dependencies
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct HealthReport {
+  /// Global status `Down` if a critical dependency is down
+  /// `Degraded` if another dependency is down
+  pub status: HealthStatus,
+  /// Node reporting his health
+  pub node_name: String,
+  /// Result of the checks
+  pub checks: Vec<HealthCheck>,
+}
+
+/// Statistics of the pruning of the events and the metrics past their retention
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct PruneStats {
+  /// When the last prune ended
+  pub last_prune: Option<chrono::NaiveDateTime>,
+  /// Duration of the last prune in milliseconds
+  pub duration: u64,
+  /// Rows deleted by the last prune by table
+  pub deleted: std::collections::HashMap<String, u64>,
+  /// Rows deleted since the daemon started by table
+  pub total_deleted: std::collections::HashMap<String, u64>,
+  /// Error of the last prune
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub error: Option<String>,
+}
+
 /// Details about the binary
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
@@ -170,6 +248,7 @@ pub enum EventActorKind {
   Secret,
   Process,
   ContainerImage,
+  Volume,
 }
 
 impl std::fmt::Display for EventActorKind {
@@ -183,6 +262,7 @@ impl std::fmt::Display for EventActorKind {
       EventActorKind::Secret => write!(f, "Secret"),
       EventActorKind::Process => write!(f, "Process"),
       EventActorKind::ContainerImage => write!(f, "ContainerImage"),
+      EventActorKind::Volume => write!(f, "Volume"),
     }
   }
 }
@@ -200,6 +280,9 @@ pub enum NativeEventAction {
   Update,
   Destroying,
   Destroy,
+  /// An object inside the destroying one have been removed
+  #[cfg_attr(feature = "serde", serde(rename = "destroy_progress"))]
+  DestroyProgress,
   Stopping,
   Stop,
   Restart,
@@ -223,6 +306,7 @@ impl FromStr for NativeEventAction {
       "update" => Ok(NativeEventAction::Update),
       "destroying" => Ok(NativeEventAction::Destroying),
       "destroy" => Ok(NativeEventAction::Destroy),
+      "destroy_progress" => Ok(NativeEventAction::DestroyProgress),
       "stopping" => Ok(NativeEventAction::Stopping),
       "stop" => Ok(NativeEventAction::Stop),
       "restart" => Ok(NativeEventAction::Restart),
@@ -248,6 +332,7 @@ impl std::fmt::Display for NativeEventAction {
       NativeEventAction::Stop => write!(f, "stop"),
       NativeEventAction::Destroying => write!(f, "destroying"),
       NativeEventAction::Destroy => write!(f, "destroy"),
+      NativeEventAction::DestroyProgress => write!(f, "destroy_progress"),
       NativeEventAction::Restart => write!(f, "restart"),
       NativeEventAction::Finish => write!(f, "finish"),
       NativeEventAction::Fail => write!(f, "fail"),
@@ -370,6 +455,11 @@ pub struct EventPartial {
 pub struct Event {
   /// Unique identifier of this event.
   pub key: uuid::Uuid,
+  /// Cursor of the event to resume a watch with `since`.
+  /// It grows with the creation of the events but doesn't follow their commit order,
+  /// an event committed after another can have a lower cursor.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub seq: i64,
   /// When the event was created.
   pub created_at: chrono::NaiveDateTime,
   /// When the event expires.
@@ -417,7 +507,54 @@ pub struct Event {
   pub metadata: Option<serde_json::Value>,
 }
 
-/// Condition to stop watching for events if their are meet
+/// Seconds of the events created before the cursor of a resumed watch
+/// replayed again, as the cursors don't follow the commit order an event committed
+/// after the cursor can have a lower one. The events received twice
+/// have the same `Key` and are to be ignored.
+pub const EVENT_WATCH_OVERLAP: i64 = 30;
+
+/// Query string to watch the events
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EventWatchQuery {
+  /// Replay the stored events created since the event of this cursor
+  /// minus `EVENT_WATCH_OVERLAP` before the new ones
+  pub since: Option<i64>,
+  /// A json as string as EventCondition, only the matching events are sent
+  pub filter: Option<String>,
+}
+
+/// Options to watch the events
+#[derive(Debug, Clone, Default)]
+pub struct EventWatchOpts {
+  /// Replay the stored events created since the event of this cursor
+  /// minus `EVENT_WATCH_OVERLAP` before the new ones
+  pub since: Option<i64>,
+  /// Only the events matching this condition are sent
+  pub filter: Option<EventCondition>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<EventWatchOpts> for EventWatchQuery {
+  type Error = serde_json::Error;
+
+  fn try_from(opts: EventWatchOpts) -> Result<Self, Self::Error> {
+    Ok(Self {
+      since: opts.since,
+      filter: opts
+        .filter
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()?,
+    })
+  }
+}
+
+/// Condition matching events, used to filter a watch with `matches`
+/// where the fields not set match any event and the ones set must all match.
+/// A watch is stopped once every condition is equal to an event,
+/// `ActorKind`, `ActorKey`, `Kind` and `Action` must then be set and match,
+/// the related actor is ignored and the other fields must match when set.
 #[derive(Default, Clone, Debug)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -443,8 +580,129 @@ pub struct EventCondition {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub related_kind: Option<EventActorKind>,
+  /// One of the kinds of the event
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, skip_serializing_if = "Vec::is_empty")
+  )]
   pub kind: Vec<EventKind>,
+  /// One of the actions of the event
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, skip_serializing_if = "Vec::is_empty")
+  )]
   pub action: Vec<NativeEventAction>,
+  /// Node where the event was generated
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub reporting_node: Option<String>,
+  /// Namespace of the actor
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub namespace: Option<String>,
+  /// Values of the attributes of the actor eg: `io.nanocl.n=prod`
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub attributes: Option<HashMap<String, String>>,
+  /// Conditions that must all match
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub all: Option<Vec<EventCondition>>,
+  /// Conditions of which one must match
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub any: Option<Vec<EventCondition>>,
+}
+
+impl EventActor {
+  /// Value of an attribute of the actor as a string
+  pub fn get_attribute(&self, name: &str) -> Option<String> {
+    let value = self.attributes.as_ref()?.get(name)?;
+    match value {
+      serde_json::Value::Null => None,
+      serde_json::Value::String(value) => Some(value.clone()),
+      value => Some(value.to_string()),
+    }
+  }
+
+  /// Namespace of the actor, from his attributes or the labels of a process
+  pub fn get_namespace(&self) -> Option<String> {
+    if self.kind == EventActorKind::Namespace {
+      return self.key.clone();
+    }
+    self
+      .get_attribute("Namespace")
+      .or_else(|| self.get_attribute("io.nanocl.n"))
+  }
+}
+
+/// Whether an optional expected value is not set or equal to the actual one
+fn is_matching<T: PartialEq>(expected: &Option<T>, actual: Option<&T>) -> bool {
+  match expected {
+    None => true,
+    Some(expected) => actual == Some(expected),
+  }
+}
+
+impl EventCondition {
+  /// Whether the event match the condition
+  pub fn matches(&self, event: &Event) -> bool {
+    let actor = event.actor.as_ref();
+    let related = event.related.as_ref();
+    let is_actor_matching =
+      is_matching(&self.actor_kind, actor.map(|actor| &actor.kind))
+        && is_matching(
+          &self.actor_key,
+          actor.and_then(|actor| actor.key.as_ref()),
+        );
+    let is_related_matching =
+      is_matching(&self.related_kind, related.map(|related| &related.kind))
+        && is_matching(
+          &self.related_key,
+          related.and_then(|related| related.key.as_ref()),
+        );
+    is_actor_matching
+      && is_related_matching
+      && (self.kind.is_empty() || self.kind.contains(&event.kind))
+      && (self.action.is_empty()
+        || self
+          .action
+          .iter()
+          .any(|action| action.to_string() == event.action))
+      && self.is_matching_extra(event)
+  }
+
+  /// Whether the event match the fields added after the stop conditions
+  fn is_matching_extra(&self, event: &Event) -> bool {
+    let actor = event.actor.as_ref();
+    let is_attributes_matching =
+      self.attributes.iter().flatten().all(|(name, value)| {
+        actor.and_then(|actor| actor.get_attribute(name)).as_ref()
+          == Some(value)
+      });
+    is_attributes_matching
+      && is_matching(
+        &self.namespace,
+        actor.and_then(|actor| actor.get_namespace()).as_ref(),
+      )
+      && is_matching(&self.reporting_node, Some(&event.reporting_node))
+      && self.all.iter().flatten().all(|c| c.matches(event))
+      && self
+        .any
+        .as_ref()
+        .map(|any| any.iter().any(|c| c.matches(event)))
+        .unwrap_or(true)
+  }
 }
 
 impl std::cmp::PartialEq<Event> for EventCondition {
@@ -471,5 +729,6 @@ impl std::cmp::PartialEq<Event> for EventCondition {
         .unwrap_or_default()
       && self.kind.clone().into_iter().any(|k| k == other.kind)
       && self.action.clone().into_iter().any(|a| a == action)
+      && self.is_matching_extra(other)
   }
 }
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::system::EventActor;
+
+/// Status of a task
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub enum TaskStatus {
+  /// The task is waiting in the queue of his object or for a free worker
+  #[default]
+  Pending,
+  /// The task is running
+  Running,
+}
+
+impl std::str::FromStr for TaskStatus {
+  type Err = std::io::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "pending" => Ok(Self::Pending),
+      "running" => Ok(Self::Running),
+      _ => Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Invalid task status {s}"),
+      )),
+    }
+  }
+}
+
+impl std::fmt::Display for TaskStatus {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let data = match self {
+      Self::Pending => "pending",
+      Self::Running => "running",
+    };
+    write!(f, "{data}")
+  }
+}
+
+/// A task is an operation on an object (cargo, vm, job, namespace)
+/// It is persisted until completion to be resumed if the daemon is restarted
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct Task {
+  /// Unique key of the task
+  pub key: String,
+  /// The created at date
+  pub created_at: chrono::NaiveDateTime,
+  /// The node running the task
+  pub node_name: String,
+  /// The action of the task (starting, stopping, updating, destroying)
+  pub action: String,
+  /// The object the task is about
+  pub actor: EventActor,
+  /// Status of the task
+  pub status: TaskStatus,
+}
+
+/// Depth of the task queues of a node
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct TaskStats {
+  /// Maximum number of tasks running at the same time
+  pub max_workers: usize,
+  /// Number of tasks running
+  pub running: usize,
+  /// Number of tasks waiting
+  pub pending: usize,
+  /// Number of tasks running or waiting by object
+  pub queues: HashMap<String, usize>,
+}
@@ -0,0 +1,95 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::system::{Event, EventCondition};
+
+/// Data of a `nanocl.io/event-webhook` resource,
+/// the events matching the conditions are sent with a POST request
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct ResourceEventWebhook {
+  /// Url receiving the events
+  pub url: String,
+  /// Only the events matching one of the conditions, every event if not set
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub conditions: Option<Vec<EventCondition>>,
+  /// Name of a `nanocl.io/webhook-secret` secret with the key as data
+  /// used to sign the body in the `X-Nanocl-Signature` header
+  /// as `sha256=<hex encoded hmac>`
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub secret: Option<String>,
+  /// Number of attempts before the delivery is dead-lettered, default to 5
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_attempts: Option<u32>,
+}
+
+/// Deliveries of an event webhook by the node since it started
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct EventWebhookStatus {
+  /// Name of the webhook resource
+  pub name: String,
+  /// Url receiving the events
+  pub url: String,
+  /// Number of events delivered
+  pub delivered: u64,
+  /// Number of failed attempts
+  pub failed: u64,
+  /// Number of events dead-lettered after their last attempt
+  pub dead_lettered: u64,
+  /// When the last event was delivered
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub last_delivered_at: Option<chrono::NaiveDateTime>,
+  /// When the last attempt failed
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub last_failed_at: Option<chrono::NaiveDateTime>,
+  /// Error of the last failed attempt
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub last_error: Option<String>,
+}
+
+/// Event that could not be delivered to a webhook
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct EventDeadLetter {
+  /// Unique identifier of the dead letter
+  pub key: uuid::Uuid,
+  /// When the event was dead-lettered
+  pub created_at: chrono::NaiveDateTime,
+  /// Node that tried to deliver the event
+  pub node_name: String,
+  /// Name of the webhook resource
+  pub webhook: String,
+  /// Url of the webhook when the event was sent
+  pub url: String,
+  /// Number of attempts made
+  pub attempts: i64,
+  /// Error of the last attempt
+  pub error: String,
+  /// The event not delivered
+  pub event: Event,
+}
@@ -1,13 +1,72 @@
+use std::sync::{OnceLock, RwLock};
+
 use env_logger;
 
-pub fn enable_logger(bin_name: &str) {
-  if std::env::var("LOG_LEVEL").is_err() {
-    std::env::set_var("LOG_LEVEL", format!("{bin_name}=debug"));
+/// Logger of the binary with a filter that can be rebuilt while running
+struct ReloadableLogger {
+  inner: RwLock<env_logger::Logger>,
+}
+
+impl log::Log for ReloadableLogger {
+  fn enabled(&self, metadata: &log::Metadata) -> bool {
+    match self.inner.read() {
+      Ok(logger) => logger.enabled(metadata),
+      Err(err) => err.into_inner().enabled(metadata),
+    }
   }
+
+  fn log(&self, record: &log::Record) {
+    match self.inner.read() {
+      Ok(logger) => logger.log(record),
+      Err(err) => err.into_inner().log(record),
+    }
+  }
+
+  fn flush(&self) {}
+}
+
+static LOGGER: OnceLock<ReloadableLogger> = OnceLock::new();
+
+/// Create a logger builder filtered by the `LOG_LEVEL` environment variable
+fn builder() -> env_logger::Builder {
   let is_test = std::env::var("TEST").is_ok();
-  env_logger::Builder::new()
+  let mut builder = env_logger::Builder::new();
+  builder
     .parse_env("LOG_LEVEL")
     .format_target(false)
-    .is_test(is_test)
-    .init();
+    .is_test(is_test);
+  builder
+}
+
+pub fn enable_logger(bin_name: &str) {
+  if std::env::var("LOG_LEVEL").is_err() {
+    std::env::set_var("LOG_LEVEL", format!("{bin_name}=debug"));
+  }
+  let logger = builder().build();
+  let max_level = logger.filter();
+  let logger = LOGGER.get_or_init(|| ReloadableLogger {
+    inner: RwLock::new(logger),
+  });
+  if log::set_logger(logger).is_ok() {
+    log::set_max_level(max_level);
+  }
+}
+
+/// Rebuild the filter of the logger enabled with `enable_logger`
+/// The level of the logs of the binary replaces the one of the `LOG_LEVEL` environment variable,
+/// without level the filter of the environment variable is restored
+pub fn set_level(bin_name: &str, level: Option<log::LevelFilter>) {
+  let Some(current) = LOGGER.get() else {
+    return;
+  };
+  let mut builder = builder();
+  if let Some(level) = level {
+    builder.filter_module(bin_name, level);
+  }
+  let logger = builder.build();
+  log::set_max_level(logger.filter());
+  match current.inner.write() {
+    Ok(mut current) => *current = logger,
+    Err(err) => *err.into_inner() = logger,
+  }
 }
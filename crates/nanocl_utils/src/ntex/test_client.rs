@@ -1,7 +1,9 @@
 use std::rc::Rc;
 
 use ntex::http::client::{ClientRequest, ClientResponse};
+use ntex::io::Sealed;
 use ntex::web::test::TestServer;
+use ntex::ws::{error::WsClientError, WsConnection};
 
 #[macro_export]
 macro_rules! test_status_code {
@@ -38,6 +40,14 @@ impl TestClient {
     self.srv.get(self.gen_url(url))
   }
 
+  /// Open a websocket connection
+  pub async fn ws(
+    &self,
+    url: &str,
+  ) -> Result<WsConnection<Sealed>, WsClientError> {
+    self.srv.ws_at(&self.gen_url(url)).await
+  }
+
   pub fn delete(&self, url: &str) -> ClientRequest {
     self
       .srv
@@ -0,0 +1,42 @@
+use nanocl_error::http_client::HttpClientResult;
+
+use nanocl_stubs::alert::Alert;
+
+use super::http_client::NanocldClient;
+
+impl NanocldClient {
+  /// ## Default path for alerts
+  const ALERT_PATH: &'static str = "/alerts";
+
+  /// List the pending and firing alerts of the cluster
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.list_alert().await;
+  /// ```
+  pub async fn list_alert(&self) -> HttpClientResult<Vec<Alert>> {
+    let res = self.send_get(Self::ALERT_PATH, None::<String>).await?;
+    Self::res_json(res).await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::ConnectOpts;
+
+  use super::*;
+
+  #[ntex::test]
+  async fn list_alert() {
+    let client = NanocldClient::connect_to(&ConnectOpts {
+      url: "http://nanocl.internal:8585".into(),
+      ..Default::default()
+    })
+    .expect("Failed to create a nanocl client");
+    client.list_alert().await.unwrap();
+  }
+}
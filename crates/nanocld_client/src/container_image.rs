@@ -0,0 +1,185 @@
+use std::error::Error;
+
+use bollard_next::service::ImageInspect;
+use futures::Stream;
+use ntex::channel::mpsc::Receiver;
+use ntex::util::Bytes;
+
+use nanocl_error::http::HttpResult;
+use nanocl_error::http_client::HttpClientResult;
+
+use nanocl_stubs::container_image::{
+  ContainerImageBuildQuery, ContainerImageBuildStream,
+  ContainerImageDeleteQuery, ContainerImagePruneResult, ContainerImagePullOpts,
+  ContainerImageSummary,
+};
+
+use super::http_client::NanocldClient;
+
+impl NanocldClient {
+  /// ## Default path for container images
+  const CONTAINER_IMAGE_PATH: &'static str = "/images";
+
+  /// List container images with the cargoes and jobs using them
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.list_image().await;
+  /// ```
+  pub async fn list_image(
+    &self,
+  ) -> HttpClientResult<Vec<ContainerImageSummary>> {
+    let res = self
+      .send_get(Self::CONTAINER_IMAGE_PATH, None::<String>)
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Pull a container image ahead of time with an optional registry secret
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.pull_image("nginx:latest", None).await;
+  /// ```
+  pub async fn pull_image(
+    &self,
+    name: &str,
+    secret: Option<&str>,
+  ) -> HttpClientResult<ImageInspect> {
+    let opts = ContainerImagePullOpts {
+      name: name.to_owned(),
+      secret: secret.map(|secret| secret.to_owned()),
+    };
+    let res = self
+      .send_post(
+        &format!("{}/pull", Self::CONTAINER_IMAGE_PATH),
+        Some(&opts),
+        None::<String>,
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Build a container image from a stream of a tar archive of his context.
+  /// The last item of the stream is the name of the image with his generated tag.
+  pub async fn build_image<S, E>(
+    &self,
+    query: &ContainerImageBuildQuery,
+    context: S,
+  ) -> HttpClientResult<Receiver<HttpResult<ContainerImageBuildStream>>>
+  where
+    S: Stream<Item = Result<Bytes, E>> + Unpin + 'static,
+    E: Error + 'static,
+  {
+    let res = self
+      .send_post_stream(
+        &format!("{}/build", Self::CONTAINER_IMAGE_PATH),
+        context,
+        Some(query),
+      )
+      .await?;
+    Ok(Self::res_stream(res).await)
+  }
+
+  /// Inspect a container image by it's name or id
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.inspect_image("nginx:latest").await;
+  /// ```
+  pub async fn inspect_image(
+    &self,
+    name: &str,
+  ) -> HttpClientResult<ImageInspect> {
+    let res = self
+      .send_get(
+        &format!("{}/{name}", Self::CONTAINER_IMAGE_PATH),
+        None::<String>,
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Remove a container image not used by a cargo or a job
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.delete_image("nginx:latest", None).await;
+  /// ```
+  pub async fn delete_image(
+    &self,
+    name: &str,
+    query: Option<&ContainerImageDeleteQuery>,
+  ) -> HttpClientResult<()> {
+    self
+      .send_delete(&format!("{}/{name}", Self::CONTAINER_IMAGE_PATH), query)
+      .await?;
+    Ok(())
+  }
+
+  /// Remove the container images not used by a cargo, a job or a container
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.prune_image().await;
+  /// ```
+  pub async fn prune_image(
+    &self,
+  ) -> HttpClientResult<ContainerImagePruneResult> {
+    let res = self
+      .send_post(
+        &format!("{}/prune", Self::CONTAINER_IMAGE_PATH),
+        None::<String>,
+        None::<String>,
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::ConnectOpts;
+
+  use super::*;
+
+  #[ntex::test]
+  async fn basic() {
+    const IMAGE: &str = "ghcr.io/next-hat/nanocl-get-started:latest";
+    let client = NanocldClient::connect_to(&ConnectOpts {
+      url: "http://nanocl.internal:8585".into(),
+      ..Default::default()
+    })
+    .expect("Failed to create a nanocl client");
+    let image = client.pull_image(IMAGE, None).await.unwrap();
+    assert!(image
+      .repo_tags
+      .unwrap_or_default()
+      .contains(&IMAGE.to_owned()));
+    client.inspect_image(IMAGE).await.unwrap();
+    let images = client.list_image().await.unwrap();
+    assert!(images
+      .iter()
+      .any(|image| image.repo_tags.contains(&IMAGE.to_owned())));
+  }
+}
@@ -453,17 +453,26 @@ impl NanocldClient {
         if bytes.last() != Some(&b'\n') {
           continue;
         }
-        let t = match serde_json::from_slice::<R>(&payload) {
-          Ok(t) => t,
-          Err(e) => {
-            let _ = tx.send(Err(HttpError::internal_server_error(format!(
+        // A chunk can hold several lines
+        let lines = std::mem::take(&mut payload);
+        let mut is_closed = false;
+        for line in lines.split(|byte| *byte == b'\n') {
+          if line.is_empty() {
+            continue;
+          }
+          let t = match serde_json::from_slice::<R>(line) {
+            Ok(t) => Ok(t),
+            Err(e) => Err(HttpError::internal_server_error(format!(
               "Unable to parse stream: {e}"
-            ))));
+            ))),
+          };
+          let is_err = t.is_err();
+          if tx.send(t).is_err() || is_err {
+            is_closed = true;
             break;
           }
-        };
-        payload.clear();
-        if tx.send(Ok(t)).is_err() {
+        }
+        if is_closed {
           break;
         }
       }
@@ -1,6 +1,8 @@
 mod http_client;
 
+pub(crate) mod alert;
 pub(crate) mod cargo;
+pub(crate) mod container_image;
 pub(crate) mod exec;
 pub(crate) mod job;
 pub(crate) mod metric;
@@ -11,6 +13,7 @@ pub(crate) mod resource;
 pub(crate) mod resource_kind;
 pub(crate) mod secret;
 pub(crate) mod system;
+pub(crate) mod task;
 pub(crate) mod vm;
 pub(crate) mod vm_image;
 
@@ -2,7 +2,10 @@ use nanocl_error::http_client::HttpClientResult;
 
 use nanocl_stubs::{
   generic::GenericFilter,
-  metric::{Metric, MetricPartial},
+  metric::{
+    HttpAnalytics, HttpAnalyticsQuery, Metric, MetricPartial, MetricQuery,
+    MetricSeries,
+  },
 };
 
 use super::http_client::NanocldClient;
@@ -76,6 +79,56 @@ impl NanocldClient {
       .await?;
     Self::res_json(res).await
   }
+
+  /// Compute a time series of a field of the metrics
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  /// use nanocld_client::stubs::metric::MetricQuery;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.query_metric(&MetricQuery {
+  ///   field: "Cpus.*.Usage".to_owned(),
+  ///   kind: Some("nanocl.io/metrs".to_owned()),
+  ///   ..Default::default()
+  /// }).await;
+  /// ```
+  pub async fn query_metric(
+    &self,
+    query: &MetricQuery,
+  ) -> HttpClientResult<Vec<MetricSeries>> {
+    let res = self
+      .send_get(&format!("{}/query", Self::METRIC_PATH), Some(query))
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Requests per second, error rates and latency percentiles
+  /// of the http requests of the proxy by domain, location or upstream
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  /// use nanocld_client::stubs::metric::{HttpAnalyticsGroupBy, HttpAnalyticsQuery};
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.http_analytics_metric(&HttpAnalyticsQuery {
+  ///   group_by: Some(HttpAnalyticsGroupBy::Upstream),
+  ///   ..Default::default()
+  /// }).await;
+  /// ```
+  pub async fn http_analytics_metric(
+    &self,
+    query: &HttpAnalyticsQuery,
+  ) -> HttpClientResult<Vec<HttpAnalytics>> {
+    let res = self
+      .send_get(&format!("{}/http", Self::METRIC_PATH), Some(query))
+      .await?;
+    Self::res_json(res).await
+  }
 }
 
 #[cfg(test)]
@@ -109,5 +162,17 @@ mod tests {
       .inspect_metric(metrics[0].key.to_string().as_str())
       .await
       .unwrap();
+    client
+      .query_metric(&MetricQuery {
+        field: "name".to_owned(),
+        kind: Some("my-source.io/type".to_owned()),
+        ..Default::default()
+      })
+      .await
+      .unwrap();
+    client
+      .http_analytics_metric(&HttpAnalyticsQuery::default())
+      .await
+      .unwrap();
   }
 }
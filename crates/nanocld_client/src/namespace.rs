@@ -3,7 +3,8 @@ use nanocl_error::http_client::HttpClientResult;
 use nanocl_stubs::{
   generic::GenericFilter,
   namespace::{
-    Namespace, NamespaceInspect, NamespacePartial, NamespaceSummary,
+    Namespace, NamespaceCloneOpts, NamespaceCloned, NamespaceDeleteQuery,
+    NamespaceInspect, NamespacePartial, NamespaceSummary,
   },
 };
 
@@ -40,9 +41,37 @@ impl NanocldClient {
     let new_item = NamespacePartial {
       name: name.to_owned(),
       metadata: None,
+      network: None,
+      quota: None,
+      limit_range: None,
     };
+    self.create_namespace_from(&new_item).await
+  }
+
+  /// Create a namespace from a partial definition
+  /// Used to create isolated namespaces with their own network
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  /// use nanocld_client::stubs::namespace::{NamespacePartial, NamespaceNetwork};
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.create_namespace_from(&NamespacePartial {
+  ///   name: "my-namespace".to_owned(),
+  ///   metadata: None,
+  ///   network: Some(NamespaceNetwork { isolated: true, ..Default::default() }),
+  ///   quota: None,
+  ///   limit_range: None,
+  /// }).await;
+  /// ```
+  pub async fn create_namespace_from(
+    &self,
+    item: &NamespacePartial,
+  ) -> HttpClientResult<Namespace> {
     let res = self
-      .send_post(Self::NAMESPACE_PATH, Some(new_item), None::<String>)
+      .send_post(Self::NAMESPACE_PATH, Some(item), None::<String>)
       .await?;
     Self::res_json(res).await
   }
@@ -86,6 +115,64 @@ impl NanocldClient {
       .await?;
     Ok(())
   }
+
+  /// Delete a namespace with everything inside it.
+  /// Cargoes, virtual machines, proxy and dns rules and volumes are removed in background,
+  /// the namespace stay in a `Destroying` state until it's done.
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.delete_namespace_cascade("my-namespace").await;
+  /// ```
+  pub async fn delete_namespace_cascade(
+    &self,
+    name: &str,
+  ) -> HttpClientResult<()> {
+    self
+      .send_delete(
+        &format!("{}/{name}", Self::NAMESPACE_PATH),
+        Some(&NamespaceDeleteQuery {
+          cascade: Some(true),
+        }),
+      )
+      .await?;
+    Ok(())
+  }
+
+  /// Clone a namespace with his cargoes, virtual machines, proxy and dns rules
+  /// The proxy rules that couldn't be cloned are returned with the new namespace
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  /// use nanocld_client::stubs::namespace::NamespaceCloneOpts;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let opts = NamespaceCloneOpts {
+  ///   name: "my-namespace-copy".to_owned(),
+  ///   ..Default::default()
+  /// };
+  /// let res = client.clone_namespace("my-namespace", &opts).await;
+  /// ```
+  pub async fn clone_namespace(
+    &self,
+    name: &str,
+    opts: &NamespaceCloneOpts,
+  ) -> HttpClientResult<NamespaceCloned> {
+    let res = self
+      .send_post(
+        &format!("{}/{name}/clone", Self::NAMESPACE_PATH),
+        Some(opts),
+        None::<String>,
+      )
+      .await?;
+    Self::res_json(res).await
+  }
 }
 
 #[cfg(test)]
@@ -7,7 +7,8 @@ use nanocl_stubs::{
   generic::{GenericFilter, GenericNspQuery},
   process::{
     Process, ProcessLogQuery, ProcessOutputLog, ProcessStats,
-    ProcessStatsQuery, ProcessWaitQuery, ProcessWaitResponse,
+    ProcessStatsQuery, ProcessStatsSummary, ProcessStatsSummaryQuery,
+    ProcessWaitQuery, ProcessWaitResponse,
   },
 };
 
@@ -204,6 +205,31 @@ impl NanocldClient {
       .await?;
     Ok(Self::res_stream(res).await)
   }
+
+  /// Summarize the sampled resource usage of the processes of a cargo, vm or job
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.stats_summary_processes("cargo", "my-cargo", None).await;
+  /// ```
+  pub async fn stats_summary_processes(
+    &self,
+    kind: &str,
+    name: &str,
+    query: Option<&ProcessStatsSummaryQuery>,
+  ) -> HttpClientResult<Vec<ProcessStatsSummary>> {
+    let res = self
+      .send_get(
+        &format!("{}/{kind}/{name}/stats/summary", Self::PROCESS_PATH),
+        query,
+      )
+      .await?;
+    Self::res_json(res).await
+  }
 }
 
 #[cfg(test)]
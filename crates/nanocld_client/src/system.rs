@@ -1,12 +1,27 @@
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+use futures::StreamExt;
 use ntex::channel::mpsc::Receiver;
+use ntex::rt;
 
 use nanocl_error::http::HttpResult;
-use nanocl_error::http_client::HttpClientResult;
+use nanocl_error::http_client::{HttpClientError, HttpClientResult};
+use nanocl_error::io::IoError;
 
-use nanocl_stubs::system::{BinaryInfo, Event, EventCondition, HostInfo};
+use nanocl_stubs::config::DaemonConfigReload;
+use nanocl_stubs::generic::GenericFilter;
+use nanocl_stubs::system::{
+  BinaryInfo, Event, EventCondition, EventWatchOpts, EventWatchQuery, HostInfo,
+  PruneStats, EVENT_WATCH_OVERLAP,
+};
+use nanocl_stubs::webhook::{EventDeadLetter, EventWebhookStatus};
 
 use super::http_client::NanocldClient;
 
+/// Seconds before resuming a watch of the events after a disconnection
+const WATCH_EVENTS_RETRY: u64 = 2;
+
 impl NanocldClient {
   /// Get the version of the daemon
   ///
@@ -24,7 +39,9 @@ impl NanocldClient {
   }
 
   /// Watch daemon events
-  /// It will emit an event when the daemon state change
+  /// It will emit an event when the daemon state change.
+  /// When the connection is lost the watch is resumed after the cursor
+  /// of the last event received, like `watch_events_since`.
   ///
   /// ## Example
   ///
@@ -41,12 +58,184 @@ impl NanocldClient {
     &self,
     conditions: Option<Vec<EventCondition>>,
   ) -> HttpClientResult<Receiver<HttpResult<Event>>> {
+    self
+      .watch_events_since(conditions, EventWatchOpts::default())
+      .await
+  }
+
+  /// Watch daemon events matching a filter after the cursor of an event
+  /// The stored events since the cursor are received first,
+  /// the stream ends when the connection is lost.
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  /// use nanocld_client::stubs::system::{EventCondition, EventKind, EventWatchOpts};
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let opts = EventWatchOpts {
+  ///   since: Some(42),
+  ///   filter: Some(EventCondition {
+  ///     kind: vec![EventKind::Error],
+  ///     ..Default::default()
+  ///   }),
+  /// };
+  /// let mut stream = client.watch_events_opts(None, opts).await?;
+  /// while let Some(event) = stream.next().await {
+  ///  println!("{:?}", event);
+  /// }
+  /// ```
+  pub async fn watch_events_opts(
+    &self,
+    conditions: Option<Vec<EventCondition>>,
+    opts: EventWatchOpts,
+  ) -> HttpClientResult<Receiver<HttpResult<Event>>> {
+    let query = Self::gen_watch_query(opts)?;
     let res = self
-      .send_post("/events/watch", conditions, None::<String>)
+      .send_post("/events/watch", conditions, Some(&query))
       .await?;
     Ok(Self::res_stream(res).await)
   }
 
+  /// Watch daemon events like `watch_events_opts` but when the connection
+  /// is lost the watch is resumed after the cursor of the last event received,
+  /// or of the last stored event when none was received yet.
+  /// The events replayed twice by the overlap of the resume are skipped,
+  /// a watch with conditions is resumed with the conditions not met yet
+  /// and ends with the stream of the daemon once they are met.
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  /// use nanocld_client::stubs::system::EventWatchOpts;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let opts = EventWatchOpts {
+  ///   since: Some(42),
+  ///   ..Default::default()
+  /// };
+  /// let mut stream = client.watch_events_since(None, opts).await?;
+  /// while let Some(event) = stream.next().await {
+  ///  println!("{:?}", event);
+  /// }
+  /// ```
+  pub async fn watch_events_since(
+    &self,
+    conditions: Option<Vec<EventCondition>>,
+    opts: EventWatchOpts,
+  ) -> HttpClientResult<Receiver<HttpResult<Event>>> {
+    let mut query = Self::gen_watch_query(opts)?;
+    let res = self
+      .send_post("/events/watch", conditions.clone(), Some(&query))
+      .await?;
+    let mut stream = Self::res_stream::<Event>(res).await;
+    if query.since.is_none() {
+      // The events emitted before the first one received are resumed from the last stored
+      let filter = GenericFilter::new().limit(1);
+      query.since = self
+        .list_event(Some(&filter))
+        .await
+        .ok()
+        .and_then(|events| events.first().map(|event| event.seq))
+        .filter(|seq| *seq > 0);
+    }
+    let (tx, rx) = ntex::channel::mpsc::channel();
+    let client = self.clone();
+    rt::spawn(async move {
+      // Keys of the events received during the overlap of a resume
+      let mut keys = HashSet::new();
+      let mut received = VecDeque::new();
+      let mut remaining = conditions.clone().unwrap_or_default();
+      loop {
+        // The daemon ends a watch with conditions once they are met
+        // but a failed stream or a shutdown must be resumed
+        let mut is_ended = true;
+        while let Some(item) = stream.next().await {
+          is_ended = match &item {
+            Ok(event) => event.action != "shutdown" || event.actor.is_some(),
+            Err(_) => false,
+          };
+          if let Ok(event) = &item {
+            if !keys.insert(event.key) {
+              continue;
+            }
+            received.push_back((event.key, event.created_at));
+            while let Some((key, created_at)) = received.front() {
+              if (event.created_at - *created_at).num_seconds()
+                <= EVENT_WATCH_OVERLAP * 2
+              {
+                break;
+              }
+              keys.remove(key);
+              received.pop_front();
+            }
+            // Daemons without cursor send 0
+            if event.seq > 0 {
+              query.since = Some(event.seq);
+            }
+            if let Some(pos) = remaining.iter().position(|c| c == event) {
+              remaining.remove(pos);
+            }
+          }
+          if tx.send(item).is_err() {
+            return;
+          }
+        }
+        if conditions.is_some() && (is_ended || remaining.is_empty()) {
+          break;
+        }
+        let conditions = conditions.is_some().then(|| remaining.clone());
+        stream = loop {
+          ntex::time::sleep(Duration::from_secs(WATCH_EVENTS_RETRY)).await;
+          if tx.is_closed() {
+            return;
+          }
+          let res = client
+            .send_post("/events/watch", conditions.clone(), Some(&query))
+            .await;
+          if let Ok(res) = res {
+            break Self::res_stream::<Event>(res).await;
+          }
+        };
+      }
+      tx.close();
+    });
+    Ok(rx)
+  }
+
+  /// List the stored events, the last created first
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.list_event(None).await;
+  /// ```
+  pub async fn list_event(
+    &self,
+    query: Option<&GenericFilter>,
+  ) -> HttpClientResult<Vec<Event>> {
+    let query = Self::convert_query(query)?;
+    let res = self.send_get("/events", Some(query)).await?;
+    Self::res_json(res).await
+  }
+
+  /// Query string of the options of a watch
+  fn gen_watch_query(
+    opts: EventWatchOpts,
+  ) -> HttpClientResult<EventWatchQuery> {
+    EventWatchQuery::try_from(opts).map_err(|err| {
+      HttpClientError::IoError(IoError::invalid_data(
+        "Query".to_owned(),
+        err.to_string(),
+      ))
+    })
+  }
+
   /// Check if the daemon is running
   ///
   /// ## Example
@@ -76,6 +265,75 @@ impl NanocldClient {
     let res = self.send_get("/info", None::<String>).await?;
     Self::res_json(res).await
   }
+
+  /// Reload the config of the daemon
+  /// Return the settings applied and the ones requiring a restart
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let reload = client.reload_config().await.unwrap();
+  /// ```
+  pub async fn reload_config(&self) -> HttpClientResult<DaemonConfigReload> {
+    let res = self
+      .send_post("/system/config/reload", None::<String>, None::<String>)
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Statistics of the pruning of the events and the metrics past their retention
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let stats = client.prune_stats().await.unwrap();
+  /// ```
+  pub async fn prune_stats(&self) -> HttpClientResult<PruneStats> {
+    let res = self.send_get("/system/prune/stats", None::<String>).await?;
+    Self::res_json(res).await
+  }
+
+  /// Deliveries of the event webhooks by the node since it started
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let webhooks = client.list_event_webhook().await.unwrap();
+  /// ```
+  pub async fn list_event_webhook(
+    &self,
+  ) -> HttpClientResult<Vec<EventWebhookStatus>> {
+    let res = self.send_get("/events/webhooks", None::<String>).await?;
+    Self::res_json(res).await
+  }
+
+  /// List the events that could not be delivered to their webhook
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let dead_letters = client.list_event_dead_letter(None).await.unwrap();
+  /// ```
+  pub async fn list_event_dead_letter(
+    &self,
+    query: Option<&GenericFilter>,
+  ) -> HttpClientResult<Vec<EventDeadLetter>> {
+    let query = Self::convert_query(query)?;
+    let res = self.send_get("/events/dead-letters", Some(query)).await?;
+    Self::res_json(res).await
+  }
 }
 
 #[cfg(test)]
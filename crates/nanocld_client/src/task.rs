@@ -0,0 +1,84 @@
+use nanocl_error::http_client::HttpClientResult;
+
+use nanocl_stubs::generic::GenericFilter;
+use nanocl_stubs::task::{Task, TaskStats};
+
+use super::http_client::NanocldClient;
+
+impl NanocldClient {
+  /// ## Default path for tasks
+  const TASK_PATH: &'static str = "/tasks";
+
+  /// List the tasks in progress
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.list_task(None).await;
+  /// ```
+  pub async fn list_task(
+    &self,
+    query: Option<&GenericFilter>,
+  ) -> HttpClientResult<Vec<Task>> {
+    let query = Self::convert_query(query)?;
+    let res = self.send_get(Self::TASK_PATH, Some(&query)).await?;
+    Self::res_json(res).await
+  }
+
+  /// Get the number of tasks running and waiting in the queues
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.task_stats().await;
+  /// ```
+  pub async fn task_stats(&self) -> HttpClientResult<TaskStats> {
+    let res = self
+      .send_get(&format!("{}/stats", Self::TASK_PATH), None::<String>)
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Cancel a task in progress by it's key
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// client.cancel_task("2f3c4a1e-6f0b-4d6e-9a35-0d1b2c3d4e5f").await?;
+  /// ```
+  pub async fn cancel_task(&self, key: &str) -> HttpClientResult<()> {
+    self
+      .send_delete(&format!("{}/{key}", Self::TASK_PATH), None::<String>)
+      .await?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::ConnectOpts;
+
+  use super::*;
+
+  #[ntex::test]
+  async fn basic() {
+    let client = NanocldClient::connect_to(&ConnectOpts {
+      url: "http://nanocl.internal:8585".into(),
+      ..Default::default()
+    })
+    .expect("Failed to create a nanocl client");
+    let tasks = client.list_task(None).await;
+    assert!(tasks.is_ok());
+    let stats = client.task_stats().await;
+    assert!(stats.is_ok());
+  }
+}